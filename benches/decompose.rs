@@ -0,0 +1,201 @@
+//! Criterion benchmarks of the recursive decomposition, run by `cargo bench --bench decompose`.
+//!
+//! Every game is deterministic, so the numbers of two runs are comparable. Each family is timed
+//! at a few sizes, both for building the decomposition tree and for the whole `cal_sv`, which are
+//! kept small enough for the IE coefficients to fit in i64.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use shapley_value_decomposition::{
+    alg::synthesis_sv::{
+        decompose_tree::{AblationType, DecomposeTree},
+        recursive_decompose::{
+            cal_banzhaf_recursive_decompose, cal_indices, cal_sv_recursive_decompose,
+            cal_sv_recursive_decompose_with, IndexKind,
+        },
+        recursive_decompose_ablation::cal_sv_recursive_decompose_ablation,
+        ProgressCallback, SvOptions,
+    },
+    dnf,
+    gen::GameGenerator,
+    Game, OwnerId,
+};
+
+fn game(implicants: impl IntoIterator<Item = Vec<u32>>) -> Game {
+    Game::from_dnf(
+        implicants
+            .into_iter()
+            .map(|imp| imp.into_iter().map(OwnerId).collect())
+            .collect(),
+    )
+}
+
+/// The game of `test_performance`, with 16 implicants over 11 owners.
+fn sixteen_implicants() -> Game {
+    Game::from_dnf(
+        dnf!(0 4 12 17 + 0 7 12 17 + 0 4 5 9 17 + 0 4 5 10 17 + 0 4 9 15 17 + 0 4 10 15 17 + 4 5 10 13 17 + 4 10 12 13 17 + 4 10 13 15 17 + 7 10 12 13 17 + 0 5 6 7 9 17 + 0 5 6 7 10 17 + 0 6 7 9 15 17 + 0 6 7 10 15 17 + 5 6 7 10 13 17 + 6 7 10 13 15 17)
+            .map_variable(|id| OwnerId(*id)),
+    )
+}
+
+/// OR of `k` disjoint implicants of 3 owners.
+fn horizontal(k: u32) -> Game {
+    game((0..k).map(|i| vec![3 * i, 3 * i + 1, 3 * i + 2]))
+}
+
+/// A single implicant of `n` owners.
+fn vertical(n: u32) -> Game {
+    game([(0..n).collect()])
+}
+
+/// OR of `k` disjoint majorities of 3 owners, i.e., `k` hybrid nodes.
+fn hybrid(k: u32) -> Game {
+    game((0..k).flat_map(|i| {
+        let [a, b, c] = [3 * i, 3 * i + 1, 3 * i + 2];
+        [vec![a, b], vec![a, c], vec![b, c]]
+    }))
+}
+
+/// Random implicants of 2 to 4 out of 10 owners, which overlap so much that the game is mostly a
+/// single dense leaf.
+fn dense_leaf(num_implicants: usize) -> Game {
+    GameGenerator {
+        num_owners: 10,
+        num_implicants,
+        implicant_size: 2..5,
+        ..Default::default()
+    }
+    .generate()
+}
+
+fn families() -> Vec<(&'static str, Vec<(usize, Game)>)> {
+    let sized = |sizes: &[u32], f: fn(u32) -> Game| {
+        sizes
+            .iter()
+            .map(|&size| (size as usize, f(size)))
+            .collect::<Vec<_>>()
+    };
+    vec![
+        ("sixteen_implicants", vec![(16, sixteen_implicants())]),
+        ("horizontal", sized(&[5, 10, 20], horizontal)),
+        ("vertical", sized(&[10, 100, 1000], vertical)),
+        ("hybrid", sized(&[4, 8, 16], hybrid)),
+        (
+            "dense_leaf",
+            [8, 12, 16]
+                .into_iter()
+                .map(|size| (size, dense_leaf(size)))
+                .collect(),
+        ),
+    ]
+}
+
+fn bench_tree(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tree");
+    for (name, games) in families() {
+        for (size, game) in &games {
+            group.bench_with_input(BenchmarkId::new(name, size), game, |b, game| {
+                b.iter(|| DecomposeTree::from_game(game, None))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_cal_sv(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cal_sv");
+    for (name, games) in families() {
+        for (size, game) in &games {
+            group.bench_with_input(BenchmarkId::new(name, size), game, |b, game| {
+                b.iter(|| cal_sv_recursive_decompose(game))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_ablation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ablation");
+    for (name, game) in [
+        ("sixteen_implicants", sixteen_implicants()),
+        ("hybrid", hybrid(4)),
+    ] {
+        group.bench_with_input(BenchmarkId::new(name, "none"), &game, |b, game| {
+            b.iter(|| cal_sv_recursive_decompose(game))
+        });
+        for ablation_type in [
+            AblationType::NoHorizontal,
+            AblationType::NoVertical,
+            AblationType::NoHybrid,
+        ] {
+            group.bench_with_input(
+                BenchmarkId::new(name, format!("{ablation_type:?}")),
+                &game,
+                |b, game| b.iter(|| cal_sv_recursive_decompose_ablation(game, ablation_type)),
+            );
+        }
+    }
+    group.finish();
+}
+
+/// The game of `test_performance`, whose cost is dominated by combining the coefficients of the
+/// children of each node, with the options that change how they are computed.
+fn bench_options(c: &mut Criterion) {
+    let mut group = c.benchmark_group("options");
+    let game = sixteen_implicants();
+    let options = [
+        ("default", SvOptions::default()),
+        (
+            "sparse_leaves",
+            SvOptions {
+                max_dense_leaf_variables: 0,
+                ..Default::default()
+            },
+        ),
+        (
+            "progress",
+            SvOptions {
+                progress: Some(ProgressCallback::new(|_| {})),
+                ..Default::default()
+            },
+        ),
+    ];
+    for (name, options) in &options {
+        group.bench_with_input(
+            BenchmarkId::new("sixteen_implicants", name),
+            &game,
+            |b, game| b.iter(|| cal_sv_recursive_decompose_with(game, options)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_indices(c: &mut Criterion) {
+    let mut group = c.benchmark_group("indices");
+    for (name, game) in [
+        ("sixteen_implicants", sixteen_implicants()),
+        ("hybrid", hybrid(8)),
+    ] {
+        group.bench_with_input(BenchmarkId::new(name, "separate"), &game, |b, game| {
+            b.iter(|| {
+                (
+                    cal_sv_recursive_decompose(game),
+                    cal_banzhaf_recursive_decompose(game),
+                )
+            })
+        });
+        group.bench_with_input(BenchmarkId::new(name, "shared"), &game, |b, game| {
+            b.iter(|| cal_indices(game, &[IndexKind::Shapley, IndexKind::Banzhaf]))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_tree,
+    bench_cal_sv,
+    bench_ablation,
+    bench_options,
+    bench_indices
+);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+//! With the `ffi` feature, generate the C header of `src/ffi.rs` and compile the C test program
+//! into a static library, which only the tests of `ffi` link with.
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "ffi")]
+    ffi::build();
+}
+
+#[cfg(feature = "ffi")]
+mod ffi {
+    use std::{env, path::PathBuf};
+
+    const HEADER: &str = "shapley_sv.h";
+    const C_TEST: &str = "tests/ffi/sv_ffi_test.c";
+
+    pub fn build() {
+        let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+        let include_dir = out_dir.join("include");
+        for path in ["src/ffi.rs", "cbindgen.toml", C_TEST] {
+            println!("cargo:rerun-if-changed={path}");
+        }
+
+        let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+            .expect("invalid cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(crate_dir.join("src/ffi.rs"))
+            .generate()
+            .expect("failed to generate the C header")
+            .write_to_file(include_dir.join(HEADER));
+
+        cc::Build::new()
+            .file(crate_dir.join(C_TEST))
+            .include(&include_dir)
+            .warnings_into_errors(true)
+            .cargo_metadata(false)
+            .compile("sv_ffi_test");
+        println!("cargo:rustc-link-search=native={}", out_dir.display());
+    }
+}
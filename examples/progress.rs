@@ -0,0 +1,54 @@
+//! Show a progress bar while computing the Shapley values of a random game.
+
+use shapley_value_decomposition::{
+    alg::synthesis_sv::{
+        recursive_decompose::cal_sv_recursive_decompose_with, ProgressCallback, ProgressPhase,
+        SvOptions,
+    },
+    gen::GameGenerator,
+};
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+fn main() {
+    let game = GameGenerator {
+        num_owners: 60,
+        num_implicants: 80,
+        implicant_size: 1..5,
+        ..Default::default()
+    }
+    .generate();
+
+    // the events are in order, so only redraw the bar when the percentage changes
+    let shown = Arc::new(AtomicUsize::new(0));
+    let options = SvOptions {
+        progress: Some(ProgressCallback::new({
+            let shown = shown.clone();
+            move |event| {
+                let percent = 100 * event.leaves_done / event.leaves_total.max(1);
+                if event.phase == ProgressPhase::Decompose
+                    || shown.swap(percent, Ordering::Relaxed) != percent
+                {
+                    print!(
+                        "\r[{:<50}] {percent:>3}% {:<9}",
+                        "#".repeat(percent / 2),
+                        format!("{:?}", event.phase)
+                    );
+                    io::stdout().flush().unwrap();
+                }
+            }
+        })),
+        ..Default::default()
+    };
+
+    let sv = cal_sv_recursive_decompose_with(&game, &options);
+    println!();
+    for (owner_id, u) in sv.sorted_desc().into_iter().take(5) {
+        println!("{owner_id}: {u:.6}");
+    }
+}
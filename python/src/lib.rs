@@ -0,0 +1,156 @@
+//! Python bindings of the Shapley values of simple games, built with maturin:
+//!
+//! ```bash
+//! cd python && maturin develop --release && pytest tests
+//! ```
+
+use pyo3::{
+    exceptions::{PyOverflowError, PyRuntimeError, PyValueError},
+    prelude::*,
+};
+use shapley_value_decomposition::{
+    alg::synthesis_sv::{
+        decompose_tree::decompose_stats,
+        recursive_decompose::try_cal_sv_recursive_decompose,
+        recursive_decompose_ablation::{
+            try_cal_sv_recursive_decompose_ablation_with, AblationType,
+        },
+    },
+    dnf::Implicant,
+    Dnf, Game, GameError, OwnerId, OwnerSet, SvError, SvOptions,
+};
+use std::collections::HashMap;
+
+/// A simple game, i.e., a monotone DNF over the owners, whose implicants are the coalitions that
+/// win.
+#[pyclass]
+#[derive(Debug, Clone)]
+struct PyGame {
+    game: Game,
+}
+
+#[pymethods]
+impl PyGame {
+    /// Game of the implicants over the owners, which must include all the owners of the
+    /// implicants. Absorbed implicants are removed, and the rest raise `ValueError` if the game is
+    /// invalid as in `Game::new`.
+    #[staticmethod]
+    fn from_implicants(implicants: Vec<Vec<u32>>, owners: Vec<u32>) -> PyResult<Self> {
+        let dnf: Dnf<OwnerId> = implicants
+            .into_iter()
+            .map(|imp| Implicant::from_iter(imp.into_iter().map(OwnerId)))
+            .collect();
+        let owner_set = OwnerSet::from_iter(owners.into_iter().map(OwnerId));
+        let game = Game::new(dnf.minimized(), owner_set).map_err(game_error)?;
+        Ok(Self { game })
+    }
+
+    /// Implicants of the DNF, each sorted, in order.
+    fn implicants(&self) -> Vec<Vec<u32>> {
+        self.game
+            .dnf
+            .iter()
+            .map(|imp| imp.iter().map(|owner_id| owner_id.0).collect())
+            .collect()
+    }
+
+    /// Owners of the game, sorted.
+    fn owners(&self) -> Vec<u32> {
+        self.game
+            .owner_set
+            .iter()
+            .map(|owner_id| owner_id.0)
+            .collect()
+    }
+
+    /// Same game without the implicants absorbed by others, which `from_implicants` has already
+    /// removed.
+    fn minimize(&self) -> Self {
+        Self {
+            game: Game {
+                dnf: self.game.dnf.minimized(),
+                owner_set: self.game.owner_set.clone(),
+            },
+        }
+    }
+
+    /// Shapley value of each owner by the recursive decomposition, with one kind of decomposition
+    /// disabled unless `ablation` is `"none"`. See `parse_ablation`.
+    ///
+    /// The GIL is released during the computation. Raise `OverflowError` if a coefficient
+    /// overflows, unless built with the `bigint` feature.
+    #[pyo3(signature = (ablation = "none"))]
+    fn compute_shapley(&self, py: Python<'_>, ablation: &str) -> PyResult<HashMap<u32, f64>> {
+        let ablation_type = parse_ablation(ablation)?;
+        let sv = py
+            .allow_threads(|| match ablation_type {
+                None => try_cal_sv_recursive_decompose(&self.game),
+                Some(ablation_type) => try_cal_sv_recursive_decompose_ablation_with(
+                    &self.game,
+                    ablation_type,
+                    &SvOptions::default(),
+                ),
+            })
+            .map_err(sv_error)?;
+        Ok(sv.iter().map(|(owner_id, u)| (owner_id.0, *u)).collect())
+    }
+
+    /// Statistics of the decomposition tree, as in `compute_shapley`.
+    #[pyo3(signature = (ablation = "none"))]
+    fn decompose_stats(
+        &self,
+        py: Python<'_>,
+        ablation: &str,
+    ) -> PyResult<HashMap<&'static str, usize>> {
+        let ablation_type = parse_ablation(ablation)?;
+        let stats = py.allow_threads(|| decompose_stats(&self.game, ablation_type));
+        Ok(HashMap::from([
+            ("depth", stats.depth),
+            ("var_nodes", stats.var_nodes),
+            ("and_nodes", stats.and_nodes),
+            ("or_nodes", stats.or_nodes),
+            ("hybrid_nodes", stats.hybrid_nodes),
+            ("leaf_nodes", stats.leaf_nodes),
+            ("max_leaf_implicants", stats.max_leaf_implicants),
+            ("total_leaf_implicants", stats.total_leaf_implicants),
+            ("max_leaf_unions", stats.max_leaf_unions),
+            ("large_leaves", stats.large_leaves),
+        ]))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PyGame({})", self.game.dnf)
+    }
+}
+
+/// `"none"`, or the kebab-case name of an `AblationType` as on the command line, e.g.,
+/// `"no-hybrid"`.
+fn parse_ablation(ablation: &str) -> PyResult<Option<AblationType>> {
+    match ablation {
+        "none" => Ok(None),
+        "no-horizontal" => Ok(Some(AblationType::NoHorizontal)),
+        "no-vertical" => Ok(Some(AblationType::NoVertical)),
+        "no-hybrid" => Ok(Some(AblationType::NoHybrid)),
+        _ => Err(PyValueError::new_err(format!(
+            "unknown ablation {ablation:?}, expected one of none, no-horizontal, no-vertical, \
+             no-hybrid"
+        ))),
+    }
+}
+
+fn game_error(e: GameError) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+fn sv_error(e: SvError) -> PyErr {
+    match e {
+        SvError::Overflow { .. } => PyOverflowError::new_err(e.to_string()),
+        _ => PyRuntimeError::new_err(e.to_string()),
+    }
+}
+
+#[pymodule]
+fn shapley_py(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyGame>()?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+"""Games of the Rust test suite, through the Python bindings."""
+
+import pytest
+
+from shapley_py import PyGame
+
+ABLATIONS = ["none", "no-horizontal", "no-vertical", "no-hybrid"]
+
+
+def game(implicants):
+    owners = sorted({o for imp in implicants for o in imp})
+    return PyGame.from_implicants(implicants, owners)
+
+
+def assert_values(expect, actual):
+    assert expect.keys() == actual.keys()
+    for owner, u in expect.items():
+        assert actual[owner] == pytest.approx(u, abs=1e-9)
+
+
+@pytest.mark.parametrize("ablation", ABLATIONS)
+@pytest.mark.parametrize(
+    "implicants, expect",
+    [
+        ([[1, 2, 3]], {1: 1 / 3, 2: 1 / 3, 3: 1 / 3}),
+        ([[1], [2], [3]], {1: 1 / 3, 2: 1 / 3, 3: 1 / 3}),
+        ([[1, 2], [1, 3]], {1: 2 / 3, 2: 1 / 6, 3: 1 / 6}),
+        ([[1, 2], [1, 3], [2, 3]], {1: 1 / 3, 2: 1 / 3, 3: 1 / 3}),
+        ([[1]], {1: 1.0}),
+    ],
+)
+def test_compute_shapley(implicants, expect, ablation):
+    assert_values(expect, game(implicants).compute_shapley(ablation))
+
+
+def test_ablations_agree():
+    g = game([[1, 2], [1, 3], [2, 3], [4, 5, 6], [4, 7], [8]])
+    expect = g.compute_shapley()
+    assert sum(expect.values()) == pytest.approx(1.0)
+    for ablation in ABLATIONS:
+        assert_values(expect, g.compute_shapley(ablation))
+
+
+def test_null_players():
+    g = PyGame.from_implicants([[1, 2]], [1, 2, 3])
+    assert_values({1: 0.5, 2: 0.5, 3: 0.0}, g.compute_shapley())
+
+
+def test_minimize():
+    g = game([[1, 2], [1, 2, 3], [2, 3], [1, 2, 3, 4]])
+    minimized = g.minimize()
+    assert g.implicants() == minimized.implicants() == [[1, 2], [2, 3]]
+    assert minimized.owners() == g.owners() == [1, 2, 3, 4]
+    assert_values(g.compute_shapley(), minimized.compute_shapley())
+
+
+def test_absorbed_unknown_owner():
+    # the implicant of the unknown owner 9 is absorbed, so it is not kept in the game
+    g = PyGame.from_implicants([[1], [1, 9]], [1])
+    assert g.implicants() == [[1]]
+    assert_values({1: 1.0}, g.compute_shapley())
+
+
+def test_decompose_stats():
+    stats = game([[1, 2], [1, 3], [2, 3], [4]]).decompose_stats()
+    assert stats["depth"] >= 1
+    assert stats["var_nodes"] + stats["leaf_nodes"] >= 1
+    assert stats.keys() == game([[1]]).decompose_stats("no-hybrid").keys()
+
+
+def test_invalid_game():
+    with pytest.raises(ValueError, match="not in the owner set"):
+        PyGame.from_implicants([[1, 2]], [1])
+    with pytest.raises(ValueError, match="unknown ablation"):
+        game([[1]]).compute_shapley("no-such-ablation")
+
+
+def test_overflow():
+    # the coefficients of 1 - (1 - x)^69 exceed i64 without the `bigint` feature
+    g = game([[i] for i in range(1, 71)])
+    try:
+        sv = g.compute_shapley()
+    except OverflowError as e:
+        assert "overflows" in str(e)
+    else:
+        assert_values({i: 1 / 70 for i in range(1, 71)}, sv)
@@ -1,8 +1,14 @@
 mod subset_utility;
 pub mod synthesis_sv;
 
+pub mod algorithm;
+pub mod exact;
+pub mod harsanyi;
 pub mod iusv;
+#[cfg(feature = "data")]
 pub mod join;
+pub mod mwc_indices;
+pub mod owen;
 pub mod permutation;
 pub mod proposed;
 pub mod proposed_ablation;
@@ -0,0 +1,237 @@
+//! Algorithms of Shapley values behind a common interface, e.g., to swap or compare them in an
+//! application or a benchmark.
+
+use crate::{
+    alg::{
+        exact::cal_sv_exact,
+        permutation::cal_sv_permutation_sampling,
+        synthesis_sv::{recursive_decompose_ablation::AblationType, ShapleyComputer},
+    },
+    Game, ShapleyValues, SvError,
+};
+use clap::ValueEnum;
+
+/// An algorithm computing the Shapley values of simple games.
+pub trait ShapleyAlgorithm {
+    fn compute(&self, game: &Game) -> Result<ShapleyValues, SvError>;
+
+    /// Short name for reports, e.g., `recursive-decompose`.
+    fn name(&self) -> &'static str;
+}
+
+/// The recursive decomposition, with one kind of decomposition disabled or none. See
+/// `ShapleyComputer`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecursiveDecompose {
+    pub ablation: Option<AblationType>,
+}
+
+impl ShapleyAlgorithm for RecursiveDecompose {
+    fn compute(&self, game: &Game) -> Result<ShapleyValues, SvError> {
+        ShapleyComputer::new(game).ablation(self.ablation).compute()
+    }
+
+    fn name(&self) -> &'static str {
+        match self.ablation {
+            None => "recursive-decompose",
+            Some(AblationType::NoHorizontal) => "recursive-decompose-no-horizontal",
+            Some(AblationType::NoVertical) => "recursive-decompose-no-vertical",
+            Some(AblationType::NoHybrid) => "recursive-decompose-no-hybrid",
+        }
+    }
+}
+
+/// Enumerate all the coalitions by `cal_sv_exact`, for up to ~25 owners.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BruteForce;
+
+impl ShapleyAlgorithm for BruteForce {
+    fn compute(&self, game: &Game) -> Result<ShapleyValues, SvError> {
+        Ok(cal_sv_exact(game))
+    }
+
+    fn name(&self) -> &'static str {
+        "brute-force"
+    }
+}
+
+/// Estimate by `cal_sv_permutation_sampling` over `samples` random permutations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PermutationSampling {
+    pub samples: usize,
+    pub seed: u64,
+}
+
+impl ShapleyAlgorithm for PermutationSampling {
+    fn compute(&self, game: &Game) -> Result<ShapleyValues, SvError> {
+        Ok(cal_sv_permutation_sampling(game, self.samples, self.seed))
+    }
+
+    fn name(&self) -> &'static str {
+        "permutation-sampling"
+    }
+}
+
+/// Kind of `ShapleyAlgorithm`, e.g., for a command line option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Algorithm {
+    /// Recursive decomposition of the DNF
+    #[clap(alias("rdsv"))]
+    RecursiveDecompose,
+    /// Enumerate all the coalitions
+    #[clap(alias("exact"))]
+    BruteForce,
+    /// Sample random permutations
+    #[clap(alias("monte-carlo"), alias("perm"))]
+    PermutationSampling,
+}
+
+impl Algorithm {
+    /// The algorithm with the parameters that apply to it, i.e., `ablation` to
+    /// `RecursiveDecompose`, and `samples` and `seed` to `PermutationSampling`.
+    pub fn with_params(
+        self,
+        ablation: Option<AblationType>,
+        samples: usize,
+        seed: u64,
+    ) -> Box<dyn ShapleyAlgorithm> {
+        match self {
+            Self::RecursiveDecompose => Box::new(RecursiveDecompose { ablation }),
+            Self::BruteForce => Box::new(BruteForce),
+            Self::PermutationSampling => Box::new(PermutationSampling { samples, seed }),
+        }
+    }
+}
+
+/// Results of `compare`.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// Name and result of each algorithm, in the given order.
+    pub results: Vec<(&'static str, Result<ShapleyValues, SvError>)>,
+    /// Largest difference between the values of an owner by two algorithms, among those that
+    /// succeeded, or 0 if fewer than two did. An owner missing from a result counts as 0.
+    pub max_deviation: f64,
+}
+
+/// Compute the Shapley values of `game` by each of `algos`, e.g., to validate one against another.
+pub fn compare(algos: &[&dyn ShapleyAlgorithm], game: &Game) -> Comparison {
+    let results: Vec<_> = algos
+        .iter()
+        .map(|algo| (algo.name(), algo.compute(game)))
+        .collect();
+    let values: Vec<&ShapleyValues> = results
+        .iter()
+        .filter_map(|(_, r)| r.as_ref().ok())
+        .collect();
+    let mut max_deviation = 0_f64;
+    for (i, a) in values.iter().enumerate() {
+        for b in &values[i + 1..] {
+            for owner_id in a.keys().chain(b.keys()) {
+                let deviation = (a.get_or_zero(owner_id) - b.get_or_zero(owner_id)).abs();
+                max_deviation = max_deviation.max(deviation);
+            }
+        }
+    }
+    Comparison {
+        results,
+        max_deviation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dnf, tests::sixteen_implicants, OwnerId, OwnerSet};
+    use itertools::Itertools;
+
+    fn games() -> Vec<Game> {
+        vec![
+            Game::from_dnf(
+                dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            ),
+            Game::from_dnf(dnf!(1 2 3 + 1 2 4).map_variable(|id| OwnerId(*id))),
+            Game::new(
+                dnf!(1 2 + 1 3 + 4).map_variable(|id| OwnerId(*id)),
+                OwnerSet::from_iter(1..=6),
+            )
+            .unwrap(),
+            sixteen_implicants(),
+        ]
+    }
+
+    #[test]
+    fn test_compare_exact() {
+        let decompositions = [
+            RecursiveDecompose { ablation: None },
+            RecursiveDecompose {
+                ablation: Some(AblationType::NoHorizontal),
+            },
+            RecursiveDecompose {
+                ablation: Some(AblationType::NoVertical),
+            },
+            RecursiveDecompose {
+                ablation: Some(AblationType::NoHybrid),
+            },
+        ];
+        let mut algos: Vec<&dyn ShapleyAlgorithm> = decompositions
+            .iter()
+            .map(|a| a as &dyn ShapleyAlgorithm)
+            .collect();
+        algos.push(&BruteForce);
+        for game in games() {
+            let comparison = compare(&algos, &game);
+            assert_eq!(5, comparison.results.len());
+            assert!(comparison.results.iter().all(|(_, r)| r.is_ok()));
+            assert!(
+                comparison.max_deviation < 1e-6,
+                "{}",
+                comparison.max_deviation
+            );
+        }
+
+        let names: Vec<_> = algos.iter().map(|a| a.name()).collect();
+        assert_eq!("recursive-decompose", names[0]);
+        assert_eq!("brute-force", names[4]);
+        assert!(names.iter().all_unique());
+    }
+
+    #[test]
+    fn test_compare_sampling() {
+        let sampling = PermutationSampling {
+            samples: 20000,
+            seed: 0,
+        };
+        for game in games() {
+            let comparison = compare(&[&RecursiveDecompose::default(), &sampling], &game);
+            assert!(
+                comparison.max_deviation < 0.02,
+                "{}",
+                comparison.max_deviation
+            );
+        }
+    }
+
+    #[test]
+    fn test_algorithm() {
+        let games = games();
+        let game = &games[0];
+        for algorithm in Algorithm::value_variants() {
+            let algo = algorithm.with_params(None, 1000, 0);
+            assert!(algo.compute(game).is_ok());
+            assert_eq!(
+                algorithm.to_possible_value().unwrap().get_name(),
+                algo.name()
+            );
+        }
+        assert_eq!(
+            Algorithm::BruteForce,
+            Algorithm::from_str("exact", false).unwrap()
+        );
+        assert_eq!(
+            "recursive-decompose-no-hybrid",
+            Algorithm::RecursiveDecompose
+                .with_params(Some(AblationType::NoHybrid), 1000, 0)
+                .name()
+        );
+    }
+}
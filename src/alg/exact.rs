@@ -0,0 +1,400 @@
+use crate::{
+    alg::synthesis_sv::recursive_decompose::cal_sv_recursive_decompose,
+    game::GeneralGame,
+    par::prelude::*,
+    utils::{binom, normalize_values},
+    BanzhafValues, Game, OwnerId, OwnerSet, ShapleyValues,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Exact Shapley values by enumerating all the coalitions of the owners.
+///
+/// The cost is exponential in the number of owners, so it is only meant to be a baseline for
+/// validating other methods on games with up to ~25 owners.
+pub fn cal_sv_exact(game: &Game) -> ShapleyValues {
+    let n = game.owner_len();
+    // weights[k] = k! (n - k - 1)! / n!
+    let weights: Vec<f64> = (0..n).map(|k| 1. / (n * binom(k, n - 1)) as f64).collect();
+    sum_over_swings(game, |k| weights[k]).into()
+}
+
+/// Shapley values straight from the definition, summing up the weighted marginal contribution
+/// `v(S + i) - v(S)` of each owner over all the coalitions via `Game::is_winning`.
+///
+/// It is much slower than `cal_sv_exact` and only meant to be a ground-truth oracle for testing
+/// on games with a handful of owners.
+pub fn cal_sv_brute_force(game: &Game) -> ShapleyValues {
+    sv_brute_force(&game.owner_set, |coalition| game.is_winning(coalition))
+}
+
+/// Shapley values of a game whose literals may be negated. Simple games, i.e., without any
+/// negated literal, are computed by `cal_sv_recursive_decompose`, and the others by brute force as
+/// in `cal_sv_brute_force`, so they are limited to a handful of owners.
+///
+/// The values still sum up to `v(N) - v({})`, but may be negative, e.g., for an owner that must be
+/// absent.
+pub fn cal_sv_general(game: &GeneralGame) -> ShapleyValues {
+    match game.to_game() {
+        Some(game) => cal_sv_recursive_decompose(&game),
+        None => sv_brute_force(&game.owner_set, |coalition| game.is_winning(coalition)),
+    }
+}
+
+fn sv_brute_force(
+    owner_set: &OwnerSet,
+    is_winning: impl Fn(&BTreeSet<OwnerId>) -> bool,
+) -> ShapleyValues {
+    let owners: Vec<OwnerId> = owner_set.iter().copied().collect();
+    let n = owners.len();
+    assert!(n <= 20, "too many owners ({n}) for brute force");
+    let value = |coalition: &BTreeSet<OwnerId>| is_winning(coalition) as i32;
+
+    let mut ans: ShapleyValues = owners.iter().map(|owner| (*owner, 0.)).collect();
+    for mask in 0..1_usize << n {
+        let mut coalition: BTreeSet<OwnerId> = (0..n)
+            .filter(|i| mask >> i & 1 == 1)
+            .map(|i| owners[i])
+            .collect();
+        let k = coalition.len();
+        if k == n {
+            continue;
+        }
+        // k! (n - k - 1)! / n!
+        let weight = 1. / (n * binom(k, n - 1)) as f64;
+        let base = value(&coalition);
+        for owner in &owners {
+            if coalition.insert(*owner) {
+                let marginal = value(&coalition) - base;
+                coalition.remove(owner);
+                *ans.get_mut(owner).unwrap() += weight * marginal as f64;
+            }
+        }
+    }
+    ans
+}
+
+/// The Shapley interaction index of every pair of owners `(i, j)` with `i < j` straight from the
+/// definition, summing up `v(S + i + j) - v(S + i) - v(S + j) + v(S)` weighted by
+/// `|S|! (n - |S| - 2)! / (n - 1)!` over all the coalitions `S` without `i` and `j`.
+///
+/// It is only meant to be a ground-truth oracle for testing on games with up to ~12 owners.
+pub fn cal_shapley_interaction_pairs_brute_force(game: &Game) -> BTreeMap<(OwnerId, OwnerId), f64> {
+    let owners: Vec<OwnerId> = game.owner_set.iter().copied().collect();
+    let n = owners.len();
+    assert!(n <= 20, "too many owners ({n}) for brute force");
+    if n < 2 {
+        return BTreeMap::new();
+    }
+
+    let is_winning = winning_table(game, &owners);
+    let value = |coalition: usize| is_winning[coalition] as i32;
+    // weights[k] = k! (n - k - 2)! / (n - 1)!
+    let weights: Vec<f64> = (0..n - 1)
+        .map(|k| 1. / ((n - 1) * binom(k, n - 2)) as f64)
+        .collect();
+
+    let mut ans = BTreeMap::new();
+    for i in 0..n {
+        for j in i + 1..n {
+            let (bit_i, bit_j) = (1 << i, 1 << j);
+            let interaction: f64 = (0..1_usize << n)
+                .filter(|coalition| coalition & (bit_i | bit_j) == 0)
+                .map(|s| {
+                    let delta =
+                        value(s | bit_i | bit_j) - value(s | bit_i) - value(s | bit_j) + value(s);
+                    weights[s.count_ones() as usize] * delta as f64
+                })
+                .sum();
+            ans.insert((owners[i], owners[j]), interaction);
+        }
+    }
+    ans
+}
+
+/// Exact normalized Banzhaf index by enumerating all the coalitions of the owners.
+pub fn cal_banzhaf_exact(game: &Game) -> BanzhafValues {
+    let mut ans = cal_banzhaf_swings_exact(game);
+    normalize_values(&mut ans);
+    ans
+}
+
+/// Exact swing counts by enumerating all the coalitions of the owners.
+pub fn cal_banzhaf_swings_exact(game: &Game) -> BanzhafValues {
+    sum_over_swings(game, |_| 1.)
+}
+
+/// Johnston index by enumerating all the coalitions of the owners: each vulnerable coalition,
+/// i.e., a winning one that some of its members (the critical ones) turn into losing by leaving,
+/// splits one unit equally among its critical members, and the totals are normalized.
+///
+/// Unlike the Banzhaf index, an owner counts less in a coalition with more critical members, which
+/// depends on the whole coalition rather than the owner alone, so it is not computed from the IE
+/// coefficients. Owners that are never critical get 0, and so does everyone if no coalition is
+/// vulnerable.
+pub fn cal_johnston(game: &Game) -> HashMap<OwnerId, f64> {
+    let owners: Vec<OwnerId> = game.owner_set.iter().copied().collect();
+    let n = owners.len();
+    assert!(
+        n < usize::BITS as usize,
+        "too many owners ({n}) to enumerate all coalitions"
+    );
+    if n == 0 {
+        return HashMap::new();
+    }
+
+    let is_winning = winning_table(game, &owners);
+    // number of critical members of each coalition, 0 for the losing ones
+    let critical_counts: Vec<u32> = (0..1_usize << n)
+        .into_par_iter()
+        .map(|coalition| {
+            if !is_winning[coalition] {
+                return 0;
+            }
+            (0..n)
+                .map(|i| 1 << i)
+                .filter(|bit| coalition & bit != 0 && !is_winning[coalition ^ bit])
+                .count() as u32
+        })
+        .collect();
+
+    let mut ans: HashMap<OwnerId, f64> = owners
+        .par_iter()
+        .enumerate()
+        .map(|(i, owner)| {
+            let bit = 1 << i;
+            let value: f64 = (0..1_usize << n)
+                .into_par_iter()
+                .filter(|coalition| {
+                    (coalition & bit) != 0 && is_winning[*coalition] && !is_winning[coalition ^ bit]
+                })
+                .map(|coalition| 1. / critical_counts[coalition] as f64)
+                .sum();
+            (*owner, value)
+        })
+        .collect();
+    normalize_values(&mut ans);
+    ans
+}
+
+/// Sum up `weight(|S|)` over all the coalitions S in which each owner is a swing player.
+fn sum_over_swings(game: &Game, weight: impl Fn(usize) -> f64 + Sync) -> HashMap<OwnerId, f64> {
+    let owners: Vec<OwnerId> = game.owner_set.iter().copied().collect();
+    let n = owners.len();
+    assert!(
+        n < usize::BITS as usize,
+        "too many owners ({n}) to enumerate all coalitions"
+    );
+    if n == 0 {
+        return HashMap::new();
+    }
+
+    let is_winning = winning_table(game, &owners);
+
+    owners
+        .par_iter()
+        .enumerate()
+        .map(|(i, owner)| {
+            let bit = 1 << i;
+            let value: f64 = (0..1_usize << n)
+                .into_par_iter()
+                .filter(|coalition| {
+                    (coalition & bit) == 0 && is_winning[coalition | bit] && !is_winning[*coalition]
+                })
+                .map(|coalition| weight(coalition.count_ones() as usize))
+                .sum();
+            (*owner, value)
+        })
+        .collect()
+}
+
+/// Evaluate the DNF on every coalition, encoded as a bitmask over the indices of `owners`.
+///
+/// Implicants with variables outside `owners` can never be satisfied and are ignored.
+fn winning_table(game: &Game, owners: &[OwnerId]) -> Vec<bool> {
+    let imp_masks: Vec<usize> = game
+        .dnf
+        .iter()
+        .filter_map(|imp| {
+            imp.iter().try_fold(0_usize, |mask, owner| {
+                let i = owners.binary_search(owner).ok()?;
+                Some(mask | 1 << i)
+            })
+        })
+        .collect();
+
+    (0..1_usize << owners.len())
+        .into_par_iter()
+        .map(|coalition| imp_masks.iter().any(|imp| coalition & imp == *imp))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::synthesis_sv::recursive_decompose_ablation::{
+            cal_sv_recursive_decompose_ablation, AblationType,
+        },
+        dnf,
+        dnf::{Dnf, GeneralDnf},
+        tests::{assert_f64_eq, game, sixteen_implicants, test_method},
+    };
+
+    #[test]
+    fn test() {
+        test_method(cal_sv_exact, true);
+    }
+
+    #[test]
+    fn test_brute_force() {
+        test_method(cal_sv_brute_force, true);
+
+        let game = Game::new(
+            dnf!(1 2).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter([1, 2, 3]),
+        )
+        .unwrap();
+        let sv = cal_sv_brute_force(&game);
+        assert_f64_eq(0.5, sv[&OwnerId(1)]);
+        assert_f64_eq(0.5, sv[&OwnerId(2)]);
+        assert_f64_eq(0., sv[&OwnerId(3)]);
+    }
+
+    #[test]
+    fn test_sv_general() {
+        // x1 and not x2, where owner 2 can only spoil the win of owner 1
+        let exp: GeneralDnf<u32> = "1 !2".parse().unwrap();
+        let game = GeneralGame::new(
+            exp.map_variable(|l| l.map(OwnerId)),
+            OwnerSet::from_iter([1, 2, 3]),
+        )
+        .unwrap();
+        assert!(game.is_winning(&BTreeSet::from([OwnerId(1), OwnerId(3)])));
+        assert!(!game.is_winning(&BTreeSet::from([OwnerId(1), OwnerId(2)])));
+        let sv = cal_sv_general(&game);
+        assert_f64_eq(0.5, sv[&OwnerId(1)]);
+        assert_f64_eq(-0.5, sv[&OwnerId(2)]);
+        assert_f64_eq(0., sv[&OwnerId(3)]);
+
+        // without negated literals, the same as the simple game
+        let simple = Game::from_dnf(dnf!(1 2 + 1 3 + 2 3 4).map_variable(|id| OwnerId(*id)));
+        let general = GeneralGame::from(&simple);
+        let expect = cal_sv_brute_force(&simple);
+        let actual = cal_sv_general(&general);
+        for (o, u) in expect.iter() {
+            assert_f64_eq(*u, actual[o]);
+        }
+        let spoiled = GeneralGame::new(
+            "1 2 !4 + 1 3 !4 + 2 3 4"
+                .parse::<GeneralDnf<u32>>()
+                .unwrap()
+                .map_variable(|l| l.map(OwnerId)),
+            simple.owner_set.clone(),
+        )
+        .unwrap();
+        // efficiency still holds, as the grand coalition wins by `2 3 4`
+        assert_f64_eq(1., cal_sv_general(&spoiled).sum());
+    }
+
+    #[test]
+    fn test_interaction_brute_force() {
+        let game = Game::new(
+            dnf!(1 2).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter([1, 2, 3]),
+        )
+        .unwrap();
+        let interaction = cal_shapley_interaction_pairs_brute_force(&game);
+        assert_eq!(3, interaction.len());
+        assert_f64_eq(1., interaction[&(OwnerId(1), OwnerId(2))]);
+        assert_f64_eq(0., interaction[&(OwnerId(1), OwnerId(3))]);
+        assert_f64_eq(0., interaction[&(OwnerId(2), OwnerId(3))]);
+
+        let game = Game::from_dnf(dnf!(1 + 2).map_variable(|id| OwnerId(*id)));
+        let interaction = cal_shapley_interaction_pairs_brute_force(&game);
+        assert_f64_eq(-1., interaction[&(OwnerId(1), OwnerId(2))]);
+    }
+
+    #[test]
+    fn test_banzhaf() {
+        // simple majority game among 3 owners
+        let game = Game::from_dnf(dnf!(1 2 + 1 3 + 2 3).map_variable(|id| OwnerId(*id)));
+
+        let swings = cal_banzhaf_swings_exact(&game);
+        assert_f64_eq(2., swings[&OwnerId(1)]);
+        assert_f64_eq(2., swings[&OwnerId(2)]);
+        assert_f64_eq(2., swings[&OwnerId(3)]);
+
+        let banzhaf = cal_banzhaf_exact(&game);
+        assert_f64_eq(0.33333333333, banzhaf[&OwnerId(1)]);
+        assert_f64_eq(0.33333333333, banzhaf[&OwnerId(2)]);
+        assert_f64_eq(0.33333333333, banzhaf[&OwnerId(3)]);
+    }
+
+    #[test]
+    fn test_johnston() {
+        // {1, 2} and {1, 3} split their unit between their two critical members, while only 1 is
+        // critical in {1, 2, 3}, so the totals are 2, 1/2 and 1/2
+        let game = Game::new(
+            dnf!(1 2 + 1 3).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=4),
+        )
+        .unwrap();
+        let johnston = cal_johnston(&game);
+        assert_eq!(4, johnston.len());
+        assert_f64_eq(2. / 3., johnston[&OwnerId(1)]);
+        assert_f64_eq(1. / 6., johnston[&OwnerId(2)]);
+        assert_f64_eq(1. / 6., johnston[&OwnerId(3)]);
+        assert_eq!(0., johnston[&OwnerId(4)]);
+
+        // symmetric owners share equally
+        let game = Game::from_dnf(dnf!(1 2 + 1 3 + 2 3).map_variable(|id| OwnerId(*id)));
+        for u in cal_johnston(&game).values() {
+            assert_f64_eq(1. / 3., *u);
+        }
+
+        // no coalition is vulnerable when the empty one wins
+        let game = Game {
+            dnf: Dnf::true_exp(),
+            owner_set: OwnerSet::from_iter(1..=2),
+        };
+        let johnston = cal_johnston(&game);
+        assert_eq!(2, johnston.len());
+        assert!(johnston.values().all(|u| *u == 0.));
+    }
+
+    #[test]
+    fn test_against_recursive_decompose() {
+        let exps = [
+            dnf!(1 2 3),
+            dnf!(1 2 3 + 1 2 4),
+            dnf!(1 + 2 + 3),
+            dnf!(1 4 5 + 2 4 5 + 3 4 5),
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5),
+            dnf!(1 3 6 8 + 3 5 6 8 + 3 4 6 8 9),
+            dnf!(1 2 5 + 1 2 6 + 1 3 5 + 1 3 6 + 4 5 + 4 6),
+            dnf!(1 2 + 1 3 + 4),
+        ];
+
+        for game in exps.map(game).into_iter().chain([sixteen_implicants()]) {
+            let expect = cal_sv_exact(&game);
+
+            let actual = cal_sv_recursive_decompose(&game);
+            assert_eq!(expect.len(), actual.len());
+            for (o, u) in &actual {
+                assert_f64_eq(expect[o], *u);
+            }
+
+            for ablation_type in [
+                AblationType::NoVertical,
+                AblationType::NoHorizontal,
+                AblationType::NoHybrid,
+            ] {
+                let actual = cal_sv_recursive_decompose_ablation(&game, ablation_type);
+                assert_eq!(expect.len(), actual.len());
+                for (o, u) in &actual {
+                    assert_f64_eq(expect[o], *u);
+                }
+            }
+        }
+    }
+}
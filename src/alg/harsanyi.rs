@@ -0,0 +1,155 @@
+//! Harsanyi dividends, i.e., the Möbius transform of the characteristic function, which split the
+//! value of a game into the synergies of its coalitions.
+
+use crate::{Game, OwnerId, OwnerSet, ShapleyValues};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Harsanyi dividend `d(T) = sum((-1)^(|T| - |S|) v(S) for S in subsets of T)` of each coalition
+/// `T` of at most `max_size` owners whose dividend is not 0, so that `v(S)` is the sum of the
+/// dividends of the subsets of `S`.
+///
+/// By inclusion-exclusion over the minimal winning coalitions, `d(T)` is the sum of
+/// `(-1)^(|F| + 1)` over the non-empty families `F` of minimal winning coalitions whose union is
+/// `T`. The minimal winning coalitions are folded in one at a time, keeping only the signed count
+/// of each distinct union of at most `max_size` owners, so the cost is bounded by the number of
+/// those unions rather than that of the families, or of all the `2^n` coalitions.
+///
+/// A game whose empty coalition wins has the single dividend `d({}) = 1`.
+pub fn cal_harsanyi_dividends(game: &Game, max_size: usize) -> BTreeMap<OwnerSet, f64> {
+    // signed count of the families so far by their unions, which only grow with more coalitions
+    let mut counts: HashMap<BTreeSet<OwnerId>, i64> = HashMap::new();
+    for mwc in game.minimal_winning_coalitions() {
+        if mwc.len() > max_size {
+            continue;
+        }
+        let mut extended: Vec<_> = counts
+            .iter()
+            .filter(|(_, count)| **count != 0)
+            .filter_map(|(union, count)| {
+                let next: BTreeSet<_> = union.union(&mwc).copied().collect();
+                (next.len() <= max_size).then_some((next, -count))
+            })
+            .collect();
+        extended.push((mwc.0, 1));
+        for (union, count) in extended {
+            *counts.entry(union).or_default() += count;
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count != 0)
+        .map(|(coalition, count)| (OwnerSet(coalition), count as f64))
+        .collect()
+}
+
+/// Shapley values from Harsanyi dividends, where each dividend is split equally among the owners
+/// of its coalition, e.g., to cross-check `cal_harsanyi_dividends`.
+///
+/// The values are exact if `max_size` of `cal_harsanyi_dividends` left no dividend out, and
+/// otherwise miss the shares of the larger coalitions. Owners in no coalition of `dividends` are
+/// not in the result.
+pub fn sv_from_harsanyi_dividends(dividends: &BTreeMap<OwnerSet, f64>) -> ShapleyValues {
+    let mut ans = ShapleyValues::new();
+    for (coalition, dividend) in dividends {
+        for owner_id in coalition.iter() {
+            *ans.entry(*owner_id).or_default() += dividend / coalition.len() as f64;
+        }
+    }
+    ans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::synthesis_sv::recursive_decompose::cal_sv_recursive_decompose,
+        dnf,
+        dnf::Implicant,
+        tests::{assert_f64_eq, game},
+        Dnf,
+    };
+    use itertools::Itertools;
+
+    fn coalition(owners: &[u32]) -> OwnerSet {
+        OwnerSet::from_iter(owners.iter().copied())
+    }
+
+    #[test]
+    fn test_harsanyi_dividends() {
+        assert_eq!(
+            BTreeMap::from([(coalition(&[1, 2, 3]), 1.)]),
+            cal_harsanyi_dividends(&game(dnf!(1 2 3)), 3)
+        );
+        assert!(cal_harsanyi_dividends(&game(dnf!(1 2 3)), 2).is_empty());
+
+        assert_eq!(
+            BTreeMap::from([
+                (coalition(&[1]), 1.),
+                (coalition(&[2]), 1.),
+                (coalition(&[1, 2]), -1.),
+            ]),
+            cal_harsanyi_dividends(&game(dnf!(1 + 2)), 2)
+        );
+
+        let game = Game {
+            dnf: Dnf::true_exp(),
+            owner_set: coalition(&[1, 2]),
+        };
+        assert_eq!(
+            BTreeMap::from([(coalition(&[]), 1.)]),
+            cal_harsanyi_dividends(&game, 2)
+        );
+    }
+
+    #[test]
+    fn test_harsanyi_dividends_definition() {
+        let game = game(dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5));
+        let n = game.owner_len();
+        let dividends = cal_harsanyi_dividends(&game, n);
+        for t in game.owner_set.iter().copied().powerset() {
+            let expect: i64 = t
+                .iter()
+                .copied()
+                .powerset()
+                .map(|s| {
+                    let sign = if (t.len() - s.len()) % 2 == 0 { 1 } else { -1 };
+                    sign * game.is_winning(&BTreeSet::from_iter(s)) as i64
+                })
+                .sum();
+            let actual = dividends.get(&OwnerSet::from_iter(t)).copied();
+            assert_eq!(expect as f64, actual.unwrap_or(0.));
+        }
+
+        // the values are exact with all the dividends
+        let expect = cal_sv_recursive_decompose(&game);
+        let actual = sv_from_harsanyi_dividends(&dividends);
+        for (o, u) in &expect {
+            assert_f64_eq(*u, actual[o]);
+        }
+
+        // truncated to pairs
+        let truncated = cal_harsanyi_dividends(&game, 2);
+        assert_eq!(BTreeMap::from([(coalition(&[4, 5]), 1.)]), truncated);
+    }
+
+    #[test]
+    fn test_harsanyi_dividends_overlapping() {
+        // any 2 of 9 owners wins, i.e., 36 minimal winning coalitions, of which there are 2^36
+        // families but only 2^9 unions
+        let implicants = (1..=9_u32)
+            .tuple_combinations()
+            .map(|(a, b)| Implicant::from_iter([OwnerId(a), OwnerId(b)]));
+        let game = Game::from_dnf(Dnf::from_iter(implicants));
+        let dividends = cal_harsanyi_dividends(&game, 9);
+        // d(T) = (-1)^|T| (|T| - 1) for |T| >= 2
+        assert_eq!(2_usize.pow(9) - 1 - 9, dividends.len());
+        for (coalition, dividend) in &dividends {
+            let t = coalition.len() as f64;
+            let sign = if coalition.len() % 2 == 0 { 1. } else { -1. };
+            assert_eq!(sign * (t - 1.), *dividend);
+        }
+        for u in sv_from_harsanyi_dividends(&dividends).values() {
+            assert_f64_eq(1. / 9., *u);
+        }
+    }
+}
@@ -1,8 +1,4 @@
-use crate::{
-    utils::{binom, hashmap_reduce},
-    Game, OwnerSet, ShapleyValues,
-};
-use rayon::prelude::*;
+use crate::{par::prelude::*, utils::binom, Game, OwnerSet, ShapleyValues};
 
 mod non_linear_comb;
 mod non_linear_lookup;
@@ -104,7 +100,7 @@ fn cal_sv_non_linear(syns: &[&OwnerSet], owner_set: &OwnerSet) -> ShapleyValues
             }
             ans
         })
-        .reduce(ShapleyValues::default, hashmap_reduce)
+        .reduce(ShapleyValues::default, ShapleyValues::merge)
 }
 
 #[cfg(test)]
@@ -114,6 +110,6 @@ mod tests {
 
     #[test]
     fn test() {
-        test_method(|game| synthesis_method(game), true);
+        test_method(synthesis_method, true);
     }
 }
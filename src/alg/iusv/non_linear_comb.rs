@@ -1,5 +1,4 @@
-use crate::OwnerSet;
-use rayon::prelude::*;
+use crate::{par::prelude::*, OwnerSet};
 use std::collections::HashSet;
 
 struct Union {
@@ -11,7 +10,7 @@ struct Union {
 impl Union {
     #[inline(always)]
     fn utility(&self) -> f64 {
-        let signed_flag = if self.num_of_set % 2 == 0 { -1. } else { 1. };
+        let signed_flag = if self.num_of_set.is_multiple_of(2) { -1. } else { 1. };
         signed_flag / self.set.len() as f64
     }
 }
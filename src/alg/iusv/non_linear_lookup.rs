@@ -1,5 +1,4 @@
-use crate::{utils::binom_coeffs, OwnerId, OwnerSet};
-use rayon::prelude::*;
+use crate::{par::prelude::*, utils::binom_coeffs, OwnerId, OwnerSet};
 
 #[derive(Clone)]
 struct Subset {
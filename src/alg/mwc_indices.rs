@@ -0,0 +1,166 @@
+//! Power indices defined directly over the minimal winning coalitions, which are the implicants
+//! of the minimized DNF.
+
+use crate::{Game, ShapleyValues};
+
+/// Deegan-Packel index: each minimal winning coalition is equally likely to form, and splits its
+/// payoff equally among its members, i.e., `DP(i) = 1 / |M| * sum(1 / |S| for S in M if i in S)`.
+///
+/// The minimal winning coalitions are from `Game::minimal_winning_coalitions`, so redundant
+/// implicants do not count. Owners in no minimal winning coalition get 0, and so does everyone if
+/// the DNF is empty.
+pub fn cal_deegan_packel(game: &Game) -> ShapleyValues {
+    let mwcs = game.minimal_winning_coalitions();
+    let mut ans = zeros(game);
+    if mwcs.is_empty() {
+        return ans;
+    }
+    let share = 1. / mwcs.len() as f64;
+    for imp in &mwcs {
+        for owner_id in imp.iter() {
+            *ans.entry(*owner_id).or_default() += share / imp.len() as f64;
+        }
+    }
+    ans
+}
+
+/// Holler (Public Good) index: the number of minimal winning coalitions containing each owner,
+/// normalized to sum up to 1.
+///
+/// The minimal winning coalitions are from `Game::minimal_winning_coalitions`, so redundant
+/// implicants do not count. Owners in no minimal winning coalition get 0, and so does everyone if
+/// the DNF is empty.
+pub fn cal_holler(game: &Game) -> ShapleyValues {
+    let mwcs = game.minimal_winning_coalitions();
+    let mut ans = zeros(game);
+    let total: usize = mwcs.iter().map(|imp| imp.len()).sum();
+    if total == 0 {
+        return ans;
+    }
+    for imp in &mwcs {
+        for owner_id in imp.iter() {
+            *ans.entry(*owner_id).or_default() += 1. / total as f64;
+        }
+    }
+    ans
+}
+
+fn zeros(game: &Game) -> ShapleyValues {
+    game.owner_set
+        .iter()
+        .map(|owner_id| (*owner_id, 0.))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dnf, tests::assert_f64_eq, Dnf, OwnerId, OwnerSet};
+
+    fn assert_values(expect: &[(u32, f64)], actual: &ShapleyValues) {
+        assert_eq!(expect.len(), actual.len());
+        for (owner_id, u) in expect {
+            assert_f64_eq(*u, actual[&OwnerId(*owner_id)]);
+        }
+    }
+
+    /// The weighted majority game [3; 2, 1, 1, 1], whose minimal winning coalitions are {1, 2},
+    /// {1, 3}, {1, 4} and {2, 3, 4}.
+    fn weighted_majority() -> Game {
+        let weights = [
+            (OwnerId(1), 2),
+            (OwnerId(2), 1),
+            (OwnerId(3), 1),
+            (OwnerId(4), 1),
+        ];
+        Game::from_weighted_voting(&weights, 3)
+    }
+
+    #[test]
+    fn test_deegan_packel() {
+        let game = weighted_majority();
+        assert_eq!(
+            dnf!(1 2 + 1 3 + 1 4 + 2 3 4).map_variable(|id| OwnerId(*id)),
+            game.dnf
+        );
+        let dp = cal_deegan_packel(&game);
+        assert_values(
+            &[(1, 3. / 8.), (2, 5. / 24.), (3, 5. / 24.), (4, 5. / 24.)],
+            &dp,
+        );
+        dp.assert_efficient(1., 1e-9);
+
+        // two overlapping minimal winning coalitions {1, 2} and {2, 3, 4}, each with weight 1/2:
+        // 1 gets 1/2 * 1/2, 2 gets 1/2 * (1/2 + 1/3), and 3 and 4 get 1/2 * 1/3
+        let game = Game::from_dnf(dnf!(1 2 + 2 3 4).map_variable(|id| OwnerId(*id)));
+        let dp = cal_deegan_packel(&game);
+        assert_values(
+            &[(1, 1. / 4.), (2, 5. / 12.), (3, 1. / 6.), (4, 1. / 6.)],
+            &dp,
+        );
+        dp.assert_efficient(1., 1e-9);
+
+        // the unanimity game splits the payoff equally
+        let game = Game::from_dnf(dnf!(1 2 3).map_variable(|id| OwnerId(*id)));
+        assert_values(
+            &[(1, 1. / 3.), (2, 1. / 3.), (3, 1. / 3.)],
+            &cal_deegan_packel(&game),
+        );
+    }
+
+    #[test]
+    fn test_holler() {
+        let holler = cal_holler(&weighted_majority());
+        assert_values(
+            &[(1, 1. / 3.), (2, 2. / 9.), (3, 2. / 9.), (4, 2. / 9.)],
+            &holler,
+        );
+        holler.assert_efficient(1., 1e-9);
+
+        // replaceable owners 1, 2 and 3 are each in one minimal winning coalition, while 4 and 5
+        // are in all three of them, and 6 is in none
+        let game = Game::new(
+            dnf!(1 4 5 + 2 4 5 + 3 4 5).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=6),
+        )
+        .unwrap();
+        let holler = cal_holler(&game);
+        assert_values(
+            &[
+                (1, 1. / 9.),
+                (2, 1. / 9.),
+                (3, 1. / 9.),
+                (4, 1. / 3.),
+                (5, 1. / 3.),
+                (6, 0.),
+            ],
+            &holler,
+        );
+        let top: Vec<_> = holler.top_k(2).into_iter().map(|(o, _)| o).collect();
+        assert_eq!(vec![OwnerId(4), OwnerId(5)], top);
+    }
+
+    #[test]
+    fn test_redundant_implicants() {
+        // 1 2 3 and 2 3 4 5 are absorbed, and 5 is a null player
+        let game = Game {
+            dnf: dnf!(1 2 + 2 3 + 1 2 3 + 2 3 4 5).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter(1..=5),
+        };
+        assert_values(
+            &[(1, 1. / 4.), (2, 1. / 2.), (3, 1. / 4.), (4, 0.), (5, 0.)],
+            &cal_deegan_packel(&game),
+        );
+        assert_values(
+            &[(1, 1. / 4.), (2, 1. / 2.), (3, 1. / 4.), (4, 0.), (5, 0.)],
+            &cal_holler(&game),
+        );
+
+        let game = Game {
+            dnf: Dnf::new(),
+            owner_set: OwnerSet::from_iter(1..=2),
+        };
+        assert_values(&[(1, 0.), (2, 0.)], &cal_deegan_packel(&game));
+        assert_values(&[(1, 0.), (2, 0.)], &cal_holler(&game));
+    }
+}
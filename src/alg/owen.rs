@@ -0,0 +1,331 @@
+//! Owen values of games with a coalition structure, where the owners first enter in blocks (e.g.,
+//! organizations), and then one by one within their blocks.
+
+use crate::{
+    alg::synthesis_sv::recursive_decompose::cal_sv_recursive_decompose, par::prelude::*,
+    utils::binom, Game, OwnerId, OwnerSet, ShapleyValues,
+};
+use std::{error, fmt};
+
+/// A partition of the owner set into blocks.
+///
+/// Use `CoalitionStructure::new` to construct a validated structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoalitionStructure(pub Vec<OwnerSet>);
+
+/// Error of an invalid coalition structure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoalitionStructureError {
+    /// A block has no owner.
+    EmptyBlock,
+    /// An owner is in more than one block.
+    OverlappingOwner(OwnerId),
+    /// Owners in the owner set but in no block.
+    MissingOwners(Vec<OwnerId>),
+    /// Owners in a block but not in the owner set.
+    UnknownOwners(Vec<OwnerId>),
+}
+
+impl fmt::Display for CoalitionStructureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyBlock => write!(f, "a block of the coalition structure is empty"),
+            Self::OverlappingOwner(owner) => write!(f, "owner {owner} is in more than one block"),
+            Self::MissingOwners(owners) => write!(f, "owners {owners:?} are in no block"),
+            Self::UnknownOwners(owners) => {
+                write!(
+                    f,
+                    "owners {owners:?} in the blocks are not in the owner set"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for CoalitionStructureError {}
+
+impl CoalitionStructure {
+    /// Create a coalition structure whose `blocks` partition `owner_set`.
+    pub fn new(
+        blocks: Vec<OwnerSet>,
+        owner_set: &OwnerSet,
+    ) -> Result<Self, CoalitionStructureError> {
+        let cs = Self(blocks);
+        cs.validate(owner_set)?;
+        Ok(cs)
+    }
+
+    /// Every owner in a block of its own, for which the Owen value is the Shapley value.
+    pub fn singletons(owner_set: &OwnerSet) -> Self {
+        Self(
+            owner_set
+                .iter()
+                .map(|owner_id| OwnerSet::from_iter([*owner_id]))
+                .collect(),
+        )
+    }
+
+    /// All the owners in a single block, for which the Owen value is also the Shapley value.
+    pub fn grand_coalition(owner_set: &OwnerSet) -> Self {
+        Self(vec![owner_set.clone()])
+    }
+
+    /// Check that the blocks are non-empty and partition `owner_set`.
+    pub fn validate(&self, owner_set: &OwnerSet) -> Result<(), CoalitionStructureError> {
+        let mut seen = OwnerSet::default();
+        for block in &self.0 {
+            if block.is_empty() {
+                return Err(CoalitionStructureError::EmptyBlock);
+            }
+            for owner_id in block.iter() {
+                if !seen.insert(*owner_id) {
+                    return Err(CoalitionStructureError::OverlappingOwner(*owner_id));
+                }
+            }
+        }
+
+        let unknown_owners: Vec<_> = seen.difference(owner_set).copied().collect();
+        if !unknown_owners.is_empty() {
+            return Err(CoalitionStructureError::UnknownOwners(unknown_owners));
+        }
+        let missing_owners: Vec<_> = owner_set.difference(&seen).copied().collect();
+        if !missing_owners.is_empty() {
+            return Err(CoalitionStructureError::MissingOwners(missing_owners));
+        }
+        Ok(())
+    }
+}
+
+/// Owen values of a game with coalition structure `cs`. See `cal_owen_value_with`.
+pub fn cal_owen_value(game: &Game, cs: &CoalitionStructure) -> ShapleyValues {
+    cal_owen_value_with(game, cs, cal_sv_recursive_decompose)
+}
+
+/// Owen values of a game with coalition structure `cs`, computing the Shapley values within a
+/// block by `cal_sv`, e.g., `cal_sv_recursive_decompose_ablation` with an ablation type.
+///
+/// The Owen value of owner `i` in block `k` is the average over the unions `Q` of the other blocks
+/// (weighted as in the Shapley value of the quotient game among the blocks) of the Shapley value
+/// of `i` in the game among block `k` where `T` wins iff `Q + T` wins in `game`. The DNF of the
+/// latter drops the implicants with owners outside `Q` and block `k`, and projects `Q` out. The
+/// cost is exponential in the number of blocks.
+///
+/// Panic if `cs` does not partition the owner set.
+pub fn cal_owen_value_with(
+    game: &Game,
+    cs: &CoalitionStructure,
+    cal_sv: impl Fn(&Game) -> ShapleyValues + Sync,
+) -> ShapleyValues {
+    cs.validate(&game.owner_set)
+        .unwrap_or_else(|e| panic!("{e}"));
+    let blocks = &cs.0;
+    let m = blocks.len();
+    assert!(m <= 20, "too many blocks ({m}) to enumerate");
+
+    let mut ans: ShapleyValues = game
+        .owner_set
+        .iter()
+        .map(|owner_id| (*owner_id, 0.))
+        .collect();
+    if m == 0 {
+        return ans;
+    }
+
+    let values = (0..m)
+        .into_par_iter()
+        .flat_map(|k| {
+            (0..1_usize << (m - 1))
+                .into_par_iter()
+                .map(move |mask| (k, mask))
+        })
+        .map(|(k, mask)| {
+            // the other blocks, skipping block `k` in the mask
+            let others: Vec<_> = (0..m)
+                .filter(|l| *l != k)
+                .enumerate()
+                .filter(|(bit, _)| mask >> bit & 1 == 1)
+                .map(|(_, l)| &blocks[l])
+                .collect();
+            // r! (m - r - 1)! / m!
+            let weight = 1. / (m * binom(others.len(), m - 1)) as f64;
+
+            let mut allowed = blocks[k].clone();
+            allowed.extend(others.iter().flat_map(|b| b.iter().copied()));
+            let exp = game.dnf.restrict_to(&allowed).project_to(&blocks[k]);
+            // no owner of the block matters if it always or never wins
+            if exp.is_false() || exp.is_true() {
+                return ShapleyValues::new();
+            }
+            let block_game = Game {
+                dnf: exp,
+                owner_set: blocks[k].clone(),
+            };
+            cal_sv(&block_game)
+                .into_iter()
+                .map(|(owner_id, u)| (owner_id, weight * u))
+                .collect::<ShapleyValues>()
+        })
+        .collect::<Vec<_>>();
+
+    // added up in the order of the blocks and unions, so the result does not depend on the threads
+    for block_values in values {
+        for (owner_id, u) in block_values {
+            *ans.entry(owner_id).or_default() += u;
+        }
+    }
+    ans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::{
+            exact::cal_sv_exact,
+            synthesis_sv::recursive_decompose_ablation::{
+                cal_sv_recursive_decompose_ablation, AblationType,
+            },
+        },
+        dnf,
+        par::ThreadPoolBuilder,
+        tests::{assert_f64_eq, game},
+    };
+    use itertools::Itertools;
+
+    fn cs(blocks: &[&[u32]]) -> CoalitionStructure {
+        CoalitionStructure(
+            blocks
+                .iter()
+                .map(|b| OwnerSet::from_iter(b.iter().copied()))
+                .collect(),
+        )
+    }
+
+    fn assert_values_eq(expect: &ShapleyValues, actual: &ShapleyValues) {
+        assert_eq!(expect.len(), actual.len());
+        for (o, u) in actual {
+            assert_f64_eq(expect[o], *u);
+        }
+    }
+
+    /// Average the marginal contributions over all the orders in which the blocks are contiguous.
+    fn owen_brute_force(game: &Game, cs: &CoalitionStructure) -> ShapleyValues {
+        let mut ans: ShapleyValues = game.owner_set.iter().map(|o| (*o, 0.)).collect();
+        let mut count = 0;
+        for block_order in cs.0.iter().permutations(cs.0.len()) {
+            let orders = block_order
+                .iter()
+                .map(|b| b.iter().copied().permutations(b.len()))
+                .multi_cartesian_product();
+            for order in orders {
+                let order: Vec<OwnerId> = order.concat();
+                for (owner_id, marginal) in game.marginal_contributions(&order) {
+                    *ans.get_mut(&owner_id).unwrap() += marginal;
+                }
+                count += 1;
+            }
+        }
+        ans.values_mut().for_each(|u| *u /= count as f64);
+        ans
+    }
+
+    #[test]
+    fn test_validate() {
+        let owner_set = OwnerSet::from_iter(1..=4);
+        assert!(CoalitionStructure::new(cs(&[&[1, 2], &[3, 4]]).0, &owner_set).is_ok());
+        assert_eq!(
+            Err(CoalitionStructureError::EmptyBlock),
+            cs(&[&[1, 2], &[], &[3, 4]]).validate(&owner_set)
+        );
+        assert_eq!(
+            Err(CoalitionStructureError::OverlappingOwner(OwnerId(2))),
+            cs(&[&[1, 2], &[2, 3, 4]]).validate(&owner_set)
+        );
+        assert_eq!(
+            Err(CoalitionStructureError::MissingOwners(vec![OwnerId(4)])),
+            cs(&[&[1, 2], &[3]]).validate(&owner_set)
+        );
+        assert_eq!(
+            Err(CoalitionStructureError::UnknownOwners(vec![OwnerId(5)])),
+            cs(&[&[1, 2], &[3, 4, 5]]).validate(&owner_set)
+        );
+    }
+
+    #[test]
+    fn test_trivial_structures() {
+        let games = [
+            game(dnf!(1 2 + 1 3 + 2 3)),
+            game(dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5)),
+            game(dnf!(1 2 + 1 3 + 4)),
+        ];
+        for game in games {
+            let expect = cal_sv_exact(&game);
+            let singletons = CoalitionStructure::singletons(&game.owner_set);
+            assert_values_eq(&expect, &cal_owen_value(&game, &singletons));
+            let grand = CoalitionStructure::grand_coalition(&game.owner_set);
+            assert_values_eq(&expect, &cal_owen_value(&game, &grand));
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let game = game(dnf!(1 2 5 + 1 2 6 + 1 3 5 + 1 3 6 + 4 5 + 4 6 + 2 7 + 3 8));
+        let cs = cs(&[&[1, 2], &[3], &[4, 5], &[6], &[7, 8]]);
+        let expect = cal_owen_value(&game, &cs);
+        for num_threads in [1, 2, 8] {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap();
+            let actual = pool.install(|| cal_owen_value(&game, &cs));
+            for (owner_id, u) in &actual {
+                assert_eq!(
+                    expect[owner_id].to_bits(),
+                    u.to_bits(),
+                    "{num_threads} threads"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_owen_value() {
+        // the block {1, 2} wins alone, and then splits its value equally
+        let majority = game(dnf!(1 2 + 1 3 + 2 3));
+        let actual = cal_owen_value(&majority, &cs(&[&[1, 2], &[3]]));
+        assert_values_eq(
+            &ShapleyValues::from([(OwnerId(1), 0.5), (OwnerId(2), 0.5), (OwnerId(3), 0.)]),
+            &actual,
+        );
+
+        let cases = [
+            (game(dnf!(1 2 + 1 3 + 2 3)), cs(&[&[1, 2], &[3]])),
+            (
+                game(dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5)),
+                cs(&[&[1, 4], &[2, 3], &[5]]),
+            ),
+            (
+                game(dnf!(1 2 5 + 1 2 6 + 1 3 5 + 1 3 6 + 4 5 + 4 6)),
+                cs(&[&[1, 2, 3], &[4, 5], &[6]]),
+            ),
+            (game(dnf!(1 2 + 1 3 + 4)), cs(&[&[1], &[2, 3, 4]])),
+        ];
+        for (game, cs) in cases {
+            let expect = owen_brute_force(&game, &cs);
+            let actual = cal_owen_value(&game, &cs);
+            assert_values_eq(&expect, &actual);
+            assert_f64_eq(1., actual.sum());
+
+            for ablation_type in [
+                AblationType::NoHorizontal,
+                AblationType::NoVertical,
+                AblationType::NoHybrid,
+            ] {
+                let actual = cal_owen_value_with(&game, &cs, |g| {
+                    cal_sv_recursive_decompose_ablation(g, ablation_type)
+                });
+                assert_values_eq(&expect, &actual);
+            }
+        }
+    }
+}
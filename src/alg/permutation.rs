@@ -1,10 +1,13 @@
 use crate::{
-    alg::subset_utility::subset_utility_with_cache, utils::hashmap_reduce, Game, OwnerId, OwnerSet,
+    alg::subset_utility::subset_utility_with_cache, par::prelude::*, Game, OwnerId, OwnerSet,
     ShapleyValues,
 };
 use dashmap::DashMap;
 use rand::prelude::*;
-use rayon::prelude::*;
+use std::{cmp, collections::BTreeSet};
+
+/// Number of permutations sampled by a single RNG in `cal_sv_permutation_sampling`.
+const SAMPLE_BATCH_SIZE: usize = 256;
 
 pub fn permutation_method(game: &Game, sample_size: usize) -> ShapleyValues {
     let cache: DashMap<OwnerSet, f64> = DashMap::new();
@@ -32,7 +35,7 @@ pub fn permutation_method(game: &Game, sample_size: usize) -> ShapleyValues {
             // info!("sample #{} done", i);
             ans
         })
-        .reduce(ShapleyValues::new, hashmap_reduce);
+        .reduce(ShapleyValues::new, ShapleyValues::merge);
 
     shapley_values.par_iter_mut().for_each(|(_, v)| {
         *v /= sample_size as f64;
@@ -41,13 +44,242 @@ pub fn permutation_method(game: &Game, sample_size: usize) -> ShapleyValues {
     shapley_values
 }
 
+/// Estimate Shapley values by sampling `sample_size` random permutations of the owners.
+///
+/// The samples are split into fixed-size batches, each with its own RNG derived from `seed`,
+/// so that the estimation is reproducible regardless of the number of threads.
+pub fn cal_sv_permutation_sampling(game: &Game, sample_size: usize, seed: u64) -> ShapleyValues {
+    assert!(sample_size > 0, "sample size must be positive");
+
+    let owners: Vec<OwnerId> = game.owner_set.iter().copied().collect();
+    let imp_lens: Vec<usize> = game.dnf.iter().map(|imp| imp.len()).collect();
+    let mut owner_imps: Vec<Vec<usize>> = vec![vec![]; owners.len()];
+    for (j, imp) in game.dnf.iter().enumerate() {
+        for owner in imp.iter() {
+            if let Ok(i) = owners.binary_search(owner) {
+                owner_imps[i].push(j);
+            }
+        }
+    }
+
+    let pivot_counts = sample_pivot_counts(owners.len(), sample_size, seed, |order| {
+        pivotal_owner(order, &owner_imps, &imp_lens)
+    });
+    counts_to_sv(owners, pivot_counts, sample_size)
+}
+
+/// Monte Carlo estimation of Shapley values over `samples` random permutations of the owners,
+/// where the pivotal owner of each permutation is found by `Game::is_winning`.
+///
+/// It is deterministic given `seed`, like `cal_sv_permutation_sampling`, which is faster but
+/// specialized to the implicants of the DNF.
+pub fn cal_sv_monte_carlo(game: &Game, samples: usize, seed: u64) -> ShapleyValues {
+    assert!(samples > 0, "sample size must be positive");
+
+    let owners: Vec<OwnerId> = game.owner_set.iter().copied().collect();
+    let pivot_counts = sample_pivot_counts(owners.len(), samples, seed, |order| {
+        if game.is_winning(&BTreeSet::new()) {
+            return None;
+        }
+        let mut coalition = BTreeSet::new();
+        order.iter().copied().find(|&i| {
+            coalition.insert(owners[i]);
+            game.is_winning(&coalition)
+        })
+    });
+    counts_to_sv(owners, pivot_counts, samples)
+}
+
+/// Count how many times each owner, indexed in `0..n`, is `pivotal` in `sample_size` random
+/// orders.
+fn sample_pivot_counts(
+    n: usize,
+    sample_size: usize,
+    seed: u64,
+    pivotal: impl Fn(&[usize]) -> Option<usize> + Sync,
+) -> Vec<usize> {
+    sample_orders(n, sample_size, seed, n, |order, counts| {
+        if let Some(i) = pivotal(order) {
+            counts[i] += 1;
+        }
+    })
+}
+
+/// Same as `sample_pivot_counts`, but by the number of owners before the pivotal one, at index
+/// `j * n + i` for owner `i` after `j` others, e.g., to estimate how many coalitions of each size
+/// an owner is pivotal for.
+pub(crate) fn sample_pivot_positions(
+    n: usize,
+    sample_size: usize,
+    seed: u64,
+    pivotal: impl Fn(&[usize]) -> Option<usize> + Sync,
+) -> Vec<usize> {
+    sample_orders(n, sample_size, seed, n * n, |order, counts| {
+        if let Some(i) = pivotal(order) {
+            let j = order.iter().position(|&o| o == i).unwrap();
+            counts[j * n + i] += 1;
+        }
+    })
+}
+
+/// `count` each of `sample_size` random orders of `0..n` into `len` counters.
+///
+/// The samples are split into fixed-size batches, each with its own RNG derived from `seed`.
+fn sample_orders(
+    n: usize,
+    sample_size: usize,
+    seed: u64,
+    len: usize,
+    count: impl Fn(&[usize], &mut [usize]) + Sync,
+) -> Vec<usize> {
+    (0..sample_size.div_ceil(SAMPLE_BATCH_SIZE))
+        .into_par_iter()
+        .map(|batch| {
+            let batch_seed = seed ^ (batch as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            let mut rng = StdRng::seed_from_u64(batch_seed);
+            let batch_size = cmp::min(SAMPLE_BATCH_SIZE, sample_size - batch * SAMPLE_BATCH_SIZE);
+            let mut order: Vec<usize> = (0..n).collect();
+            let mut counts = vec![0_usize; len];
+
+            for _ in 0..batch_size {
+                order.shuffle(&mut rng);
+                count(&order, &mut counts);
+            }
+
+            counts
+        })
+        .reduce(
+            || vec![0; len],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                a
+            },
+        )
+}
+
+fn counts_to_sv(owners: Vec<OwnerId>, counts: Vec<usize>, sample_size: usize) -> ShapleyValues {
+    owners
+        .into_iter()
+        .zip(counts)
+        .map(|(owner, count)| (owner, count as f64 / sample_size as f64))
+        .collect()
+}
+
+/// Add the owners one by one following `order`, and return the one who turns the coalition into
+/// a winning one.
+pub(crate) fn pivotal_owner(
+    order: &[usize],
+    owner_imps: &[Vec<usize>],
+    imp_lens: &[usize],
+) -> Option<usize> {
+    // no one is pivotal if the empty coalition is already winning
+    if imp_lens.contains(&0) {
+        return None;
+    }
+
+    let mut missing = imp_lens.to_vec();
+    for &i in order {
+        for &j in &owner_imps[i] {
+            missing[j] -= 1;
+            if missing[j] == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::test_method;
+    use crate::{
+        alg::synthesis_sv::recursive_decompose_ablation::{
+            cal_sv_recursive_decompose_ablation, AblationType,
+        },
+        dnf,
+        par::ThreadPoolBuilder,
+        tests::{assert_f64_eq, test_method},
+    };
 
     #[test]
     fn test() {
         test_method(|game| permutation_method(game, 100), false);
     }
+
+    #[test]
+    fn test_permutation_sampling() {
+        test_method(|game| cal_sv_permutation_sampling(game, 1000, 0), false);
+
+        let game = Game::from_dnf(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+        );
+        let actual = cal_sv_permutation_sampling(&game, 20_000, 42);
+        for ablation_type in [
+            AblationType::NoVertical,
+            AblationType::NoHorizontal,
+            AblationType::NoHybrid,
+        ] {
+            let expect = cal_sv_recursive_decompose_ablation(&game, ablation_type);
+            assert_eq!(expect.len(), actual.len());
+            for (o, u) in &expect {
+                assert!(
+                    (u - actual[o]).abs() < 0.02,
+                    "owner: {o}, expect: {u}, actual: {}",
+                    actual[o]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_permutation_sampling_reproducible() {
+        let game = Game::from_dnf(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+        );
+        let expect = cal_sv_permutation_sampling(&game, 5000, 7);
+        assert_eq!(expect, cal_sv_permutation_sampling(&game, 5000, 7));
+
+        let pool = ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let actual = pool.install(|| cal_sv_permutation_sampling(&game, 5000, 7));
+        assert_eq!(expect, actual);
+    }
+
+    #[test]
+    fn test_monte_carlo() {
+        test_method(|game| cal_sv_monte_carlo(game, 1000, 0), false);
+
+        let game = Game::from_dnf(dnf!(1 2 3).map_variable(|id| OwnerId(*id)));
+        let sv = cal_sv_monte_carlo(&game, 30_000, 42);
+        for o in [1, 2, 3] {
+            assert!((0.33333333333 - sv[&OwnerId(o)]).abs() < 0.02);
+        }
+        assert_f64_eq(1., sv.values().sum());
+        assert_eq!(sv, cal_sv_monte_carlo(&game, 30_000, 42));
+
+        // the same permutations as `cal_sv_permutation_sampling`
+        let game = Game::from_dnf(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+        );
+        assert_eq!(
+            cal_sv_permutation_sampling(&game, 5000, 7),
+            cal_sv_monte_carlo(&game, 5000, 7)
+        );
+    }
+
+    #[test]
+    fn test_pivot_positions() {
+        // 0 and 1 together, or 2 alone
+        let owner_imps = vec![vec![0], vec![0], vec![1]];
+        let imp_lens = vec![2, 1];
+        let pivotal = |order: &[usize]| pivotal_owner(order, &owner_imps, &imp_lens);
+        let positions = sample_pivot_positions(3, 1000, 3, pivotal);
+        let counts = sample_pivot_counts(3, 1000, 3, pivotal);
+        for i in 0..3 {
+            assert_eq!(counts[i], (0..3).map(|j| positions[j * 3 + i]).sum::<usize>());
+        }
+        // 0 and 1 are never pivotal first, and 2 never last
+        assert_eq!(0, positions[0] + positions[1]);
+        assert_eq!(0, positions[8]);
+        assert_eq!(1000, positions.iter().sum::<usize>());
+    }
 }
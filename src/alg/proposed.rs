@@ -14,6 +14,6 @@ mod tests {
 
     #[test]
     fn test_recursive_decompose() {
-        test_method(|game| proposed_method(game), true);
+        test_method(proposed_method, true);
     }
 }
@@ -1,3 +1,20 @@
+pub mod attribution;
+pub mod batch;
+mod computer;
+pub mod decompose_tree;
+mod error;
 pub mod iec;
+#[cfg(feature = "serde-index")]
+pub mod index;
+pub mod interaction;
+mod options;
 pub mod recursive_decompose;
 pub mod recursive_decompose_ablation;
+pub mod session;
+pub mod topk;
+
+pub use computer::ShapleyComputer;
+pub use error::SvError;
+pub use options::{
+    CancelToken, LeafFallback, ProgressCallback, ProgressEvent, ProgressPhase, SvOptions,
+};
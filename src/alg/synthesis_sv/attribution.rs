@@ -0,0 +1,233 @@
+use super::{
+    decompose_tree::{install_watched, DecomposeTree, TreeContext},
+    iec::*,
+    SvError, SvOptions,
+};
+use crate::{Game, OwnerId};
+use std::collections::HashMap;
+
+/// Kind of a node of a decomposition tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    Var,
+    And,
+    Or,
+    Hybrid,
+    Leaf,
+}
+
+/// A node on the path from the root of the decomposition tree to an owner, with the part of the
+/// Shapley value of the owner that is decided there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeContribution {
+    /// Index of the child taken at each node from the root, i.e., empty for the root.
+    pub path: Vec<usize>,
+    pub kind: NodeKind,
+    /// Value of the owner if it played the sub-expression of the node alone, e.g., 1 at the root.
+    /// At the Var or Leaf node of the owner, it is the Shapley value of the owner.
+    pub partial_value: f64,
+    /// `partial_value` minus that of the parent, i.e., how much the owner gains (usually a
+    /// negative amount, which goes to its siblings) at the node, or `partial_value` at the root.
+    pub contribution: f64,
+}
+
+/// Shapley value of each owner broken down along the decomposition tree, from the root to the
+/// Var or Leaf node of the owner, e.g., to explain which sub-expressions take a share of its
+/// value.
+///
+/// The contributions of an owner sum up to its Shapley value, as the partial values telescope.
+/// Null players, and all the owners of a game that always or never wins, have no contributions.
+///
+/// Panic if a coefficient overflows. See `try_cal_sv_with_attribution`.
+pub fn cal_sv_with_attribution(game: &Game) -> HashMap<OwnerId, Vec<NodeContribution>> {
+    try_cal_sv_with_attribution(game, &SvOptions::default()).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Same as `cal_sv_with_attribution`, but with the given options, and return an error instead of
+/// panicking when a coefficient overflows or a leaf is beyond `options.max_leaf_unions` without
+/// `options.leaf_fallback`, e.g., `SvError::Cancelled` after `options.timeout`. The progress
+/// events of `options.progress` stop after building the tree.
+pub fn try_cal_sv_with_attribution(
+    game: &Game,
+    options: &SvOptions,
+) -> Result<HashMap<OwnerId, Vec<NodeContribution>>, SvError> {
+    let ctx = TreeContext {
+        options: options.clone(),
+        ..Default::default()
+    };
+    try_attribution(game, &ctx)
+}
+
+fn try_attribution(
+    game: &Game,
+    ctx: &TreeContext,
+) -> Result<HashMap<OwnerId, Vec<NodeContribution>>, SvError> {
+    let mut ans = HashMap::new();
+    if !game.dnf.is_false() && !game.dnf.is_true() {
+        ans = install_watched(ctx, |ctx| {
+            let tree = DecomposeTree::build(game, ctx)?;
+            let mut ans = HashMap::new();
+            let mut path = vec![];
+            let mut trail = vec![];
+            attribute(
+                &tree,
+                &vertical_identity(),
+                &mut path,
+                &mut trail,
+                &mut ans,
+                ctx,
+            )?;
+            Ok(ans)
+        })?;
+    }
+    for owner_id in game.owner_set.iter() {
+        ans.entry(*owner_id).or_default();
+    }
+    Ok(ans)
+}
+
+/// Add the contributions of the owners of `tree`, whose gamma map is `gamma_map`, to `ans`, after
+/// those of the nodes above it in `trail`.
+fn attribute(
+    tree: &DecomposeTree,
+    gamma_map: &IECoeffs,
+    path: &mut Vec<usize>,
+    trail: &mut Vec<NodeContribution>,
+    ans: &mut HashMap<OwnerId, Vec<NodeContribution>>,
+    ctx: &TreeContext,
+) -> Result<(), SvError> {
+    ctx.check_cancelled()?;
+    let parent_value = trail.last().map_or(0., |c| c.partial_value);
+    let contribution = |kind, partial_value: f64| NodeContribution {
+        path: path.clone(),
+        kind,
+        partial_value,
+        contribution: partial_value - parent_value,
+    };
+    let node_value = IECoeffs::monomial(1, 1).checked_mul(gamma_map)?.to_sv();
+    let kind = match tree {
+        DecomposeTree::Var(owner_id) => {
+            let mut contributions = trail.clone();
+            contributions.push(contribution(NodeKind::Var, node_value));
+            ans.insert(*owner_id, contributions);
+            return Ok(());
+        }
+        DecomposeTree::Leaf { .. } => {
+            for (owner_id, coeffs) in tree.cal_coeffs_for(gamma_map, None, ctx)? {
+                let mut contributions = trail.clone();
+                contributions.push(contribution(NodeKind::Leaf, coeffs.to_sv()));
+                ans.insert(owner_id, contributions);
+            }
+            return Ok(());
+        }
+        DecomposeTree::And { .. } => NodeKind::And,
+        DecomposeTree::Or { .. } => NodeKind::Or,
+        DecomposeTree::Hybrid { .. } => NodeKind::Hybrid,
+    };
+    trail.push(contribution(kind, node_value));
+    for (i, child) in tree.children().iter().enumerate() {
+        path.push(i);
+        let child_gamma_map = tree.child_gamma_map(i, gamma_map)?;
+        attribute(child, &child_gamma_map, path, trail, ans, ctx)?;
+        path.pop();
+    }
+    trail.pop();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::synthesis_sv::{
+            decompose_tree::AblationType, recursive_decompose::cal_sv_recursive_decompose,
+            CancelToken,
+        },
+        dnf,
+        tests::{assert_f64_eq, game},
+        Dnf, OwnerSet,
+    };
+
+    #[test]
+    fn test_attribution() {
+        let games = [
+            game(dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5)),
+            game(dnf!(1 2 + 1 3 + 2 3 + 4 5 + 6 7 8)),
+            game(dnf!(1 2 3 + 2 3 4 + 3 4 5 + 1 5 + 6 7 + 6 8 + 7 8 9 10 11 12)),
+            game(dnf!(1 2)),
+            game(dnf!(1 2 + 1 3 + 4)),
+        ];
+        for game in games {
+            let sv = cal_sv_recursive_decompose(&game);
+            for ablation_type in [None, Some(AblationType::NoHybrid)] {
+                let ctx = TreeContext::new(ablation_type);
+                let attribution = try_attribution(&game, &ctx).unwrap();
+                assert_eq!(sv.len(), attribution.len());
+                for (owner_id, contributions) in &attribution {
+                    let root = &contributions[0];
+                    assert!(root.path.is_empty());
+                    if contributions.len() > 1 {
+                        assert_f64_eq(1., root.partial_value);
+                    }
+                    for (depth, c) in contributions.iter().enumerate() {
+                        assert_eq!(depth, c.path.len());
+                    }
+                    let last = contributions.last().unwrap();
+                    assert!(matches!(last.kind, NodeKind::Var | NodeKind::Leaf));
+                    assert_f64_eq(sv[owner_id], last.partial_value);
+                    assert_f64_eq(
+                        sv[owner_id],
+                        contributions.iter().map(|c| c.contribution).sum(),
+                    );
+                }
+            }
+        }
+
+        // Or(And(1, Or(2, 3)), 4)
+        let contributions = &cal_sv_with_attribution(&game(dnf!(1 2 + 1 3 + 4)))[&OwnerId(2)];
+        let kinds: Vec<_> = contributions.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            vec![NodeKind::Or, NodeKind::And, NodeKind::Or, NodeKind::Var],
+            kinds
+        );
+        // a hybrid node of 3 inputs
+        let contributions = &cal_sv_with_attribution(&game(dnf!(1 2 + 1 3 + 2 3)))[&OwnerId(1)];
+        assert_eq!(NodeKind::Hybrid, contributions[0].kind);
+        assert_f64_eq(1. / 3., contributions[1].partial_value);
+        assert_f64_eq(1. / 3. - 1., contributions[1].contribution);
+    }
+
+    #[test]
+    fn test_attribution_null_players() {
+        let game = Game::new(
+            dnf!(1 2).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=3),
+        )
+        .unwrap();
+        let attribution = cal_sv_with_attribution(&game);
+        assert!(attribution[&OwnerId(3)].is_empty());
+        assert_f64_eq(0.5, attribution[&OwnerId(1)].last().unwrap().partial_value);
+
+        let game = Game::from_dnf(Dnf::true_exp());
+        assert!(cal_sv_with_attribution(&game).values().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn test_attribution_cancelled() {
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let options = SvOptions {
+            cancel: Some(cancel),
+            ..Default::default()
+        };
+        let game = game(dnf!(1 2 + 1 3 + 2 3 + 4));
+        assert_eq!(
+            Err(SvError::Cancelled),
+            try_cal_sv_with_attribution(&game, &options)
+        );
+        assert_eq!(
+            cal_sv_with_attribution(&game),
+            try_cal_sv_with_attribution(&game, &SvOptions::default()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Shapley values of many games at once.
+
+use super::{
+    decompose_tree::{try_cal_owner_coeffs_for, AblationType, TreeContext},
+    SvOptions,
+};
+use crate::{par::prelude::*, Game, OwnerId, ShapleyValues};
+use std::collections::HashMap;
+
+/// Number of games whose Shapley values are added up by a single task in order, so that the sums
+/// are the same whatever the number of threads.
+const AGGREGATE_CHUNK: usize = 64;
+
+/// Number of chunks of games buffered at once by `cal_sv_batch_aggregate_iter`.
+const STREAM_CHUNKS: usize = 64;
+
+/// Shapley values of each game in `games` in order, with one kind of decomposition disabled as in
+/// `cal_sv_recursive_decompose_ablation`.
+///
+/// The games are processed in parallel, one rayon task per game, while each game is decomposed
+/// sequentially. For a large batch of small games (e.g., tens of thousands of 4-player games),
+/// this is faster than computing the games one by one with the parallelism inside each game,
+/// whose rayon jobs cost more than the tiny nodes of a decomposition. For a few large games,
+/// computing them one by one is faster, as the batch cannot use more threads than games.
+///
+/// Panic if a coefficient overflows.
+pub fn cal_sv_batch(games: &[Game], ablation_type: AblationType) -> Vec<ShapleyValues> {
+    let ctx = batch_context(Some(ablation_type));
+    games
+        .par_iter()
+        .map(|game| {
+            let mut sv = ShapleyValues::new();
+            add_sv(game, &ctx, &mut sv);
+            sv
+        })
+        .collect()
+}
+
+/// How the Shapley values of an owner across the games of a batch are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Sum,
+    /// The sum divided by the number of games, including those the owner is not in.
+    Mean,
+}
+
+/// Shapley value of each owner across all the `games`, e.g., the games of the output tuples of a
+/// query over the same owners, combined by `agg`.
+///
+/// Owners missing from some games count as null players of them, i.e., with a value of 0. As in
+/// `cal_sv_batch`, the games are processed in parallel and each sequentially. Each task adds up
+/// the values of a chunk of consecutive games into its own map, and the chunks are then added up
+/// in order, so the result is the same in every run.
+///
+/// Panic if a coefficient overflows.
+pub fn cal_sv_batch_aggregate(games: &[Game], agg: Aggregation) -> ShapleyValues {
+    let mut total = ShapleyValues::new();
+    add_chunks(games, &batch_context(None), &mut total);
+    aggregate(total, games.len(), agg)
+}
+
+/// Same as `cal_sv_batch_aggregate`, but consume the games from an iterator, of which only a
+/// bounded number are in memory at once. The result is exactly that of `cal_sv_batch_aggregate`
+/// on the same games.
+pub fn cal_sv_batch_aggregate_iter(
+    games: impl IntoIterator<Item = Game>,
+    agg: Aggregation,
+) -> ShapleyValues {
+    let ctx = batch_context(None);
+    let mut games = games.into_iter();
+    let mut buffer = Vec::with_capacity(AGGREGATE_CHUNK * STREAM_CHUNKS);
+    let (mut total, mut len) = (ShapleyValues::new(), 0);
+    loop {
+        buffer.clear();
+        buffer.extend(games.by_ref().take(AGGREGATE_CHUNK * STREAM_CHUNKS));
+        if buffer.is_empty() {
+            break;
+        }
+        len += buffer.len();
+        add_chunks(&buffer, &ctx, &mut total);
+    }
+    aggregate(total, len, agg)
+}
+
+/// Games are decomposed sequentially, as they are processed in parallel.
+fn batch_context(ablation_type: Option<AblationType>) -> TreeContext<'static> {
+    TreeContext {
+        ablation_type,
+        options: SvOptions {
+            parallel_threshold: usize::MAX,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Add the Shapley values of a game to `sv`.
+fn add_sv(game: &Game, ctx: &TreeContext, sv: &mut HashMap<OwnerId, f64>) {
+    let coeffs = try_cal_owner_coeffs_for(game, ctx, None).unwrap_or_else(|e| panic!("{e}"));
+    for (owner_id, coeffs) in coeffs {
+        *sv.entry(owner_id).or_default() += coeffs.to_sv();
+    }
+}
+
+/// Add the Shapley values of the games to `total`, by chunks of `AGGREGATE_CHUNK` games.
+fn add_chunks(games: &[Game], ctx: &TreeContext, total: &mut ShapleyValues) {
+    let sums: Vec<HashMap<OwnerId, f64>> = games
+        .par_chunks(AGGREGATE_CHUNK)
+        .map(|chunk| {
+            let mut sum = HashMap::new();
+            for game in chunk {
+                add_sv(game, ctx, &mut sum);
+            }
+            sum
+        })
+        .collect();
+    for sum in sums {
+        for (owner_id, u) in sum {
+            *total.entry(owner_id).or_default() += u;
+        }
+    }
+}
+
+fn aggregate(mut total: ShapleyValues, len: usize, agg: Aggregation) -> ShapleyValues {
+    if agg == Aggregation::Mean && len > 0 {
+        total.values_mut().for_each(|u| *u /= len as f64);
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::{
+            exact::cal_sv_exact,
+            synthesis_sv::{
+                recursive_decompose::cal_sv_recursive_decompose,
+                recursive_decompose_ablation::cal_sv_recursive_decompose_ablation,
+            },
+        },
+        dnf,
+        gen::GameGenerator,
+        tests::assert_f64_eq,
+    };
+
+    #[test]
+    fn test_cal_sv_batch() {
+        let gen = GameGenerator {
+            num_owners: 6,
+            num_implicants: 5,
+            ..Default::default()
+        };
+        let games: Vec<_> = gen.games().take(50).collect();
+        for ablation_type in [
+            AblationType::NoHorizontal,
+            AblationType::NoVertical,
+            AblationType::NoHybrid,
+        ] {
+            let batch = cal_sv_batch(&games, ablation_type);
+            assert_eq!(games.len(), batch.len());
+            for (game, actual) in games.iter().zip(&batch) {
+                let expect = cal_sv_recursive_decompose_ablation(game, ablation_type);
+                let exact = cal_sv_exact(game);
+                assert_eq!(expect.len(), actual.len());
+                for (o, u) in actual {
+                    assert_f64_eq(expect[o], *u);
+                    assert_f64_eq(exact[o], *u);
+                }
+            }
+        }
+
+        assert!(cal_sv_batch(&[], AblationType::NoHybrid).is_empty());
+    }
+
+    #[test]
+    fn test_cal_sv_batch_aggregate() {
+        // disjoint supports but for owner 3
+        let games = [dnf!(1 2 + 1 3 + 2 3), dnf!(3 4 + 5)]
+            .map(|exp| Game::from_dnf(exp.map_variable(|id| OwnerId(*id))));
+        let individual: Vec<_> = games.iter().map(cal_sv_recursive_decompose).collect();
+
+        let sum = cal_sv_batch_aggregate(&games, Aggregation::Sum);
+        assert_eq!(5, sum.len());
+        for owner_id in (1..=5).map(OwnerId) {
+            let expect =
+                individual[0].get_or_zero(&owner_id) + individual[1].get_or_zero(&owner_id);
+            assert_eq!(expect, sum[&owner_id]);
+        }
+        let mean = cal_sv_batch_aggregate(&games, Aggregation::Mean);
+        for (owner_id, u) in sum.iter() {
+            assert_eq!(u / 2., mean[owner_id]);
+        }
+        assert_eq!(sum, cal_sv_batch_aggregate_iter(games, Aggregation::Sum));
+
+        // more games than a chunk, in the same order either way
+        let gen = GameGenerator {
+            num_owners: 8,
+            num_implicants: 4,
+            ..Default::default()
+        };
+        let games: Vec<_> = gen.games().take(3 * AGGREGATE_CHUNK + 5).collect();
+        let mean = cal_sv_batch_aggregate(&games, Aggregation::Mean);
+        assert_eq!(
+            mean,
+            cal_sv_batch_aggregate_iter(games.iter().cloned(), Aggregation::Mean)
+        );
+        for (owner_id, u) in mean.iter() {
+            let expect: f64 = games
+                .iter()
+                .map(|game| cal_sv_recursive_decompose(game).get_or_zero(owner_id))
+                .sum();
+            assert_f64_eq(expect / games.len() as f64, *u);
+        }
+
+        assert!(cal_sv_batch_aggregate(&[], Aggregation::Mean).is_empty());
+        assert!(cal_sv_batch_aggregate_iter([], Aggregation::Sum).is_empty());
+    }
+}
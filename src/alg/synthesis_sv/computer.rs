@@ -0,0 +1,157 @@
+use super::{
+    decompose_tree::{try_cal_sv_exact_with, try_cal_sv_with, AblationType},
+    SvError, SvOptions,
+};
+use crate::{ExactShapleyValues, Game, ShapleyValues};
+
+/// Shapley values of a game by the recursive decomposition, configured by chained calls. It is
+/// the entry point to the algorithm, and the place for new options.
+///
+/// The defaults are the same as `cal_sv_recursive_decompose`: the full decomposition in `f64`
+/// with `SvOptions::default()`, of which the `cal_sv_recursive_decompose*` functions are
+/// shorthands.
+///
+/// ```
+/// use shapley_value_decomposition::{dnf, Game, OwnerId, ShapleyComputer};
+///
+/// // owner 1 and either of owners 2 and 3
+/// let game = Game::from_dnf(dnf!(1 2 + 1 3).map_variable(|id| OwnerId(*id)));
+/// let sv = ShapleyComputer::new(&game)
+///     .exact_rationals(true)
+///     .compute()
+///     .unwrap();
+/// assert_eq!(2. / 3., sv[&OwnerId(1)]);
+/// assert_eq!(1. / 6., sv[&OwnerId(2)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShapleyComputer<'a> {
+    game: &'a Game,
+    ablation_type: Option<AblationType>,
+    exact_rationals: bool,
+    options: SvOptions,
+}
+
+impl<'a> ShapleyComputer<'a> {
+    pub fn new(game: &'a Game) -> Self {
+        Self {
+            game,
+            ablation_type: None,
+            exact_rationals: false,
+            options: SvOptions::default(),
+        }
+    }
+
+    /// Disable one kind of decomposition, as in `cal_sv_recursive_decompose_ablation`, or none.
+    pub fn ablation(mut self, ablation_type: Option<AblationType>) -> Self {
+        self.ablation_type = ablation_type;
+        self
+    }
+
+    /// Sum up the value of each owner in exact rational numbers and only round the result to
+    /// `f64`, which is off by at most half an ulp. `SvOptions::leaf_fallback` is ignored then.
+    pub fn exact_rationals(mut self, exact_rationals: bool) -> Self {
+        self.exact_rationals = exact_rationals;
+        self
+    }
+
+    pub fn options(mut self, options: SvOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Compute the values, or fail with an error as `try_cal_sv_recursive_decompose_with`.
+    pub fn compute(&self) -> Result<ShapleyValues, SvError> {
+        if self.exact_rationals {
+            return Ok(self.compute_exact()?.to_f64());
+        }
+        try_cal_sv_with(self.game, self.ablation_type, &self.options)
+    }
+
+    /// Compute the values in exact rational numbers, whatever `exact_rationals`.
+    pub fn compute_exact(&self) -> Result<ExactShapleyValues, SvError> {
+        try_cal_sv_exact_with(self.game, self.ablation_type, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::synthesis_sv::{
+            recursive_decompose::{cal_sv_recursive_decompose, cal_sv_recursive_decompose_exact},
+            recursive_decompose_ablation::cal_sv_recursive_decompose_ablation,
+        },
+        dnf,
+        tests::assert_f64_eq,
+        OwnerId, OwnerSet,
+    };
+
+    fn games() -> Vec<Game> {
+        vec![
+            Game::from_dnf(
+                dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            ),
+            Game::from_dnf(dnf!(1 2 3 + 1 2 4).map_variable(|id| OwnerId(*id))),
+            Game::new(
+                dnf!(1 2 + 1 3 + 4).map_variable(|id| OwnerId(*id)),
+                OwnerSet::from_iter(1..=6),
+            )
+            .unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_defaults() {
+        for game in games() {
+            let sv = ShapleyComputer::new(&game).compute().unwrap();
+            assert_eq!(cal_sv_recursive_decompose(&game), sv);
+            assert_eq!(
+                cal_sv_recursive_decompose_exact(&game),
+                ShapleyComputer::new(&game).compute_exact().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_ablation_and_exact() {
+        for game in games() {
+            for ablation_type in [
+                AblationType::NoHorizontal,
+                AblationType::NoVertical,
+                AblationType::NoHybrid,
+            ] {
+                let expect = cal_sv_recursive_decompose_ablation(&game, ablation_type);
+                let computer = ShapleyComputer::new(&game).ablation(Some(ablation_type));
+                assert_eq!(expect, computer.compute().unwrap());
+
+                let exact = computer.exact_rationals(true).compute().unwrap();
+                assert_eq!(expect.len(), exact.len());
+                for (o, u) in &exact {
+                    assert_f64_eq(expect[o], *u);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_options() {
+        // a single leaf without horizontal decomposition
+        let games = games();
+        let game = &games[2];
+        let options = SvOptions {
+            max_leaf_unions: 1,
+            ..Default::default()
+        };
+        let computer = ShapleyComputer::new(game)
+            .ablation(Some(AblationType::NoHorizontal))
+            .options(options);
+        assert!(matches!(
+            computer.compute(),
+            Err(SvError::LeafTooLarge { .. })
+        ));
+        assert!(matches!(
+            computer.exact_rationals(true).compute(),
+            Err(SvError::LeafTooLarge { .. })
+        ));
+    }
+}
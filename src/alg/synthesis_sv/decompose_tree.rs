@@ -0,0 +1,2064 @@
+use super::{
+    iec::*, CancelToken, LeafFallback, ProgressCallback, ProgressEvent, ProgressPhase, SvError,
+    SvOptions,
+};
+use crate::{
+    alg::permutation::{pivotal_owner, sample_pivot_positions},
+    dnf::{
+        dense::{DenseIndex, MAX_DENSE_VARIABLES},
+        recursive_decompose, Dnf, RecursiveDecompose,
+    },
+    par::prelude::*,
+    product_tree::{try_all_products_linear, ProductTree},
+    union_combination::*,
+    utils::try_hashmap_reduce,
+    ExactShapleyValues, Game, OwnerId, ShapleyValues,
+};
+use clap::ValueEnum;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{self, Write},
+    hash::Hash,
+    mem,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, ValueEnum, Serialize, Deserialize)]
+pub enum AblationType {
+    NoHorizontal,
+    NoVertical,
+    NoHybrid,
+}
+
+/// Statistics of the decomposition tree of a game. See `DecomposeTree::stats`.
+pub fn decompose_stats(game: &Game, ablation_type: Option<AblationType>) -> DecomposeStats {
+    DecomposeTree::from_game(game, ablation_type).stats()
+}
+
+/// Summarize how a game is decomposed, e.g., to find out why it is slow.
+pub fn analyze_decomposition(
+    game: &Game,
+    ablation_type: Option<AblationType>,
+) -> DecompositionSummary {
+    let tree = DecomposeTree::from_game(game, ablation_type);
+    let mut leaf_variables = vec![];
+    tree.visit(&mut |node| {
+        if let DecomposeTree::Leaf { exp, .. } = node {
+            leaf_variables.push(exp.variable_count());
+        }
+    });
+    DecompositionSummary {
+        stats: tree.stats(),
+        leaf_variables,
+    }
+}
+
+/// Summary of the decomposition tree of a game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecompositionSummary {
+    pub stats: DecomposeStats,
+    /// Number of variables in each leaf, in pre-order of the tree.
+    pub leaf_variables: Vec<usize>,
+}
+
+/// Statistics of a decomposition tree.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecomposeStats {
+    /// Number of nodes on the longest path from the root to a node without children.
+    pub depth: usize,
+    pub var_nodes: usize,
+    pub and_nodes: usize,
+    pub or_nodes: usize,
+    pub hybrid_nodes: usize,
+    pub leaf_nodes: usize,
+    pub max_leaf_implicants: usize,
+    pub total_leaf_implicants: usize,
+    /// Maximum size of the `UnionCombination` generated for the expression of a leaf. A leaf with
+    /// more unions than `SvOptions::max_leaf_unions` is not enumerated, and counts as the upper
+    /// bound `2^n - 1` of its `n` implicants instead.
+    pub max_leaf_unions: usize,
+    /// Number of leaves with more unions than `SvOptions::max_leaf_unions`.
+    pub large_leaves: usize,
+}
+
+impl DecomposeStats {
+    fn merge_child(&mut self, child: Self) {
+        self.depth = cmp::max(self.depth, child.depth + 1);
+        self.var_nodes += child.var_nodes;
+        self.and_nodes += child.and_nodes;
+        self.or_nodes += child.or_nodes;
+        self.hybrid_nodes += child.hybrid_nodes;
+        self.leaf_nodes += child.leaf_nodes;
+        self.max_leaf_implicants = cmp::max(self.max_leaf_implicants, child.max_leaf_implicants);
+        self.total_leaf_implicants += child.total_leaf_implicants;
+        self.max_leaf_unions = cmp::max(self.max_leaf_unions, child.max_leaf_unions);
+        self.large_leaves += child.large_leaves;
+    }
+}
+
+impl fmt::Display for DecomposeStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "depth: {}, nodes: {} var / {} and / {} or / {} hybrid / {} leaf, \
+             leaf implicants: {} max / {} total, max leaf unions: {}, large leaves: {}",
+            self.depth,
+            self.var_nodes,
+            self.and_nodes,
+            self.or_nodes,
+            self.hybrid_nodes,
+            self.leaf_nodes,
+            self.max_leaf_implicants,
+            self.total_leaf_implicants,
+            self.max_leaf_unions,
+            self.large_leaves
+        )
+    }
+}
+
+/// Coefficients of each owner in the game. Owners not in the DNF are null players with empty
+/// coefficients.
+///
+/// Panic if a coefficient overflows. See `try_cal_owner_coeffs`.
+pub(crate) fn cal_owner_coeffs(
+    game: &Game,
+    ablation_type: Option<AblationType>,
+) -> HashMap<OwnerId, IECoeffs> {
+    try_cal_owner_coeffs(game, ablation_type).unwrap_or_else(|e| panic!("{e}"))
+}
+
+pub(crate) fn try_cal_owner_coeffs(
+    game: &Game,
+    ablation_type: Option<AblationType>,
+) -> Result<HashMap<OwnerId, IECoeffs>, SvError> {
+    try_cal_owner_coeffs_for(game, &TreeContext::new(ablation_type), None)
+}
+
+/// Same as `cal_owner_coeffs`, along with the coefficients of the probability that the game wins,
+/// i.e., those of the root, which is built as a subtree for them.
+pub(crate) fn cal_owner_and_game_coeffs(game: &Game) -> (HashMap<OwnerId, IECoeffs>, IECoeffs) {
+    let exp = game.dnf.minimized();
+    let (mut ans, game_coeffs) = if exp.is_false() {
+        (HashMap::new(), IECoeffs::default())
+    } else if exp.is_true() {
+        (HashMap::new(), IECoeffs::monomial(0, 1))
+    } else {
+        let tree =
+            DecomposeTree::subtree(&exp, &TreeContext::default()).unwrap_or_else(|e| panic!("{e}"));
+        let owner_coeffs = tree
+            .cal_coeffs(&IECoeffs::monomial(0, 1))
+            .unwrap_or_else(|e| panic!("{e}"));
+        (owner_coeffs, tree.coeffs().clone())
+    };
+    add_null_owners(&mut ans, game, None);
+    (ans, game_coeffs)
+}
+
+/// Shapley values of the game by the decomposition with `options`, where a leaf beyond
+/// `options.max_leaf_unions` is estimated by `options.leaf_fallback` if set.
+pub(crate) fn try_cal_sv_with(
+    game: &Game,
+    ablation_type: Option<AblationType>,
+    options: &SvOptions,
+) -> Result<ShapleyValues, SvError> {
+    let ctx = TreeContext {
+        ablation_type,
+        options: options.clone(),
+        ..Default::default()
+    };
+    options.install(|| {
+        Ok(try_cal_owner_coeffs_for(game, &ctx, None)?
+            .into_par_iter()
+            .map(|(owner_id, coeffs)| (owner_id, coeffs.to_sv()))
+            .collect())
+    })
+}
+
+/// Same as `try_cal_sv_with`, but in exact rational arithmetic. `options.leaf_fallback` is
+/// ignored, as an estimate is not exact.
+pub(crate) fn try_cal_sv_exact_with(
+    game: &Game,
+    ablation_type: Option<AblationType>,
+    options: &SvOptions,
+) -> Result<ExactShapleyValues, SvError> {
+    let ctx = TreeContext {
+        ablation_type,
+        options: SvOptions {
+            leaf_fallback: None,
+            ..options.clone()
+        },
+        ..Default::default()
+    };
+    options.install(|| {
+        let values: BTreeMap<_, _> = try_cal_owner_coeffs_for(game, &ctx, None)?
+            .into_par_iter()
+            .map(|(owner_id, coeffs)| (owner_id, coeffs.to_sv_exact()))
+            .collect();
+        Ok(values.into())
+    })
+}
+
+/// Coefficients of the owners in `targets` (or all the owners if `None`) that are in the game.
+pub(crate) fn try_cal_owner_coeffs_for(
+    game: &Game,
+    ctx: &TreeContext,
+    targets: Option<&BTreeSet<OwnerId>>,
+) -> Result<HashMap<OwnerId, IECoeffs>, SvError> {
+    // no owner is ever pivotal in a game that always or never wins, e.g., an unsatisfiable
+    // subgame, which cannot be decomposed
+    let mut ans = if game.dnf.is_false() || game.dnf.is_true() {
+        HashMap::new()
+    } else if let Some(veto_players) = unanimity_owners(&game.dnf) {
+        // the veto players are pivotal exactly when all the others of them have joined
+        veto_players
+            .iter()
+            .filter(|owner_id| targets.is_none_or(|t| t.contains(*owner_id)))
+            .map(|owner_id| (*owner_id, IECoeffs::monomial(veto_players.len(), 1)))
+            .collect()
+    } else {
+        install_watched(ctx, |ctx| {
+            let tree = DecomposeTree::build(game, ctx)?;
+            if let Some(progress) = ctx.progress {
+                progress.set_phase(ProgressPhase::Sv);
+            }
+            let gamma_map = IECoeffs::monomial(0, 1);
+            tree.cal_coeffs_for(&gamma_map, targets, ctx)
+        })?
+    };
+    add_null_owners(&mut ans, game, targets);
+    Ok(ans)
+}
+
+/// Run `f` in the pool of `ctx.options`, with a context tracking the progress and the timeout of
+/// the options from now on. The timeout is ignored on wasm32, which has no thread to run the
+/// watchdog on.
+pub(crate) fn install_watched<R: Send>(
+    ctx: &TreeContext,
+    f: impl FnOnce(&TreeContext) -> Result<R, SvError> + Send,
+) -> Result<R, SvError> {
+    let progress = ctx.options.progress.as_ref().map(LeafProgress::new);
+    #[cfg(not(target_arch = "wasm32"))]
+    let watchdog = ctx.options.timeout.map(Watchdog::new);
+    let ctx = &TreeContext {
+        progress: progress.as_ref(),
+        #[cfg(not(target_arch = "wasm32"))]
+        timeout: watchdog.as_ref().map(|w| &w.token),
+        ..ctx.clone()
+    };
+    ctx.options.install(|| f(ctx))
+}
+
+/// Build the tree of a game, as `try_cal_owner_coeffs_for` does before evaluating it.
+#[cfg(feature = "serde-index")]
+pub(crate) fn try_build_tree(game: &Game, ctx: &TreeContext) -> Result<DecomposeTree, SvError> {
+    ctx.options.install(|| DecomposeTree::build(game, ctx))
+}
+
+/// Same as `try_cal_owner_coeffs_for`, but with the `tree` of the game from `try_build_tree`.
+#[cfg(feature = "serde-index")]
+pub(crate) fn try_cal_tree_owner_coeffs_for(
+    game: &Game,
+    tree: &DecomposeTree,
+    ctx: &TreeContext,
+    targets: Option<&BTreeSet<OwnerId>>,
+) -> Result<HashMap<OwnerId, IECoeffs>, SvError> {
+    let mut ans = ctx
+        .options
+        .install(|| tree.cal_coeffs_for(&IECoeffs::monomial(0, 1), targets, ctx))?;
+    add_null_owners(&mut ans, game, targets);
+    Ok(ans)
+}
+
+/// Add empty coefficients for the owners in `targets` that are null players, i.e., not in `ans`.
+fn add_null_owners(
+    ans: &mut HashMap<OwnerId, IECoeffs>,
+    game: &Game,
+    targets: Option<&BTreeSet<OwnerId>>,
+) {
+    for owner_id in game.owner_set.iter() {
+        if targets.is_none_or(|t| t.contains(owner_id)) {
+            ans.entry(*owner_id).or_default();
+        }
+    }
+}
+
+/// Merge the coefficients of owners from different subtrees, whose keys are disjoint.
+fn merge_coeffs<K: Eq + Hash>(
+    a: HashMap<K, IECoeffs>,
+    b: HashMap<K, IECoeffs>,
+) -> Result<HashMap<K, IECoeffs>, SvError> {
+    Ok(try_hashmap_reduce(a, b, IECoeffs::checked_add_owned)?)
+}
+
+/// The veto players of `exp` if they win by themselves, i.e., the minimized DNF is a single
+/// implicant, which covers dictators. All the other owners are null players.
+fn unanimity_owners(exp: &Dnf<OwnerId>) -> Option<BTreeSet<OwnerId>> {
+    let mut imps = exp.iter();
+    let mut veto_players = imps.next()?.0.clone();
+    for imp in imps {
+        veto_players.retain(|owner_id| imp.contains(owner_id));
+    }
+    // every implicant is a superset of the veto players, so only they can be of the same length
+    exp.iter()
+        .any(|imp| imp.len() == veto_players.len())
+        .then_some(veto_players)
+}
+
+/// How a decomposition tree is built and evaluated.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TreeContext<'a> {
+    pub ablation_type: Option<AblationType>,
+    /// Cache of the coefficients of subtrees, which may be shared across games.
+    pub cache: Option<&'a CoeffsCache>,
+    /// Non-root subtrees of other trees by `DecomposeTree::nodes_by_key`, built with the same
+    /// `ablation_type`, which are reused instead of being built again.
+    pub reuse: Option<&'a HashMap<String, &'a DecomposeTree>>,
+    pub options: SvOptions,
+    /// Progress of the computation if `options.progress` is set.
+    pub progress: Option<&'a LeafProgress>,
+    /// Cancelled by the watchdog of `options.timeout` if set.
+    pub timeout: Option<&'a CancelToken>,
+}
+
+impl TreeContext<'_> {
+    pub fn new(ablation_type: Option<AblationType>) -> Self {
+        Self {
+            ablation_type,
+            ..Default::default()
+        }
+    }
+
+    fn advance_progress(&self, leaves: usize) {
+        if let Some(progress) = self.progress {
+            progress.advance(leaves);
+        }
+    }
+
+    /// Fail with `SvError::Cancelled` if `options.cancel` or the timeout is cancelled.
+    pub(crate) fn check_cancelled(&self) -> Result<(), SvError> {
+        let cancelled = |token: Option<&CancelToken>| token.is_some_and(|t| t.is_cancelled());
+        if cancelled(self.options.cancel.as_ref()) || cancelled(self.timeout) {
+            return Err(SvError::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+/// Thread cancelling `token` after a timeout, unless the watchdog is dropped before, which
+/// disconnects the channel it waits on.
+#[cfg(not(target_arch = "wasm32"))]
+struct Watchdog {
+    token: CancelToken,
+    _done: mpsc::Sender<()>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Watchdog {
+    fn new(timeout: Duration) -> Self {
+        let token = CancelToken::new();
+        let (done, wait) = mpsc::channel();
+        thread::spawn({
+            let token = token.clone();
+            move || {
+                if let Err(RecvTimeoutError::Timeout) = wait.recv_timeout(timeout) {
+                    token.cancel();
+                }
+            }
+        });
+        Self { token, _done: done }
+    }
+}
+
+/// Number of leaves done of a tree, shared by the rayon workers building and evaluating it. See
+/// `ProgressEvent`.
+#[derive(Debug)]
+pub(crate) struct LeafProgress {
+    callback: ProgressCallback,
+    done: AtomicUsize,
+    total: AtomicUsize,
+    /// The current phase and the last number of leaves done reported, locked while calling back
+    /// so that the events are in order.
+    reported: Mutex<(ProgressPhase, usize)>,
+}
+
+impl LeafProgress {
+    fn new(callback: &ProgressCallback) -> Self {
+        Self {
+            callback: callback.clone(),
+            done: AtomicUsize::new(0),
+            total: AtomicUsize::new(0),
+            reported: Mutex::new((ProgressPhase::Decompose, 0)),
+        }
+    }
+
+    /// Report the end of the decomposition into `leaves` leaves, and start building the tree.
+    fn set_leaves(&self, leaves: usize) {
+        self.total.store(2 * leaves, Ordering::Relaxed);
+        let mut reported = self.reported.lock().unwrap();
+        self.report(ProgressPhase::Decompose, 0);
+        reported.0 = ProgressPhase::Build;
+    }
+
+    fn set_phase(&self, phase: ProgressPhase) {
+        self.reported.lock().unwrap().0 = phase;
+    }
+
+    fn advance(&self, leaves: usize) {
+        if leaves == 0 {
+            return;
+        }
+        let leaves_done = self.done.fetch_add(leaves, Ordering::Relaxed) + leaves;
+        let mut reported = self.reported.lock().unwrap();
+        // a larger count may have been reported by another worker in the meantime
+        if leaves_done > reported.1 {
+            reported.1 = leaves_done;
+            self.report(reported.0, leaves_done);
+        }
+    }
+
+    fn report(&self, phase: ProgressPhase, leaves_done: usize) {
+        (self.callback.0)(ProgressEvent {
+            phase,
+            leaves_done,
+            leaves_total: self.total.load(Ordering::Relaxed),
+        });
+    }
+}
+
+/// Number of Var and Leaf nodes of the tree built from `d`, where the disabled kind of nodes are
+/// leaves.
+fn count_leaves(d: &RecursiveDecompose<OwnerId>, ablation_type: Option<AblationType>) -> usize {
+    let mut ans = 0;
+    let mut stack = vec![d];
+    while let Some(d) = stack.pop() {
+        match d {
+            RecursiveDecompose::And(children)
+                if ablation_type != Some(AblationType::NoVertical) =>
+            {
+                stack.extend(children)
+            }
+            RecursiveDecompose::Or(children)
+                if ablation_type != Some(AblationType::NoHorizontal) =>
+            {
+                stack.extend(children)
+            }
+            RecursiveDecompose::Hybrid { sub_exps, .. }
+                if ablation_type != Some(AblationType::NoHybrid) =>
+            {
+                stack.extend(sub_exps)
+            }
+            _ => ans += 1,
+        }
+    }
+    ans
+}
+
+/// Thread-safe cache of the IE coefficients of subtrees, which only depend on the structure of a
+/// subtree up to renaming its owners. See `DecomposeTree::from_game_cached`.
+///
+/// Only Hybrid and Leaf nodes are cached, as the coefficients of And and Or nodes are cheap
+/// products of their children. The children of a node never share owners, so a sub-expression
+/// repeated in a game is always renamed, e.g., the same join under different branches over
+/// different tuples, and only this cache shares it. Each occurrence still gets its own gamma map
+/// when the coefficients of its owners are computed.
+#[derive(Debug, Default)]
+pub struct CoeffsCache {
+    coeffs: DashMap<String, IECoeffs>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl CoeffsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of subtrees whose coefficients are reused.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of subtrees whose coefficients are computed.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.coeffs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+
+    /// The cached coefficients of `key`, or those computed by `f`, which are only cached on
+    /// success.
+    fn get_or_try_insert_with<E>(
+        &self,
+        key: &str,
+        f: impl FnOnce() -> Result<IECoeffs, E>,
+    ) -> Result<IECoeffs, E> {
+        if let Some(coeffs) = self.coeffs.get(key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(coeffs.clone());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let coeffs = f()?;
+        self.coeffs.insert(key.to_string(), coeffs.clone());
+        Ok(coeffs)
+    }
+}
+
+/// A canonical string of the structure of a decomposition, which is equal for two decompositions
+/// if they are the same up to renaming the variables and reordering the children of And and Or.
+fn shape_key(d: &RecursiveDecompose<OwnerId>) -> String {
+    decompose_key(d, false)
+}
+
+/// Same as `shape_key`, but with the owners of the Var nodes, so that it is only equal for two
+/// decompositions of the same sub-expression. See `DecomposeTree::nodes_by_key`.
+fn exact_key(d: &RecursiveDecompose<OwnerId>) -> String {
+    decompose_key(d, true)
+}
+
+fn decompose_key(d: &RecursiveDecompose<OwnerId>, with_owners: bool) -> String {
+    post_order_keys(
+        d,
+        |d| match d {
+            RecursiveDecompose::Var(_) => &[][..],
+            RecursiveDecompose::And(children) | RecursiveDecompose::Or(children) => children,
+            RecursiveDecompose::Hybrid { sub_exps, .. } => sub_exps,
+        },
+        |d, child_keys| {
+            let kind = match d {
+                RecursiveDecompose::Var(id) => NodeKind::Var(*id),
+                RecursiveDecompose::And(_) => NodeKind::And,
+                RecursiveDecompose::Or(_) => NodeKind::Or,
+                RecursiveDecompose::Hybrid { hybrid_exp, .. } => NodeKind::Hybrid(hybrid_exp),
+            };
+            kind.key(child_keys, with_owners)
+        },
+        |_, _| {},
+    )
+}
+
+/// The kind of a node of a decomposition, from which its key is made.
+enum NodeKind<'a> {
+    Var(OwnerId),
+    And,
+    Or,
+    Hybrid(&'a Dnf<usize>),
+    Leaf(&'a Dnf<OwnerId>),
+}
+
+impl NodeKind<'_> {
+    fn key(self, mut child_keys: Vec<String>, with_owners: bool) -> String {
+        match self {
+            NodeKind::Var(id) if with_owners => format!("x{}", id.0),
+            NodeKind::Var(_) => "x".to_string(),
+            NodeKind::And | NodeKind::Or => {
+                child_keys.sort_unstable();
+                let op = if matches!(self, NodeKind::And) {
+                    "and"
+                } else {
+                    "or"
+                };
+                format!("{op}({})", child_keys.join(","))
+            }
+            NodeKind::Hybrid(hybrid_exp) => {
+                format!("hybrid[{hybrid_exp}]({})", child_keys.join(","))
+            }
+            NodeKind::Leaf(exp) => format!("leaf[{exp}]"),
+        }
+    }
+}
+
+/// The key of the root of a tree, computed by `key` from the node and the keys of its children
+/// in post-order, where each node is passed to `visit` along with its key.
+fn post_order_keys<'a, N>(
+    root: &'a N,
+    children: impl Fn(&'a N) -> &'a [N],
+    key: impl Fn(&'a N, Vec<String>) -> String,
+    mut visit: impl FnMut(&'a N, &str),
+) -> String {
+    // the keys of the children of a node are the last ones on `keys`
+    let mut stack = vec![(root, false)];
+    let mut keys: Vec<String> = vec![];
+    while let Some((node, visited)) = stack.pop() {
+        let node_children = children(node);
+        if !visited && !node_children.is_empty() {
+            stack.push((node, true));
+            stack.extend(node_children.iter().rev().map(|c| (c, false)));
+            continue;
+        }
+        let child_keys = keys.split_off(keys.len() - node_children.len());
+        let node_key = key(node, child_keys);
+        visit(node, &node_key);
+        keys.push(node_key);
+    }
+    keys.pop().expect("the key of the root")
+}
+
+/// Decomposition tree of a game, with the IE coefficients of each subtree.
+///
+/// Sub-expressions that are not decomposed further (e.g., due to an `AblationType`) are kept as
+/// `Leaf` nodes and computed by inclusion-exclusion over their implicants.
+///
+/// The `coeffs` of a node are those of the probability that its subtree is true, except that they
+/// are empty for the root of a game, whose coefficients are never needed and thus not computed.
+#[derive(Debug, Clone)]
+pub enum DecomposeTree {
+    Var(OwnerId),
+    And {
+        coeffs: IECoeffs,
+        products: Vec<IECoeffs>,
+        children: Vec<DecomposeTree>,
+    },
+    Or {
+        coeffs: IECoeffs,
+        products: Vec<IECoeffs>,
+        children: Vec<DecomposeTree>,
+    },
+    Hybrid {
+        coeffs: IECoeffs,
+        hybrid_coeffs: HybridCoeffs,
+        hybrid_exp: Dnf<usize>,
+        children: Vec<DecomposeTree>,
+    },
+    Leaf {
+        coeffs: IECoeffs,
+        exp: Dnf<OwnerId>,
+    },
+}
+
+/// Drop the nodes one by one rather than recursively, which would overflow the stack on a deep
+/// decomposition, as in `DecomposeTree::new`.
+impl Drop for DecomposeTree {
+    fn drop(&mut self) {
+        let mut stack = self.take_children();
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.take_children());
+        }
+    }
+}
+
+impl DecomposeTree {
+    /// Build the decomposition tree of a game. Its DNF is minimized first, so absorbed implicants
+    /// only cost a copy. A game that always or never wins is a single leaf without any owner.
+    ///
+    /// With `ablation_type`, the corresponding kind of decomposition is disabled.
+    ///
+    /// Panic if a coefficient overflows.
+    pub fn from_game(game: &Game, ablation_type: Option<AblationType>) -> Self {
+        Self::build(game, &TreeContext::new(ablation_type)).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Same as `from_game`, but reuse the coefficients of subtrees already computed in `cache`,
+    /// which may be shared across games.
+    pub fn from_game_cached(
+        game: &Game,
+        ablation_type: Option<AblationType>,
+        cache: &CoeffsCache,
+    ) -> Self {
+        let ctx = TreeContext {
+            cache: Some(cache),
+            ..TreeContext::new(ablation_type)
+        };
+        Self::build(game, &ctx).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    pub(crate) fn build(game: &Game, ctx: &TreeContext) -> Result<Self, SvError> {
+        // absorbed implicants do not change the game but would blow up the leaves
+        let exp = game.dnf.minimized();
+        // a game that always or never wins cannot be decomposed, and has no owner in its DNF
+        if exp.is_false() || exp.is_true() {
+            return Ok(Self::Leaf {
+                coeffs: IECoeffs::default(),
+                exp,
+            });
+        }
+        let d = recursive_decompose(&exp, &exp.all_variables());
+        if let Some(progress) = ctx.progress {
+            progress.set_leaves(count_leaves(&d, ctx.ablation_type));
+        }
+        Self::new(d, true, ctx)
+    }
+
+    /// Build the tree of a minimized, non-constant DNF as a subtree, i.e., with the IE coefficients
+    /// of its root computed so that it can be combined with other subtrees.
+    pub(crate) fn subtree(exp: &Dnf<OwnerId>, ctx: &TreeContext) -> Result<Self, SvError> {
+        let d = recursive_decompose(exp, &exp.all_variables());
+        Self::new(d, false, ctx)
+    }
+
+    /// Build the tree of `input` level by level from the bottom up, where the nodes of a level
+    /// are built in parallel. The levels are explicit lists of nodes rather than a recursion, which
+    /// would overflow the stack on a deep decomposition, e.g., of `x1 (x2 + x3 (x4 + ...))`.
+    fn new(
+        input: RecursiveDecompose<OwnerId>,
+        is_root: bool,
+        ctx: &TreeContext,
+    ) -> Result<Self, SvError> {
+        // the children of the nodes of a level are consecutive in the next level, in order
+        let mut levels: Vec<Vec<PendingNode>> = vec![];
+        let mut next = vec![input];
+        while !next.is_empty() {
+            // the coefficients of the root are never used
+            let is_root = is_root && levels.is_empty();
+            let split: Vec<_> = next
+                .into_par_iter()
+                .with_min_len(ctx.options.min_len())
+                .map(|input| PendingNode::split(input, is_root, ctx))
+                .collect::<Result<_, _>>()?;
+            let mut level = Vec::with_capacity(split.len());
+            next = vec![];
+            for (node, children) in split {
+                level.push(node);
+                next.extend(children);
+            }
+            levels.push(level);
+        }
+
+        let mut built: Vec<DecomposeTree> = vec![];
+        for level in levels.into_iter().rev() {
+            let mut children = built.into_iter();
+            let level: Vec<_> = level
+                .into_iter()
+                .map(|node| {
+                    let len = node.children_len();
+                    (node, children.by_ref().take(len).collect::<Vec<_>>())
+                })
+                .collect();
+            built = level
+                .into_par_iter()
+                .with_min_len(ctx.options.min_len())
+                .map(|(node, children)| node.build(children, ctx))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(built.pop().expect("the root is built"))
+    }
+
+    /// IE coefficients of the probability that the subtree is true, borrowed so that combining
+    /// the children of a node copies none of them. They are empty for the root of a game.
+    pub(crate) fn coeffs(&self) -> &IECoeffs {
+        static VAR_COEFFS: Lazy<IECoeffs> = Lazy::new(|| IECoeffs::monomial(1, 1));
+        match self {
+            DecomposeTree::Var(_) => &VAR_COEFFS,
+            DecomposeTree::And { coeffs, .. }
+            | DecomposeTree::Or { coeffs, .. }
+            | DecomposeTree::Hybrid { coeffs, .. }
+            | DecomposeTree::Leaf { coeffs, .. } => coeffs,
+        }
+    }
+
+    /// Coefficients of each owner, from which the Shapley value (or other indices) are derived.
+    pub(crate) fn cal_coeffs(
+        &self,
+        gamma_map: &IECoeffs,
+    ) -> Result<HashMap<OwnerId, IECoeffs>, SvError> {
+        self.cal_coeffs_for(gamma_map, None, &TreeContext::default())
+    }
+
+    /// Same as `cal_coeffs`, but only for the owners in `targets` if given. Subtrees without any
+    /// target only contribute their coefficients to the products and are never descended into.
+    ///
+    /// The gamma maps are passed down level by level, where the nodes of a level are evaluated in
+    /// parallel, over explicit lists of nodes rather than by recursion as in `DecomposeTree::new`.
+    pub(crate) fn cal_coeffs_for(
+        &self,
+        gamma_map: &IECoeffs,
+        targets: Option<&BTreeSet<OwnerId>>,
+        ctx: &TreeContext,
+    ) -> Result<HashMap<OwnerId, IECoeffs>, SvError> {
+        let mut ans = HashMap::new();
+        let mut level = vec![(self, gamma_map.clone())];
+        while !level.is_empty() {
+            let (coeffs, next) = level
+                .into_par_iter()
+                .with_min_len(ctx.options.min_len())
+                .map(|(node, gamma_map)| node.descend(&gamma_map, targets, ctx))
+                .try_reduce(
+                    || (HashMap::new(), vec![]),
+                    |(a, mut next), (b, b_next)| {
+                        next.extend(b_next);
+                        Ok((merge_coeffs(a, b)?, next))
+                    },
+                )?;
+            ans = merge_coeffs(ans, coeffs)?;
+            level = next;
+        }
+        Ok(ans)
+    }
+
+    /// The coefficients of the owners of this node in `targets` given its `gamma_map`, i.e., of
+    /// its Var children or itself, along with the children to descend into with their gamma maps.
+    fn descend(
+        &self,
+        gamma_map: &IECoeffs,
+        targets: Option<&BTreeSet<OwnerId>>,
+        ctx: &TreeContext,
+    ) -> Result<Descent<'_>, SvError> {
+        ctx.check_cancelled()?;
+        let options = &ctx.options;
+        let is_target = |owner_id: &OwnerId| targets.is_none_or(|t| t.contains(owner_id));
+        let has_target = |c: &DecomposeTree| targets.is_none_or(|t| c.has_any(t));
+        let next_gamma_maps = |filter: &(dyn Fn(&DecomposeTree) -> bool + Sync)| {
+            self.children()
+                .par_iter()
+                .with_min_len(options.min_len())
+                .enumerate()
+                .filter(|&(_, c)| filter(c))
+                .map(|(i, c)| -> Result<_, SvError> {
+                    Ok((c, self.child_gamma_map(i, gamma_map)?))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        match self {
+            DecomposeTree::Var(owner_id) if !is_target(owner_id) => Ok((HashMap::new(), vec![])),
+            DecomposeTree::Var(owner_id) => {
+                let map_group_with_owner = IECoeffs::monomial(1, 1);
+                let coeffs = map_group_with_owner.checked_mul(gamma_map)?;
+                ctx.advance_progress(1);
+                Ok((HashMap::from([(*owner_id, coeffs)]), vec![]))
+            }
+            DecomposeTree::And { children, .. } | DecomposeTree::Or { children, .. } => {
+                let var_children: Vec<_> = children
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, c)| match c {
+                        Self::Var(id) if is_target(id) => Some((i, id)),
+                        _ => None,
+                    })
+                    .collect();
+
+                let mut ans = HashMap::new();
+                if let Some((i, _)) = var_children.first() {
+                    let next_gamma_map = self.child_gamma_map(*i, gamma_map)?;
+                    let coeffs = IECoeffs::monomial(1, 1).checked_mul(&next_gamma_map)?;
+                    ctx.advance_progress(var_children.len());
+                    for (_, id) in var_children {
+                        ans.insert(*id, coeffs.clone());
+                    }
+                }
+
+                let next = next_gamma_maps(&|c| !matches!(c, Self::Var(_)) && has_target(c))?;
+                Ok((ans, next))
+            }
+            DecomposeTree::Hybrid { .. } => Ok((HashMap::new(), next_gamma_maps(&has_target)?)),
+            DecomposeTree::Leaf { exp, .. } => {
+                let targets: Vec<_> = exp.all_variables().into_iter().filter(is_target).collect();
+                let ans = match (
+                    leaf_owner_coeffs(exp, gamma_map, &targets, ctx),
+                    options.leaf_fallback,
+                ) {
+                    (
+                        Err(SvError::LeafTooLarge { .. } | SvError::TooManyCombinations { .. }),
+                        Some(fallback),
+                    ) => LeafSamples::new(exp, fallback).owner_coeffs(gamma_map, &targets)?,
+                    (ans, _) => ans?,
+                };
+                ctx.advance_progress(1);
+                Ok((ans, vec![]))
+            }
+        }
+    }
+
+    /// The gamma map passed down to the `i`-th child given the one of this node, i.e., the
+    /// coefficients of the events where the child is pivotal for the root.
+    ///
+    /// Panic for Var and Leaf nodes, which have no children.
+    pub(crate) fn child_gamma_map(
+        &self,
+        i: usize,
+        gamma_map: &IECoeffs,
+    ) -> Result<IECoeffs, CoeffOverflow> {
+        match self {
+            DecomposeTree::And { products, .. } => gamma_map.checked_mul(&products[i]),
+            DecomposeTree::Or { products, .. } => {
+                gamma_map.checked_sub(&gamma_map.checked_mul(&products[i])?)
+            }
+            DecomposeTree::Hybrid {
+                hybrid_coeffs,
+                hybrid_exp,
+                ..
+            } => {
+                let owner_set = BTreeSet::from([i]);
+                let exp_p2 = hybrid_exp.partial_eval(&owner_set, true);
+                let exp_p3 = hybrid_exp.partial_exp_complement(&owner_set);
+                let exp_p2_unions = exp_to_input_unions(&exp_p2);
+                let exp_p3_unions = exp_to_input_unions(&exp_p3);
+                let map_p2 = hybrid_coeffs.exp_unions_coeffs(&exp_p2_unions)?;
+                let iece_map =
+                    hybrid_coeffs.exp_unions_interaction(&exp_p2_unions, &exp_p3_unions)?;
+                gamma_map.checked_mul(&map_p2.checked_sub(&iece_map)?)
+            }
+            DecomposeTree::Var(_) | DecomposeTree::Leaf { .. } => {
+                panic!("Var and Leaf nodes have no children")
+            }
+        }
+    }
+
+    /// Coefficients of each pair of owners `(i, j)` with `i < j`, from which the Shapley
+    /// interaction index is derived, i.e., `p^2` times `gamma_map` times the second derivative
+    /// of the subtree by the two owners. Pairs with a zero derivative may be missing.
+    ///
+    /// A pair within a child is passed down to it with the gamma map of the child, as for single
+    /// owners. A pair split across two children `a` and `b` factorizes into the derivatives of
+    /// `a` and `b` by their owners and the second derivative of this node by `a` and `b`.
+    pub(crate) fn cal_pair_coeffs(
+        &self,
+        gamma_map: &IECoeffs,
+    ) -> Result<HashMap<(OwnerId, OwnerId), IECoeffs>, SvError> {
+        match self {
+            DecomposeTree::Var(_) => Ok(HashMap::new()),
+            DecomposeTree::Leaf { exp, .. } => {
+                let vars: Vec<_> = exp.all_variables().into_iter().collect();
+                let pair_gamma_map = IECoeffs::monomial(2, 1).checked_mul(gamma_map)?;
+                (0..vars.len())
+                    .into_par_iter()
+                    .flat_map(|a| (a + 1..vars.len()).into_par_iter().map(move |b| (a, b)))
+                    .map(|(a, b)| -> Result<_, SvError> {
+                        let (i, j) = (vars[a], vars[b]);
+                        let single = |o| BTreeSet::from([o]);
+                        let exp_11 = exp.partial_eval(&BTreeSet::from([i, j]), true);
+                        let exp_10 = exp.partial_eval(&single(i), true);
+                        let exp_10 = exp_10.partial_exp_complement(&single(j));
+                        let exp_01 = exp.partial_eval(&single(j), true);
+                        let exp_01 = exp_01.partial_exp_complement(&single(i));
+                        let exp_00 = exp.partial_exp_complement(&BTreeSet::from([i, j]));
+                        let derivative = leaf_exp_coeffs(&exp_11)?
+                            .checked_sub(&leaf_exp_coeffs(&exp_10)?)?
+                            .checked_sub(&leaf_exp_coeffs(&exp_01)?)?
+                            .checked_add(&leaf_exp_coeffs(&exp_00)?)?;
+                        Ok(((i, j), pair_gamma_map.checked_mul(&derivative)?))
+                    })
+                    .collect()
+            }
+            _ => {
+                let children = self.children();
+                // p times the derivative of each child by its owners
+                let derivatives: Vec<_> = children
+                    .par_iter()
+                    .map(|c| c.cal_coeffs(&vertical_identity()))
+                    .collect::<Result<_, _>>()?;
+
+                let within = children
+                    .par_iter()
+                    .enumerate()
+                    .filter(|(_, c)| !matches!(c, Self::Var(_)))
+                    .map(|(a, c)| c.cal_pair_coeffs(&self.child_gamma_map(a, gamma_map)?));
+
+                let across = (0..children.len())
+                    .into_par_iter()
+                    .map(|a| -> Result<_, SvError> {
+                        let factors = self.children_pair_factors(a)?;
+                        let mut ans = HashMap::new();
+                        for (b, factor) in factors.iter().enumerate().skip(a + 1) {
+                            for (i, d_i) in &derivatives[a] {
+                                // multiply the factors with p first, so that no constant is dropped
+                                let coeffs = d_i.checked_mul(gamma_map)?.checked_mul(factor)?;
+                                for (j, d_j) in &derivatives[b] {
+                                    let key = if i < j { (*i, *j) } else { (*j, *i) };
+                                    ans.insert(key, coeffs.checked_mul(d_j)?);
+                                }
+                            }
+                        }
+                        Ok(ans)
+                    });
+
+                within
+                    .chain(across)
+                    .try_reduce(HashMap::default, merge_coeffs)
+            }
+        }
+    }
+
+    /// The second derivative of this node by its `a`-th child and each of its children, which is
+    /// the identity for `a` itself.
+    ///
+    /// Panic for Var and Leaf nodes, which have no children.
+    fn children_pair_factors(&self, a: usize) -> Result<Vec<IECoeffs>, CoeffOverflow> {
+        fn others(children: &[DecomposeTree], a: usize) -> Vec<&IECoeffs> {
+            children
+                .iter()
+                .enumerate()
+                .filter(|(b, _)| *b != a)
+                .map(|(_, c)| c.coeffs())
+                .collect()
+        }
+        // products of all the other children except each one, with the identity put back at `a`
+        let products_except =
+            |others: Vec<&IECoeffs>,
+             identity_op: fn() -> IECoeffs,
+             product_op: fn(&IECoeffs, &IECoeffs, &mut IECoeffs) -> Result<(), CoeffOverflow>|
+             -> Result<_, CoeffOverflow> {
+                let mut products = if others.len() == 1 {
+                    vec![identity_op()]
+                } else {
+                    children_products(others, identity_op, product_op, true)?.0
+                };
+                products.insert(a, vertical_identity());
+                Ok(products)
+            };
+
+        match self {
+            // the product of the other children
+            DecomposeTree::And { children, .. } => {
+                products_except(others(children, a), vertical_identity, vertical_op_into)
+            }
+            // minus the product of the complements of the other children
+            DecomposeTree::Or { children, .. } => {
+                let mut products =
+                    products_except(others(children, a), horizontal_identity, horizontal_op_into)?;
+                for (b, p) in products.iter_mut().enumerate() {
+                    if b != a {
+                        *p = p.checked_sub(&vertical_identity())?;
+                    }
+                }
+                Ok(products)
+            }
+            DecomposeTree::Hybrid {
+                hybrid_coeffs,
+                hybrid_exp,
+                children,
+                ..
+            } => (0..children.len())
+                .map(|b| {
+                    if b == a {
+                        return Ok(vertical_identity());
+                    }
+                    let single = |c| BTreeSet::from([c]);
+                    let exp_11 = hybrid_exp.partial_eval(&BTreeSet::from([a, b]), true);
+                    let exp_10 = hybrid_exp.partial_eval(&single(a), true);
+                    let exp_10 = exp_10.partial_exp_complement(&single(b));
+                    let exp_01 = hybrid_exp.partial_eval(&single(b), true);
+                    let exp_01 = exp_01.partial_exp_complement(&single(a));
+                    let exp_00 = hybrid_exp.partial_exp_complement(&BTreeSet::from([a, b]));
+                    let coeffs = |exp: &Dnf<usize>| {
+                        if exp.is_false() {
+                            Ok(IECoeffs::default())
+                        } else if exp.is_true() {
+                            Ok(vertical_identity())
+                        } else {
+                            hybrid_coeffs.exp_coeffs(exp)
+                        }
+                    };
+                    coeffs(&exp_11)?
+                        .checked_sub(&coeffs(&exp_10)?)?
+                        .checked_sub(&coeffs(&exp_01)?)?
+                        .checked_add(&coeffs(&exp_00)?)
+                })
+                .collect(),
+            DecomposeTree::Var(_) | DecomposeTree::Leaf { .. } => {
+                panic!("Var and Leaf nodes have no children")
+            }
+        }
+    }
+
+    /// Whether any owner in `targets` appears in the subtree.
+    fn has_any(&self, targets: &BTreeSet<OwnerId>) -> bool {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            let found = match node {
+                DecomposeTree::Var(owner_id) => targets.contains(owner_id),
+                DecomposeTree::Leaf { exp, .. } => {
+                    exp.implicants().any(|t| !t.is_disjoint(targets))
+                }
+                _ => {
+                    stack.extend(node.children());
+                    false
+                }
+            };
+            if found {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Move the children out of the node, e.g., to drop them one by one.
+    fn take_children(&mut self) -> Vec<DecomposeTree> {
+        match self {
+            DecomposeTree::Var(_) | DecomposeTree::Leaf { .. } => vec![],
+            DecomposeTree::And { children, .. }
+            | DecomposeTree::Or { children, .. }
+            | DecomposeTree::Hybrid { children, .. } => mem::take(children),
+        }
+    }
+
+    /// The nodes of a subtree by the `exact_key` of their decompositions, to reuse them as is when
+    /// building a tree of the same sub-expressions, e.g., in `SvSession` after an edit. Leaf nodes
+    /// have keys of their own, which are never equal to those of a decomposition.
+    pub(crate) fn nodes_by_key(&self) -> HashMap<String, &DecomposeTree> {
+        let mut ans = HashMap::new();
+        post_order_keys(
+            self,
+            DecomposeTree::children,
+            |node, child_keys| {
+                let kind = match node {
+                    DecomposeTree::Var(id) => NodeKind::Var(*id),
+                    DecomposeTree::And { .. } => NodeKind::And,
+                    DecomposeTree::Or { .. } => NodeKind::Or,
+                    DecomposeTree::Hybrid { hybrid_exp, .. } => NodeKind::Hybrid(hybrid_exp),
+                    DecomposeTree::Leaf { exp, .. } => NodeKind::Leaf(exp),
+                };
+                kind.key(child_keys, true)
+            },
+            |node, key| {
+                ans.insert(key.to_string(), node);
+            },
+        );
+        ans
+    }
+
+    /// Whether the coefficients of each node are those that `build` computes from its children,
+    /// with empty ones at the root. The coefficients of a leaf are only checked to be those of a
+    /// probability by `IECoeffs::is_win_probability`, as computing them again would take as long
+    /// as building the tree.
+    ///
+    /// Panic unless the tree is well formed, e.g., a Hybrid node with as many inputs in
+    /// `hybrid_coeffs` and variables in `hybrid_exp` as children.
+    #[cfg(feature = "serde-index")]
+    pub(crate) fn coeffs_are_consistent(&self) -> bool {
+        let mut stack = vec![(self, true)];
+        while let Some((node, is_root)) = stack.pop() {
+            let children = node.children();
+            let children_coeffs: Vec<_> = children.iter().map(DecomposeTree::coeffs).collect();
+            let consistent = match node {
+                DecomposeTree::Var(_) => true,
+                DecomposeTree::And {
+                    coeffs, products, ..
+                } => children_products(
+                    children_coeffs,
+                    vertical_identity,
+                    vertical_op_into,
+                    is_root,
+                )
+                .is_ok_and(|(p, c)| p == *products && c == *coeffs),
+                DecomposeTree::Or {
+                    coeffs, products, ..
+                } => children_products(
+                    children_coeffs,
+                    horizontal_identity,
+                    horizontal_op_into,
+                    is_root,
+                )
+                .is_ok_and(|(p, c)| p == *products && c == *coeffs),
+                DecomposeTree::Hybrid {
+                    coeffs,
+                    hybrid_coeffs,
+                    hybrid_exp,
+                    ..
+                } => {
+                    HybridCoeffs::new(&children_coeffs).is_ok_and(|h| h == *hybrid_coeffs)
+                        && if is_root {
+                            coeffs.is_empty()
+                        } else {
+                            hybrid_coeffs
+                                .exp_coeffs(hybrid_exp)
+                                .is_ok_and(|c| c == *coeffs)
+                        }
+                }
+                DecomposeTree::Leaf { coeffs, exp } => {
+                    if is_root {
+                        coeffs.is_empty()
+                    } else {
+                        coeffs.is_win_probability(exp.variable_count())
+                    }
+                }
+            };
+            if !consistent {
+                return false;
+            }
+            stack.extend(children.iter().map(|c| (c, false)));
+        }
+        true
+    }
+
+    /// Children of the node, which are empty for Var and Leaf nodes.
+    pub fn children(&self) -> &[DecomposeTree] {
+        match self {
+            DecomposeTree::Var(_) | DecomposeTree::Leaf { .. } => &[],
+            DecomposeTree::And { children, .. }
+            | DecomposeTree::Or { children, .. }
+            | DecomposeTree::Hybrid { children, .. } => children,
+        }
+    }
+
+    /// Visit all the nodes in pre-order.
+    pub fn visit(&self, f: &mut impl FnMut(&DecomposeTree)) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            f(node);
+            stack.extend(node.children().iter().rev());
+        }
+    }
+
+    /// Statistics of the tree, with the unions of each leaf enumerated up to the default
+    /// `SvOptions::max_leaf_unions`.
+    pub fn stats(&self) -> DecomposeStats {
+        self.stats_with(&SvOptions::default())
+    }
+
+    /// Same as `stats`, but with the unions of each leaf enumerated up to
+    /// `options.max_leaf_unions`, and as bitsets up to `options.max_dense_leaf_variables`.
+    pub fn stats_with(&self, options: &SvOptions) -> DecomposeStats {
+        // nodes in pre-order with their parents, so each node is merged into its parent only after
+        // all of its descendants, which come later, are merged into it
+        let mut nodes: Vec<(&DecomposeTree, Option<usize>)> = vec![];
+        let mut stack = vec![(self, None)];
+        while let Some((node, parent)) = stack.pop() {
+            let i = nodes.len();
+            nodes.push((node, parent));
+            stack.extend(node.children().iter().map(|c| (c, Some(i))));
+        }
+        let mut stats: Vec<_> = nodes
+            .iter()
+            .map(|(node, _)| node.node_stats(options))
+            .collect();
+        for (i, (_, parent)) in nodes.iter().enumerate().rev() {
+            if let Some(parent) = parent {
+                let child = mem::take(&mut stats[i]);
+                stats[*parent].merge_child(child);
+            }
+        }
+        stats.swap_remove(0)
+    }
+
+    /// Statistics of the node alone, without its children.
+    fn node_stats(&self, options: &SvOptions) -> DecomposeStats {
+        let mut ans = DecomposeStats {
+            depth: 1,
+            ..Default::default()
+        };
+        match self {
+            DecomposeTree::Var(_) => ans.var_nodes = 1,
+            DecomposeTree::Leaf { exp, .. } => {
+                ans.leaf_nodes = 1;
+                ans.max_leaf_implicants = exp.len();
+                ans.total_leaf_implicants = exp.len();
+                let index = leaf_index(exp, options);
+                // without `options.cancel` nor `options.timeout`, which are for computations
+                let ctx = TreeContext::default();
+                ans.max_leaf_unions =
+                    match leaf_exp_to_unions(exp, index.as_ref(), options.max_leaf_unions, &ctx) {
+                        Ok(unions) => unions.len(),
+                        Err(SvError::LeafTooLarge { .. }) => {
+                            ans.large_leaves = 1;
+                            // a union for each non-empty subset of the implicants at most
+                            1usize
+                                .checked_shl(exp.len() as u32)
+                                .map_or(usize::MAX, |n| n - 1)
+                        }
+                        Err(e) => unreachable!("unions of a leaf are only cancelled by ctx: {e}"),
+                    };
+            }
+            DecomposeTree::And { .. } => ans.and_nodes = 1,
+            DecomposeTree::Or { .. } => ans.or_nodes = 1,
+            DecomposeTree::Hybrid { .. } => ans.hybrid_nodes = 1,
+        }
+        ans
+    }
+
+    /// Export the tree in Graphviz DOT format for debugging, e.g., `dot -Tsvg tree.dot`.
+    ///
+    /// Each node shows its kind and number of children. Leaf nodes also show the number of
+    /// implicants and variables of their expressions, and Hybrid nodes show the size of their
+    /// hybrid expressions.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph DecomposeTree {\n");
+        // nodes are numbered in pre-order, and the edge to a child follows its subtree
+        let mut next_id = 0;
+        let mut stack = vec![DotStep::Node(self, None)];
+        while let Some(step) = stack.pop() {
+            match step {
+                DotStep::Node(node, parent) => {
+                    let id = next_id;
+                    next_id += 1;
+                    node.write_dot_node(id, &mut out);
+                    if let Some(parent) = parent {
+                        stack.push(DotStep::Edge(parent, id));
+                    }
+                    stack.extend(
+                        node.children()
+                            .iter()
+                            .rev()
+                            .map(|c| DotStep::Node(c, Some(id))),
+                    );
+                }
+                DotStep::Edge(parent, child) => {
+                    writeln!(out, "    n{parent} -> n{child};").unwrap();
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn write_dot_node(&self, id: usize, out: &mut String) {
+        let label = match self {
+            DecomposeTree::Var(owner_id) => format!("Var {owner_id}"),
+            DecomposeTree::And { children, .. } => format!("And\\nchildren: {}", children.len()),
+            DecomposeTree::Or { children, .. } => format!("Or\\nchildren: {}", children.len()),
+            DecomposeTree::Hybrid {
+                hybrid_exp,
+                children,
+                ..
+            } => format!(
+                "Hybrid\\nchildren: {}\\nhybrid_exp: {} implicants",
+                children.len(),
+                hybrid_exp.len()
+            ),
+            DecomposeTree::Leaf { exp, .. } => format!(
+                "Leaf\\nimplicants: {}\\nvariables: {}",
+                exp.len(),
+                exp.variable_count()
+            ),
+        };
+        writeln!(out, "    n{id} [label=\"{label}\"];").unwrap();
+    }
+}
+
+/// A step of `DecomposeTree::to_dot`: write a node with the id of its parent, or an edge.
+enum DotStep<'a> {
+    Node(&'a DecomposeTree, Option<usize>),
+    Edge(usize, usize),
+}
+
+#[derive(Debug, Clone)]
+struct LeafExpUnion {
+    input_set: LeafSet,
+    num_of_imp: usize,
+}
+
+/// The owner coefficients found at a node, and the children to descend into with their gamma maps.
+type Descent<'a> = (
+    HashMap<OwnerId, IECoeffs>,
+    Vec<(&'a DecomposeTree, IECoeffs)>,
+);
+
+/// A node of a decomposition whose children are not built yet, in `DecomposeTree::new`.
+enum PendingNode {
+    Var(OwnerId),
+    And {
+        is_root: bool,
+        children: usize,
+    },
+    Or {
+        is_root: bool,
+        children: usize,
+    },
+    /// `memo` is the `shape_key` of the node if there is a cache.
+    Hybrid {
+        is_root: bool,
+        memo: Option<String>,
+        hybrid_exp: Dnf<usize>,
+        children: usize,
+    },
+    Leaf {
+        is_root: bool,
+        memo: Option<String>,
+        exp: Dnf<OwnerId>,
+    },
+    /// A subtree of `ctx.reuse`, which is already built.
+    Reused(DecomposeTree),
+}
+
+impl PendingNode {
+    /// The node of `input` along with its children to build, where the disabled kind of nodes
+    /// are leaves.
+    fn split(
+        input: RecursiveDecompose<OwnerId>,
+        is_root: bool,
+        ctx: &TreeContext,
+    ) -> Result<(Self, Vec<RecursiveDecompose<OwnerId>>), SvError> {
+        ctx.check_cancelled()?;
+        if let Some(reuse) = ctx.reuse.filter(|_| !is_root) {
+            if let Some(tree) = reuse.get(&exact_key(&input)) {
+                return Ok((Self::Reused((*tree).clone()), vec![]));
+            }
+        }
+        let ablation_type = ctx.ablation_type;
+        // the coefficients of the root are never computed, let alone cached
+        let memo = match ctx.cache {
+            Some(_) if !is_root => Some(shape_key(&input)),
+            _ => None,
+        };
+        let node = match input {
+            RecursiveDecompose::Var(id) => (Self::Var(id), vec![]),
+            RecursiveDecompose::And(children)
+                if ablation_type != Some(AblationType::NoVertical) =>
+            {
+                let node = Self::And {
+                    is_root,
+                    children: children.len(),
+                };
+                (node, children)
+            }
+            RecursiveDecompose::Or(children)
+                if ablation_type != Some(AblationType::NoHorizontal) =>
+            {
+                let node = Self::Or {
+                    is_root,
+                    children: children.len(),
+                };
+                (node, children)
+            }
+            RecursiveDecompose::Hybrid {
+                hybrid_exp,
+                sub_exps,
+            } if ablation_type != Some(AblationType::NoHybrid) => {
+                let node = Self::Hybrid {
+                    is_root,
+                    memo,
+                    hybrid_exp,
+                    children: sub_exps.len(),
+                };
+                (node, sub_exps)
+            }
+            input => {
+                let node = Self::Leaf {
+                    is_root,
+                    memo,
+                    exp: input.expand(),
+                };
+                (node, vec![])
+            }
+        };
+        Ok(node)
+    }
+
+    fn children_len(&self) -> usize {
+        match self {
+            Self::Var(_) | Self::Leaf { .. } | Self::Reused(_) => 0,
+            Self::And { children, .. }
+            | Self::Or { children, .. }
+            | Self::Hybrid { children, .. } => *children,
+        }
+    }
+
+    /// The node of the tree over its built `children`.
+    fn build(
+        self,
+        children: Vec<DecomposeTree>,
+        ctx: &TreeContext,
+    ) -> Result<DecomposeTree, SvError> {
+        ctx.check_cancelled()?;
+        let mut children_coeffs = Vec::with_capacity(children.len());
+        for c in &children {
+            children_coeffs.push(c.coeffs());
+        }
+        let tree = match self {
+            Self::Var(id) => {
+                ctx.advance_progress(1);
+                DecomposeTree::Var(id)
+            }
+            Self::And { is_root, .. } => {
+                let (products, coeffs) = children_products(
+                    children_coeffs,
+                    vertical_identity,
+                    vertical_op_into,
+                    is_root,
+                )?;
+                DecomposeTree::And {
+                    coeffs,
+                    products,
+                    children,
+                }
+            }
+            Self::Or { is_root, .. } => {
+                let (products, coeffs) = children_products(
+                    children_coeffs,
+                    horizontal_identity,
+                    horizontal_op_into,
+                    is_root,
+                )?;
+                DecomposeTree::Or {
+                    coeffs,
+                    products,
+                    children,
+                }
+            }
+            Self::Hybrid {
+                is_root,
+                memo,
+                hybrid_exp,
+                ..
+            } => {
+                let hybrid_coeffs = HybridCoeffs::new(&children_coeffs)?;
+                let coeffs = if is_root {
+                    IECoeffs::default()
+                } else {
+                    memoized(memo.as_deref(), ctx, || {
+                        Ok(hybrid_coeffs.exp_coeffs(&hybrid_exp)?)
+                    })?
+                };
+                DecomposeTree::Hybrid {
+                    coeffs,
+                    hybrid_coeffs,
+                    hybrid_exp,
+                    children,
+                }
+            }
+            Self::Leaf { is_root, memo, exp } => {
+                let coeffs = if is_root {
+                    IECoeffs::default()
+                } else {
+                    memoized(memo.as_deref(), ctx, || {
+                        let index = leaf_index(&exp, &ctx.options);
+                        let exp_unions = leaf_exp_to_unions(
+                            &exp,
+                            index.as_ref(),
+                            ctx.options.max_leaf_unions,
+                            ctx,
+                        );
+                        match (exp_unions, ctx.options.leaf_fallback) {
+                            (Err(SvError::LeafTooLarge { .. }), Some(fallback)) => {
+                                Ok(LeafSamples::new(&exp, fallback).coeffs()?)
+                            }
+                            (exp_unions, _) => Ok(leaf_exp_unions_coeffs(&exp_unions?)?),
+                        }
+                    })?
+                };
+                ctx.advance_progress(1);
+                DecomposeTree::Leaf { coeffs, exp }
+            }
+            Self::Reused(tree) => tree,
+        };
+        Ok(tree)
+    }
+}
+
+/// The coefficients computed by `f`, or cached in `ctx.cache` by the `shape_key` of a node if
+/// there is a cache.
+fn memoized(
+    key: Option<&str>,
+    ctx: &TreeContext,
+    f: impl FnOnce() -> Result<IECoeffs, SvError>,
+) -> Result<IECoeffs, SvError> {
+    match (ctx.cache, key) {
+        (Some(cache), Some(key)) => cache.get_or_try_insert_with(key, f),
+        _ => f(),
+    }
+}
+
+/// Owners in a union of implicants of a leaf, as the bits of a `DenseIndex` of the variables of
+/// the leaf if it has few enough of them. All the sets of a leaf are of the same kind.
+#[derive(Debug, Clone)]
+enum LeafSet {
+    Dense(u128),
+    Sparse(BTreeSet<OwnerId>),
+}
+
+impl LeafSet {
+    fn new(imp: &BTreeSet<OwnerId>, index: Option<&DenseIndex<OwnerId>>) -> Self {
+        match index {
+            Some(index) => LeafSet::Dense(index.bits(imp)),
+            None => LeafSet::Sparse(imp.clone()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            LeafSet::Dense(bits) => bits.count_ones() as usize,
+            LeafSet::Sparse(set) => set.len(),
+        }
+    }
+
+    fn union(&self, other: &LeafSet) -> LeafSet {
+        match (self, other) {
+            (LeafSet::Dense(a), LeafSet::Dense(b)) => LeafSet::Dense(a | b),
+            (LeafSet::Sparse(a), LeafSet::Sparse(b)) => {
+                LeafSet::Sparse(a.union(b).copied().collect())
+            }
+            _ => unreachable!("dense and sparse sets of the same leaf"),
+        }
+    }
+
+    /// `self.union(other).len()`, without building the union of sparse sets.
+    fn union_len(&self, other: &LeafSet) -> usize {
+        match (self, other) {
+            (LeafSet::Dense(a), LeafSet::Dense(b)) => (a | b).count_ones() as usize,
+            (LeafSet::Sparse(a), LeafSet::Sparse(b)) => a.union(b).count(),
+            _ => unreachable!("dense and sparse sets of the same leaf"),
+        }
+    }
+}
+
+/// Index of the variables of a leaf for dense `LeafSet`s, unless it has more than
+/// `SvOptions::max_dense_leaf_variables` of them.
+fn leaf_index(exp: &Dnf<OwnerId>, options: &SvOptions) -> Option<DenseIndex<OwnerId>> {
+    DenseIndex::new(exp.all_variables(), options.max_dense_leaf_variables)
+}
+
+/// From this many children on, the products of all the children but one are computed from prefix
+/// and suffix products rather than a `ProductTree`, which takes n log n products.
+const LINEAR_PRODUCTS_MIN_CHILDREN: usize = 64;
+
+/// The products of all the children but one, and the product of all of them, which is left empty
+/// if `is_root`.
+fn children_products(
+    children_coeffs: Vec<&IECoeffs>,
+    identity_op: fn() -> IECoeffs,
+    product_op: fn(&IECoeffs, &IECoeffs, &mut IECoeffs) -> Result<(), CoeffOverflow>,
+    is_root: bool,
+) -> Result<(Vec<IECoeffs>, IECoeffs), CoeffOverflow> {
+    if children_coeffs.len() >= LINEAR_PRODUCTS_MIN_CHILDREN {
+        let (products, root) = try_all_products_linear(&children_coeffs, identity_op, product_op)?;
+        Ok((products, if is_root { IECoeffs::default() } else { root }))
+    } else {
+        let product_tree = ProductTree::try_new(children_coeffs, product_op, !is_root)?;
+        let products = product_tree.try_all_products(identity_op, product_op)?;
+        let root = if is_root {
+            IECoeffs::default()
+        } else {
+            product_tree.root()
+        };
+        Ok((products, root))
+    }
+}
+
+/// Unions of the implicants of a leaf for inclusion-exclusion.
+///
+/// Fail with `SvError::LeafTooLarge` once there are more than `max_unions` of them, or with
+/// `SvError::Cancelled` as soon as `ctx` is cancelled. The sets are dense with an `index` of the
+/// variables of `exp`.
+fn leaf_exp_to_unions(
+    exp: &Dnf<OwnerId>,
+    index: Option<&DenseIndex<OwnerId>>,
+    max_unions: usize,
+    ctx: &TreeContext,
+) -> Result<UnionCombination<LeafExpUnion>, SvError> {
+    let var_len = exp.variable_count();
+    let imp_list: Vec<_> = exp
+        .implicants()
+        .map(|imp| LeafSet::new(imp, index))
+        .collect();
+    UnionCombination::try_new(
+        imp_list.len(),
+        max_unions,
+        || SvError::LeafTooLarge {
+            implicants: imp_list.len(),
+            variables: var_len,
+        },
+        |i| LeafExpUnion {
+            num_of_imp: 1,
+            input_set: imp_list[i].clone(),
+        },
+        |old, i| {
+            ctx.check_cancelled()?;
+            let new_set = old.input_set.union(&imp_list[i]);
+            // whether new set is full and cur_id != MAX_ID
+            if new_set.len() == var_len && i != imp_list.len() - 1 {
+                Ok(None)
+            } else {
+                Ok(Some(LeafExpUnion {
+                    num_of_imp: old.num_of_imp + 1,
+                    input_set: new_set,
+                }))
+            }
+        },
+    )
+}
+
+/// Coefficients of each owner of a leaf in `targets`, given the `gamma_map` of the leaf, by
+/// inclusion-exclusion over the implicants of the leaf with the owner and those without it.
+fn leaf_owner_coeffs(
+    exp: &Dnf<OwnerId>,
+    gamma_map: &IECoeffs,
+    targets: &[OwnerId],
+    ctx: &TreeContext,
+) -> Result<HashMap<OwnerId, IECoeffs>, SvError> {
+    let options = &ctx.options;
+    let index = leaf_index(exp, options);
+    targets
+        .par_iter()
+        .with_min_len(options.min_len())
+        .map(|c| -> Result<_, SvError> {
+            let owner_set = BTreeSet::from([*c]);
+            let exp_p2 = exp.partial_eval(&owner_set, true);
+            let exp_p3 = exp.partial_exp_complement(&owner_set);
+
+            let exp_p2_unions =
+                leaf_exp_to_unions(&exp_p2, index.as_ref(), options.max_leaf_unions, ctx)?;
+            let map_p2 = leaf_exp_unions_coeffs(&exp_p2_unions)?;
+
+            let exp_p3_unions =
+                leaf_exp_to_unions(&exp_p3, index.as_ref(), options.max_leaf_unions, ctx)?;
+            let combinations = exp_p2_unions.len().saturating_mul(exp_p3_unions.len());
+            if options
+                .max_union_combinations
+                .is_some_and(|m| combinations > m)
+            {
+                return Err(SvError::TooManyCombinations {
+                    variables: exp.variable_count(),
+                    combinations,
+                });
+            }
+            let iece_map = leaf_exp_unions_interaction(&exp_p2_unions, &exp_p3_unions, ctx)?;
+
+            let next_gamma_map = if exp_p2.variable_count() == 0 {
+                gamma_map.checked_sub(&gamma_map.checked_mul(&iece_map)?)?
+            } else {
+                gamma_map.checked_mul(&map_p2.checked_sub(&iece_map)?)?
+            };
+
+            let map_group_with_owner = IECoeffs::monomial(1, 1);
+            let coeffs = map_group_with_owner.checked_mul(&next_gamma_map)?;
+            Ok(HashMap::from([(*c, coeffs)]))
+        })
+        .try_reduce(HashMap::default, merge_coeffs)
+}
+
+fn leaf_exp_unions_coeffs(
+    exp_unions: &UnionCombination<LeafExpUnion>,
+) -> Result<IECoeffs, CoeffOverflow> {
+    exp_unions
+        .0
+        .par_iter()
+        .map(|u| {
+            let u = u.get();
+            let sign = if u.num_of_imp % 2 == 0 { -1 } else { 1 };
+            Ok(IECoeffs::monomial(u.input_set.len(), sign))
+        })
+        .try_reduce(IECoeffs::default, IECoeffs::checked_add_owned)
+}
+
+/// Coefficients of the probability that `exp` is true, which is 1 if it is TRUE.
+fn leaf_exp_coeffs(exp: &Dnf<OwnerId>) -> Result<IECoeffs, SvError> {
+    let index = DenseIndex::new(exp.all_variables(), MAX_DENSE_VARIABLES);
+    let exp_unions = leaf_exp_to_unions(exp, index.as_ref(), usize::MAX, &TreeContext::default())?;
+    Ok(leaf_exp_unions_coeffs(&exp_unions)?)
+}
+
+fn leaf_exp_unions_interaction(
+    exp_unions1: &UnionCombination<LeafExpUnion>,
+    exp_unions2: &UnionCombination<LeafExpUnion>,
+    ctx: &TreeContext,
+) -> Result<IECoeffs, SvError> {
+    exp_unions1
+        .0
+        .par_iter()
+        .map(|u1| -> Result<_, SvError> {
+            ctx.check_cancelled()?;
+            let u1 = u1.get();
+            let coeffs = exp_unions2
+                .0
+                .par_iter()
+                .map(|u2| {
+                    let u2 = u2.get();
+                    let len = u1.input_set.union_len(&u2.input_set);
+                    let sign = if (u1.num_of_imp + u2.num_of_imp) % 2 == 0 {
+                        1
+                    } else {
+                        -1
+                    };
+                    Ok(IECoeffs::monomial(len, sign))
+                })
+                .try_reduce(IECoeffs::default, IECoeffs::checked_add_owned)?;
+            Ok(coeffs)
+        })
+        .try_reduce(IECoeffs::default, |a, b| Ok(a.checked_add_owned(b)?))
+}
+
+/// Monte Carlo estimate of a leaf beyond the limits of `SvOptions`, by `SvOptions::leaf_fallback`.
+struct LeafSamples {
+    variables: Vec<OwnerId>,
+    samples: usize,
+    /// How many times the `i`-th variable is pivotal after `j` others, at `j * n + i`.
+    pivots: Vec<usize>,
+}
+
+impl LeafSamples {
+    fn new(exp: &Dnf<OwnerId>, fallback: LeafFallback) -> Self {
+        assert!(fallback.samples > 0, "sample size must be positive");
+        let variables: Vec<OwnerId> = exp.all_variables().into_iter().collect();
+        let imp_lens: Vec<usize> = exp.implicants().map(|imp| imp.len()).collect();
+        let mut owner_imps: Vec<Vec<usize>> = vec![vec![]; variables.len()];
+        for (j, imp) in exp.implicants().enumerate() {
+            for owner_id in imp {
+                if let Ok(i) = variables.binary_search(owner_id) {
+                    owner_imps[i].push(j);
+                }
+            }
+        }
+        // leaves have disjoint variables, so each of them is sampled independently, yet the same
+        // way in every run
+        let seed = fallback.seed ^ u64::from(variables[0].0).wrapping_mul(0xD1B5_4A32_D192_ED03);
+        let pivots = sample_pivot_positions(variables.len(), fallback.samples, seed, |order| {
+            pivotal_owner(order, &owner_imps, &imp_lens)
+        });
+        Self {
+            variables,
+            samples: fallback.samples,
+            pivots,
+        }
+    }
+
+    /// Estimated coefficients of the probability that the leaf is true. The first `j` owners of
+    /// an order win if and only if the pivotal owner is among them.
+    fn coeffs(&self) -> Result<IECoeffs, CoeffOverflow> {
+        let n = self.variables.len();
+        let mut wins = 0;
+        let counts: Vec<f64> = (0..=n)
+            .map(|j| {
+                let count = binom_f64(n, j) * wins as f64 / self.samples as f64;
+                if j < n {
+                    wins += self.pivots[j * n..(j + 1) * n].iter().sum::<usize>();
+                }
+                count
+            })
+            .collect();
+        IECoeffs::from_size_counts(&counts)
+    }
+
+    /// Estimated coefficients of the probability that the `i`-th variable is pivotal among the
+    /// other `n - 1`. It is after `j` others in `1 / n` of the orders.
+    fn pivotal_coeffs(&self, i: usize) -> Result<IECoeffs, CoeffOverflow> {
+        let n = self.variables.len();
+        let counts: Vec<f64> = (0..n)
+            .map(|j| {
+                binom_f64(n - 1, j) * (n * self.pivots[j * n + i]) as f64 / self.samples as f64
+            })
+            .collect();
+        IECoeffs::from_size_counts(&counts)
+    }
+
+    /// Same as `leaf_owner_coeffs`, but estimated.
+    fn owner_coeffs(
+        &self,
+        gamma_map: &IECoeffs,
+        targets: &[OwnerId],
+    ) -> Result<HashMap<OwnerId, IECoeffs>, CoeffOverflow> {
+        // multiplied by the owner first, so that no constant term of `gamma_map` is dropped
+        let map_group_with_owner = IECoeffs::monomial(1, 1).checked_mul(gamma_map)?;
+        targets
+            .par_iter()
+            .map(|owner_id| {
+                let i = self
+                    .variables
+                    .binary_search(owner_id)
+                    .expect("targets are variables of the leaf");
+                let coeffs = map_group_with_owner.checked_mul(&self.pivotal_coeffs(i)?)?;
+                Ok((*owner_id, coeffs))
+            })
+            .collect()
+    }
+}
+
+/// `C(n, k)` in floating point, which is close enough to be rounded with the counts it scales.
+fn binom_f64(n: usize, k: usize) -> f64 {
+    (0..k).fold(1., |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dnf, par::ThreadPoolBuilder};
+    use num_rational::BigRational;
+    use num_traits::One;
+    use std::sync::Arc;
+
+    /// A chain of `depth` nested nodes over owners `0..=depth`, each with an owner and the rest of
+    /// the chain as children, alternating between And and Or if `alternating`.
+    fn deep_chain(depth: u32, alternating: bool) -> RecursiveDecompose<OwnerId> {
+        let mut d = RecursiveDecompose::Var(OwnerId(0));
+        for i in 1..=depth {
+            let children = vec![RecursiveDecompose::Var(OwnerId(i)), d];
+            d = if alternating && i % 2 == 1 {
+                RecursiveDecompose::Or(children)
+            } else {
+                RecursiveDecompose::And(children)
+            };
+        }
+        d
+    }
+
+    #[test]
+    fn test_deep_chain() {
+        let depth = 5000;
+        // only p^5001 is ever involved
+        let tree = DecomposeTree::new(
+            deep_chain(depth as u32, false),
+            true,
+            &TreeContext::default(),
+        )
+        .unwrap();
+        let coeffs = tree.cal_coeffs(&vertical_identity()).unwrap();
+        assert_eq!(depth + 1, coeffs.len());
+        for c in coeffs.values() {
+            assert_eq!(&IECoeffs::monomial(depth + 1, 1), c);
+        }
+
+        // only the owners of the top half, on the worker threads of a small pool
+        let targets = (depth as u32 / 2..=depth as u32).map(OwnerId).collect();
+        let ctx = TreeContext {
+            options: SvOptions {
+                thread_pool: Some(Arc::new(
+                    ThreadPoolBuilder::new().num_threads(2).build().unwrap(),
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let top = ctx
+            .options
+            .install(|| tree.cal_coeffs_for(&vertical_identity(), Some(&targets), &ctx))
+            .unwrap();
+        assert_eq!(top.len(), targets.len());
+        assert!(top.iter().all(|(owner_id, c)| coeffs[owner_id] == *c));
+        drop(tree);
+
+        // the coefficients grow exponentially with the alternations and overflow ~65 levels above
+        // the bottom, which is only reached after splitting all the levels
+        #[cfg(not(feature = "bigint"))]
+        {
+            let tree = DecomposeTree::new(
+                deep_chain(depth as u32, true),
+                true,
+                &TreeContext::default(),
+            );
+            assert!(matches!(tree, Err(SvError::Overflow { .. })));
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let game = Game::from_dnf(dnf!(1 2 + 1 3 + 4).map_variable(|id| OwnerId(*id)));
+        // Or(And(1, Or(2, 3)), 4)
+        let stats = decompose_stats(&game, None);
+        assert_eq!(
+            DecomposeStats {
+                depth: 4,
+                var_nodes: 4,
+                and_nodes: 1,
+                or_nodes: 2,
+                ..Default::default()
+            },
+            stats
+        );
+        assert_eq!(
+            "depth: 4, nodes: 4 var / 1 and / 2 or / 0 hybrid / 0 leaf, \
+             leaf implicants: 0 max / 0 total, max leaf unions: 0, large leaves: 0",
+            stats.to_string()
+        );
+
+        assert_eq!(
+            DecomposeStats {
+                depth: 1,
+                leaf_nodes: 1,
+                max_leaf_implicants: 3,
+                total_leaf_implicants: 3,
+                max_leaf_unions: 7,
+                ..Default::default()
+            },
+            decompose_stats(&game, Some(AblationType::NoHorizontal))
+        );
+
+        let game = Game::from_dnf(dnf!(1 2 + 1 3 + 2 3).map_variable(|id| OwnerId(*id)));
+        assert_eq!(
+            DecomposeStats {
+                depth: 2,
+                var_nodes: 3,
+                hybrid_nodes: 1,
+                ..Default::default()
+            },
+            decompose_stats(&game, None)
+        );
+        assert_eq!(
+            DecomposeStats {
+                depth: 1,
+                leaf_nodes: 1,
+                max_leaf_implicants: 3,
+                total_leaf_implicants: 3,
+                max_leaf_unions: 5,
+                ..Default::default()
+            },
+            decompose_stats(&game, Some(AblationType::NoHybrid))
+        );
+
+        // beyond the limit, the upper bound of the 3 implicants
+        let options = SvOptions {
+            max_leaf_unions: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            DecomposeStats {
+                depth: 1,
+                leaf_nodes: 1,
+                max_leaf_implicants: 3,
+                total_leaf_implicants: 3,
+                max_leaf_unions: 7,
+                large_leaves: 1,
+                ..Default::default()
+            },
+            DecomposeTree::from_game(&game, Some(AblationType::NoHybrid)).stats_with(&options)
+        );
+    }
+
+    #[test]
+    fn test_analyze_decomposition() {
+        let game = Game::from_dnf(dnf!(1 2 + 1 3 + 4).map_variable(|id| OwnerId(*id)));
+
+        let summary = analyze_decomposition(&game, None);
+        assert_eq!(decompose_stats(&game, None), summary.stats);
+        assert!(summary.leaf_variables.is_empty());
+
+        let summary = analyze_decomposition(&game, Some(AblationType::NoHorizontal));
+        assert_eq!(vec![4], summary.leaf_variables);
+
+        // the And node of 1 2 + 1 3 becomes a leaf
+        let summary = analyze_decomposition(&game, Some(AblationType::NoVertical));
+        assert_eq!(vec![3], summary.leaf_variables);
+        assert_eq!(1, summary.stats.or_nodes);
+        assert_eq!(2, summary.stats.max_leaf_implicants);
+
+        let json = serde_json::to_string(&summary).unwrap();
+        assert_eq!(summary, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn test_to_dot() {
+        // simple majority game among 3 owners
+        let game = Game::from_dnf(dnf!(1 2 + 1 3 + 2 3).map_variable(|id| OwnerId(*id)));
+
+        let tree = DecomposeTree::from_game(&game, None);
+        assert_eq!(include_str!("testdata/majority.dot"), tree.to_dot());
+
+        let tree = DecomposeTree::from_game(&game, Some(AblationType::NoHybrid));
+        assert_eq!(
+            include_str!("testdata/majority_no_hybrid.dot"),
+            tree.to_dot()
+        );
+    }
+
+    #[test]
+    fn test_many_children() {
+        // a veto owner 0 and an Or of enough children for the linear products, in exact arithmetic
+        // as the coefficients of this many owners cancel out each other far beyond an f64
+        let others = LINEAR_PRODUCTS_MIN_CHILDREN as u32;
+        let dnf: Dnf<OwnerId> = (1..=others)
+            .map(|i| [0, i].into_iter().map(OwnerId).collect())
+            .collect();
+        let game = Game::from_dnf(dnf);
+        let sv = try_cal_sv_exact_with(&game, None, &SvOptions::default()).unwrap();
+        assert_eq!(BigRational::one(), sv.0.values().sum());
+        for i in 2..=others {
+            assert_eq!(sv.0[&OwnerId(1)], sv.0[&OwnerId(i)]);
+        }
+        assert!(sv.0[&OwnerId(0)] > sv.0[&OwnerId(1)]);
+    }
+}
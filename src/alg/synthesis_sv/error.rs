@@ -0,0 +1,55 @@
+use super::iec::{CoeffOverflow, SetLen};
+use std::{error, fmt};
+
+/// Error when computing Shapley values by the decomposition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SvError {
+    /// An IE coefficient overflows. Enable the `bigint` feature to compute in arbitrary precision.
+    Overflow { degree: SetLen },
+    /// A leaf of the decomposition has more unions of implicants than `SvOptions::max_leaf_unions`.
+    LeafTooLarge { implicants: usize, variables: usize },
+    /// The unions of implicants of a leaf paired up for the coefficients of one of its owners are
+    /// more than `SvOptions::max_union_combinations`.
+    TooManyCombinations {
+        variables: usize,
+        combinations: usize,
+    },
+    /// `SvOptions::cancel` is cancelled or `SvOptions::timeout` has passed.
+    Cancelled,
+}
+
+impl fmt::Display for SvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow { degree } => write!(
+                f,
+                "IE coefficient of degree {degree} overflows, consider enabling the `bigint` feature"
+            ),
+            Self::LeafTooLarge {
+                implicants,
+                variables,
+            } => write!(
+                f,
+                "a leaf of {implicants} implicants over {variables} owners has too many unions, \
+                 consider raising `max_leaf_unions` or a Monte Carlo fallback"
+            ),
+            Self::TooManyCombinations {
+                variables,
+                combinations,
+            } => write!(
+                f,
+                "an owner of a leaf over {variables} owners needs {combinations} combinations of \
+                 unions, consider raising `max_union_combinations` or a Monte Carlo fallback"
+            ),
+            Self::Cancelled => write!(f, "the computation is cancelled"),
+        }
+    }
+}
+
+impl error::Error for SvError {}
+
+impl From<CoeffOverflow> for SvError {
+    fn from(e: CoeffOverflow) -> Self {
+        Self::Overflow { degree: e.degree }
+    }
+}
@@ -3,5 +3,6 @@ mod ie_coeffs;
 
 pub use hybrid_coeffs::{exp_to_input_unions, ExpInputUnion, HybridCoeffs};
 pub use ie_coeffs::{
-    horizontal_identity, horizontal_op, vertical_identity, vertical_op, Coeff, IECoeffs, SetLen,
+    horizontal_identity, horizontal_op, horizontal_op_into, to_stratified_many, vertical_identity,
+    vertical_op, vertical_op_into, Coeff, CoeffOverflow, IECoeffs, SetLen,
 };
@@ -1,13 +1,15 @@
 use super::*;
 use crate::{
     dnf::{Dnf, Implicant},
+    par::prelude::*,
     union_combination::*,
 };
 use bit_set::BitSet;
-use rayon::prelude::*;
-use std::collections::HashMap;
+#[cfg(feature = "serde-index")]
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{borrow::Borrow, collections::HashMap};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HybridCoeffs {
     input_len: usize,
     /// A map of element set to their coeffs without sign information.
@@ -19,18 +21,18 @@ pub struct HybridCoeffs {
 }
 
 impl HybridCoeffs {
-    pub fn new(input: &[IECoeffs]) -> Self {
+    pub fn new(input: &[impl Borrow<IECoeffs> + Sync]) -> Result<Self, CoeffOverflow> {
         let len = input.len();
         match len {
             0 => unreachable!(),
             1 => {
-                let coeffs = input[0].clone();
+                let coeffs = input[0].borrow().clone();
                 let input_set = BitSet::from_iter([0]);
                 let coeffs_map = HashMap::from([(input_set, coeffs)]);
-                return Self {
+                return Ok(Self {
                     input_len: len,
                     coeffs_map,
-                };
+                });
             }
             _ => {}
         }
@@ -41,10 +43,12 @@ impl HybridCoeffs {
             coeffs: IECoeffs,
         }
 
-        let unions: UnionCombination<UnionData> = UnionCombination::new(
+        let unions: UnionCombination<UnionData> = UnionCombination::try_new(
             len,
+            usize::MAX,
+            || -> CoeffOverflow { unreachable!("unlimited unions") },
             |i| {
-                let coeffs = input[i].clone();
+                let coeffs = input[i].borrow().clone();
                 let mut input_set = BitSet::with_capacity(len);
                 input_set.insert(i);
                 UnionData { input_set, coeffs }
@@ -52,13 +56,13 @@ impl HybridCoeffs {
             |old, i| {
                 let mut new_set = old.input_set.clone();
                 new_set.insert(i);
-                let coeffs = vertical_op(&old.coeffs, &input[i]);
-                Some(UnionData {
+                let coeffs = vertical_op(&old.coeffs, input[i].borrow())?;
+                Ok(Some(UnionData {
                     input_set: new_set,
                     coeffs,
-                })
+                }))
             },
-        );
+        )?;
 
         let mut coeffs_map = HashMap::with_capacity(unions.len());
 
@@ -67,20 +71,25 @@ impl HybridCoeffs {
             coeffs_map.insert(u.input_set, u.coeffs);
         }
 
-        Self {
+        Ok(Self {
             input_len: len,
             coeffs_map,
-        }
+        })
+    }
+
+    /// Number of inputs, i.e., children of the hybrid node.
+    pub fn input_len(&self) -> usize {
+        self.input_len
     }
 
-    pub fn exp_coeffs(&self, exp: &Dnf<usize>) -> IECoeffs {
+    pub fn exp_coeffs(&self, exp: &Dnf<usize>) -> Result<IECoeffs, CoeffOverflow> {
         match exp.len() {
             0 => unreachable!(),
             1 => {
                 let imp = exp.iter().next().unwrap();
                 let input_set = imp_to_bitset(imp, self.input_len);
                 let coeffs = self.coeffs_map[&input_set].clone();
-                return coeffs;
+                return Ok(coeffs);
             }
             _ => {}
         }
@@ -89,7 +98,10 @@ impl HybridCoeffs {
         self.exp_unions_coeffs(&unions)
     }
 
-    pub fn exp_unions_coeffs(&self, exp_unions: &UnionCombination<ExpInputUnion>) -> IECoeffs {
+    pub fn exp_unions_coeffs(
+        &self,
+        exp_unions: &UnionCombination<ExpInputUnion>,
+    ) -> Result<IECoeffs, CoeffOverflow> {
         exp_unions
             .0
             .par_iter()
@@ -97,14 +109,18 @@ impl HybridCoeffs {
                 let u = u.get();
                 let sign = if u.num_of_imp % 2 == 0 { -1 } else { 1 };
                 let mut coeffs = self.coeffs_map[&u.input_set].clone();
-                coeffs.apply_sign(sign);
-                coeffs
+                coeffs.apply_sign(sign)?;
+                Ok(coeffs)
             })
-            .sum()
+            .try_reduce(IECoeffs::default, IECoeffs::checked_add_owned)
     }
 
     #[cfg(test)]
-    pub fn interaction(&self, exp1: &Dnf<usize>, exp2: &Dnf<usize>) -> IECoeffs {
+    pub fn interaction(
+        &self,
+        exp1: &Dnf<usize>,
+        exp2: &Dnf<usize>,
+    ) -> Result<IECoeffs, CoeffOverflow> {
         let unions1 = exp_to_input_unions(exp1);
         let unions2 = exp_to_input_unions(exp2);
         self.exp_unions_interaction(&unions1, &unions2)
@@ -114,7 +130,7 @@ impl HybridCoeffs {
         &self,
         exp_unions1: &UnionCombination<ExpInputUnion>,
         exp_unions2: &UnionCombination<ExpInputUnion>,
-    ) -> IECoeffs {
+    ) -> Result<IECoeffs, CoeffOverflow> {
         exp_unions1
             .0
             .par_iter()
@@ -129,11 +145,78 @@ impl HybridCoeffs {
                     } else {
                         -1
                     };
-                    coeffs.apply_sign(sign);
-                    coeffs
+                    coeffs.apply_sign(sign)?;
+                    Ok(coeffs)
                 })
             })
-            .sum()
+            .try_reduce(IECoeffs::default, IECoeffs::checked_add_owned)
+    }
+}
+
+#[cfg(feature = "serde-index")]
+#[derive(Serialize)]
+struct HybridCoeffsRef<'a> {
+    input_len: usize,
+    coeffs_map: Vec<(Vec<usize>, &'a IECoeffs)>,
+}
+
+#[cfg(feature = "serde-index")]
+#[derive(Deserialize)]
+struct HybridCoeffsData {
+    input_len: usize,
+    coeffs_map: Vec<(Vec<usize>, IECoeffs)>,
+}
+
+/// Serialized with the input sets as sorted lists of indices, sorted in turn.
+#[cfg(feature = "serde-index")]
+impl Serialize for HybridCoeffs {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut coeffs_map: Vec<_> = self
+            .coeffs_map
+            .iter()
+            .map(|(input_set, coeffs)| (input_set.iter().collect::<Vec<_>>(), coeffs))
+            .collect();
+        coeffs_map.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        HybridCoeffsRef {
+            input_len: self.input_len,
+            coeffs_map,
+        }
+        .serialize(s)
+    }
+}
+
+/// Deserialization fails unless there are the coefficients of every non-empty set of inputs, as
+/// built by `new`, so that looking them up never panics.
+#[cfg(feature = "serde-index")]
+impl<'de> Deserialize<'de> for HybridCoeffs {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let data = HybridCoeffsData::deserialize(d)?;
+        let input_len = data.input_len;
+        if input_len == 0 || input_len >= usize::BITS as usize {
+            return Err(D::Error::custom(format!(
+                "invalid number of inputs {input_len}"
+            )));
+        }
+        let mut coeffs_map = HashMap::with_capacity(data.coeffs_map.len());
+        for (inputs, coeffs) in data.coeffs_map {
+            let mut input_set = BitSet::with_capacity(input_len);
+            for i in inputs {
+                if i >= input_len {
+                    return Err(D::Error::custom(format!("input {i} out of range")));
+                }
+                input_set.insert(i);
+            }
+            if input_set.is_empty() || coeffs_map.insert(input_set, coeffs).is_some() {
+                return Err(D::Error::custom("empty or duplicate input set"));
+            }
+        }
+        if coeffs_map.len() != (1 << input_len) - 1 {
+            return Err(D::Error::custom("missing input sets"));
+        }
+        Ok(Self {
+            input_len,
+            coeffs_map,
+        })
     }
 }
 
@@ -153,7 +236,7 @@ pub struct ExpInputUnion {
 }
 
 pub fn exp_to_input_unions(exp: &Dnf<usize>) -> UnionCombination<ExpInputUnion> {
-    let var_len = exp.all_variables().len();
+    let var_len = exp.variable_count();
     let imp_list: Vec<_> = exp.iter().collect();
     UnionCombination::new(
         imp_list.len(),
@@ -195,8 +278,8 @@ mod tests {
             ie_coeffs! { 1 => 2, 3 => -2, 4 => 1},
         ];
 
-        let hybrid_coeffs = HybridCoeffs::new(&input);
-        let actual = hybrid_coeffs.exp_coeffs(&hybrid_exp);
+        let hybrid_coeffs = HybridCoeffs::new(&input).unwrap();
+        let actual = hybrid_coeffs.exp_coeffs(&hybrid_exp).unwrap();
         let expect = ie_coeffs! { 2 => 2, 3 => 2, 4 => -4, 5 => -1, 6 => 3, 7 => -1 };
         assert_eq!(expect, actual);
     }
@@ -210,8 +293,8 @@ mod tests {
             ie_coeffs! { 1 => 3, 2 => -3, 3 => 1 },
         ];
 
-        let hybrid_coeffs = HybridCoeffs::new(&input);
-        let actual = hybrid_coeffs.exp_coeffs(&hybrid_exp);
+        let hybrid_coeffs = HybridCoeffs::new(&input).unwrap();
+        let actual = hybrid_coeffs.exp_coeffs(&hybrid_exp).unwrap();
         let expect = ie_coeffs! { 2 => 7, 3 => -12, 4 => 8, 5 => -2 };
         assert_eq!(expect, actual);
     }
@@ -225,8 +308,8 @@ mod tests {
             ie_coeffs! { 1 => 3, 2 => -3, 3 => 1 },
         ];
 
-        let hybrid_coeffs = HybridCoeffs::new(&input);
-        let actual = hybrid_coeffs.exp_coeffs(&exp);
+        let hybrid_coeffs = HybridCoeffs::new(&input).unwrap();
+        let actual = hybrid_coeffs.exp_coeffs(&exp).unwrap();
         let expect = ie_coeffs! { 2 => 2, 3 => 2, 4 => -4, 5 => -1, 6 => 3, 7 => -1 };
         assert_eq!(expect, actual);
     }
@@ -241,8 +324,8 @@ mod tests {
             ie_coeffs! { 1 => 3, 2 => -3, 3 => 1 },
         ];
 
-        let hybrid_coeffs = HybridCoeffs::new(&input);
-        let actual = hybrid_coeffs.interaction(&exp1, &exp2);
+        let hybrid_coeffs = HybridCoeffs::new(&input).unwrap();
+        let actual = hybrid_coeffs.interaction(&exp1, &exp2).unwrap();
         let expect = ie_coeffs! { 2 => 3, 3 => -3, 4 => 1 };
         assert_eq!(expect, actual);
     }
@@ -260,9 +343,31 @@ mod tests {
             ie_coeffs! { 3 => 2, 5 => -1 },
         ];
 
-        let hybrid_coeffs = HybridCoeffs::new(&input);
-        let actual = hybrid_coeffs.interaction(&exp1, &exp2);
+        let hybrid_coeffs = HybridCoeffs::new(&input).unwrap();
+        let actual = hybrid_coeffs.interaction(&exp1, &exp2).unwrap();
         let expect = ie_coeffs! { 2 => 6, 3 => -9, 4 => 8, 5 => -10, 6 => 16, 7 => -29, 8 => 36, 9 => -18, 10 => -7, 11 => 13, 12 => -6, 13 => 1 };
         assert_eq!(expect, actual);
     }
+
+    #[cfg(feature = "serde-index")]
+    #[test]
+    fn test_serde() {
+        let input = vec![
+            ie_coeffs! { 1 => 1 },
+            ie_coeffs! { 1 => 2, 2 => -1 },
+            ie_coeffs! { 2 => 1 },
+        ];
+        let hybrid_coeffs = HybridCoeffs::new(&input).unwrap();
+        let json = serde_json::to_string(&hybrid_coeffs).unwrap();
+        let loaded: HybridCoeffs = serde_json::from_str(&json).unwrap();
+        assert_eq!(3, loaded.input_len());
+        assert_eq!(hybrid_coeffs.coeffs_map, loaded.coeffs_map);
+
+        let single = r#"{"input_len":1,"coeffs_map":[[[0],[[1,"1"]]]]}"#;
+        assert!(serde_json::from_str::<HybridCoeffs>(single).is_ok());
+        let out_of_range = r#"{"input_len":1,"coeffs_map":[[[1],[[1,"1"]]]]}"#;
+        assert!(serde_json::from_str::<HybridCoeffs>(out_of_range).is_err());
+        let missing = r#"{"input_len":2,"coeffs_map":[[[0],[[1,"1"]]]]}"#;
+        assert!(serde_json::from_str::<HybridCoeffs>(missing).is_err());
+    }
 }
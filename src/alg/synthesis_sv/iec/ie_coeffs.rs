@@ -1,12 +1,112 @@
-use rayon::prelude::*;
-use std::{
-    collections::HashMap,
-    iter::Sum,
-    ops::{Add, Mul, Sub},
-};
+use crate::par::prelude::*;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+#[cfg(feature = "serde-index")]
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::HashMap, error, fmt};
 
 pub type SetLen = usize;
-pub type Coeff = i32;
+
+/// Coefficient type. The arithmetic is checked, and an overflow is an error of `CoeffOverflow`.
+#[cfg(not(feature = "bigint"))]
+pub type Coeff = i64;
+
+/// Coefficient type of arbitrary precision, which never overflows.
+#[cfg(feature = "bigint")]
+pub type Coeff = BigInt;
+
+/// Error when a coefficient overflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoeffOverflow {
+    /// Set length (i.e., degree) of the overflowed coefficient.
+    pub degree: SetLen,
+}
+
+impl fmt::Display for CoeffOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IE coefficient of degree {} overflows", self.degree)
+    }
+}
+
+impl error::Error for CoeffOverflow {}
+
+#[cfg(not(feature = "bigint"))]
+#[inline]
+fn try_add_coeff(a: &Coeff, b: &Coeff, degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    a.checked_add(*b).ok_or(CoeffOverflow { degree })
+}
+
+#[cfg(not(feature = "bigint"))]
+#[inline]
+fn try_sub_coeff(a: &Coeff, b: &Coeff, degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    a.checked_sub(*b).ok_or(CoeffOverflow { degree })
+}
+
+#[cfg(not(feature = "bigint"))]
+#[inline]
+fn try_mul_coeff(a: &Coeff, b: &Coeff, degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    a.checked_mul(*b).ok_or(CoeffOverflow { degree })
+}
+
+#[cfg(not(feature = "bigint"))]
+#[inline]
+fn try_neg_coeff(a: &Coeff, degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    a.checked_neg().ok_or(CoeffOverflow { degree })
+}
+
+/// `x` rounded to the nearest coefficient.
+#[cfg(not(feature = "bigint"))]
+fn coeff_from_f64(x: f64, degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    let x = x.round();
+    // `i64::MAX as f64` is 2^63, which is out of range
+    if x.is_finite() && x.abs() < i64::MAX as f64 {
+        Ok(x as Coeff)
+    } else {
+        Err(CoeffOverflow { degree })
+    }
+}
+
+#[cfg(feature = "bigint")]
+#[inline]
+fn try_add_coeff(a: &Coeff, b: &Coeff, _degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    Ok(a + b)
+}
+
+#[cfg(feature = "bigint")]
+#[inline]
+fn try_sub_coeff(a: &Coeff, b: &Coeff, _degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    Ok(a - b)
+}
+
+#[cfg(feature = "bigint")]
+#[inline]
+fn try_mul_coeff(a: &Coeff, b: &Coeff, _degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    Ok(a * b)
+}
+
+#[cfg(feature = "bigint")]
+#[inline]
+fn try_neg_coeff(a: &Coeff, _degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    Ok(-a)
+}
+
+/// `x` rounded to the nearest coefficient, which fails only if it is not finite.
+#[cfg(feature = "bigint")]
+fn coeff_from_f64(x: f64, degree: SetLen) -> Result<Coeff, CoeffOverflow> {
+    num_traits::FromPrimitive::from_f64(x.round()).ok_or(CoeffOverflow { degree })
+}
+
+#[cfg(not(feature = "bigint"))]
+#[inline]
+fn coeff_to_bigint(a: &Coeff) -> BigInt {
+    BigInt::from(*a)
+}
+
+#[cfg(feature = "bigint")]
+#[inline]
+fn coeff_to_bigint(a: &Coeff) -> BigInt {
+    a.clone()
+}
 
 /// A hashmap of iec coefficients index by the size of subset.
 #[derive(
@@ -29,121 +129,389 @@ pub type Coeff = i32;
 pub struct IECoeffs(pub(crate) HashMap<SetLen, Coeff>);
 
 impl IECoeffs {
+    /// Coefficients with a single term `coeff` of `set_len`.
+    pub fn monomial(set_len: SetLen, coeff: i32) -> Self {
+        Self::from([(set_len, Coeff::from(coeff))])
+    }
+
+    /// Coefficients of `sum(counts[j] * p^j * (1 - p)^(n - j))` for `n = counts.len() - 1`, i.e.,
+    /// of the probability that a game of `n` owners wins given the number `counts[j]` of its
+    /// winning coalitions of each size `j`. The counts may be estimates, which are rounded to the
+    /// nearest integers.
+    pub fn from_size_counts(counts: &[f64]) -> Result<Self, CoeffOverflow> {
+        use num_traits::Zero;
+        let n = counts.len().saturating_sub(1);
+        let mut ans = Self::default();
+        // row `n - j` of Pascal's triangle, i.e., C(n - j, t) for t in 0..=n-j, where an
+        // overflowed binomial coefficient is `None`
+        let mut row = vec![Some(Coeff::from(1))];
+        for j in (0..counts.len()).rev() {
+            if j < n {
+                let mut next = Vec::with_capacity(row.len() + 1);
+                next.push(Some(Coeff::from(1)));
+                for t in 1..row.len() {
+                    next.push(match (&row[t - 1], &row[t]) {
+                        (Some(a), Some(b)) => try_add_coeff(a, b, j + t).ok(),
+                        _ => None,
+                    });
+                }
+                next.push(Some(Coeff::from(1)));
+                row = next;
+            }
+            let count = coeff_from_f64(counts[j], j)?;
+            if count.is_zero() {
+                continue;
+            }
+            // the term of `p^k` in `p^j * (1 - p)^(n - j)` is `C(n - j, k - j) * (-1)^(k - j)`
+            for (t, binom) in row.iter().enumerate() {
+                let k = j + t;
+                let binom = binom.as_ref().ok_or(CoeffOverflow { degree: k })?;
+                let term = try_mul_coeff(&count, binom, k)?;
+                let e = ans.entry(k).or_default();
+                *e = if t % 2 == 0 {
+                    try_add_coeff(e, &term, k)?
+                } else {
+                    try_sub_coeff(e, &term, k)?
+                };
+            }
+        }
+        Ok(ans)
+    }
+
+    /// The terms are added up by `stable_sum`, so the value is the same in every run.
+    #[cfg(not(feature = "bigint"))]
     pub fn to_sv(&self) -> f64 {
-        self.par_iter()
-            .map(|(set_len, coeff)| *coeff as f64 / *set_len as f64)
+        crate::utils::stable_sum(
+            self.iter()
+                .map(|(set_len, coeff)| *coeff as f64 / *set_len as f64),
+        )
+    }
+
+    /// Large coefficients cancel out each other, so they are summed up exactly before rounding.
+    #[cfg(feature = "bigint")]
+    pub fn to_sv(&self) -> f64 {
+        use num_traits::ToPrimitive;
+        self.to_sv_exact().to_f64().unwrap_or(f64::NAN)
+    }
+
+    /// Same as `to_sv` but in exact rational arithmetic.
+    pub fn to_sv_exact(&self) -> BigRational {
+        self.iter()
+            .map(|(set_len, coeff)| {
+                BigRational::new(coeff_to_bigint(coeff), BigInt::from(*set_len))
+            })
             .sum()
     }
 
-    pub fn apply_sign(&mut self, sign: i32) {
-        if sign == 1 {
-            return;
+    /// The Shapley interaction index of a pair of owners from their coefficients, i.e., the
+    /// integral of `f(p) / p^2` where `f` is `p^2` times the second derivative of the game. Terms
+    /// with set length below 2 must be zero.
+    pub fn to_interaction(&self) -> f64 {
+        use num_traits::{ToPrimitive, Zero};
+        let value: BigRational = self
+            .iter()
+            .filter(|(_, coeff)| !coeff.is_zero())
+            .map(|(set_len, coeff)| {
+                debug_assert!(*set_len >= 2, "pair coefficients of set length {set_len}");
+                BigRational::new(coeff_to_bigint(coeff), BigInt::from(*set_len - 1))
+            })
+            .sum();
+        value.to_f64().unwrap_or(f64::NAN)
+    }
+
+    /// Evaluate the coefficients of an owner at probability 1/2, i.e., the (raw) Banzhaf index.
+    #[cfg(not(feature = "bigint"))]
+    pub fn to_banzhaf(&self) -> f64 {
+        crate::utils::stable_sum(
+            self.iter()
+                .map(|(set_len, coeff)| *coeff as f64 * 0.5_f64.powi(*set_len as i32 - 1)),
+        )
+    }
+
+    /// Evaluate the coefficients of an owner at probability 1/2, i.e., the (raw) Banzhaf index.
+    #[cfg(feature = "bigint")]
+    pub fn to_banzhaf(&self) -> f64 {
+        use num_traits::ToPrimitive;
+        let value: BigRational = self
+            .iter()
+            .map(|(set_len, coeff)| {
+                BigRational::new(coeff.clone(), BigInt::from(2).pow(*set_len as u32 - 1))
+            })
+            .sum();
+        value.to_f64().unwrap_or(f64::NAN)
+    }
+
+    /// Number of coalitions among `n` owners in which an owner with these coefficients is a swing
+    /// player, i.e., `to_banzhaf` times `2^(n - 1)` in exact arithmetic.
+    ///
+    /// Panic if a set length is larger than `n`, e.g., if the DNF has owners outside the owner set.
+    pub fn to_swing_count(&self, n: usize) -> BigInt {
+        self.iter()
+            .map(|(set_len, coeff)| {
+                assert!(
+                    *set_len <= n,
+                    "set length {set_len} of more than {n} owners"
+                );
+                coeff_to_bigint(coeff) << (n - *set_len)
+            })
+            .sum()
+    }
+
+    /// Probability that an owner with these coefficients is pivotal when it joins a uniformly
+    /// random coalition of `s` of the other `n - 1` owners, for each `s` in `0..n`.
+    ///
+    /// The coefficients are those of the polynomial `sum(c_k * p^(k - 1))` in the probability `p`
+    /// that each other owner joins, and these are its coefficients in the Bernstein basis of
+    /// degree `n - 1`, i.e., `sum(c_k * C(s, k - 1) / C(n - 1, k - 1))`. Every coalition size is
+    /// equally likely in a random permutation, so `to_sv` is their mean. `to_sv` does not go
+    /// through them, as it takes time linear in the number of coefficients without knowing `n`,
+    /// whereas these take the binomial coefficients of all the `n` sizes.
+    ///
+    /// For many owners of the same game, `to_stratified_many` computes the binomial coefficients
+    /// once for all of them.
+    ///
+    /// Panic if a set length is 0 or larger than `n`.
+    pub fn to_stratified(&self, n: usize) -> Vec<f64> {
+        to_stratified_many(&[self], n).pop().unwrap_or_default()
+    }
+
+    /// Whether these may be the coefficients of the probability that a non-constant monotone
+    /// expression of `n` variables is true, i.e., of degree at most `n`, 0 when `p` is 0 and 1
+    /// when `p` is 1.
+    pub fn is_win_probability(&self, n: usize) -> bool {
+        use num_traits::{One, Zero};
+        self.keys().all(|set_len| *set_len <= n)
+            && self.get(&0).is_none_or(Zero::is_zero)
+            && self.values().map(coeff_to_bigint).sum::<BigInt>().is_one()
+    }
+
+    /// Multiply each coefficient by its set length, i.e., `p * f'(p)` for the polynomial
+    /// `f(p) = sum(c_k * p^k)`, so that `gamma_map.checked_mul(&f.degree_weighted()?)?.to_sv()` is
+    /// the total Shapley value of the owners of a subtree with coefficients `f`.
+    pub fn degree_weighted(&self) -> Result<Self, CoeffOverflow> {
+        self.iter()
+            .map(|(set_len, coeff)| {
+                let weight = Coeff::from(*set_len as i32);
+                Ok((*set_len, try_mul_coeff(coeff, &weight, *set_len)?))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// `self + rhs` term by term, or an error when a coefficient overflows, as for all the
+    /// arithmetic of the coefficients.
+    pub fn checked_add(&self, rhs: &IECoeffs) -> Result<IECoeffs, CoeffOverflow> {
+        let mut ans = self.clone();
+        for (k, v) in rhs.iter() {
+            let e = ans.entry(*k).or_default();
+            *e = try_add_coeff(e, v, *k)?;
         }
+        Ok(ans)
+    }
 
-        self.iter_mut().for_each(|(_, v)| {
-            *v *= sign;
-        });
+    /// `self - rhs` term by term.
+    pub fn checked_sub(&self, rhs: &IECoeffs) -> Result<IECoeffs, CoeffOverflow> {
+        let mut ans = self.clone();
+        for (k, v) in rhs.iter() {
+            let e = ans.entry(*k).or_default();
+            *e = try_sub_coeff(e, v, *k)?;
+        }
+        Ok(ans)
     }
-}
 
-impl Add<Self> for IECoeffs {
-    type Output = Self;
+    /// `self * rhs` as polynomials, where the constant term is dropped.
+    pub fn checked_mul(&self, rhs: &IECoeffs) -> Result<IECoeffs, CoeffOverflow> {
+        let mut ans = IECoeffs::default();
+        self.add_product_to(rhs, false, &mut ans)?;
+        Ok(ans)
+    }
 
-    fn add(self, rhs: IECoeffs) -> Self::Output {
+    /// Same as `checked_add`, but consume both sides, adding the shorter one into the other, e.g.,
+    /// to reduce the terms of inclusion-exclusion.
+    pub fn checked_add_owned(self, rhs: IECoeffs) -> Result<IECoeffs, CoeffOverflow> {
         let (to_consume, mut to_mutate) = if self.len() < rhs.len() {
             (self, rhs)
         } else {
             (rhs, self)
         };
         for (k, v) in to_consume {
-            *to_mutate.entry(k).or_default() += v;
+            let e = to_mutate.entry(k).or_default();
+            *e = try_add_coeff(e, &v, k)?;
         }
-        to_mutate
+        Ok(to_mutate)
     }
-}
 
-impl<'a, 'b> Add<&'b IECoeffs> for &'a IECoeffs {
-    type Output = IECoeffs;
+    /// Add (or subtract if `negate`) `self * rhs` to `out` term by term, without allocating the
+    /// product.
+    fn add_product_to(
+        &self,
+        rhs: &IECoeffs,
+        negate: bool,
+        out: &mut IECoeffs,
+    ) -> Result<(), CoeffOverflow> {
+        for (l_k, l_v) in self.iter() {
+            for (r_k, r_v) in rhs.iter() {
+                let k = l_k + r_k;
 
-    fn add(self, rhs: &'b IECoeffs) -> Self::Output {
-        let (to_consume, mut to_mutate) = if self.len() < rhs.len() {
-            (self, rhs.clone())
-        } else {
-            (rhs, self.clone())
-        };
-        for (k, v) in to_consume.iter() {
-            *to_mutate.entry(*k).or_default() += *v;
+                if k != 0_usize {
+                    let v = try_mul_coeff(l_v, r_v, k)?;
+                    let e = out.entry(k).or_default();
+                    *e = if negate {
+                        try_sub_coeff(e, &v, k)?
+                    } else {
+                        try_add_coeff(e, &v, k)?
+                    };
+                }
+            }
         }
-        to_mutate
+        Ok(())
     }
-}
 
-impl Sum for IECoeffs {
-    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        let mut ans = IECoeffs::default();
-        for v in iter {
-            ans = ans + v;
+    pub fn apply_sign(&mut self, sign: i32) -> Result<(), CoeffOverflow> {
+        if sign == 1 {
+            return Ok(());
         }
-        ans
-    }
-}
-
-impl Sub<Self> for IECoeffs {
-    type Output = Self;
 
-    fn sub(mut self, rhs: IECoeffs) -> Self::Output {
-        for (k, v) in rhs {
-            *self.entry(k).or_default() -= v;
+        for (k, v) in self.iter_mut() {
+            *v = try_neg_coeff(v, *k)?;
         }
-        self
+        Ok(())
     }
 }
 
-impl<'a, 'b> Sub<&'b IECoeffs> for &'a IECoeffs {
-    type Output = IECoeffs;
-
-    fn sub(self, rhs: &'b IECoeffs) -> Self::Output {
-        let mut ans = self.clone();
-        for (k, v) in rhs.iter() {
-            *ans.entry(*k).or_default() -= *v;
-        }
-        ans
+/// Serialized as `[[set_len, "coeff"], ...]` sorted by `set_len`, with the coefficients as
+/// decimal strings so that files are the same with or without the `bigint` feature.
+#[cfg(feature = "serde-index")]
+impl Serialize for IECoeffs {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut terms: Vec<_> = self.iter().map(|(k, v)| (*k, v.to_string())).collect();
+        terms.sort_unstable();
+        terms.serialize(s)
     }
 }
 
-impl<'a, 'b> Mul<&'b IECoeffs> for &'a IECoeffs {
-    type Output = IECoeffs;
+#[cfg(feature = "serde-index")]
+impl<'de> Deserialize<'de> for IECoeffs {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        Vec::<(SetLen, String)>::deserialize(d)?
+            .into_iter()
+            .map(|(k, v)| {
+                v.parse()
+                    .map(|v| (k, v))
+                    .map_err(|_| D::Error::custom(format!("invalid coefficient {v:?}")))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map(Self)
+    }
+}
 
-    fn mul(self, rhs: &'b IECoeffs) -> Self::Output {
-        let mut ans = IECoeffs::default();
-        for (l_k, l_v) in self.iter() {
-            for (r_k, r_v) in rhs.iter() {
-                let k = l_k + r_k;
-                let v = l_v * r_v;
+/// `IECoeffs::to_stratified` of the owners with `coeffs` among the same `n` owners, in order.
+///
+/// Each term `c_k * C(s, k - 1) / C(n - 1, k - 1)` is `c_k * (k - 1)! * (n - k)! * C(s, k - 1)`
+/// over the common denominator `(n - 1)!`, so the numerators are integers, and the rows of
+/// Pascal's triangle are built once, in `O(n^2)` additions, and shared by all the owners.
+///
+/// Panic if a set length is 0 or larger than `n`.
+pub fn to_stratified_many(coeffs: &[&IECoeffs], n: usize) -> Vec<Vec<f64>> {
+    use num_traits::{One, ToPrimitive, Zero};
+    // factorials[k] = k!
+    let mut factorials = vec![BigInt::one()];
+    for k in 1..n {
+        let next = &factorials[k - 1] * BigInt::from(k);
+        factorials.push(next);
+    }
+    let numerators: Vec<Vec<(usize, BigInt)>> = coeffs
+        .iter()
+        .map(|coeffs| {
+            coeffs
+                .iter()
+                .filter(|(_, coeff)| !coeff.is_zero())
+                .map(|(set_len, coeff)| {
+                    assert!(
+                        (1..=n).contains(set_len),
+                        "set length {set_len} of an owner among {n} owners"
+                    );
+                    let j = *set_len - 1;
+                    let weight = &factorials[j] * &factorials[n - 1 - j];
+                    (j, coeff_to_bigint(coeff) * weight)
+                })
+                .collect()
+        })
+        .collect();
+    let mut ans: Vec<Vec<f64>> = coeffs.iter().map(|_| Vec::with_capacity(n)).collect();
+    if n == 0 {
+        return ans;
+    }
+    let denominator = &factorials[n - 1];
 
-                if k != 0_usize {
-                    *ans.entry(k).or_default() += v;
-                }
-            }
+    // row s of Pascal's triangle, i.e., C(s, j) for j in 0..=s
+    let mut row = vec![BigInt::one()];
+    for s in 0..n {
+        let values: Vec<f64> = numerators
+            .par_iter()
+            .map(|terms| {
+                let numerator: BigInt = terms
+                    .iter()
+                    .filter(|(j, _)| *j <= s)
+                    .map(|(j, term)| term * &row[*j])
+                    .sum();
+                BigRational::new_raw(numerator, denominator.clone())
+                    .to_f64()
+                    .unwrap_or(f64::NAN)
+            })
+            .collect();
+        for (stratified, value) in ans.iter_mut().zip(values) {
+            stratified.push(value);
         }
-        ans
+        let mut next = Vec::with_capacity(s + 2);
+        next.push(BigInt::one());
+        next.extend(row.windows(2).map(|w| &w[0] + &w[1]));
+        next.push(BigInt::one());
+        row = next;
     }
+    ans
 }
 
 pub fn horizontal_identity() -> IECoeffs {
     IECoeffs::default()
 }
 
-pub fn horizontal_op(a: &IECoeffs, b: &IECoeffs) -> IECoeffs {
-    a + b - a * b
+pub fn horizontal_op(a: &IECoeffs, b: &IECoeffs) -> Result<IECoeffs, CoeffOverflow> {
+    let mut ans = IECoeffs::default();
+    horizontal_op_into(a, b, &mut ans)?;
+    Ok(ans)
+}
+
+/// Same as `horizontal_op`, i.e., `a + b - a * b`, but overwrite `out`, reusing its allocation.
+pub fn horizontal_op_into(
+    a: &IECoeffs,
+    b: &IECoeffs,
+    out: &mut IECoeffs,
+) -> Result<(), CoeffOverflow> {
+    out.clear();
+    for (k, v) in a.iter().chain(b.iter()) {
+        let e = out.entry(*k).or_default();
+        *e = try_add_coeff(e, v, *k)?;
+    }
+    a.add_product_to(b, true, out)
 }
 
 pub fn vertical_identity() -> IECoeffs {
-    IECoeffs::from([(0, 1)])
+    IECoeffs::monomial(0, 1)
+}
+
+pub fn vertical_op(a: &IECoeffs, b: &IECoeffs) -> Result<IECoeffs, CoeffOverflow> {
+    a.checked_mul(b)
 }
 
-pub fn vertical_op(a: &IECoeffs, b: &IECoeffs) -> IECoeffs {
-    a * b
+/// Same as `vertical_op`, i.e., `a * b`, but overwrite `out`, reusing its allocation.
+pub fn vertical_op_into(
+    a: &IECoeffs,
+    b: &IECoeffs,
+    out: &mut IECoeffs,
+) -> Result<(), CoeffOverflow> {
+    out.clear();
+    a.add_product_to(b, false, out)
 }
 
 #[macro_export]
@@ -157,7 +525,7 @@ macro_rules! ie_coeffs {
             let _cap = $crate::ie_coeffs!(@count $($key),*);
             let mut _map = ::std::collections::HashMap::with_capacity(_cap);
             $(
-                let _ = _map.insert($key, $value);
+                let _ = _map.insert($key, $crate::alg::synthesis_sv::iec::Coeff::from($value));
             )*
             $crate::alg::synthesis_sv::iec::IECoeffs(_map)
         }
@@ -166,11 +534,161 @@ macro_rules! ie_coeffs {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_arithmetic() {
         let a = ie_coeffs! { 1 => 1, 2 => 2 };
         let b = ie_coeffs! { 1 => 3, 2 => 4 };
         let c = ie_coeffs! { 2 => 3, 3 => 10, 4 => 8 };
-        assert_eq!(c, &a * &b);
+        assert_eq!(Ok(c), a.checked_mul(&b));
+        assert_eq!(Ok(ie_coeffs! { 1 => 4, 2 => 6 }), a.checked_add(&b));
+        assert_eq!(a.checked_add(&b), a.clone().checked_add_owned(b.clone()));
+        assert_eq!(Ok(ie_coeffs! { 1 => -2, 2 => -2 }), a.checked_sub(&b));
+        assert_eq!(Ok(ie_coeffs! { 1 => 1, 2 => 4 }), a.degree_weighted());
+
+        let mut negated = a.clone();
+        negated.apply_sign(-1).unwrap();
+        assert_eq!(
+            Ok(ie_coeffs! { 1 => 0, 2 => 0 }),
+            a.checked_add_owned(negated)
+        );
+    }
+
+    #[test]
+    fn test_from_size_counts() {
+        // `1 + 2` wins in both coalitions of size 1 and the one of size 2, i.e., `2p - p^2`
+        let coeffs = IECoeffs::from_size_counts(&[0., 2., 1.]).unwrap();
+        assert_eq!(
+            Ok(coeffs),
+            horizontal_op(&ie_coeffs! { 1 => 1 }, &ie_coeffs! { 1 => 1 })
+        );
+        // `1 2 3` only in the grand coalition, and estimates are rounded
+        assert_eq!(
+            Ok(ie_coeffs! { 3 => 1 }),
+            IECoeffs::from_size_counts(&[0., 0.4, 0., 0.9])
+        );
+        // the constant term of a game that always wins
+        assert_eq!(
+            Ok(ie_coeffs! { 0 => 1, 1 => 0 }),
+            IECoeffs::from_size_counts(&[1., 1.])
+        );
+        assert_eq!(Ok(IECoeffs::default()), IECoeffs::from_size_counts(&[]));
+
+        #[cfg(not(feature = "bigint"))]
+        assert_eq!(
+            Err(CoeffOverflow { degree: 1 }),
+            IECoeffs::from_size_counts(&[0., 1e19])
+        );
+    }
+
+    #[test]
+    fn test_ops_into() {
+        let a = ie_coeffs! { 0 => 1, 1 => 1, 2 => 2 };
+        let b = ie_coeffs! { 1 => 3, 2 => -4 };
+        // a stale buffer is overwritten
+        let mut out = ie_coeffs! { 7 => 1 };
+        vertical_op_into(&a, &b, &mut out).unwrap();
+        assert_eq!(a.checked_mul(&b), Ok(out.clone()));
+        horizontal_op_into(&a, &b, &mut out).unwrap();
+        let expect = a
+            .checked_add(&b)
+            .unwrap()
+            .checked_sub(&a.checked_mul(&b).unwrap());
+        assert_eq!(expect, Ok(out.clone()));
+        assert_eq!(Ok(out), horizontal_op(&a, &b));
+    }
+
+    #[test]
+    fn test_to_stratified() {
+        // owner 1 of `1 2 3` is pivotal only when the others have joined
+        assert_eq!(vec![0., 0., 1.], ie_coeffs! { 3 => 1 }.to_stratified(3));
+        // owner 1 of `1 + 2` only when 2 has not
+        assert_eq!(
+            vec![1., 0.],
+            ie_coeffs! { 1 => 1, 2 => -1 }.to_stratified(2)
+        );
+        // owner 1 of `1 2 + 1 3`, i.e., `p + p - p^2`, with a null player
+        let coeffs = ie_coeffs! { 2 => 2, 3 => -1 };
+        assert_eq!(vec![0., 2. / 3., 1., 1.], coeffs.to_stratified(4));
+        crate::tests::assert_f64_eq(
+            coeffs.to_sv(),
+            coeffs.to_stratified(4).iter().sum::<f64>() / 4.,
+        );
+        assert!(IECoeffs::default().to_stratified(0).is_empty());
+
+        // shared between owners
+        let many = [ie_coeffs! { 3 => 1 }, coeffs, IECoeffs::default()];
+        let refs: Vec<_> = many.iter().collect();
+        let rows = to_stratified_many(&refs, 4);
+        for (coeffs, row) in many.iter().zip(&rows) {
+            assert_eq!(coeffs.to_stratified(4), *row);
+        }
+        assert_eq!(vec![0.; 4], rows[2]);
+    }
+
+    #[test]
+    fn test_checked_overflow() {
+        let max = ie_coeffs! { 1 => i64::MAX };
+        let min = ie_coeffs! { 3 => i64::MIN };
+        let one = ie_coeffs! { 1 => 1, 3 => 1 };
+        let a = ie_coeffs! { 1 => 1_i64 << 32 };
+        let b = ie_coeffs! { 2 => 1_i64 << 31 };
+
+        #[cfg(not(feature = "bigint"))]
+        {
+            assert_eq!(Err(CoeffOverflow { degree: 1 }), max.checked_add(&one));
+            assert_eq!(Err(CoeffOverflow { degree: 3 }), min.checked_sub(&one));
+            assert_eq!(Err(CoeffOverflow { degree: 3 }), a.checked_mul(&b));
+            assert_eq!(Err(CoeffOverflow { degree: 3 }), vertical_op(&a, &b));
+            assert_eq!(Err(CoeffOverflow { degree: 3 }), min.degree_weighted());
+
+            let mut negated = min.clone();
+            assert_eq!(Err(CoeffOverflow { degree: 3 }), negated.apply_sign(-1));
+        }
+
+        #[cfg(feature = "bigint")]
+        {
+            assert_eq!(
+                Coeff::from(i64::MAX) + 1,
+                max.checked_add(&one).unwrap()[&1]
+            );
+            assert_eq!(
+                Coeff::from(i64::MIN) - 1,
+                min.checked_sub(&one).unwrap()[&3]
+            );
+            assert_eq!(Coeff::from(1_i64) << 63, a.checked_mul(&b).unwrap()[&3]);
+        }
+    }
+
+    #[test]
+    fn test_checked_pathological() {
+        // 70 singleton implicants, i.e., 1 - (1 - x)^70, whose coefficients are up to C(70, 35)
+        let x = IECoeffs::monomial(1, 1);
+        let exp_coeffs = (0..70).try_fold(horizontal_identity(), |acc, _| {
+            acc.checked_add(&x)?.checked_sub(&acc.checked_mul(&x)?)
+        });
+
+        #[cfg(not(feature = "bigint"))]
+        assert!(matches!(exp_coeffs, Err(CoeffOverflow { degree }) if degree <= 70));
+
+        #[cfg(feature = "bigint")]
+        {
+            use num_traits::One;
+
+            let exp_coeffs = exp_coeffs.unwrap();
+            assert_eq!(70, exp_coeffs.len());
+            assert_eq!(-Coeff::one(), exp_coeffs[&70]);
+        }
+    }
+
+    #[cfg(feature = "serde-index")]
+    #[test]
+    fn test_serde() {
+        let a = ie_coeffs! { 3 => -4, 1 => i64::MAX, 2 => 0 };
+        let json = serde_json::to_string(&a).unwrap();
+        assert_eq!(r#"[[1,"9223372036854775807"],[2,"0"],[3,"-4"]]"#, json);
+        assert_eq!(a, serde_json::from_str(&json).unwrap());
+        assert!(serde_json::from_str::<IECoeffs>(r#"[[1,"x"]]"#).is_err());
     }
 }
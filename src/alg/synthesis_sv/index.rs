@@ -0,0 +1,514 @@
+//! Decomposition trees persisted to disk, so that the Shapley values of a large game are computed
+//! again without decomposing it, e.g., across runs of a service.
+
+use super::{
+    decompose_tree::{
+        try_build_tree, try_cal_tree_owner_coeffs_for, AblationType, DecomposeTree, TreeContext,
+    },
+    iec::{HybridCoeffs, IECoeffs},
+    SvError,
+};
+use crate::{dnf::Dnf, par::prelude::*, Game, OwnerId, ShapleyValues};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, error, fmt, fs, io, path::Path};
+
+/// Version of the on-disk format of `SvIndex`, bumped on any incompatible change.
+pub const SV_INDEX_VERSION: u32 = 2;
+
+/// The decomposition tree of a game with its IE coefficients, which can be saved to and loaded
+/// from a file.
+///
+/// The file is JSON with the IE coefficients as decimal strings, so that it is the same with or
+/// without the `bigint` feature. The nodes of the tree are a flat list in post-order, where each
+/// node refers to its children by their positions in the list, so a tree of any depth is saved
+/// without nesting JSON values. Loading validates the version of the format, that the nodes form
+/// a tree, that the owners of the tree are those of the game, and that the IE coefficients of each
+/// node are those of its children, so a corrupted file is an error rather than a panic or wrong
+/// values.
+#[derive(Debug, Clone)]
+pub struct SvIndex {
+    game: Game,
+    ablation_type: Option<AblationType>,
+    tree: DecomposeTree,
+}
+
+/// `SvIndex` as saved, borrowing the nodes of the tree.
+#[derive(Serialize)]
+struct SvIndexRef<'a> {
+    version: u32,
+    game: &'a Game,
+    ablation_type: Option<AblationType>,
+    nodes: Vec<NodeRef<'a>>,
+}
+
+/// `SvIndex` as loaded, before its tree is rebuilt from the nodes.
+#[derive(Deserialize)]
+struct SvIndexData {
+    version: u32,
+    game: Game,
+    ablation_type: Option<AblationType>,
+    nodes: Vec<NodeData>,
+}
+
+/// A node of `DecomposeTree` with the positions of its children in the list of nodes.
+#[derive(Serialize)]
+enum NodeRef<'a> {
+    Var(OwnerId),
+    And {
+        coeffs: &'a IECoeffs,
+        products: &'a [IECoeffs],
+        children: Vec<usize>,
+    },
+    Or {
+        coeffs: &'a IECoeffs,
+        products: &'a [IECoeffs],
+        children: Vec<usize>,
+    },
+    Hybrid {
+        coeffs: &'a IECoeffs,
+        hybrid_coeffs: &'a HybridCoeffs,
+        hybrid_exp: &'a Dnf<usize>,
+        children: Vec<usize>,
+    },
+    Leaf {
+        coeffs: &'a IECoeffs,
+        exp: &'a Dnf<OwnerId>,
+    },
+}
+
+/// Same as `NodeRef`, but owned.
+#[derive(Deserialize)]
+enum NodeData {
+    Var(OwnerId),
+    And {
+        coeffs: IECoeffs,
+        products: Vec<IECoeffs>,
+        children: Vec<usize>,
+    },
+    Or {
+        coeffs: IECoeffs,
+        products: Vec<IECoeffs>,
+        children: Vec<usize>,
+    },
+    Hybrid {
+        coeffs: IECoeffs,
+        hybrid_coeffs: HybridCoeffs,
+        hybrid_exp: Dnf<usize>,
+        children: Vec<usize>,
+    },
+    Leaf {
+        coeffs: IECoeffs,
+        exp: Dnf<OwnerId>,
+    },
+}
+
+/// The nodes of `tree` in post-order, i.e., the root last.
+fn tree_to_nodes(tree: &DecomposeTree) -> Vec<NodeRef<'_>> {
+    let mut nodes = vec![];
+    // the positions of the children of a node are the last ones on `positions`
+    let mut positions = vec![];
+    let mut stack = vec![(tree, false)];
+    while let Some((node, visited)) = stack.pop() {
+        let children = node.children();
+        if !visited && !children.is_empty() {
+            stack.push((node, true));
+            stack.extend(children.iter().rev().map(|c| (c, false)));
+            continue;
+        }
+        let children = positions.split_off(positions.len() - children.len());
+        positions.push(nodes.len());
+        nodes.push(match node {
+            DecomposeTree::Var(owner_id) => NodeRef::Var(*owner_id),
+            DecomposeTree::And {
+                coeffs, products, ..
+            } => NodeRef::And {
+                coeffs,
+                products,
+                children,
+            },
+            DecomposeTree::Or {
+                coeffs, products, ..
+            } => NodeRef::Or {
+                coeffs,
+                products,
+                children,
+            },
+            DecomposeTree::Hybrid {
+                coeffs,
+                hybrid_coeffs,
+                hybrid_exp,
+                ..
+            } => NodeRef::Hybrid {
+                coeffs,
+                hybrid_coeffs,
+                hybrid_exp,
+                children,
+            },
+            DecomposeTree::Leaf { coeffs, exp } => NodeRef::Leaf { coeffs, exp },
+        });
+    }
+    nodes
+}
+
+/// The tree of nodes in post-order as saved by `tree_to_nodes`, or `None` unless each node but
+/// the last one is a child of exactly one node after it.
+fn nodes_to_tree(nodes: Vec<NodeData>) -> Option<DecomposeTree> {
+    let mut built: Vec<Option<DecomposeTree>> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        // only the nodes before are built, and each of them is taken once
+        let mut take_children = |children: Vec<usize>| -> Option<Vec<DecomposeTree>> {
+            children
+                .into_iter()
+                .map(|c| built.get_mut(c)?.take())
+                .collect()
+        };
+        let tree = match node {
+            NodeData::Var(owner_id) => DecomposeTree::Var(owner_id),
+            NodeData::And {
+                coeffs,
+                products,
+                children,
+            } => DecomposeTree::And {
+                coeffs,
+                products,
+                children: take_children(children)?,
+            },
+            NodeData::Or {
+                coeffs,
+                products,
+                children,
+            } => DecomposeTree::Or {
+                coeffs,
+                products,
+                children: take_children(children)?,
+            },
+            NodeData::Hybrid {
+                coeffs,
+                hybrid_coeffs,
+                hybrid_exp,
+                children,
+            } => DecomposeTree::Hybrid {
+                coeffs,
+                hybrid_coeffs,
+                hybrid_exp,
+                children: take_children(children)?,
+            },
+            NodeData::Leaf { coeffs, exp } => DecomposeTree::Leaf { coeffs, exp },
+        };
+        built.push(Some(tree));
+    }
+    let root = built.pop()??;
+    built.iter().all(Option::is_none).then_some(root)
+}
+
+impl SvIndex {
+    /// Decompose a game.
+    ///
+    /// Panic if a coefficient overflows or a leaf is too large. See `try_build`.
+    pub fn build(game: &Game) -> Self {
+        Self::try_build(game, None).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Decompose a game, with the kind of decomposition of `ablation_type` disabled.
+    pub fn try_build(game: &Game, ablation_type: Option<AblationType>) -> Result<Self, SvError> {
+        let tree = try_build_tree(game, &TreeContext::new(ablation_type))?;
+        Ok(Self {
+            game: game.clone(),
+            ablation_type,
+            tree,
+        })
+    }
+
+    /// The game of the index.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Shapley values of the game.
+    pub fn cal_sv(&self) -> ShapleyValues {
+        self.try_cal_sv_for(None).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Shapley values of the owners in both `owners` and the game.
+    pub fn cal_sv_for(&self, owners: &BTreeSet<OwnerId>) -> ShapleyValues {
+        self.try_cal_sv_for(Some(owners))
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn try_cal_sv_for(&self, owners: Option<&BTreeSet<OwnerId>>) -> Result<ShapleyValues, SvError> {
+        let ctx = TreeContext::new(self.ablation_type);
+        Ok(
+            try_cal_tree_owner_coeffs_for(&self.game, &self.tree, &ctx, owners)?
+                .into_par_iter()
+                .map(|(owner_id, coeffs)| (owner_id, coeffs.to_sv()))
+                .collect(),
+        )
+    }
+
+    /// Save the index as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SvIndexError> {
+        let index = SvIndexRef {
+            version: SV_INDEX_VERSION,
+            game: &self.game,
+            ablation_type: self.ablation_type,
+            nodes: tree_to_nodes(&self.tree),
+        };
+        fs::write(path, serde_json::to_vec(&index)?)?;
+        Ok(())
+    }
+
+    /// Load an index saved by `save`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SvIndexError> {
+        let data: SvIndexData = serde_json::from_slice(&fs::read(path)?)?;
+        if data.version != SV_INDEX_VERSION {
+            return Err(SvIndexError::Version(data.version));
+        }
+        let index = Self {
+            game: data.game,
+            ablation_type: data.ablation_type,
+            tree: nodes_to_tree(data.nodes).ok_or(SvIndexError::InvalidTree)?,
+        };
+        if !index.tree_is_consistent() {
+            return Err(SvIndexError::InvalidTree);
+        }
+        if !index.tree.coeffs_are_consistent() {
+            return Err(SvIndexError::InvalidCoeffs);
+        }
+        Ok(index)
+    }
+
+    /// Whether the owners of the tree are those of the minimized DNF of the game, and each node
+    /// has the coefficients of its children looked up by `cal_coeffs_for`.
+    fn tree_is_consistent(&self) -> bool {
+        let mut owners = BTreeSet::new();
+        let mut consistent = true;
+        self.tree.visit(&mut |node| match node {
+            DecomposeTree::Var(owner_id) => consistent &= owners.insert(*owner_id),
+            DecomposeTree::And {
+                products, children, ..
+            }
+            | DecomposeTree::Or {
+                products, children, ..
+            } => consistent &= !children.is_empty() && products.len() == children.len(),
+            DecomposeTree::Hybrid {
+                hybrid_coeffs,
+                hybrid_exp,
+                children,
+                ..
+            } => {
+                consistent &= hybrid_coeffs.input_len() == children.len()
+                    && hybrid_exp.all_variables().into_iter().eq(0..children.len())
+            }
+            DecomposeTree::Leaf { exp, .. } => {
+                let variables = exp.all_variables();
+                consistent &= owners.is_disjoint(&variables);
+                owners.extend(variables);
+            }
+        });
+        consistent && owners == self.game.dnf.minimized().all_variables()
+    }
+}
+
+/// Error when saving or loading an `SvIndex`.
+#[derive(Debug)]
+pub enum SvIndexError {
+    Io(io::Error),
+    /// The file is not a valid JSON index.
+    Format(serde_json::Error),
+    /// The file is of another version of the format than `SV_INDEX_VERSION`.
+    Version(u32),
+    /// The nodes do not form a tree, or the tree does not match the game, e.g., its owners
+    /// differ.
+    InvalidTree,
+    /// The IE coefficients of a node are not those of its children, or those of a leaf are not of
+    /// a probability. See `DecomposeTree::coeffs_are_consistent`.
+    InvalidCoeffs,
+}
+
+impl fmt::Display for SvIndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Format(e) => write!(f, "invalid index: {e}"),
+            Self::Version(version) => write!(
+                f,
+                "index of version {version}, expected version {SV_INDEX_VERSION}"
+            ),
+            Self::InvalidTree => write!(f, "the decomposition tree does not match the game"),
+            Self::InvalidCoeffs => write!(f, "the IE coefficients of the tree are corrupted"),
+        }
+    }
+}
+
+impl error::Error for SvIndexError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Format(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SvIndexError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SvIndexError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Format(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::synthesis_sv::recursive_decompose::cal_sv_recursive_decompose,
+        dnf,
+        dnf::{Dnf, Implicant},
+        tests::{assert_f64_eq, game},
+    };
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sv-index-{name}-{}.json", std::process::id()))
+    }
+
+    #[test]
+    fn test_save_load() {
+        let games: [Dnf<u32>; 4] = [
+            dnf!(1 2 + 1 3 + 2 3 + 4 5 6 + 6 7),
+            dnf!(1 2 + 2 3 + 3 4 + 4 1 + 5),
+            dnf!(1 2 3),
+            Dnf::false_exp(),
+        ];
+        for (i, dnf) in games.into_iter().enumerate() {
+            let game = game(dnf);
+            let path = temp_path(&format!("save-load-{i}"));
+            SvIndex::build(&game).save(&path).unwrap();
+            let index = SvIndex::load(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(&game, index.game());
+            let expect = cal_sv_recursive_decompose(&game);
+            let actual = index.cal_sv();
+            assert_eq!(expect.len(), actual.len());
+            for (owner_id, u) in expect.iter() {
+                assert_f64_eq(*u, actual[owner_id]);
+            }
+        }
+
+        let game = game(dnf!(1 2 + 1 3 + 4));
+        let index = SvIndex::try_build(&game, Some(AblationType::NoHybrid)).unwrap();
+        let sv = index.cal_sv_for(&BTreeSet::from([OwnerId(1), OwnerId(9)]));
+        assert_eq!(1, sv.len());
+        assert_f64_eq(
+            cal_sv_recursive_decompose(&game)[&OwnerId(1)],
+            sv[&OwnerId(1)],
+        );
+    }
+
+    #[test]
+    fn test_save_deep_tree() {
+        // x1 (x2 + x3 (x4 + x5 (...))), nested far deeper than `serde_json` reads
+        let dnf: Dnf<OwnerId> = (0..50)
+            .map(|k| {
+                (0..=k)
+                    .map(|i| OwnerId(2 * i + 1))
+                    .chain([OwnerId(2 * k + 2)])
+                    .collect::<Implicant<_>>()
+            })
+            .collect();
+        let game = Game::from_dnf(dnf);
+        let index = SvIndex::build(&game);
+        assert!(index.tree.stats().depth > 50);
+
+        let path = temp_path("deep");
+        index.save(&path).unwrap();
+        let loaded = SvIndex::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let expect = cal_sv_recursive_decompose(&game);
+        let actual = loaded.cal_sv();
+        assert_eq!(expect.len(), actual.len());
+        for (owner_id, u) in expect.iter() {
+            assert_f64_eq(*u, actual[owner_id]);
+        }
+    }
+
+    #[test]
+    fn test_load_corrupted() {
+        let game = game(dnf!(1 2 + 1 3 + 2 3 + 4));
+        let path = temp_path("corrupted");
+        SvIndex::build(&game).save(&path).unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+
+        // truncated
+        fs::write(&path, &json[..json.len() / 2]).unwrap();
+        assert!(matches!(SvIndex::load(&path), Err(SvIndexError::Format(_))));
+
+        // another version
+        let other_version = json.replacen("\"version\":2", "\"version\":99", 1);
+        fs::write(&path, other_version).unwrap();
+        assert!(matches!(
+            SvIndex::load(&path),
+            Err(SvIndexError::Version(99))
+        ));
+
+        // an owner of the tree renamed, i.e., not in the game
+        let renamed = json.replacen("{\"Var\":4}", "{\"Var\":5}", 1);
+        assert_ne!(json, renamed);
+        fs::write(&path, renamed).unwrap();
+        assert!(matches!(
+            SvIndex::load(&path),
+            Err(SvIndexError::InvalidTree)
+        ));
+
+        // a node shared by two parents, i.e., not a tree
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let root = value["nodes"].as_array_mut().unwrap().last_mut().unwrap();
+        let children = &mut root["Or"]["children"];
+        children[1] = children[0].clone();
+        fs::write(&path, value.to_string()).unwrap();
+        assert!(matches!(
+            SvIndex::load(&path),
+            Err(SvIndexError::InvalidTree)
+        ));
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(SvIndex::load(&path), Err(SvIndexError::Io(_))));
+    }
+
+    #[test]
+    fn test_load_corrupted_coeffs() {
+        // the majority of 1, 2 and 3 is a hybrid node, or a leaf without hybrid decomposition
+        let game = game(dnf!(1 2 + 1 3 + 2 3 + 4));
+        let path = temp_path("corrupted-coeffs");
+        for (ablation_type, kind) in [(None, "Hybrid"), (Some(AblationType::NoHybrid), "Leaf")] {
+            let index = SvIndex::try_build(&game, ablation_type).unwrap();
+            index.save(&path).unwrap();
+            assert!(SvIndex::load(&path).is_ok());
+
+            let mut value: serde_json::Value =
+                serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+            let node = value["nodes"]
+                .as_array_mut()
+                .unwrap()
+                .iter_mut()
+                .find(|node| node.get(kind).is_some())
+                .unwrap();
+            // 3 p^2 - 2 p^3 of the majority
+            let coeffs = &mut node[kind]["coeffs"];
+            assert_eq!(serde_json::json!([[2, "3"], [3, "-2"]]), *coeffs);
+            coeffs[0][1] = "4".into();
+            fs::write(&path, value.to_string()).unwrap();
+            assert!(matches!(
+                SvIndex::load(&path),
+                Err(SvIndexError::InvalidCoeffs)
+            ));
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
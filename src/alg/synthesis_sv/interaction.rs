@@ -0,0 +1,102 @@
+use super::{
+    decompose_tree::{AblationType, DecomposeTree},
+    iec::*,
+};
+use crate::{Game, OwnerId};
+use std::collections::BTreeMap;
+
+/// The Shapley interaction index of every pair of owners `(i, j)` with `i < j`, i.e., the
+/// weighted average of `v(S + i + j) - v(S + i) - v(S + j) + v(S)` over the coalitions `S`
+/// without `i` and `j`, with weights `|S|! (n - |S| - 2)! / (n - 1)!`.
+///
+/// A positive index means that the two owners are more valuable together than apart, e.g., two
+/// tuples in the same implicant, while a negative one means that they are substitutes. Pairs
+/// with a null player are zero.
+///
+/// Panic if a coefficient overflows.
+pub fn cal_shapley_interaction_pairs(game: &Game) -> BTreeMap<(OwnerId, OwnerId), f64> {
+    cal_shapley_interaction_pairs_with(game, None)
+}
+
+fn cal_shapley_interaction_pairs_with(
+    game: &Game,
+    ablation_type: Option<AblationType>,
+) -> BTreeMap<(OwnerId, OwnerId), f64> {
+    let mut ans = BTreeMap::new();
+    for (k, i) in game.owner_set.iter().enumerate() {
+        for j in game.owner_set.iter().skip(k + 1) {
+            ans.insert((*i, *j), 0.);
+        }
+    }
+    if game.dnf.is_false() || game.dnf.is_true() {
+        return ans;
+    }
+
+    let tree = DecomposeTree::from_game(game, ablation_type);
+    let pair_coeffs = tree
+        .cal_pair_coeffs(&vertical_identity())
+        .unwrap_or_else(|e| panic!("{e}"));
+    for (pair, coeffs) in pair_coeffs {
+        ans.insert(pair, coeffs.to_interaction());
+    }
+    ans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::exact::cal_shapley_interaction_pairs_brute_force,
+        dnf,
+        tests::{assert_f64_eq, game},
+        OwnerSet,
+    };
+
+    #[test]
+    fn test_interaction_pairs() {
+        let games = [
+            game(dnf!(1 2)),
+            game(dnf!(1 + 2)),
+            game(dnf!(1 2 + 3)),
+            game(dnf!(1 2 + 1 3 + 2 3)),
+            game(dnf!(1 2 + 1 3 + 2 3 + 4 5 + 6 7 8)),
+            game(dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5)),
+            game(dnf!(1 2 5 + 1 2 6 + 1 3 5 + 1 3 6 + 4 5 + 4 6)),
+            game(dnf!(1 2 3 + 2 3 4 + 3 4 5 + 1 5 + 6 7 + 6 8 + 7 8 9 10 11 12)),
+            game(dnf!(1 2 4 5 + 1 3 4 5 + 2 3 4 5 + 6 4 + 6 5 + 7 8 + 9 10 + 9 11 + 10 11)),
+        ];
+        let ablation_types = [
+            None,
+            Some(AblationType::NoHorizontal),
+            Some(AblationType::NoVertical),
+            Some(AblationType::NoHybrid),
+        ];
+        for game in games {
+            let expect = cal_shapley_interaction_pairs_brute_force(&game);
+            for ablation_type in ablation_types {
+                let actual = cal_shapley_interaction_pairs_with(&game, ablation_type);
+                assert_eq!(expect.len(), actual.len());
+                for (pair, v) in &expect {
+                    assert_f64_eq(*v, actual[pair]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_interaction_signs() {
+        // 1 and 2 are complements, 3 and 4 are substitutes, and 5 is a null player
+        let game = Game::new(
+            dnf!(1 2 + 3 + 4).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=5),
+        )
+        .unwrap();
+        let actual = cal_shapley_interaction_pairs(&game);
+        assert_eq!(10, actual.len());
+        assert!(actual[&(OwnerId(1), OwnerId(2))] > 0.);
+        assert!(actual[&(OwnerId(3), OwnerId(4))] < 0.);
+        for i in 1..=4 {
+            assert_eq!(0., actual[&(OwnerId(i), OwnerId(5))]);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::par::ThreadPool;
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Options of the recursive decomposition. The default matches `cal_sv_recursive_decompose`.
+///
+/// The results are the same with any thread pool or `sequential`, as the coefficients are summed
+/// up exactly.
+///
+/// In fact the Shapley values are bit-identical across runs with any of these options, so they are
+/// safe for snapshot tests without a deterministic mode. The parallel reductions only ever add up
+/// integer IE coefficients, and the floating-point value of each owner is then computed from its
+/// own coefficients by `IECoeffs::to_sv`, whose terms are summed by `stable_sum` regardless of
+/// their order. The Monte Carlo estimate of `leaf_fallback` is also the same for a given seed, as
+/// each batch of samples has its own RNG and only the integer counts are reduced before they are
+/// rounded to coefficients. Merging values of several games in parallel, e.g., by
+/// `ShapleyValues::merge`, is not, unless they are folded in order as in `cal_sv_batch_aggregate`.
+#[derive(Debug, Clone)]
+pub struct SvOptions {
+    /// Children of a node are processed sequentially if there are fewer of them than this, which
+    /// saves the overhead of rayon on small games, e.g., in batches of thousands of 3-4 player
+    /// games. With 0 (the default) children are always processed in parallel.
+    pub parallel_threshold: usize,
+    /// Maximum number of unions of implicants enumerated by inclusion-exclusion for a leaf, which
+    /// is exponential in the number of its implicants. A larger leaf fails with
+    /// `SvError::LeafTooLarge` instead of running out of memory. 10 million by default.
+    pub max_leaf_unions: usize,
+    /// Maximum number of pairs of unions of implicants of a leaf combined for each of its owners,
+    /// which is about the square of the number of unions. Beyond it the computation fails with
+    /// `SvError::TooManyCombinations` rather than seemingly hanging. None (no limit) by default.
+    pub max_union_combinations: Option<usize>,
+    /// Estimate the coefficients of a leaf beyond `max_leaf_unions` or `max_union_combinations`
+    /// by Monte Carlo sampling instead of failing, while the rest of the tree stays exact. None by
+    /// default.
+    pub leaf_fallback: Option<LeafFallback>,
+    /// Leaves with at most this many owners, capped at 128, enumerate the unions of their
+    /// implicants as bitsets rather than `BTreeSet`s, which is much faster. 128 by default, and 0
+    /// always uses `BTreeSet`s. The results are the same either way.
+    pub max_dense_leaf_variables: usize,
+    /// Run in this thread pool instead of the global pool of rayon, e.g., to keep it apart from
+    /// the pool of an application.
+    pub thread_pool: Option<Arc<ThreadPool>>,
+    /// Run on the calling thread, ignoring `thread_pool`, e.g., for profiling. The tree is built and
+    /// evaluated without entering any pool, and the children of a node are never split into rayon
+    /// jobs, so concurrent callers do not wait on each other. Operations on the DNF of a node,
+    /// e.g., decomposing it, may still be parallel in the global pool.
+    pub sequential: bool,
+    /// Called as the leaves of the decomposition are done, e.g., to show a progress bar. None by
+    /// default.
+    pub progress: Option<ProgressCallback>,
+    /// Give up with `SvError::Cancelled` once this token is cancelled, e.g., by another thread, or
+    /// once the `Arc<AtomicBool>` it is made from is set. It is checked at every node of the
+    /// decomposition and while enumerating the unions of the implicants of a leaf, so the rayon
+    /// tasks left all return early. None by default.
+    pub cancel: Option<CancelToken>,
+    /// Give up with `SvError::Cancelled` after this long, counted from the start of the
+    /// decomposition. It is ignored on wasm32, where no thread can be spawned to wait for it, so
+    /// the computation always runs to the end there. None by default.
+    pub timeout: Option<Duration>,
+}
+
+/// Progress of the recursive decomposition of a game, counted in leaves of the decomposition
+/// (owners and sub-expressions computed by inclusion-exclusion). Each leaf is done twice: once
+/// when its coefficients are computed while building the tree, and once when the coefficients of
+/// its owners are computed.
+///
+/// The events come from rayon workers, but one at a time and with `leaves_done` strictly
+/// increasing, so some counts may be skipped. The total is known from the first event on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressEvent {
+    pub phase: ProgressPhase,
+    pub leaves_done: usize,
+    pub leaves_total: usize,
+}
+
+/// Phase of the computation of a `ProgressEvent`, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProgressPhase {
+    /// The DNF is decomposed, and the leaves counted. Reported once with no leaf done.
+    Decompose,
+    /// The coefficients of the subtrees are computed bottom-up.
+    Build,
+    /// The coefficients of the owners are computed top-down.
+    Sv,
+}
+
+/// Callback of `SvOptions::progress`, which is called from rayon workers.
+#[derive(Clone)]
+pub struct ProgressCallback(pub Arc<dyn Fn(ProgressEvent) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(f: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Token to cancel computations from another thread. See `SvOptions::cancel`.
+///
+/// The clones of a token are cancelled together, and a cancelled token stays cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A token set by an existing flag, e.g., the shutdown flag of a service, which cancels the
+/// computations once it is true.
+impl From<Arc<AtomicBool>> for CancelToken {
+    fn from(flag: Arc<AtomicBool>) -> Self {
+        Self(flag)
+    }
+}
+
+/// Monte Carlo estimate of a leaf too large to compute exactly. See `SvOptions::leaf_fallback`.
+///
+/// Random orders of the owners of the leaf alone are sampled, and the number of its winning
+/// coalitions (and those each owner is pivotal for) of each size are estimated from the positions
+/// of the pivotal owners. These counts are rounded to integers, so the estimated coefficients are
+/// combined with those of the rest of the tree as exactly as any others. Each leaf is sampled
+/// with its own seed derived from `seed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeafFallback {
+    /// Number of sampled permutations.
+    pub samples: usize,
+    pub seed: u64,
+}
+
+impl Default for SvOptions {
+    fn default() -> Self {
+        Self {
+            parallel_threshold: 0,
+            max_leaf_unions: 10_000_000,
+            max_union_combinations: None,
+            leaf_fallback: None,
+            max_dense_leaf_variables: 128,
+            thread_pool: None,
+            sequential: false,
+            progress: None,
+            cancel: None,
+            timeout: None,
+        }
+    }
+}
+
+impl SvOptions {
+    /// The minimum number of children processed by a rayon job, for `with_min_len`.
+    pub(crate) fn min_len(&self) -> usize {
+        if self.sequential {
+            usize::MAX
+        } else {
+            self.parallel_threshold.max(1)
+        }
+    }
+
+    /// Run `f` in the pool of the options, or right away on the calling thread by default or if
+    /// `sequential`.
+    pub(crate) fn install<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(pool) if !self.sequential => pool.install(f),
+            _ => f(),
+        }
+    }
+}
+
+/// Options are equal if they share the same thread pool, progress callback and cancel token, which
+/// cannot be compared otherwise.
+impl PartialEq for SvOptions {
+    fn eq(&self, other: &Self) -> bool {
+        fn same<T, U: ?Sized>(a: &Option<T>, b: &Option<T>, arc: impl Fn(&T) -> &Arc<U>) -> bool {
+            match (a, b) {
+                (Some(a), Some(b)) => Arc::ptr_eq(arc(a), arc(b)),
+                (None, None) => true,
+                _ => false,
+            }
+        }
+        self.parallel_threshold == other.parallel_threshold
+            && self.max_leaf_unions == other.max_leaf_unions
+            && self.max_union_combinations == other.max_union_combinations
+            && self.leaf_fallback == other.leaf_fallback
+            && self.max_dense_leaf_variables == other.max_dense_leaf_variables
+            && same(&self.thread_pool, &other.thread_pool, |pool| pool)
+            && self.sequential == other.sequential
+            && same(&self.progress, &other.progress, |callback| &callback.0)
+            && same(&self.cancel, &other.cancel, |token| &token.0)
+            && self.timeout == other.timeout
+    }
+}
+
+impl Eq for SvOptions {}
@@ -1,231 +1,302 @@
-use super::iec::*;
+use super::{
+    decompose_tree::{
+        cal_owner_and_game_coeffs, cal_owner_coeffs, try_cal_owner_coeffs_for, CoeffsCache,
+        TreeContext,
+    },
+    iec::to_stratified_many,
+    ShapleyComputer, SvError, SvOptions,
+};
 use crate::{
-    dnf::{recursive_decompose, Dnf, RecursiveDecompose},
-    product_tree::ProductTree,
-    utils::hashmap_reduce,
-    Game, OwnerId, ShapleyValues,
+    par::prelude::*, utils::normalize_values, BanzhafValues, ExactShapleyValues, Game, OwnerId,
+    OwnerSet, ShapleyValues,
 };
-use rayon::prelude::*;
-use std::collections::BTreeSet;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
+/// Shapley values by the full recursive decomposition. Same as `ShapleyComputer::new(game)`, but
+/// panic if a coefficient overflows.
 pub fn cal_sv_recursive_decompose(game: &Game) -> ShapleyValues {
-    let d = recursive_decompose(&game.dnf, &game.owner_set);
-    let tree = DecomposeTree::new(d, true);
-    let gamma_map = IECoeffs::from([(0, 1)]);
-    tree.cal_sv(&gamma_map)
+    try_cal_sv_recursive_decompose(game).unwrap_or_else(|e| panic!("{e}"))
 }
 
-enum DecomposeTree {
-    Var(OwnerId),
-    And {
-        coeffs: Option<IECoeffs>,
-        products: Vec<IECoeffs>,
-        children: Vec<DecomposeTree>,
-    },
-    Or {
-        coeffs: Option<IECoeffs>,
-        products: Vec<IECoeffs>,
-        children: Vec<DecomposeTree>,
-    },
-    Hybrid {
-        coeffs: Option<IECoeffs>,
-        hybrid_coeffs: HybridCoeffs,
-        hybrid_exp: Dnf<usize>,
-        children: Vec<DecomposeTree>,
-    },
+/// Same as `cal_sv_recursive_decompose`, but return an error instead of panicking when an IE
+/// coefficient overflows, which never happens with the `bigint` feature.
+pub fn try_cal_sv_recursive_decompose(game: &Game) -> Result<ShapleyValues, SvError> {
+    ShapleyComputer::new(game).compute()
 }
 
-impl DecomposeTree {
-    fn new(input: RecursiveDecompose<OwnerId>, is_root: bool) -> Self {
-        match input {
-            RecursiveDecompose::Var(id) => Self::Var(id),
-            RecursiveDecompose::And(children) => {
-                let children: Vec<_> = children
-                    .into_par_iter()
-                    .map(|c| DecomposeTree::new(c, false))
-                    .collect();
-                let mut children_coeffs = Vec::with_capacity(children.len());
-                for c in &children {
-                    children_coeffs.push(c.coeffs());
-                }
-                let product_tree: ProductTree<IECoeffs> =
-                    ProductTree::new(children_coeffs, vertical_op, !is_root);
-                let products = product_tree.all_products(vertical_identity, vertical_op);
-                let coeffs = if is_root {
-                    None
-                } else {
-                    Some(product_tree.root())
-                };
-                Self::And {
-                    coeffs,
-                    products,
-                    children,
-                }
-            }
-            RecursiveDecompose::Or(children) => {
-                let children: Vec<_> = children
-                    .into_par_iter()
-                    .map(|c| DecomposeTree::new(c, false))
-                    .collect();
-                let mut children_coeffs = Vec::with_capacity(children.len());
-                for c in &children {
-                    children_coeffs.push(c.coeffs());
-                }
-                let product_tree: ProductTree<IECoeffs> =
-                    ProductTree::new(children_coeffs, horizontal_op, !is_root);
-                let products = product_tree.all_products(horizontal_identity, horizontal_op);
-                let coeffs = if is_root {
-                    None
-                } else {
-                    Some(product_tree.root())
-                };
-                Self::Or {
-                    coeffs,
-                    products,
-                    children,
-                }
-            }
-            RecursiveDecompose::Hybrid {
-                hybrid_exp,
-                sub_exps,
-            } => {
-                let children: Vec<_> = sub_exps
-                    .into_par_iter()
-                    .map(|c| DecomposeTree::new(c, false))
-                    .collect();
-                let mut children_coeffs = Vec::with_capacity(children.len());
-                for c in &children {
-                    children_coeffs.push(c.coeffs());
-                }
-                let hybrid_coeffs = HybridCoeffs::new(&children_coeffs);
-                let coeffs = if is_root {
-                    None
-                } else {
-                    Some(hybrid_coeffs.exp_coeffs(&hybrid_exp))
-                };
-                Self::Hybrid {
-                    coeffs,
-                    hybrid_coeffs,
-                    hybrid_exp,
-                    children,
-                }
-            }
-        }
-    }
+/// Same as `cal_sv_recursive_decompose`, but with the given options.
+///
+/// Panic if a coefficient overflows, or a leaf is beyond `options.max_leaf_unions` without
+/// `options.leaf_fallback`.
+pub fn cal_sv_recursive_decompose_with(game: &Game, options: &SvOptions) -> ShapleyValues {
+    try_cal_sv_recursive_decompose_with(game, options).unwrap_or_else(|e| panic!("{e}"))
+}
 
-    fn coeffs(&self) -> IECoeffs {
-        match self {
-            DecomposeTree::Var(_) => IECoeffs::from([(1, 1)]),
-            DecomposeTree::And { coeffs, .. } => coeffs.clone().unwrap(),
-            DecomposeTree::Or { coeffs, .. } => coeffs.clone().unwrap(),
-            DecomposeTree::Hybrid { coeffs, .. } => coeffs.clone().unwrap(),
-        }
-    }
+/// Same as `cal_sv_recursive_decompose_with`, but return an error instead of panicking, e.g.,
+/// `SvError::Cancelled` soon after `options.cancel` is cancelled or `options.timeout` has passed.
+pub fn try_cal_sv_recursive_decompose_with(
+    game: &Game,
+    options: &SvOptions,
+) -> Result<ShapleyValues, SvError> {
+    ShapleyComputer::new(game)
+        .options(options.clone())
+        .compute()
+}
 
-    fn cal_sv(&self, gamma_map: &IECoeffs) -> ShapleyValues {
-        match self {
-            DecomposeTree::Var(owner_id) => {
-                let map_group_with_owner = IECoeffs::from([(1, 1)]);
-                let sv = (&map_group_with_owner * gamma_map).to_sv();
-                ShapleyValues::from([(*owner_id, sv)])
-            }
-            DecomposeTree::And {
-                products, children, ..
-            } => {
-                let var_children: Vec<_> = children
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, c)| match c {
-                        Self::Var(id) => Some((i, id)),
-                        _ => None,
-                    })
-                    .collect();
+/// Same as `cal_sv_recursive_decompose`, but reuse the coefficients of structurally identical
+/// subtrees through `cache`, which can be shared across games, e.g., in a batch.
+pub fn cal_sv_recursive_decompose_cached(game: &Game, cache: &CoeffsCache) -> ShapleyValues {
+    let ctx = TreeContext {
+        cache: Some(cache),
+        ..Default::default()
+    };
+    try_cal_owner_coeffs_for(game, &ctx, None)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .into_par_iter()
+        .map(|(owner_id, coeffs)| (owner_id, coeffs.to_sv()))
+        .collect()
+}
 
-                let mut ans = children
-                    .par_iter()
-                    .enumerate()
-                    .filter(|(_, c)| !matches!(c, Self::Var(_)))
-                    .map(|(i, c)| {
-                        let iece_map = &products[i];
-                        let next_gamma_map = gamma_map * iece_map;
-                        c.cal_sv(&next_gamma_map)
-                    })
-                    .reduce(ShapleyValues::default, hashmap_reduce);
-
-                if let Some((i, _)) = var_children.first() {
-                    let iece_map = &products[*i];
-                    let next_gamma_map = gamma_map * iece_map;
-                    let sv = (&IECoeffs::from([(1, 1)]) * &next_gamma_map).to_sv();
-                    for (_, id) in var_children {
-                        ans.insert(*id, sv);
-                    }
-                }
+/// Shapley values of only the owners in `owners`. The whole game is still decomposed, but only
+/// the children and the leaf owners leading to `owners` are evaluated, which is much faster when
+/// `owners` is small.
+///
+/// Owners in `owners` but not in the game are ignored.
+pub fn cal_sv_for(game: &Game, owners: &BTreeSet<OwnerId>) -> ShapleyValues {
+    try_cal_owner_coeffs_for(game, &TreeContext::default(), Some(owners))
+        .unwrap_or_else(|e| panic!("{e}"))
+        .into_par_iter()
+        .map(|(owner_id, coeffs)| (owner_id, coeffs.to_sv()))
+        .collect()
+}
 
-                ans
-            }
-            DecomposeTree::Or {
-                products, children, ..
-            } => {
-                let var_children: Vec<_> = children
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, c)| match c {
-                        Self::Var(id) => Some((i, id)),
-                        _ => None,
-                    })
-                    .collect();
+/// Shapley values of only the owners in `targets`, skipping the subtrees of the decomposition
+/// without any of them. See `cal_sv_for`.
+pub fn cal_sv_for_owners(game: &Game, targets: &OwnerSet) -> ShapleyValues {
+    cal_sv_for(game, targets)
+}
 
-                let mut ans = children
+/// Same as `cal_sv_recursive_decompose`, but only the owner with the smallest id of each of
+/// `Game::symmetry_classes` is computed by `cal_sv_for`, and its value copied to the rest of
+/// the class. It pays off when a few large classes make up most of the owners.
+pub fn cal_sv_by_symmetry(game: &Game) -> ShapleyValues {
+    let classes = game.symmetry_classes();
+    let representatives: BTreeSet<_> = classes.iter().map(|c| *c.first().unwrap()).collect();
+    let sv = cal_sv_for(game, &representatives);
+    classes
+        .iter()
+        .flat_map(|c| {
+            let u = sv.get_or_zero(c.first().unwrap());
+            c.iter().map(move |owner_id| (*owner_id, u))
+        })
+        .collect()
+}
+
+/// Shapley value of the owners in `group` merged into a single owner, i.e., the value of the
+/// whole group when its owners always join together. See `Game::merge_owners`.
+///
+/// Owners in `group` but not in the game are ignored, so the value is 0 if none of them is.
+pub fn cal_group_sv(game: &Game, group: &OwnerSet) -> f64 {
+    let Some(new_id) = group.iter().find(|o| game.owner_set.contains(*o)) else {
+        return 0.;
+    };
+    let merged = game.merge_owners(group, *new_id);
+    cal_sv_for(&merged, &OwnerSet::from_iter([*new_id])).get_or_zero(new_id)
+}
+
+/// Shapley values in exact rational arithmetic, free of floating point errors.
+///
+/// The coefficients are computed in integers, and only the final weighted sum is rational.
+pub fn cal_sv_recursive_decompose_exact(game: &Game) -> ExactShapleyValues {
+    ShapleyComputer::new(game)
+        .compute_exact()
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Normalized Banzhaf index, i.e., the swing counts scaled to sum up to 1.
+pub fn cal_banzhaf_recursive_decompose(game: &Game) -> BanzhafValues {
+    let mut ans = cal_banzhaf_swings_recursive_decompose(game);
+    normalize_values(&mut ans);
+    ans
+}
+
+/// Number of coalitions in which each owner is a swing player, rounded from `cal_swing_counts`
+/// so that it works for any number of owners.
+pub fn cal_banzhaf_swings_recursive_decompose(game: &Game) -> BanzhafValues {
+    let n = game.owner_len();
+    cal_owner_coeffs(game, None)
+        .into_par_iter()
+        .map(|(owner_id, coeffs)| {
+            let swings = coeffs.to_swing_count(n).to_f64().unwrap_or(f64::INFINITY);
+            (owner_id, swings)
+        })
+        .collect()
+}
+
+/// Kind of power index computed by `cal_indices`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IndexKind {
+    /// Same as `cal_sv_recursive_decompose`.
+    Shapley,
+    /// Same as `cal_banzhaf_recursive_decompose`, i.e., normalized.
+    Banzhaf,
+    /// Same as `cal_banzhaf_swings_recursive_decompose`.
+    BanzhafSwings,
+}
+
+/// Several power indices of each owner from a single decomposition of the game, e.g., both the
+/// Shapley value and the Banzhaf index for the price of one.
+///
+/// The IE coefficients of the owners are the same for every index, which only differ in how they
+/// are weighted at the end, so the tree is built and evaluated once. The values are equal to those
+/// of the standalone functions of `IndexKind`.
+pub fn cal_indices(game: &Game, kinds: &[IndexKind]) -> BTreeMap<IndexKind, ShapleyValues> {
+    let n = game.owner_len();
+    let coeffs = cal_owner_coeffs(game, None);
+    let swings = || -> BanzhafValues {
+        coeffs
+            .par_iter()
+            .map(|(owner_id, coeffs)| {
+                let swings = coeffs.to_swing_count(n).to_f64().unwrap_or(f64::INFINITY);
+                (*owner_id, swings)
+            })
+            .collect()
+    };
+    kinds
+        .iter()
+        .map(|kind| {
+            let values = match kind {
+                IndexKind::Shapley => coeffs
                     .par_iter()
-                    .enumerate()
-                    .filter(|(_, c)| !matches!(c, Self::Var(_)))
-                    .map(|(i, c)| {
-                        let iece_map = &products[i];
-                        let next_gamma_map = gamma_map - &(gamma_map * iece_map);
-                        c.cal_sv(&next_gamma_map)
-                    })
-                    .reduce(ShapleyValues::default, hashmap_reduce);
-
-                if let Some((i, _)) = var_children.first() {
-                    let iece_map = &products[*i];
-                    let next_gamma_map = gamma_map - &(gamma_map * iece_map);
-                    let sv = (&IECoeffs::from([(1, 1)]) * &next_gamma_map).to_sv();
-                    for (_, id) in var_children {
-                        ans.insert(*id, sv);
-                    }
+                    .map(|(owner_id, coeffs)| (*owner_id, coeffs.to_sv()))
+                    .collect(),
+                IndexKind::Banzhaf => {
+                    let mut values = swings();
+                    normalize_values(&mut values);
+                    values.into_iter().collect()
                 }
+                IndexKind::BanzhafSwings => swings().into_iter().collect(),
+            };
+            (*kind, values)
+        })
+        .collect()
+}
 
-                ans
-            }
-            DecomposeTree::Hybrid {
-                hybrid_coeffs,
-                hybrid_exp,
-                children,
-                ..
-            } => children
-                .par_iter()
-                .enumerate()
-                .map(|(i, c)| {
-                    let owner_set = BTreeSet::from([i]);
-                    let exp_p2 = hybrid_exp.partial_eval(&owner_set, true);
-                    let exp_p3 = hybrid_exp.partial_exp_complement(&owner_set);
-                    let exp_p2_unions = exp_to_input_unions(&exp_p2);
-                    let exp_p3_unions = exp_to_input_unions(&exp_p3);
-                    let map_p2 = hybrid_coeffs.exp_unions_coeffs(&exp_p2_unions);
-                    let iece_map =
-                        hybrid_coeffs.exp_unions_interaction(&exp_p2_unions, &exp_p3_unions);
-                    let next_gamma_map = gamma_map * &(map_p2 - iece_map);
-                    c.cal_sv(&next_gamma_map)
-                })
-                .reduce(ShapleyValues::default, hashmap_reduce),
+/// Exact number of coalitions in which each owner is a swing player, i.e., wins with the owner
+/// but loses without, from which the Banzhaf index and the like are derived.
+///
+/// Panic if a count does not fit in `u128`, which is only possible with more than 128 owners.
+pub fn cal_swing_counts(game: &Game) -> HashMap<OwnerId, u128> {
+    let n = game.owner_len();
+    cal_owner_coeffs(game, None)
+        .into_par_iter()
+        .map(|(owner_id, coeffs)| {
+            let swings = u128::try_from(coeffs.to_swing_count(n))
+                .unwrap_or_else(|_| panic!("swing count of owner {owner_id} overflows u128"));
+            (owner_id, swings)
+        })
+        .collect()
+}
+
+/// Probability that each owner is pivotal when it joins a uniformly random coalition of `s` of
+/// the other owners, at index `s` of its row. See `IECoeffs::to_stratified`, whose binomial
+/// coefficients are computed once for all the owners.
+///
+/// A random permutation puts an owner after each number of others with probability `1 / n`, so
+/// the Shapley value of an owner is the mean of its row, and null players have rows of zeros.
+pub fn cal_sv_stratified(game: &Game) -> BTreeMap<OwnerId, Vec<f64>> {
+    let mut coeffs = cal_owner_coeffs(game, None);
+    for owner_id in game.owner_set.iter() {
+        coeffs.entry(*owner_id).or_default();
+    }
+    let (owners, coeffs): (Vec<_>, Vec<_>) = coeffs.iter().unzip();
+    let stratified = to_stratified_many(&coeffs, game.owner_len());
+    owners.into_iter().copied().zip(stratified).collect()
+}
+
+/// Coleman's indices of each owner. See `cal_coleman`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColemanIndices {
+    /// Ratio of the coalitions that an owner turns from losing into winning by joining, i.e., its
+    /// swing count over the number of losing coalitions.
+    pub power_to_initiate: HashMap<OwnerId, f64>,
+    /// Ratio of the winning coalitions that an owner turns into losing by leaving, i.e., its swing
+    /// count over the number of winning coalitions.
+    pub power_to_prevent: HashMap<OwnerId, f64>,
+}
+
+/// Coleman's power to initiate and power to prevent of each owner.
+///
+/// The swing counts are from the coefficients of the owners as in `cal_swing_counts`, and the
+/// number of winning coalitions from the coefficients of the whole game evaluated the same way, so
+/// nothing is enumerated. Every index is 0 if there is no losing (resp. winning) coalition.
+pub fn cal_coleman(game: &Game) -> ColemanIndices {
+    let n = game.owner_len();
+    let (owner_coeffs, game_coeffs) = cal_owner_and_game_coeffs(game);
+    // the game coefficients at probability 1/2 scaled by 2^n, as for swing counts
+    let winning = game_coeffs.to_swing_count(n);
+    let losing = (BigInt::from(1) << n) - &winning;
+    let ratio = |swings: &BigInt, total: &BigInt| {
+        if total.is_zero() {
+            0.
+        } else {
+            BigRational::new(swings.clone(), total.clone())
+                .to_f64()
+                .unwrap_or(f64::NAN)
         }
+    };
+
+    let swings: Vec<_> = owner_coeffs
+        .into_par_iter()
+        .map(|(owner_id, coeffs)| (owner_id, coeffs.to_swing_count(n)))
+        .collect();
+    ColemanIndices {
+        power_to_initiate: swings
+            .iter()
+            .map(|(owner_id, s)| (*owner_id, ratio(s, &losing)))
+            .collect(),
+        power_to_prevent: swings
+            .iter()
+            .map(|(owner_id, s)| (*owner_id, ratio(s, &winning)))
+            .collect(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{dnf, tests::assert_f64_eq, OwnerSet};
+    use crate::{
+        alg::{
+            exact::{cal_banzhaf_swings_exact, cal_sv_brute_force},
+            synthesis_sv::{
+                decompose_tree::{decompose_stats, try_cal_sv_with},
+                recursive_decompose_ablation::{cal_sv_recursive_decompose_ablation, AblationType},
+                CancelToken, LeafFallback, ProgressCallback, ProgressPhase,
+            },
+        },
+        dnf,
+        dnf::{Dnf, Implicant},
+        gen::GameGenerator,
+        par::ThreadPoolBuilder,
+        tests::{assert_f64_eq, game, sixteen_implicants},
+        utils::{binom, stable_sum},
+        OwnerId, OwnerSet,
+    };
+    use itertools::Itertools;
+    use num_rational::BigRational;
+    use num_traits::{One, Zero};
+    use std::{
+        collections::BTreeMap,
+        iter, mem,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        time::{Duration, Instant},
+    };
 
     #[test]
     fn test_cal_sv_recursive_decompose() {
@@ -336,25 +407,968 @@ mod tests {
         assert_f64_eq(0.009523809523809545, sv[&OwnerId(9)]);
     }
 
+    #[test]
+    fn test_unanimity() {
+        // only the veto players 1 and 2 matter once 1 2 3 is absorbed
+        let game = Game {
+            dnf: dnf!(1 2 + 1 2 3).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([1, 2, 3, 4]),
+        };
+        let expect = cal_sv_brute_force(&game);
+        let sv = cal_sv_recursive_decompose(&game);
+        assert_eq!(4, sv.len());
+        for (o, u) in &sv {
+            assert_f64_eq(expect[o], *u);
+        }
+        assert_f64_eq(0.5, sv[&OwnerId(1)]);
+        assert_f64_eq(0., sv[&OwnerId(3)]);
+
+        let swings = cal_banzhaf_swings_recursive_decompose(&game);
+        let expect = cal_banzhaf_swings_exact(&game);
+        for (o, u) in &swings {
+            assert_f64_eq(expect[o], *u);
+        }
+
+        let sv = cal_sv_for(&game, &OwnerSet::from_iter([2, 3]));
+        assert_eq!(2, sv.len());
+        assert_f64_eq(0.5, sv[&OwnerId(2)]);
+        assert_f64_eq(0., sv[&OwnerId(3)]);
+    }
+
+    #[test]
+    fn test_null_owners() {
+        let game = Game::new(
+            dnf!(1 2).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter([1, 2, 3]),
+        )
+        .unwrap();
+
+        let sv = cal_sv_recursive_decompose(&game);
+        assert_eq!(3, sv.len());
+        assert_f64_eq(0.5, sv[&OwnerId(1)]);
+        assert_f64_eq(0.5, sv[&OwnerId(2)]);
+        assert_eq!(0., sv[&OwnerId(3)]);
+
+        let sv = cal_sv_recursive_decompose_exact(&game);
+        assert_eq!(BigRational::zero(), sv[&OwnerId(3)]);
+
+        let swings = cal_banzhaf_swings_recursive_decompose(&game);
+        assert_f64_eq(2., swings[&OwnerId(1)]);
+        assert_eq!(0., swings[&OwnerId(3)]);
+    }
+
+    #[test]
+    fn test_stratified() {
+        let factorial = |k: usize| (1..=k).map(|i| i as f64).product::<f64>();
+        let games = [
+            Game::from_dnf(
+                dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            ),
+            Game::from_dnf(dnf!(1 2 + 1 3 + 2 3 + 4 5 + 6 7 8).map_variable(|id| OwnerId(*id))),
+            Game::new(
+                dnf!(1 2 + 1 3 + 4).map_variable(|id| OwnerId(*id)),
+                OwnerSet::from_iter(1..=6),
+            )
+            .unwrap(),
+            Game::from_dnf(Dnf::true_exp()),
+        ];
+        for game in games {
+            let n = game.owner_len();
+            let sv = cal_sv_recursive_decompose(&game);
+            let coeffs = cal_owner_coeffs(&game, None);
+            let stratified = cal_sv_stratified(&game);
+            assert_eq!(n, stratified.len());
+            for (owner_id, row) in &stratified {
+                assert_eq!(n, row.len());
+                // `to_sv` is the mean of `to_stratified`, without going through it
+                let owner_coeffs = &coeffs[owner_id];
+                assert_eq!(*row, owner_coeffs.to_stratified(n));
+                assert_f64_eq(
+                    owner_coeffs.to_sv(),
+                    stable_sum(row.iter().copied()) / n as f64,
+                );
+                assert!(row.iter().all(|p| (0. ..=1.).contains(p)), "{row:?}");
+                // weight of each coalition of size s before the owner in a random permutation
+                let weighted = row.iter().enumerate().map(|(s, p)| {
+                    binom(s, n - 1) as f64 * factorial(s) * factorial(n - 1 - s) / factorial(n) * p
+                });
+                assert_f64_eq(sv.get_or_zero(owner_id), weighted.sum());
+
+                let others = game.owner_set.iter().filter(|o| *o != owner_id);
+                for (s, p) in row.iter().enumerate() {
+                    let coalitions: Vec<BTreeSet<_>> = others
+                        .clone()
+                        .copied()
+                        .combinations(s)
+                        .map(BTreeSet::from_iter)
+                        .collect();
+                    let pivotal = coalitions
+                        .iter()
+                        .filter(|c| {
+                            let mut with = (*c).clone();
+                            with.insert(*owner_id);
+                            game.is_winning(&with) && !game.is_winning(c)
+                        })
+                        .count();
+                    assert_f64_eq(pivotal as f64 / coalitions.len() as f64, *p);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cal_sv_recursive_decompose_exact() {
+        let game = Game {
+            dnf: dnf!(1 2 3).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([OwnerId(1), OwnerId(2), OwnerId(3)]),
+        };
+
+        let sv = cal_sv_recursive_decompose_exact(&game);
+        assert_eq!(BigRational::new(1.into(), 3.into()), sv[&OwnerId(1)]);
+        assert_eq!(BigRational::new(1.into(), 3.into()), sv[&OwnerId(2)]);
+        assert_eq!(BigRational::new(1.into(), 3.into()), sv[&OwnerId(3)]);
+
+        let game = Game {
+            dnf: dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([
+                OwnerId(1),
+                OwnerId(2),
+                OwnerId(3),
+                OwnerId(4),
+                OwnerId(5),
+            ]),
+        };
+
+        let sv = cal_sv_recursive_decompose_exact(&game);
+        assert_eq!(BigRational::new(1.into(), 15.into()), sv[&OwnerId(1)]);
+        assert_eq!(BigRational::new(7.into(), 30.into()), sv[&OwnerId(2)]);
+        assert_eq!(BigRational::new(19.into(), 60.into()), sv[&OwnerId(4)]);
+        assert_eq!(BigRational::one(), sv.values().sum::<BigRational>());
+
+        let game = Game {
+            dnf: dnf!(1 3 6 8 + 3 5 6 8 + 3 4 6 8 9).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([
+                OwnerId(1),
+                OwnerId(3),
+                OwnerId(4),
+                OwnerId(5),
+                OwnerId(6),
+                OwnerId(8),
+                OwnerId(9),
+            ]),
+        };
+
+        let sv = cal_sv_recursive_decompose_exact(&game);
+        assert_eq!(BigRational::one(), sv.values().sum::<BigRational>());
+    }
+
+    #[test]
+    fn test_exact_values() {
+        let r = |n: i64, d: i64| BigRational::new(n.into(), d.into());
+        let cases = [
+            (dnf!(1 2 3), vec![(1, r(1, 3)), (2, r(1, 3)), (3, r(1, 3))]),
+            (
+                dnf!(1 2 3 + 1 2 4),
+                vec![(1, r(5, 12)), (2, r(5, 12)), (3, r(1, 12)), (4, r(1, 12))],
+            ),
+            (
+                dnf!(1 2 3 4 + 1 2 3 5 + 6),
+                vec![
+                    (1, r(1, 15)),
+                    (2, r(1, 15)),
+                    (3, r(1, 15)),
+                    (4, r(1, 60)),
+                    (5, r(1, 60)),
+                    (6, r(23, 30)),
+                ],
+            ),
+            (
+                dnf!(1 + 2 + 3),
+                vec![(1, r(1, 3)), (2, r(1, 3)), (3, r(1, 3))],
+            ),
+            (
+                dnf!(1 4 5 + 2 4 5 + 3 4 5),
+                vec![
+                    (1, r(1, 30)),
+                    (2, r(1, 30)),
+                    (3, r(1, 30)),
+                    (4, r(9, 20)),
+                    (5, r(9, 20)),
+                ],
+            ),
+        ];
+
+        for (exp, expect) in cases {
+            let game = Game::from_dnf(exp.map_variable(|id| OwnerId(*id)));
+            let expect: ExactShapleyValues =
+                expect.into_iter().map(|(id, v)| (OwnerId(id), v)).collect();
+            let actual = cal_sv_recursive_decompose_exact(&game);
+            assert_eq!(expect, actual);
+
+            let approx = actual.to_f64();
+            for (o, u) in cal_sv_recursive_decompose(&game) {
+                assert_f64_eq(u, approx[&o]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cal_banzhaf_recursive_decompose() {
+        let game = Game {
+            dnf: dnf!(1 2 3).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([OwnerId(1), OwnerId(2), OwnerId(3)]),
+        };
+
+        let banzhaf = cal_banzhaf_recursive_decompose(&game);
+        assert_f64_eq(0.33333333333, banzhaf[&OwnerId(1)]);
+        assert_f64_eq(0.33333333333, banzhaf[&OwnerId(2)]);
+        assert_f64_eq(0.33333333333, banzhaf[&OwnerId(3)]);
+
+        let swings = cal_banzhaf_swings_recursive_decompose(&game);
+        assert_f64_eq(1., swings[&OwnerId(1)]);
+        assert_f64_eq(1., swings[&OwnerId(2)]);
+        assert_f64_eq(1., swings[&OwnerId(3)]);
+
+        // test for dictator
+        let game = Game {
+            dnf: dnf!(1).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([OwnerId(1)]),
+        };
+
+        let banzhaf = cal_banzhaf_recursive_decompose(&game);
+        assert_f64_eq(1., banzhaf[&OwnerId(1)]);
+
+        // test for simple majority, i.e., the weighted voting game [2; 1, 1, 1]
+        let game = Game {
+            dnf: dnf!(1 2 + 1 3 + 2 3).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([OwnerId(1), OwnerId(2), OwnerId(3)]),
+        };
+
+        let banzhaf = cal_banzhaf_recursive_decompose(&game);
+        assert_f64_eq(0.33333333333, banzhaf[&OwnerId(1)]);
+        assert_f64_eq(0.33333333333, banzhaf[&OwnerId(2)]);
+        assert_f64_eq(0.33333333333, banzhaf[&OwnerId(3)]);
+
+        let swings = cal_banzhaf_swings_recursive_decompose(&game);
+        assert_f64_eq(2., swings[&OwnerId(1)]);
+        assert_f64_eq(2., swings[&OwnerId(2)]);
+        assert_f64_eq(2., swings[&OwnerId(3)]);
+
+        // test for hybrid
+        let game = Game {
+            dnf: dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([
+                OwnerId(1),
+                OwnerId(2),
+                OwnerId(3),
+                OwnerId(4),
+                OwnerId(5),
+            ]),
+        };
+
+        let swings = cal_banzhaf_swings_recursive_decompose(&game);
+        assert_f64_eq(2., swings[&OwnerId(1)]);
+        assert_f64_eq(6., swings[&OwnerId(2)]);
+        assert_f64_eq(2., swings[&OwnerId(3)]);
+        assert_f64_eq(8., swings[&OwnerId(4)]);
+        assert_f64_eq(8., swings[&OwnerId(5)]);
+
+        let banzhaf = cal_banzhaf_recursive_decompose(&game);
+        assert_f64_eq(0.07692307692, banzhaf[&OwnerId(1)]);
+        assert_f64_eq(0.23076923076, banzhaf[&OwnerId(2)]);
+        assert_f64_eq(0.07692307692, banzhaf[&OwnerId(3)]);
+        assert_f64_eq(0.30769230769, banzhaf[&OwnerId(4)]);
+        assert_f64_eq(0.30769230769, banzhaf[&OwnerId(5)]);
+    }
+
+    #[test]
+    fn test_cal_indices() {
+        let games = [
+            Game::from_dnf(
+                dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            ),
+            Game::new(
+                dnf!(1 2 + 1 3 + 4).map_variable(|id| OwnerId(*id)),
+                OwnerSet::from_iter(1..=6),
+            )
+            .unwrap(),
+            Game::from_dnf(Dnf::true_exp()),
+        ];
+        for game in games {
+            let indices = cal_indices(
+                &game,
+                &[
+                    IndexKind::Banzhaf,
+                    IndexKind::Shapley,
+                    IndexKind::BanzhafSwings,
+                ],
+            );
+            assert!(indices.keys().copied().eq([
+                IndexKind::Shapley,
+                IndexKind::Banzhaf,
+                IndexKind::BanzhafSwings
+            ]));
+            assert_eq!(
+                cal_sv_recursive_decompose(&game),
+                indices[&IndexKind::Shapley]
+            );
+            assert_eq!(
+                cal_banzhaf_recursive_decompose(&game),
+                *indices[&IndexKind::Banzhaf]
+            );
+            assert_eq!(
+                cal_banzhaf_swings_recursive_decompose(&game),
+                *indices[&IndexKind::BanzhafSwings]
+            );
+        }
+
+        let game = Game::from_dnf(dnf!(1 2).map_variable(|id| OwnerId(*id)));
+        let indices = cal_indices(&game, &[IndexKind::Shapley]);
+        assert_eq!(1, indices.len());
+        assert_f64_eq(0.5, indices[&IndexKind::Shapley][&OwnerId(1)]);
+        assert!(cal_indices(&game, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_banzhaf_against_exact() {
+        let exps = [
+            dnf!(1 2 3 + 1 2 4),
+            dnf!(1 4 5 + 2 4 5 + 3 4 5),
+            dnf!(1 3 6 8 + 3 5 6 8 + 3 4 6 8 9),
+            dnf!(1 2 5 + 1 2 6 + 1 3 5 + 1 3 6 + 4 5 + 4 6),
+            dnf!(1 2 + 1 3 + 4),
+        ];
+
+        for exp in exps {
+            let game = Game::from_dnf(exp.map_variable(|id| OwnerId(*id)));
+            let expect = cal_banzhaf_swings_exact(&game);
+            let actual = cal_banzhaf_swings_recursive_decompose(&game);
+            assert_eq!(expect.len(), actual.len());
+            for (o, u) in &actual {
+                assert_f64_eq(expect[o], *u);
+            }
+        }
+    }
+
     #[test]
     fn test_performance() {
+        let game = sixteen_implicants();
+
+        let _ = cal_sv_recursive_decompose(&game);
+    }
+
+    #[test]
+    fn test_trivial_games() {
+        let owner_set = OwnerSet::from_iter([1, 2, 3]);
+        let cases = [
+            // never wins
+            (Dnf::new(), [0., 0., 0.]),
+            // always wins, so no owner is ever pivotal
+            (Dnf::true_exp(), [0., 0., 0.]),
+            // a dictator among null players
+            (dnf!(2).map_variable(|id| OwnerId(*id)), [0., 1., 0.]),
+        ];
+        for (exp, expect) in cases {
+            // not `Game::new`, which rejects a game that never wins
+            let game = Game {
+                dnf: exp,
+                owner_set: owner_set.clone(),
+            };
+            let mut results = vec![
+                cal_sv_recursive_decompose(&game),
+                cal_sv_recursive_decompose_with(&game, &SvOptions::default()),
+                cal_sv_for(&game, &owner_set),
+                cal_sv_brute_force(&game),
+            ];
+            for ablation_type in [
+                AblationType::NoHorizontal,
+                AblationType::NoVertical,
+                AblationType::NoHybrid,
+            ] {
+                results.push(cal_sv_recursive_decompose_ablation(&game, ablation_type));
+                let _ = decompose_stats(&game, Some(ablation_type));
+            }
+            for sv in results {
+                assert_eq!(3, sv.len(), "{}", game.dnf);
+                for (owner_id, u) in owner_set.iter().zip(expect) {
+                    assert_eq!(u, sv[owner_id], "{}", game.dnf);
+                }
+            }
+        }
+
+        // the only owner
+        let game = Game::from_dnf(dnf!(1).map_variable(|id| OwnerId(*id)));
+        assert_eq!(1., cal_sv_recursive_decompose(&game)[&OwnerId(1)]);
+        assert_eq!(
+            Some(&1),
+            cal_swing_counts(&game).get(&OwnerId(1)),
+            "the owner is pivotal in the empty coalition"
+        );
+    }
+
+    #[test]
+    fn test_coleman() {
+        let games = [
+            dnf!(1 2 + 1 3 + 2 3 + 4 5 6 + 4 7 + 8),
+            dnf!(1 2 + 2 3 + 3 4),
+            dnf!(1),
+        ];
+        for exp in games {
+            let exp = exp.map_variable(|id| OwnerId(*id));
+            // a null player doubles every count
+            let owner_set = exp
+                .all_variables()
+                .into_iter()
+                .chain([OwnerId(9)])
+                .collect();
+            let game = Game::new(exp, owner_set).unwrap();
+            let owners: Vec<_> = game.owner_set.iter().copied().collect();
+            let coalitions: Vec<BTreeSet<OwnerId>> = owners
+                .iter()
+                .copied()
+                .powerset()
+                .map(BTreeSet::from_iter)
+                .collect();
+            let winning = coalitions.iter().filter(|c| game.is_winning(c)).count();
+            let losing = coalitions.len() - winning;
+
+            let coleman = cal_coleman(&game);
+            assert_eq!(owners.len(), coleman.power_to_initiate.len());
+            assert_eq!(owners.len(), coleman.power_to_prevent.len());
+            for owner_id in &owners {
+                let swings = coalitions
+                    .iter()
+                    .filter(|c| !c.contains(owner_id) && !game.is_winning(c))
+                    .filter(|c| {
+                        let mut c = (*c).clone();
+                        c.insert(*owner_id);
+                        game.is_winning(&c)
+                    })
+                    .count();
+                assert_f64_eq(
+                    swings as f64 / losing as f64,
+                    coleman.power_to_initiate[owner_id],
+                );
+                assert_f64_eq(
+                    swings as f64 / winning as f64,
+                    coleman.power_to_prevent[owner_id],
+                );
+            }
+            assert_eq!(0., coleman.power_to_prevent[&OwnerId(9)]);
+        }
+
+        // no winning coalition, nor losing one
+        for exp in [Dnf::false_exp(), Dnf::true_exp()] {
+            let game = Game {
+                dnf: exp,
+                owner_set: OwnerSet::from_iter([OwnerId(1)]),
+            };
+            let coleman = cal_coleman(&game);
+            assert_eq!(0., coleman.power_to_initiate[&OwnerId(1)]);
+            assert_eq!(0., coleman.power_to_prevent[&OwnerId(1)]);
+        }
+    }
+
+    #[test]
+    fn test_progress() {
+        let game =
+            Game::from_dnf(dnf!(1 2 + 1 3 + 2 3 + 4 5 6 + 4 7 + 8).map_variable(|id| OwnerId(*id)));
+        let events = Arc::new(Mutex::new(vec![]));
+        let options = SvOptions {
+            progress: Some(ProgressCallback::new({
+                let events = events.clone();
+                move |event| events.lock().unwrap().push(event)
+            })),
+            ..Default::default()
+        };
+        let expect = cal_sv_recursive_decompose(&game);
+        for ablation_type in [None, Some(AblationType::NoHybrid)] {
+            let actual = try_cal_sv_with(&game, ablation_type, &options).unwrap();
+            assert_eq!(expect.len(), actual.len());
+
+            let events = mem::take(&mut *events.lock().unwrap());
+            assert!(!events.is_empty());
+            assert_eq!(ProgressPhase::Decompose, events[0].phase);
+            assert_eq!(0, events[0].leaves_done);
+            let total = events[0].leaves_total;
+            assert!(events.iter().all(|e| e.leaves_total == total));
+            for (prev, next) in events.iter().tuple_windows() {
+                assert!(prev.leaves_done < next.leaves_done, "{ablation_type:?}");
+                assert!(prev.phase <= next.phase, "{ablation_type:?}");
+            }
+            // every leaf is done twice, once in either phase
+            let last = events.last().unwrap();
+            assert_eq!(ProgressPhase::Sv, last.phase);
+            assert_eq!(total, last.leaves_done, "{ablation_type:?}");
+        }
+    }
+
+    #[test]
+    fn test_cancel() {
+        let game = sixteen_implicants();
+        let cancel = CancelToken::new();
+        let options = SvOptions {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+        assert!(try_cal_sv_recursive_decompose_with(&game, &options).is_ok());
+        cancel.cancel();
+        assert_eq!(
+            Err(SvError::Cancelled),
+            try_cal_sv_recursive_decompose_with(&game, &options)
+        );
+
+        // the leaf of all the implicants without Hybrid nodes takes far longer than 1 ms
+        let options = SvOptions {
+            timeout: Some(Duration::from_millis(1)),
+            ..Default::default()
+        };
+        let begin = Instant::now();
+        assert_eq!(
+            Err(SvError::Cancelled),
+            try_cal_sv_with(&game, Some(AblationType::NoHybrid), &options)
+        );
+        assert!(begin.elapsed() < Duration::from_secs(5));
+
+        // the watchdog is gone before the timeout
+        let options = SvOptions {
+            timeout: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        assert!(try_cal_sv_recursive_decompose_with(&game, &options).is_ok());
+    }
+
+    #[test]
+    fn test_cancel_flag() {
+        let game = sixteen_implicants();
+        let flag = Arc::new(AtomicBool::new(false));
+        let options = SvOptions {
+            cancel: Some(flag.clone().into()),
+            ..Default::default()
+        };
+        flag.store(true, Ordering::Relaxed);
+        assert_eq!(
+            Err(SvError::Cancelled),
+            try_cal_sv_recursive_decompose_with(&game, &options)
+        );
+
+        // set by a rayon worker once the DNF is decomposed, before the leaf is enumerated
+        flag.store(false, Ordering::Relaxed);
+        let options = SvOptions {
+            progress: Some(ProgressCallback::new({
+                let flag = flag.clone();
+                move |event| {
+                    if event.phase == ProgressPhase::Decompose {
+                        flag.store(true, Ordering::Relaxed);
+                    }
+                }
+            })),
+            ..options
+        };
+        assert_eq!(
+            Err(SvError::Cancelled),
+            try_cal_sv_with(&game, Some(AblationType::NoHybrid), &options)
+        );
+        assert!(flag.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_thread_pool() {
+        let game = sixteen_implicants();
+        let expect = cal_sv_recursive_decompose(&game);
+
+        // bitwise the same for any number of threads
+        for num_threads in [1, 2, 8] {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap();
+            let options = SvOptions {
+                thread_pool: Some(Arc::new(pool)),
+                ..Default::default()
+            };
+            let actual = cal_sv_recursive_decompose_with(&game, &options);
+            assert_eq!(expect.len(), actual.len());
+            for (owner_id, u) in &actual {
+                assert_eq!(
+                    expect[owner_id].to_bits(),
+                    u.to_bits(),
+                    "{num_threads} threads"
+                );
+            }
+        }
+
+        let options = SvOptions {
+            sequential: true,
+            ..Default::default()
+        };
+        assert_eq!(expect, cal_sv_recursive_decompose_with(&game, &options));
+
+        // the pools and tokens are compared by pointer
+        let pool = || {
+            Some(Arc::new(
+                ThreadPoolBuilder::new().num_threads(2).build().unwrap(),
+            ))
+        };
+        let options = SvOptions {
+            thread_pool: pool(),
+            cancel: Some(CancelToken::new()),
+            ..options
+        };
+        assert_eq!(options, options.clone());
+        let other_pool = SvOptions {
+            thread_pool: pool(),
+            ..options.clone()
+        };
+        assert_ne!(options, other_pool);
+        let other_token = SvOptions {
+            cancel: Some(CancelToken::new()),
+            ..options.clone()
+        };
+        assert_ne!(options, other_token);
+        assert_eq!(SvOptions::default(), SvOptions::default());
+    }
+
+    #[test]
+    fn test_repeated_runs() {
+        let gen = GameGenerator {
+            num_owners: 20,
+            num_implicants: 12,
+            implicant_size: 1..4,
+            ..Default::default()
+        };
+        let fallback = SvOptions {
+            max_leaf_unions: 1,
+            leaf_fallback: Some(LeafFallback {
+                samples: 5000,
+                seed: 7,
+            }),
+            ..Default::default()
+        };
+        for game in gen.games().take(5) {
+            for options in [&SvOptions::default(), &fallback] {
+                let to_bits = |sv: ShapleyValues| -> BTreeMap<_, _> {
+                    sv.iter().map(|(o, u)| (*o, u.to_bits())).collect()
+                };
+                let expect = to_bits(cal_sv_recursive_decompose_with(&game, options));
+                for _ in 0..10 {
+                    let actual = to_bits(cal_sv_recursive_decompose_with(&game, options));
+                    assert_eq!(expect, actual, "{}", game.dnf);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dense_leaves() {
+        let sparse = SvOptions {
+            max_dense_leaf_variables: 0,
+            ..Default::default()
+        };
+        let gen = GameGenerator {
+            num_owners: 12,
+            num_implicants: 8,
+            implicant_size: 1..5,
+            ..Default::default()
+        };
+        for game in gen.games().take(20) {
+            for ablation_type in [None, Some(AblationType::NoHybrid)] {
+                let expect = try_cal_sv_with(&game, ablation_type, &sparse).unwrap();
+                let actual = try_cal_sv_with(&game, ablation_type, &SvOptions::default()).unwrap();
+                assert_eq!(expect.len(), actual.len());
+                for (owner_id, u) in &actual {
+                    assert_eq!(expect[owner_id].to_bits(), u.to_bits(), "{}", game.dnf);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sv_by_symmetry() {
+        let game = Game::new(
+            dnf!(1 4 5 + 2 4 5 + 3 4 5).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=6),
+        )
+        .unwrap();
+        let gen = GameGenerator {
+            num_owners: 8,
+            num_implicants: 6,
+            implicant_size: 1..4,
+            ..Default::default()
+        };
+        for game in iter::once(game).chain(gen.games().take(50)) {
+            let expect = cal_sv_recursive_decompose(&game);
+            let actual = cal_sv_by_symmetry(&game);
+            assert_eq!(expect.len(), actual.len());
+            for (owner_id, u) in &actual {
+                assert_f64_eq(expect[owner_id], *u);
+            }
+        }
+    }
+
+    #[test]
+    fn test_group_sv() {
+        // the value of the group from the definition, where it joins as a whole
+        fn brute_force(game: &Game, group: &OwnerSet) -> f64 {
+            let others: Vec<_> = game
+                .owner_set
+                .iter()
+                .filter(|o| !group.contains(*o))
+                .copied()
+                .collect();
+            let n = others.len() + 1;
+            let mut ans = 0.;
+            for mask in 0..1_usize << others.len() {
+                let mut coalition: BTreeSet<_> = (0..others.len())
+                    .filter(|i| mask >> i & 1 == 1)
+                    .map(|i| others[i])
+                    .collect();
+                let k = coalition.len();
+                let before = game.is_winning(&coalition);
+                coalition.extend(group.iter().copied());
+                if game.is_winning(&coalition) && !before {
+                    ans += 1. / (n * binom(k, n - 1)) as f64;
+                }
+            }
+            ans
+        }
+
+        let game = Game::new(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=6),
+        )
+        .unwrap();
+        let groups = [
+            OwnerSet::from_iter([1, 3]),
+            OwnerSet::from_iter([2, 4]),
+            OwnerSet::from_iter([4, 5]),
+            OwnerSet::from_iter([1, 2, 6]),
+            OwnerSet::from_iter([5]),
+        ];
+        for group in groups {
+            let expect = brute_force(&game, &group);
+            assert_f64_eq(expect, cal_group_sv(&game, &group));
+            let merged = game.merge_owners(&group, *group.first().unwrap());
+            assert_f64_eq(expect, cal_sv_brute_force(&merged)[group.first().unwrap()]);
+        }
+        // a single owner is its own Shapley value
+        let sv = cal_sv_recursive_decompose(&game);
+        assert_f64_eq(
+            sv[&OwnerId(2)],
+            cal_group_sv(&game, &OwnerSet::from_iter([2])),
+        );
+
+        // the whole owner set
+        assert_f64_eq(1., cal_group_sv(&game, &game.owner_set));
+        // null players, and owners not in the game
+        assert_f64_eq(0., cal_group_sv(&game, &OwnerSet::from_iter([6])));
+        assert_f64_eq(0., cal_group_sv(&game, &OwnerSet::from_iter([7, 8])));
+        assert_f64_eq(0., cal_group_sv(&game, &OwnerSet::default()));
+    }
+
+    #[test]
+    fn test_for_owners() {
         let game = Game {
-                dnf: dnf!(0 4 12 17 + 0 7 12 17 + 0 4 5 9 17 + 0 4 5 10 17 + 0 4 9 15 17 + 0 4 10 15 17 + 4 5 10 13 17 + 4 10 12 13 17 + 4 10 13 15 17 + 7 10 12 13 17 + 0 5 6 7 9 17 + 0 5 6 7 10 17 + 0 6 7 9 15 17 + 0 6 7 10 15 17 + 5 6 7 10 13 17 + 6 7 10 13 15 17).map_variable(|id| OwnerId(*id)),
-                owner_set: OwnerSet::from_iter([
-                    OwnerId(0),
-                    OwnerId(4),
-                    OwnerId(5),
-                    OwnerId(6),
-                    OwnerId(7),
-                    OwnerId(9),
-                    OwnerId(10),
-                    OwnerId(12),
-                    OwnerId(13),
-                    OwnerId(15),
-                    OwnerId(17),
-                ]),
+            owner_set: OwnerSet::from_iter([0, 4, 5, 6, 7, 9, 10, 12, 13, 15, 17, 20]),
+            ..sixteen_implicants()
+        };
+        let expect = cal_sv_recursive_decompose(&game);
+
+        for owner_id in game.owner_set.iter() {
+            let actual = cal_sv_for_owners(&game, &OwnerSet::from_iter([*owner_id]));
+            assert_eq!(1, actual.len());
+            assert_f64_eq(expect[owner_id], actual[owner_id]);
+        }
+
+        let actual = cal_sv_for_owners(&game, &OwnerSet::from_iter([4, 13, 20, 99]));
+        assert_eq!(3, actual.len());
+        for (o, u) in &actual {
+            assert_f64_eq(expect[o], *u);
+        }
+
+        let actual = cal_sv_for_owners(&game, &game.owner_set);
+        assert_eq!(expect.len(), actual.len());
+        for (o, u) in &actual {
+            assert_f64_eq(expect[o], *u);
+        }
+    }
+
+    #[test]
+    fn test_sv_for() {
+        let gen = GameGenerator {
+            num_owners: 10,
+            num_implicants: 8,
+            implicant_size: 1..4,
+            ..Default::default()
+        };
+        for (i, game) in gen.games().take(30).enumerate() {
+            let expect = cal_sv_recursive_decompose(&game);
+            let owners: BTreeSet<_> = (0..12)
+                .filter(|o| (*o as usize + i).is_multiple_of(3))
+                .map(OwnerId)
+                .collect();
+            let actual = cal_sv_for(&game, &owners);
+            assert_eq!(owners.iter().filter(|o| o.0 < 10).count(), actual.len());
+            for (o, u) in &actual {
+                assert!(owners.contains(o));
+                assert_f64_eq(expect[o], *u);
+            }
+        }
+
+        // pruned subtrees of the other components
+        let game =
+            Game::from_dnf(dnf!(1 2 + 1 3 + 2 3 + 4 5 + 6 7 8).map_variable(|id| OwnerId(*id)));
+        let expect = cal_sv_recursive_decompose(&game);
+        let actual = cal_sv_for(&game, &OwnerSet::from_iter([1, 5]));
+        assert_eq!(2, actual.len());
+        for (o, u) in &actual {
+            assert_f64_eq(expect[o], *u);
+        }
+    }
+
+    #[test]
+    fn test_with_options() {
+        let games = [
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5),
+            dnf!(1 2 5 + 1 2 6 + 1 3 5 + 1 3 6 + 4 5 + 4 6),
+            dnf!(1 2 + 1 3 + 4),
+        ]
+        .map(game)
+        .into_iter()
+        .chain([sixteen_implicants()]);
+        for game in games {
+            let expect = cal_sv_recursive_decompose(&game);
+            for parallel_threshold in [0, 1, 2, 4, usize::MAX] {
+                let options = SvOptions {
+                    parallel_threshold,
+                    ..Default::default()
+                };
+                let actual = cal_sv_recursive_decompose_with(&game, &options);
+                assert_eq!(expect.len(), actual.len());
+                for (o, u) in &actual {
+                    assert_f64_eq(expect[o], *u);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cached() {
+        let cache = CoeffsCache::new();
+
+        // three isomorphic majority games joined by And
+        let game = Game::from_dnf(
+            (dnf!(1 2 + 1 3 + 2 3) & dnf!(4 5 + 4 6 + 5 6) & dnf!(7 8 + 7 9 + 8 9))
+                .map_variable(|id| OwnerId(*id)),
+        );
+        let expect = cal_sv_recursive_decompose(&game);
+        let actual = cal_sv_recursive_decompose_cached(&game, &cache);
+        assert_eq!(expect.len(), actual.len());
+        for (o, u) in &actual {
+            assert_f64_eq(expect[o], *u);
+        }
+        assert_eq!(1, cache.misses());
+        assert_eq!(2, cache.hits());
+
+        // nothing is computed again for the same game
+        let game = sixteen_implicants();
+        let expect = cal_sv_recursive_decompose(&game);
+        let _ = cal_sv_recursive_decompose_cached(&game, &cache);
+        let misses = cache.misses();
+        let actual = cal_sv_recursive_decompose_cached(&game, &cache);
+        assert_eq!(misses, cache.misses());
+        for (o, u) in &actual {
+            assert_f64_eq(expect[o], *u);
+        }
+    }
+
+    #[test]
+    fn test_cached_duplicates() {
+        // the majority game of 3 owners under the conjunctions of 1 and of 5 9, i.e.,
+        // Or(And(1, Hybrid), And(5, 9, Hybrid)), which become leaves without Hybrid nodes
+        let game = Game::from_dnf(
+            (dnf!(1 2 3 + 1 2 4 + 1 3 4) | dnf!(5 9 6 7 + 5 9 6 8 + 5 9 7 8))
+                .map_variable(|id| OwnerId(*id)),
+        );
+        for ablation_type in [None, Some(AblationType::NoHybrid)] {
+            let cache = CoeffsCache::new();
+            let ctx = TreeContext {
+                cache: Some(&cache),
+                ..TreeContext::new(ablation_type)
             };
+            let expect =
+                try_cal_owner_coeffs_for(&game, &TreeContext::new(ablation_type), None).unwrap();
+            let actual = try_cal_owner_coeffs_for(&game, &ctx, None).unwrap();
+            assert_eq!(expect.len(), actual.len());
+            for (o, coeffs) in &actual {
+                assert_f64_eq(expect[o].to_sv(), coeffs.to_sv());
+            }
+            assert_eq!(1, cache.misses());
+            assert_eq!(1, cache.hits());
+        }
+    }
 
-        let _ = cal_sv_recursive_decompose(&game);
+    #[test]
+    fn test_deep_dnf() {
+        // x1 (x2 + x3 (x4 + x5 (...))), with an And and an Or per implicant
+        let chain = |implicants: u32| {
+            let exp: Dnf<OwnerId> = (0..implicants)
+                .map(|k| {
+                    (0..=k)
+                        .map(|i| OwnerId(2 * i + 1))
+                        .chain([OwnerId(2 * k + 2)])
+                        .collect::<Implicant<_>>()
+                })
+                .collect();
+            Game::from_dnf(exp)
+        };
+
+        // the two owners only in the last implicant are symmetric
+        let sv = cal_sv_recursive_decompose(&chain(30));
+        assert_f64_eq(1., sv.sum());
+        assert_eq!(sv[&OwnerId(59)], sv[&OwnerId(60)]);
+
+        // 400 levels deep, where the coefficients grow exponentially with the depth, and overflow
+        // ~65 implicants above the bottom
+        let game = chain(200);
+        #[cfg(not(feature = "bigint"))]
+        assert!(matches!(
+            try_cal_sv_recursive_decompose(&game),
+            Err(SvError::Overflow { .. })
+        ));
+        #[cfg(feature = "bigint")]
+        {
+            let sv = cal_sv_recursive_decompose_exact(&game);
+            assert_eq!(BigRational::one(), sv.0.values().sum());
+            assert_eq!(sv.0[&OwnerId(399)], sv.0[&OwnerId(400)]);
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        // the coefficients of 1 - (1 - x)^69 exceed i64
+        let exp: Dnf<OwnerId> = (1..=70)
+            .map(|id| Implicant::from_iter([OwnerId(id)]))
+            .collect();
+        let game = Game::from_dnf(exp);
+
+        #[cfg(not(feature = "bigint"))]
+        assert!(matches!(
+            try_cal_sv_recursive_decompose(&game),
+            Err(SvError::Overflow { .. })
+        ));
+
+        #[cfg(feature = "bigint")]
+        {
+            use crate::alg::permutation::cal_sv_monte_carlo;
+
+            let sv = try_cal_sv_recursive_decompose(&game).unwrap();
+            let estimate = cal_sv_monte_carlo(&game, 5000, 0);
+            assert_eq!(70, sv.len());
+            for (o, u) in &sv {
+                assert_f64_eq(1. / 70., *u);
+                assert!((u - estimate[o]).abs() < 0.01);
+            }
+        }
     }
 }
@@ -1,351 +1,185 @@
-use super::iec::*;
-use crate::{
-    dnf::{recursive_decompose, Dnf, RecursiveDecompose},
-    product_tree::ProductTree,
-    union_combination::*,
-    utils::hashmap_reduce,
-    Game, OwnerId, ShapleyValues,
-};
-use clap::ValueEnum;
-use rayon::prelude::*;
-use std::collections::BTreeSet;
-
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone, ValueEnum)]
-pub enum AblationType {
-    NoHorizontal,
-    NoVertical,
-    NoHybrid,
-}
-
+use super::{decompose_tree::cal_owner_coeffs, ShapleyComputer, SvError, SvOptions};
+use crate::{par::prelude::*, utils::normalize_values, BanzhafValues, Game, ShapleyValues};
+use num_traits::ToPrimitive;
+
+pub use super::decompose_tree::AblationType;
+
+/// Shapley values by the recursive decomposition with one kind of decomposition disabled.
+///
+/// Absorbed implicants in the DNF are removed by `Dnf::minimized` before decomposing, so they do
+/// not inflate the leaves computed by inclusion-exclusion.
+///
+/// The keys are always the owner set of the game, with a value of 0 for each owner that is in
+/// no implicant, even if the game always or never wins.
+///
+/// With the `check-efficiency` feature, debug builds assert that the values sum up to
+/// `Game::expected_sv_sum` within `EFFICIENCY_TOLERANCE`.
 pub fn cal_sv_recursive_decompose_ablation(
     game: &Game,
     ablation_type: AblationType,
 ) -> ShapleyValues {
-    let d = recursive_decompose(&game.dnf, &game.owner_set);
-    let tree = DecomposeTree::new(d, true, ablation_type);
-    let gamma_map = IECoeffs::from([(0, 1)]);
-    tree.cal_sv(&gamma_map)
-}
-
-enum DecomposeTree {
-    Var(OwnerId),
-    And {
-        coeffs: Option<IECoeffs>,
-        products: Vec<IECoeffs>,
-        children: Vec<DecomposeTree>,
-    },
-    Or {
-        coeffs: Option<IECoeffs>,
-        products: Vec<IECoeffs>,
-        children: Vec<DecomposeTree>,
-    },
-    Hybrid {
-        coeffs: Option<IECoeffs>,
-        hybrid_coeffs: HybridCoeffs,
-        hybrid_exp: Dnf<usize>,
-        children: Vec<DecomposeTree>,
-    },
-    Leaf {
-        coeffs: Option<IECoeffs>,
-        exp: Dnf<OwnerId>,
-    },
-}
-
-impl DecomposeTree {
-    fn new(input: RecursiveDecompose<OwnerId>, is_root: bool, ablation_type: AblationType) -> Self {
-        match input {
-            RecursiveDecompose::Var(id) => Self::Var(id),
-            RecursiveDecompose::And(children) if ablation_type != AblationType::NoVertical => {
-                let children: Vec<_> = children
-                    .into_par_iter()
-                    .map(|c| DecomposeTree::new(c, false, ablation_type))
-                    .collect();
-                let mut children_coeffs = Vec::with_capacity(children.len());
-                for c in &children {
-                    children_coeffs.push(c.coeffs());
-                }
-                let product_tree: ProductTree<IECoeffs> =
-                    ProductTree::new(children_coeffs, vertical_op, !is_root);
-                let products = product_tree.all_products(vertical_identity, vertical_op);
-                let coeffs = if is_root {
-                    None
-                } else {
-                    Some(product_tree.root())
-                };
-                Self::And {
-                    coeffs,
-                    products,
-                    children,
-                }
-            }
-            RecursiveDecompose::Or(children) if ablation_type != AblationType::NoHorizontal => {
-                let children: Vec<_> = children
-                    .into_par_iter()
-                    .map(|c| DecomposeTree::new(c, false, ablation_type))
-                    .collect();
-                let mut children_coeffs = Vec::with_capacity(children.len());
-                for c in &children {
-                    children_coeffs.push(c.coeffs());
-                }
-                let product_tree: ProductTree<IECoeffs> =
-                    ProductTree::new(children_coeffs, horizontal_op, !is_root);
-                let products = product_tree.all_products(horizontal_identity, horizontal_op);
-                let coeffs = if is_root {
-                    None
-                } else {
-                    Some(product_tree.root())
-                };
-                Self::Or {
-                    coeffs,
-                    products,
-                    children,
-                }
-            }
-            RecursiveDecompose::Hybrid {
-                hybrid_exp,
-                sub_exps,
-            } if ablation_type != AblationType::NoHybrid => {
-                let children: Vec<_> = sub_exps
-                    .into_par_iter()
-                    .map(|c| DecomposeTree::new(c, false, ablation_type))
-                    .collect();
-                let mut children_coeffs = Vec::with_capacity(children.len());
-                for c in &children {
-                    children_coeffs.push(c.coeffs());
-                }
-                let hybrid_coeffs = HybridCoeffs::new(&children_coeffs);
-                let coeffs = if is_root {
-                    None
-                } else {
-                    Some(hybrid_coeffs.exp_coeffs(&hybrid_exp))
-                };
-                Self::Hybrid {
-                    coeffs,
-                    hybrid_coeffs,
-                    hybrid_exp,
-                    children,
-                }
-            }
-            _ => {
-                let exp: Dnf<OwnerId> = input.expand();
-                let coeffs = if is_root {
-                    None
-                } else {
-                    let exp_unions = leaf_exp_to_unions(&exp);
-                    let coeffs = leaf_exp_unions_coeffs(&exp_unions);
-                    Some(coeffs)
-                };
-                Self::Leaf { coeffs, exp }
-            }
-        }
-    }
-
-    fn coeffs(&self) -> IECoeffs {
-        match self {
-            DecomposeTree::Var(_) => IECoeffs::from([(1, 1)]),
-            DecomposeTree::And { coeffs, .. } => coeffs.clone().unwrap(),
-            DecomposeTree::Or { coeffs, .. } => coeffs.clone().unwrap(),
-            DecomposeTree::Hybrid { coeffs, .. } => coeffs.clone().unwrap(),
-            DecomposeTree::Leaf { coeffs, .. } => coeffs.clone().unwrap(),
-        }
-    }
-
-    fn cal_sv(&self, gamma_map: &IECoeffs) -> ShapleyValues {
-        match self {
-            DecomposeTree::Var(owner_id) => {
-                let map_group_with_owner = IECoeffs::from([(1, 1)]);
-                let sv = (&map_group_with_owner * gamma_map).to_sv();
-                ShapleyValues::from([(*owner_id, sv)])
-            }
-            DecomposeTree::And {
-                products, children, ..
-            } => {
-                let var_children: Vec<_> = children
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, c)| match c {
-                        Self::Var(id) => Some((i, id)),
-                        _ => None,
-                    })
-                    .collect();
-
-                let mut ans = children
-                    .par_iter()
-                    .enumerate()
-                    .filter(|(_, c)| !matches!(c, Self::Var(_)))
-                    .map(|(i, c)| {
-                        let iece_map = &products[i];
-                        let next_gamma_map = gamma_map * iece_map;
-                        c.cal_sv(&next_gamma_map)
-                    })
-                    .reduce(ShapleyValues::default, hashmap_reduce);
-
-                if let Some((i, _)) = var_children.first() {
-                    let iece_map = &products[*i];
-                    let next_gamma_map = gamma_map * iece_map;
-                    let sv = (&IECoeffs::from([(1, 1)]) * &next_gamma_map).to_sv();
-                    for (_, id) in var_children {
-                        ans.insert(*id, sv);
-                    }
-                }
-
-                ans
-            }
-            DecomposeTree::Or {
-                products, children, ..
-            } => {
-                let var_children: Vec<_> = children
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, c)| match c {
-                        Self::Var(id) => Some((i, id)),
-                        _ => None,
-                    })
-                    .collect();
-
-                let mut ans = children
-                    .par_iter()
-                    .enumerate()
-                    .filter(|(_, c)| !matches!(c, Self::Var(_)))
-                    .map(|(i, c)| {
-                        let iece_map = &products[i];
-                        let next_gamma_map = gamma_map - &(gamma_map * iece_map);
-                        c.cal_sv(&next_gamma_map)
-                    })
-                    .reduce(ShapleyValues::default, hashmap_reduce);
-
-                if let Some((i, _)) = var_children.first() {
-                    let iece_map = &products[*i];
-                    let next_gamma_map = gamma_map - &(gamma_map * iece_map);
-                    let sv = (&IECoeffs::from([(1, 1)]) * &next_gamma_map).to_sv();
-                    for (_, id) in var_children {
-                        ans.insert(*id, sv);
-                    }
-                }
-
-                ans
-            }
-            DecomposeTree::Hybrid {
-                hybrid_coeffs,
-                hybrid_exp,
-                children,
-                ..
-            } => children
-                .par_iter()
-                .enumerate()
-                .map(|(i, c)| {
-                    let owner_set = BTreeSet::from([i]);
-                    let exp_p2 = hybrid_exp.partial_eval(&owner_set, true);
-                    let exp_p3 = hybrid_exp.partial_exp_complement(&owner_set);
-                    let exp_p2_unions = exp_to_input_unions(&exp_p2);
-                    let exp_p3_unions = exp_to_input_unions(&exp_p3);
-                    let map_p2 = hybrid_coeffs.exp_unions_coeffs(&exp_p2_unions);
-                    let iece_map =
-                        hybrid_coeffs.exp_unions_interaction(&exp_p2_unions, &exp_p3_unions);
-                    let next_gamma_map = gamma_map * &(map_p2 - iece_map);
-                    c.cal_sv(&next_gamma_map)
-                })
-                .reduce(ShapleyValues::default, hashmap_reduce),
-            DecomposeTree::Leaf { exp, .. } => exp
-                .all_variables()
-                .par_iter()
-                .map(|&c| {
-                    let owner_set = BTreeSet::from([c]);
-                    let exp_p2 = exp.partial_eval(&owner_set, true);
-                    let exp_p3 = exp.partial_exp_complement(&owner_set);
-
-                    let exp_p2_unions = leaf_exp_to_unions(&exp_p2);
-                    let map_p2 = leaf_exp_unions_coeffs(&exp_p2_unions);
-
-                    let exp_p3_unions = leaf_exp_to_unions(&exp_p3);
-                    let iece_map = leaf_exp_unions_interaction(&exp_p2_unions, &exp_p3_unions);
-
-                    let next_gamma_map = if exp_p2.all_variables().is_empty() {
-                        gamma_map - &(gamma_map * &iece_map)
-                    } else {
-                        gamma_map * &(map_p2 - iece_map)
-                    };
-
-                    let map_group_with_owner = IECoeffs::from([(1, 1)]);
-                    let sv = (&map_group_with_owner * &next_gamma_map).to_sv();
-                    ShapleyValues::from([(c, sv)])
-                })
-                .reduce(ShapleyValues::default, hashmap_reduce),
-        }
-    }
+    let ans = ShapleyComputer::new(game)
+        .ablation(Some(ablation_type))
+        .compute()
+        .unwrap_or_else(|e| panic!("{e}"));
+    #[cfg(feature = "check-efficiency")]
+    debug_assert!(
+        (ans.sum() - game.expected_sv_sum()).abs() <= EFFICIENCY_TOLERANCE,
+        "Shapley values of {} with {ablation_type:?} sum up to {} rather than {}",
+        game.dnf,
+        ans.sum(),
+        game.expected_sv_sum()
+    );
+    ans
 }
 
-#[derive(Debug, Clone)]
-struct LeafExpUnion {
-    input_set: BTreeSet<OwnerId>,
-    num_of_imp: usize,
+/// Same as `cal_sv_recursive_decompose_ablation`, but with the given options, and return an error
+/// instead of panicking when a coefficient overflows or a leaf is beyond
+/// `options.max_leaf_unions` without `options.leaf_fallback`.
+pub fn try_cal_sv_recursive_decompose_ablation_with(
+    game: &Game,
+    ablation_type: AblationType,
+    options: &SvOptions,
+) -> Result<ShapleyValues, SvError> {
+    ShapleyComputer::new(game)
+        .ablation(Some(ablation_type))
+        .options(options.clone())
+        .compute()
 }
 
-fn leaf_exp_to_unions(exp: &Dnf<OwnerId>) -> UnionCombination<LeafExpUnion> {
-    let var_len = exp.all_variables().len();
-    let imp_list: Vec<_> = exp.iter().collect();
-    UnionCombination::new(
-        imp_list.len(),
-        |i| LeafExpUnion {
-            num_of_imp: 1,
-            input_set: imp_list[i].0.clone(),
-        },
-        |old, i| {
-            let new_imp = imp_list[i];
-            let mut new_set = old.input_set.clone();
-            new_set.extend(new_imp.iter().copied());
-            // whether new set is full and cur_id != MAX_ID
-            if new_set.len() == var_len && i != imp_list.len() - 1 {
-                None
-            } else {
-                Some(LeafExpUnion {
-                    num_of_imp: old.num_of_imp + 1,
-                    input_set: new_set,
-                })
-            }
-        },
-    )
-}
+/// Tolerance of the sum of the Shapley values checked with the `check-efficiency` feature.
+pub const EFFICIENCY_TOLERANCE: f64 = 1e-6;
 
-fn leaf_exp_unions_coeffs(exp_unions: &UnionCombination<LeafExpUnion>) -> IECoeffs {
-    exp_unions
-        .0
-        .par_iter()
-        .map(|u| {
-            let u = u.get();
-            let sign = if u.num_of_imp % 2 == 0 { -1 } else { 1 };
-            IECoeffs::from([(u.input_set.len(), sign)])
-        })
-        .sum()
+/// Normalized Banzhaf index, i.e., the swing counts scaled to sum up to 1.
+pub fn cal_banzhaf_recursive_decompose_ablation(
+    game: &Game,
+    ablation_type: AblationType,
+) -> BanzhafValues {
+    let mut ans = cal_banzhaf_swings_recursive_decompose_ablation(game, ablation_type);
+    normalize_values(&mut ans);
+    ans
 }
 
-fn leaf_exp_unions_interaction(
-    exp_unions1: &UnionCombination<LeafExpUnion>,
-    exp_unions2: &UnionCombination<LeafExpUnion>,
-) -> IECoeffs {
-    exp_unions1
-        .0
-        .par_iter()
-        .flat_map(|u1| {
-            let u1 = u1.get();
-            exp_unions2.0.par_iter().map(move |u2| {
-                let u2 = u2.get();
-                let input_set: BTreeSet<_> = u1.input_set.union(&u2.input_set).collect();
-                let sign = if (u1.num_of_imp + u2.num_of_imp) % 2 == 0 {
-                    1
-                } else {
-                    -1
-                };
-                IECoeffs::from([(input_set.len(), sign)])
-            })
+/// Number of coalitions in which each owner is a swing player.
+pub fn cal_banzhaf_swings_recursive_decompose_ablation(
+    game: &Game,
+    ablation_type: AblationType,
+) -> BanzhafValues {
+    let n = game.owner_len();
+    cal_owner_coeffs(game, Some(ablation_type))
+        .into_par_iter()
+        .map(|(owner_id, coeffs)| {
+            let swings = coeffs.to_swing_count(n).to_f64().unwrap_or(f64::INFINITY);
+            (owner_id, swings)
         })
-        .sum()
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{dnf, tests::assert_f64_eq, OwnerSet};
+    use crate::{
+        alg::{
+            exact::cal_sv_brute_force,
+            synthesis_sv::{decompose_tree::decompose_stats, LeafFallback},
+        },
+        dnf,
+        tests::{assert_f64_eq, monotone_dnf, sixteen_implicants},
+        Dnf, OwnerId, OwnerSet,
+    };
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_null_owners() {
+        let cases = [
+            // one null owner
+            (
+                dnf!(1 2 + 1 3),
+                vec![1, 2, 3, 4],
+                vec![0.66666666666, 0.16666666666, 0.16666666666, 0.],
+            ),
+            // several null owners
+            (
+                dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5),
+                vec![1, 2, 3, 4, 5, 6, 7],
+                vec![
+                    0.06666666666,
+                    0.23333333333,
+                    0.06666666666,
+                    0.31666666666,
+                    0.31666666666,
+                    0.,
+                    0.,
+                ],
+            ),
+            // all but one owner are null
+            (dnf!(2), vec![1, 2, 3, 4], vec![0., 1., 0., 0.]),
+        ];
+
+        for (exp, owners, expect) in cases {
+            let game = Game::new(
+                exp.map_variable(|id| OwnerId(*id)),
+                OwnerSet::from_iter(owners.iter().copied()),
+            )
+            .unwrap();
+
+            for ablation_type in [
+                AblationType::NoHorizontal,
+                AblationType::NoVertical,
+                AblationType::NoHybrid,
+            ] {
+                let sv = cal_sv_recursive_decompose_ablation(&game, ablation_type);
+                assert_eq!(owners.len(), sv.len());
+                for (owner, u) in owners.iter().zip(&expect) {
+                    assert_f64_eq(*u, sv[&OwnerId(*owner)]);
+                }
+                assert_f64_eq(1., sv.values().sum());
+            }
+        }
+    }
+
+    #[test]
+    fn test_null_owners_keys() {
+        let owner_set = OwnerSet::from_iter(1..=5);
+        let games = [
+            dnf!(1 2 + 1 3 + 4),
+            dnf!(1 2 3),
+            Dnf::true_exp(),
+            Dnf::false_exp(),
+        ]
+        // not `Game::new`, which rejects a game that never wins
+        .map(|exp| Game {
+            dnf: exp.map_variable(|id| OwnerId(*id)),
+            owner_set: owner_set.clone(),
+        });
+        // a leaf of 3 unions without horizontal decomposition falls back to sampling
+        let fallback = SvOptions {
+            max_leaf_unions: 1,
+            leaf_fallback: Some(LeafFallback {
+                samples: 100,
+                seed: 0,
+            }),
+            ..Default::default()
+        };
+        for game in &games {
+            for ablation_type in [
+                AblationType::NoHorizontal,
+                AblationType::NoVertical,
+                AblationType::NoHybrid,
+            ] {
+                let sv = cal_sv_recursive_decompose_ablation(game, ablation_type);
+                assert!(sv.keys().sorted().eq(owner_set.iter()), "{}", game.dnf);
+                assert_eq!(0., sv[&OwnerId(5)]);
+
+                let sv =
+                    try_cal_sv_recursive_decompose_ablation_with(game, ablation_type, &fallback)
+                        .unwrap();
+                assert!(sv.keys().sorted().eq(owner_set.iter()), "{}", game.dnf);
+                assert_eq!(0., sv[&OwnerId(5)]);
+            }
+        }
+    }
 
     #[test]
     fn test_cal_sv_recursive_decompose_ablation() {
@@ -458,22 +292,7 @@ mod tests {
 
     #[test]
     fn test_performance() {
-        let game = Game {
-                dnf: dnf!(0 4 12 17 + 0 7 12 17 + 0 4 5 9 17 + 0 4 5 10 17 + 0 4 9 15 17 + 0 4 10 15 17 + 4 5 10 13 17 + 4 10 12 13 17 + 4 10 13 15 17 + 7 10 12 13 17 + 0 5 6 7 9 17 + 0 5 6 7 10 17 + 0 6 7 9 15 17 + 0 6 7 10 15 17 + 5 6 7 10 13 17 + 6 7 10 13 15 17).map_variable(|id| OwnerId(*id)),
-                owner_set: OwnerSet::from_iter([
-                    OwnerId(0),
-                    OwnerId(4),
-                    OwnerId(5),
-                    OwnerId(6),
-                    OwnerId(7),
-                    OwnerId(9),
-                    OwnerId(10),
-                    OwnerId(12),
-                    OwnerId(13),
-                    OwnerId(15),
-                    OwnerId(17),
-                ]),
-            };
+        let game = sixteen_implicants();
 
         let sv = cal_sv_recursive_decompose_ablation(&game, AblationType::NoHybrid);
         assert_f64_eq(0.013492063492063444, sv[&OwnerId(6)]);
@@ -520,6 +339,37 @@ mod tests {
         assert_f64_eq(0.31666666666, sv[&OwnerId(5)]);
     }
 
+    #[test]
+    fn test_banzhaf_ablation() {
+        let game = Game {
+            dnf: dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([
+                OwnerId(1),
+                OwnerId(2),
+                OwnerId(3),
+                OwnerId(4),
+                OwnerId(5),
+            ]),
+        };
+
+        for ablation_type in [
+            AblationType::NoVertical,
+            AblationType::NoHorizontal,
+            AblationType::NoHybrid,
+        ] {
+            let swings = cal_banzhaf_swings_recursive_decompose_ablation(&game, ablation_type);
+            assert_f64_eq(2., swings[&OwnerId(1)]);
+            assert_f64_eq(6., swings[&OwnerId(2)]);
+            assert_f64_eq(2., swings[&OwnerId(3)]);
+            assert_f64_eq(8., swings[&OwnerId(4)]);
+            assert_f64_eq(8., swings[&OwnerId(5)]);
+
+            let banzhaf = cal_banzhaf_recursive_decompose_ablation(&game, ablation_type);
+            let sum: f64 = banzhaf.values().sum();
+            assert_f64_eq(1., sum);
+        }
+    }
+
     #[test]
     fn test_vertical_decom() {
         let game = Game {
@@ -569,4 +419,253 @@ mod tests {
         assert_f64_eq(0.08333333333333337, sv[&OwnerId(3)]);
         assert_f64_eq(0.5833333333333334, sv[&OwnerId(4)]);
     }
+
+    #[test]
+    fn test_redundant_implicants() {
+        let core = dnf!(1 2 + 3 4 + 5 6).map_variable(|id| OwnerId(*id));
+        let mut exp = core.clone();
+        for imp in core.iter() {
+            for extra in [[7, 8], [1, 7], [3, 8], [5, 9], [2, 4], [6, 9]] {
+                let mut t = imp.clone();
+                t.extend(extra.map(OwnerId));
+                exp.insert(t);
+            }
+        }
+        assert_eq!(20, exp.len());
+        assert_eq!(core, exp.minimized());
+
+        let owner_set = OwnerSet::from_iter(1..=9);
+        let redundant_game = Game {
+            dnf: exp,
+            owner_set: owner_set.clone(),
+        };
+        let core_game = Game::new(core, owner_set).unwrap();
+        let expect = cal_sv_brute_force(&core_game);
+        for ablation_type in [
+            AblationType::NoVertical,
+            AblationType::NoHorizontal,
+            AblationType::NoHybrid,
+        ] {
+            assert_eq!(
+                decompose_stats(&core_game, Some(ablation_type)),
+                decompose_stats(&redundant_game, Some(ablation_type))
+            );
+            let actual = cal_sv_recursive_decompose_ablation(&redundant_game, ablation_type);
+            assert_eq!(expect.len(), actual.len());
+            for (o, u) in &actual {
+                assert_f64_eq(expect[o], *u);
+            }
+        }
+    }
+
+    const ABLATION_TYPES: [AblationType; 3] = [
+        AblationType::NoVertical,
+        AblationType::NoHorizontal,
+        AblationType::NoHybrid,
+    ];
+
+    /// Small random games of up to 9 owners and 8 implicants, possibly with null owners.
+    fn small_game() -> impl Strategy<Value = Game> {
+        (monotone_dnf(9, 8), 0_u32..=2).prop_map(|(exp, null_owners)| {
+            let mut owner_set: OwnerSet = exp.all_variables().into_iter().collect();
+            owner_set.extend((0..null_owners).map(|i| OwnerId(100 + i)));
+            Game::new(exp, owner_set).unwrap()
+        })
+    }
+
+    #[test]
+    fn test_leaf_too_large() {
+        // the 3-out-of-5 majority is a single leaf of 10 implicants without hybrid decomposition
+        let game = Game::from_dnf(
+            dnf!(1 2 3 + 1 2 4 + 1 2 5 + 1 3 4 + 1 3 5 + 1 4 5 + 2 3 4 + 2 3 5 + 2 4 5 + 3 4 5)
+                .map_variable(|id| OwnerId(*id)),
+        );
+        let options = SvOptions {
+            max_leaf_unions: 5,
+            ..Default::default()
+        };
+        // the coefficients of the root leaf are never used, but those of the 2-out-of-4 majority
+        // once an owner joins are
+        assert_eq!(
+            Err(SvError::LeafTooLarge {
+                implicants: 6,
+                variables: 4
+            }),
+            try_cal_sv_recursive_decompose_ablation_with(&game, AblationType::NoHybrid, &options)
+        );
+
+        let sv = try_cal_sv_recursive_decompose_ablation_with(
+            &game,
+            AblationType::NoHybrid,
+            &SvOptions::default(),
+        )
+        .unwrap();
+        for u in sv.values() {
+            assert_f64_eq(0.2, *u);
+        }
+
+        let options = SvOptions {
+            leaf_fallback: Some(LeafFallback {
+                samples: 20000,
+                seed: 0,
+            }),
+            ..options
+        };
+        let estimate =
+            try_cal_sv_recursive_decompose_ablation_with(&game, AblationType::NoHybrid, &options)
+                .unwrap();
+        assert_eq!(5, estimate.len());
+        for u in estimate.values() {
+            assert!((u - 0.2).abs() < 0.02, "{estimate:?}");
+        }
+    }
+
+    #[test]
+    fn test_leaf_fallback_one_leaf() {
+        // the 3-out-of-5 majority of 1-5 is the only leaf without hybrid decomposition, and only
+        // it is sampled, while the rest of the tree stays exact
+        let game = Game::from_dnf(
+            dnf!(1 2 3 6 + 1 2 4 6 + 1 2 5 6 + 1 3 4 6 + 1 3 5 6 + 1 4 5 6 + 2 3 4 6 + 2 3 5 6
+                + 2 4 5 6 + 3 4 5 6 + 1 2 3 7 + 1 2 4 7 + 1 2 5 7 + 1 3 4 7 + 1 3 5 7 + 1 4 5 7
+                + 2 3 4 7 + 2 3 5 7 + 2 4 5 7 + 3 4 5 7 + 8 9 + 10)
+            .map_variable(|id| OwnerId(*id)),
+        );
+        let expect = try_cal_sv_recursive_decompose_ablation_with(
+            &game,
+            AblationType::NoHybrid,
+            &Default::default(),
+        )
+        .unwrap();
+        let options = SvOptions {
+            max_leaf_unions: 5,
+            leaf_fallback: Some(LeafFallback {
+                samples: 20000,
+                seed: 0,
+            }),
+            ..Default::default()
+        };
+        let estimate =
+            try_cal_sv_recursive_decompose_ablation_with(&game, AblationType::NoHybrid, &options)
+                .unwrap();
+        assert_eq!(10, estimate.len());
+        for (owner_id, u) in estimate.iter() {
+            if owner_id.0 <= 5 {
+                assert!((u - expect[owner_id]).abs() < 0.02, "{estimate:?}");
+            } else {
+                // the winning coalitions of the leaf are estimated exactly once rounded
+                assert_f64_eq(expect[owner_id], *u);
+            }
+        }
+        assert!((estimate.sum() - 1.).abs() < 0.02, "{estimate:?}");
+    }
+
+    #[test]
+    fn test_too_many_combinations() {
+        // the unions of the 3-out-of-5 majority game are within the default limits, but each owner
+        // combines those of the 2-out-of-4 and 3-out-of-4 majority games of the others
+        let game = Game::from_dnf(
+            dnf!(1 2 3 + 1 2 4 + 1 2 5 + 1 3 4 + 1 3 5 + 1 4 5 + 2 3 4 + 2 3 5 + 2 4 5 + 3 4 5)
+                .map_variable(|id| OwnerId(*id)),
+        );
+        let options = SvOptions {
+            max_union_combinations: Some(10),
+            ..Default::default()
+        };
+        let result =
+            try_cal_sv_recursive_decompose_ablation_with(&game, AblationType::NoHybrid, &options);
+        assert!(
+            matches!(
+                result,
+                Err(SvError::TooManyCombinations {
+                    variables: 5,
+                    combinations,
+                }) if combinations > 10
+            ),
+            "{result:?}"
+        );
+
+        let options = SvOptions {
+            max_union_combinations: Some(10_000),
+            ..Default::default()
+        };
+        let sv =
+            try_cal_sv_recursive_decompose_ablation_with(&game, AblationType::NoHybrid, &options)
+                .unwrap();
+        for u in sv.values() {
+            assert_f64_eq(0.2, *u);
+        }
+
+        let options = SvOptions {
+            max_union_combinations: Some(10),
+            leaf_fallback: Some(LeafFallback {
+                samples: 20000,
+                seed: 0,
+            }),
+            ..Default::default()
+        };
+        let estimate =
+            try_cal_sv_recursive_decompose_ablation_with(&game, AblationType::NoHybrid, &options)
+                .unwrap();
+        for u in estimate.values() {
+            assert!((u - 0.2).abs() < 0.02, "{estimate:?}");
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_against_brute_force(game in small_game()) {
+            let expect = cal_sv_brute_force(&game);
+            for ablation_type in ABLATION_TYPES {
+                let actual = cal_sv_recursive_decompose_ablation(&game, ablation_type);
+                prop_assert_eq!(expect.len(), actual.len());
+                for (o, u) in &actual {
+                    prop_assert!(
+                        (expect[o] - u).abs() < 1e-9,
+                        "{}, {:?}: {} vs {}", game.dnf, o, expect[o], u
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn test_ablation_types_agree(game in small_game()) {
+            let values = ABLATION_TYPES.map(|t| cal_sv_recursive_decompose_ablation(&game, t));
+            for (o, u) in &values[0] {
+                for other in &values[1..] {
+                    prop_assert!(
+                        (other[o] - u).abs() < 1e-9,
+                        "{}, {:?}: {} vs {}", game.dnf, o, other[o], u
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn test_efficiency(exp in monotone_dnf(9, 8)) {
+            // the owner set is the support of the DNF, which is neither TRUE nor FALSE
+            let game = Game::from_dnf(exp);
+            for ablation_type in ABLATION_TYPES {
+                let sum = cal_sv_recursive_decompose_ablation(&game, ablation_type).sum();
+                prop_assert!((sum - 1.).abs() < 1e-9, "{}: sum {}", game.dnf, sum);
+            }
+        }
+
+        #[test]
+        fn test_dummy_owner(game in small_game()) {
+            let mut with_dummy = game.clone();
+            with_dummy.owner_set.insert(OwnerId(1000));
+            for ablation_type in ABLATION_TYPES {
+                let expect = cal_sv_recursive_decompose_ablation(&game, ablation_type);
+                let actual = cal_sv_recursive_decompose_ablation(&with_dummy, ablation_type);
+                prop_assert_eq!(expect.len() + 1, actual.len());
+                prop_assert_eq!(0., actual[&OwnerId(1000)]);
+                for (o, u) in &expect {
+                    prop_assert!(
+                        (actual[o] - u).abs() < 1e-9,
+                        "{}, {:?}: {} vs {}", game.dnf, o, actual[o], u
+                    );
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,416 @@
+use super::{
+    decompose_tree::{CoeffsCache, DecomposeTree, TreeContext},
+    iec::*,
+    SvError,
+};
+use crate::{
+    dnf::{Dnf, Implicant},
+    par::prelude::*,
+    product_tree::ProductTree,
+    utils::try_hashmap_reduce,
+    Game, GameError, OwnerId, ShapleyValues,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+/// A game under editing, which keeps the decomposition trees to recompute the Shapley values
+/// incrementally after adding or removing implicants.
+///
+/// The DNF is split into connected components, i.e., the top-level Or decomposition, and each
+/// component keeps its own tree. An edit only rebuilds the trees of the components sharing
+/// owners with the changed implicant, while the others are reused as is. Within a rebuilt
+/// component, the subtrees of the sub-expressions left unchanged by the edit are reused too, so
+/// only the nodes along the modified path are built again, and those of the same shape as a node
+/// built before get their coefficients from a `CoeffsCache`. `recompute` then combines the
+/// components with a fresh `ProductTree`, which is cheap as it only involves their root
+/// coefficients.
+///
+/// The cache is shared by the clones of a session.
+#[derive(Debug, Clone)]
+pub struct SvSession {
+    game: Game,
+    components: Vec<Component>,
+    cache: Arc<CoeffsCache>,
+    sv: Option<ShapleyValues>,
+}
+
+#[derive(Debug, Clone)]
+struct Component {
+    variables: BTreeSet<OwnerId>,
+    exp: Dnf<OwnerId>,
+    tree: DecomposeTree,
+}
+
+impl Component {
+    /// Panic if a coefficient overflows.
+    fn new(exp: Dnf<OwnerId>, ctx: &TreeContext) -> Self {
+        Self {
+            variables: exp.all_variables(),
+            tree: DecomposeTree::subtree(&exp, ctx).unwrap_or_else(|e| panic!("{e}")),
+            exp,
+        }
+    }
+}
+
+impl SvSession {
+    /// Start a session from a game, whose DNF is minimized first.
+    pub fn new(mut game: Game) -> Self {
+        game.dnf.minimize();
+        let mut session = Self {
+            game,
+            components: vec![],
+            cache: Arc::new(CoeffsCache::new()),
+            sv: None,
+        };
+        let imps = session.game.dnf.iter().cloned().collect::<Vec<_>>();
+        session.rebuild(imps, &[]);
+        session
+    }
+
+    /// The current game.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Add an implicant to the DNF, removing the implicants absorbed by it.
+    ///
+    /// Return `false` if the implicant is already absorbed by the DNF, which is left unchanged.
+    /// The owners of the implicant are required to be in the owner set. An empty implicant
+    /// absorbs all the others and makes every coalition win, so that every owner gets zero.
+    pub fn add_implicant(&mut self, imp: Implicant<OwnerId>) -> Result<bool, GameError> {
+        let unknown_owners: Vec<_> = imp
+            .iter()
+            .filter(|owner_id| !self.game.owner_set.contains(owner_id))
+            .copied()
+            .collect();
+        if !unknown_owners.is_empty() {
+            return Err(GameError::UnknownOwners(unknown_owners));
+        }
+        if self.game.dnf.iter().any(|t| t.is_subset(&imp)) {
+            return Ok(false);
+        }
+
+        self.game.dnf.retain(|t| !imp.is_subset(t));
+        self.game.dnf.insert(imp.clone());
+        // supersets of `imp` share its owners, so they are all in the affected components, unless
+        // `imp` is empty and absorbs every component
+        let affected = self.take_components(|c| imp.is_empty() || !c.variables.is_disjoint(&imp));
+        let imps = affected
+            .iter()
+            .flat_map(|c| c.exp.iter())
+            .filter(|t| !imp.is_subset(t))
+            .cloned()
+            .chain([imp.clone()]);
+        self.rebuild(imps, &affected);
+        Ok(true)
+    }
+
+    /// Remove an implicant from the DNF.
+    ///
+    /// Return `false` if the implicant is not in the DNF.
+    pub fn remove_implicant(&mut self, imp: &Implicant<OwnerId>) -> bool {
+        if !self.game.dnf.remove(imp) {
+            return false;
+        }
+
+        let affected = self.take_components(|c| c.exp.contains(imp));
+        let imps = affected
+            .iter()
+            .flat_map(|c| c.exp.iter())
+            .filter(|t| *t != imp)
+            .cloned();
+        self.rebuild(imps, &affected);
+        true
+    }
+
+    /// Shapley values of the current game.
+    ///
+    /// Owners not in any implicant get zero, including all the owners when the DNF is empty.
+    ///
+    /// Panic if a coefficient overflows.
+    pub fn recompute(&mut self) -> ShapleyValues {
+        if let Some(sv) = &self.sv {
+            return sv.clone();
+        }
+
+        let mut coeffs = self.cal_coeffs().unwrap_or_else(|e| panic!("{e}"));
+        for owner_id in self.game.owner_set.iter() {
+            coeffs.entry(*owner_id).or_default();
+        }
+
+        let sv: ShapleyValues = coeffs
+            .into_par_iter()
+            .map(|(owner_id, coeffs)| (owner_id, coeffs.to_sv()))
+            .collect();
+        self.sv = Some(sv.clone());
+        sv
+    }
+
+    /// Coefficients of the owners in the DNF, whose components are combined by a horizontal
+    /// product.
+    fn cal_coeffs(&self) -> Result<HashMap<OwnerId, IECoeffs>, SvError> {
+        let gamma_map = vertical_identity();
+        match self.components.len() {
+            0 => Ok(HashMap::new()),
+            1 => self.components[0].tree.cal_coeffs(&gamma_map),
+            _ => {
+                let children_coeffs = self.components.iter().map(|c| c.tree.coeffs()).collect();
+                let product_tree =
+                    ProductTree::try_new(children_coeffs, horizontal_op_into, false)?;
+                let products =
+                    product_tree.try_all_products(horizontal_identity, horizontal_op_into)?;
+                self.components
+                    .par_iter()
+                    .zip(products)
+                    .map(|(c, iece_map)| {
+                        let next_gamma_map =
+                            gamma_map.checked_sub(&gamma_map.checked_mul(&iece_map)?)?;
+                        c.tree.cal_coeffs(&next_gamma_map)
+                    })
+                    .try_reduce(HashMap::default, |a, b| {
+                        Ok(try_hashmap_reduce(a, b, IECoeffs::checked_add_owned)?)
+                    })
+            }
+        }
+    }
+
+    /// Remove and return the components matching `f`, invalidating the cached Shapley values.
+    fn take_components(&mut self, f: impl Fn(&Component) -> bool) -> Vec<Component> {
+        self.sv = None;
+        let (taken, kept) = std::mem::take(&mut self.components)
+            .into_iter()
+            .partition(f);
+        self.components = kept;
+        taken
+    }
+
+    /// Build the trees of the components of `imps`, which may be split or merged by the edit,
+    /// reusing the subtrees of the `affected` components.
+    fn rebuild(
+        &mut self,
+        imps: impl IntoIterator<Item = Implicant<OwnerId>>,
+        affected: &[Component],
+    ) {
+        let reuse: HashMap<_, _> = affected
+            .iter()
+            .flat_map(|c| c.tree.nodes_by_key())
+            .collect();
+        let ctx = TreeContext {
+            cache: Some(self.cache.as_ref()),
+            reuse: Some(&reuse),
+            ..Default::default()
+        };
+        // an empty implicant wins by itself, so no owner is pivotal and there is no component
+        let imps = imps.into_iter().filter(|t| !t.is_empty());
+        let rebuilt: Vec<_> = connected_components(imps)
+            .into_par_iter()
+            .map(|exp| Component::new(exp, &ctx))
+            .collect();
+        self.components.extend(rebuilt);
+    }
+}
+
+/// Group implicants into DNFs whose owners are disjoint from each other.
+fn connected_components(imps: impl IntoIterator<Item = Implicant<OwnerId>>) -> Vec<Dnf<OwnerId>> {
+    let mut components: Vec<(BTreeSet<OwnerId>, Dnf<OwnerId>)> = vec![];
+    for imp in imps {
+        let mut variables = imp.0.clone();
+        let mut exp = Dnf::from([imp]);
+        let mut i = 0;
+        while i < components.len() {
+            if components[i].0.is_disjoint(&variables) {
+                i += 1;
+            } else {
+                let (vars, mut other) = components.swap_remove(i);
+                variables.extend(vars);
+                exp.append(&mut other);
+            }
+        }
+        components.push((variables, exp));
+    }
+    components.into_iter().map(|(_, exp)| exp).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::{
+            exact::cal_sv_brute_force,
+            synthesis_sv::recursive_decompose::cal_sv_recursive_decompose,
+        },
+        dnf,
+        tests::assert_f64_eq,
+        OwnerSet,
+    };
+
+    fn imp(ids: &[u32]) -> Implicant<OwnerId> {
+        ids.iter().copied().map(OwnerId).collect()
+    }
+
+    fn assert_sv_eq(expect: &ShapleyValues, actual: &ShapleyValues) {
+        assert_eq!(expect.len(), actual.len());
+        for (o, u) in actual {
+            assert_f64_eq(expect[o], *u);
+        }
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let exp = dnf!(1 2 + 3 4 + 2 5 + 6).map_variable(|id| OwnerId(*id));
+        let mut components = connected_components(exp);
+        components.sort();
+        assert_eq!(
+            vec![
+                dnf!(6).map_variable(|id| OwnerId(*id)),
+                dnf!(1 2 + 2 5).map_variable(|id| OwnerId(*id)),
+                dnf!(3 4).map_variable(|id| OwnerId(*id)),
+            ],
+            components
+        );
+
+        let mut components = connected_components([imp(&[1, 2]), imp(&[3, 4]), imp(&[2, 3])]);
+        assert_eq!(1, components.len());
+        assert_eq!(3, components.pop().unwrap().len());
+    }
+
+    enum Edit {
+        Add(&'static [u32]),
+        Remove(&'static [u32]),
+    }
+
+    #[test]
+    fn test_edits() {
+        let game = Game::new(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=9),
+        )
+        .unwrap();
+        let mut session = SvSession::new(game.clone());
+        assert_sv_eq(&cal_sv_recursive_decompose(&game), &session.recompute());
+
+        // (edit, whether the DNF changes)
+        let edits = [
+            // a new component
+            (Edit::Add(&[6, 7]), true),
+            // absorbed by an existing implicant
+            (Edit::Add(&[4, 5, 6]), false),
+            // merge two components
+            (Edit::Add(&[5, 6]), true),
+            // absorb existing implicants
+            (Edit::Add(&[2]), true),
+            // split a component
+            (Edit::Remove(&[5, 6]), true),
+            // not in the DNF
+            (Edit::Remove(&[1, 2, 4]), false),
+            (Edit::Add(&[8]), true),
+            (Edit::Remove(&[2]), true),
+            (Edit::Remove(&[4, 5]), true),
+            (Edit::Remove(&[6, 7]), true),
+        ];
+        for (i, (edit, changed)) in edits.into_iter().enumerate() {
+            let before = session.game().dnf.clone();
+            let actual_changed = match edit {
+                Edit::Add(ids) => session.add_implicant(imp(ids)).unwrap(),
+                Edit::Remove(ids) => session.remove_implicant(&imp(ids)),
+            };
+            assert_eq!(changed, actual_changed, "edit #{i}");
+            assert_eq!(changed, before != session.game().dnf, "edit #{i}");
+
+            let game = session.game().clone();
+            assert_eq!(Ok(()), game.validate(), "edit #{i}");
+            let actual = session.recompute();
+            assert_sv_eq(&cal_sv_brute_force(&game), &actual);
+            assert_sv_eq(&cal_sv_recursive_decompose(&game), &actual);
+        }
+        assert_eq!(dnf!(8).map_variable(|id| OwnerId(*id)), session.game().dnf);
+    }
+
+    #[test]
+    fn test_reuse_subtrees() {
+        // 0 (maj(1, 2, 3) + maj(4 5, 6, 7) + maj(8 9 10, 11, 12)), whose majorities are Hybrid
+        // nodes of different shapes
+        let game = Game::new(
+            dnf!(0 1 2 + 0 1 3 + 0 2 3 + 0 4 5 6 + 0 4 5 7 + 0 6 7
+                + 0 8 9 10 11 + 0 8 9 10 12 + 0 11 12)
+            .map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(0..=20),
+        )
+        .unwrap();
+        let mut session = SvSession::new(game);
+        // the Hybrid nodes built, whose coefficients are looked up in the cache
+        let built = |session: &SvSession| session.cache.hits() + session.cache.misses();
+        assert_eq!(3, session.cache.misses());
+        assert_eq!(3, built(&session));
+
+        // the component is rebuilt, but all its majorities are reused as is
+        assert_eq!(Ok(true), session.add_implicant(imp(&[0, 19, 20])));
+        assert_eq!(3, built(&session));
+        let expect = cal_sv_recursive_decompose(session.game());
+        assert_sv_eq(&expect, &session.recompute());
+
+        // a new majority of the same shape as maj(4 5, 6, 7), whose coefficients are cached
+        assert_eq!(Ok(true), session.add_implicant(imp(&[0, 13, 14, 15])));
+        assert_eq!(Ok(true), session.add_implicant(imp(&[0, 13, 14, 16])));
+        assert_eq!(3, built(&session));
+        assert_eq!(Ok(true), session.add_implicant(imp(&[0, 15, 16])));
+        assert_eq!(1, session.cache.hits());
+        assert_eq!(3, session.cache.misses());
+        let expect = cal_sv_recursive_decompose(session.game());
+        assert_sv_eq(&expect, &session.recompute());
+
+        // removing an implicant reuses the other majorities
+        assert!(session.remove_implicant(&imp(&[0, 1, 2])));
+        assert_eq!(4, built(&session));
+        let expect = cal_sv_recursive_decompose(session.game());
+        assert_sv_eq(&expect, &session.recompute());
+    }
+
+    #[test]
+    fn test_empty_dnf() {
+        let game = Game::new(
+            dnf!(1 2).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter([1, 2, 3]),
+        )
+        .unwrap();
+        let mut session = SvSession::new(game);
+        assert!(session.remove_implicant(&imp(&[1, 2])));
+        let sv = session.recompute();
+        assert_eq!(3, sv.len());
+        assert!(sv.values().all(|u| *u == 0.));
+
+        assert_eq!(
+            Err(GameError::UnknownOwners(vec![OwnerId(4)])),
+            session.add_implicant(imp(&[1, 4]))
+        );
+        assert_eq!(Ok(true), session.add_implicant(imp(&[3])));
+        assert_f64_eq(1., session.recompute()[&OwnerId(3)]);
+    }
+
+    #[test]
+    fn test_empty_implicant() {
+        let game = Game::new(
+            dnf!(1 2 + 3).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter([1, 2, 3]),
+        )
+        .unwrap();
+        let mut session = SvSession::new(game);
+        session.recompute();
+
+        // every coalition wins
+        assert_eq!(Ok(true), session.add_implicant(imp(&[])));
+        assert_eq!(Dnf::true_exp(), session.game().dnf);
+        let sv = session.recompute();
+        assert_eq!(3, sv.len());
+        assert!(sv.values().all(|u| *u == 0.));
+        assert_eq!(Ok(false), session.add_implicant(imp(&[1])));
+
+        assert!(session.remove_implicant(&imp(&[])));
+        assert_eq!(Ok(true), session.add_implicant(imp(&[1, 2])));
+        let expect = cal_sv_recursive_decompose(session.game());
+        assert_sv_eq(&expect, &session.recompute());
+        assert_f64_eq(0.5, expect[&OwnerId(1)]);
+    }
+}
@@ -0,0 +1,261 @@
+use super::{decompose_tree::DecomposeTree, iec::*, SvError};
+use crate::{Game, OwnerId};
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+/// Margin of the float bounds, so that rounding never prunes an owner of the top k.
+const BOUND_EPS: f64 = 1e-9;
+
+/// Statistics of `cal_sv_topk_with_stats`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TopkStats {
+    /// Number of nodes of the decomposition tree that are evaluated, i.e., the Shapley values of
+    /// their owners (for Var and Leaf nodes) or the bounds of their children are computed.
+    pub expanded: usize,
+    /// Number of subtrees skipped as none of their owners can be in the top k.
+    pub pruned: usize,
+}
+
+/// The `k` owners with the largest Shapley values, sorted in descending order of the values.
+///
+/// Ties are broken by `OwnerId` in ascending order, so exactly `min(k, n)` owners are returned.
+/// See `cal_sv_topk_with_stats`.
+///
+/// Panic if a coefficient overflows.
+pub fn cal_sv_topk(game: &Game, k: usize) -> Vec<(OwnerId, f64)> {
+    cal_sv_topk_with_stats(game, k).0
+}
+
+/// Same as `cal_sv_topk`, but also return how much of the decomposition tree is pruned.
+///
+/// The decomposition tree is descended best-first by an upper bound of each subtree: the total
+/// Shapley value of its owners, which is the integral of its gamma map times the derivative of
+/// its coefficients. As the values of a monotone game are non-negative, no owner in a subtree can
+/// exceed it. Once the k-th largest value computed so far is above the bound of every pending
+/// subtree, the remaining subtrees are pruned. The returned values are exact.
+pub fn cal_sv_topk_with_stats(game: &Game, k: usize) -> (Vec<(OwnerId, f64)>, TopkStats) {
+    let is_constant = game.dnf.is_false() || game.dnf.is_true();
+    let tree = (k > 0 && !is_constant).then(|| DecomposeTree::from_game(game, None));
+    let mut search = TopkSearch::new(k);
+    if let Some(tree) = &tree {
+        search.run(tree).unwrap_or_else(|e| panic!("{e}"));
+    }
+
+    // null players only matter if there are not enough owners in the DNF, which is never the case
+    // once anything is pruned
+    let mut ans = search.values;
+    if search.stats.pruned == 0 {
+        for owner_id in game.owner_set.iter() {
+            if !ans.iter().any(|(o, _)| o == owner_id) {
+                ans.push((*owner_id, BigRational::default()));
+            }
+        }
+    }
+    ans.sort_by(|(o1, u1), (o2, u2)| u2.cmp(u1).then(o1.cmp(o2)));
+    let ans = ans
+        .into_iter()
+        .take(k)
+        .map(|(owner_id, u)| (owner_id, u.to_f64().unwrap_or(f64::NAN)))
+        .collect();
+    (ans, search.stats)
+}
+
+struct TopkSearch<'a> {
+    k: usize,
+    /// Exact values of the evaluated owners.
+    values: Vec<(OwnerId, BigRational)>,
+    /// The k largest values so far, as a min-heap.
+    top: BinaryHeap<Reverse<BigRational>>,
+    pending: BinaryHeap<Pending<'a>>,
+    stats: TopkStats,
+}
+
+/// A subtree to evaluate, ordered by its bound.
+struct Pending<'a> {
+    bound: f64,
+    node: &'a DecomposeTree,
+    gamma_map: IECoeffs,
+}
+
+impl PartialEq for Pending<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending<'_> {}
+
+impl PartialOrd for Pending<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bound.total_cmp(&other.bound)
+    }
+}
+
+impl<'a> TopkSearch<'a> {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            values: vec![],
+            top: BinaryHeap::new(),
+            pending: BinaryHeap::new(),
+            stats: TopkStats::default(),
+        }
+    }
+
+    fn run(&mut self, root: &'a DecomposeTree) -> Result<(), SvError> {
+        self.expand(root, vertical_identity())?;
+        while let Some(p) = self.pending.pop() {
+            if self.kth_value().is_some_and(|u| u > p.bound + BOUND_EPS) {
+                self.stats.pruned = 1 + self.pending.len();
+                return Ok(());
+            }
+            self.expand(p.node, p.gamma_map)?;
+        }
+        Ok(())
+    }
+
+    fn kth_value(&self) -> Option<f64> {
+        if self.top.len() < self.k {
+            return None;
+        }
+        self.top
+            .peek()
+            .map(|Reverse(u)| u.to_f64().unwrap_or(f64::NAN))
+    }
+
+    fn expand(&mut self, node: &'a DecomposeTree, gamma_map: IECoeffs) -> Result<(), SvError> {
+        self.stats.expanded += 1;
+        match node {
+            DecomposeTree::Var(_) | DecomposeTree::Leaf { .. } => {
+                for (owner_id, coeffs) in node.cal_coeffs(&gamma_map)? {
+                    let u = coeffs.to_sv_exact();
+                    self.top.push(Reverse(u.clone()));
+                    if self.top.len() > self.k {
+                        self.top.pop();
+                    }
+                    self.values.push((owner_id, u));
+                }
+            }
+            _ => {
+                for (i, c) in node.children().iter().enumerate() {
+                    let gamma_map = node.child_gamma_map(i, &gamma_map)?;
+                    let bound = gamma_map
+                        .checked_mul(&c.coeffs().degree_weighted()?)?
+                        .to_sv();
+                    self.pending.push(Pending {
+                        bound,
+                        node: c,
+                        gamma_map,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::synthesis_sv::recursive_decompose::cal_sv_recursive_decompose,
+        dnf,
+        tests::{assert_f64_eq, game},
+        OwnerSet,
+    };
+
+    fn ids(topk: &[(OwnerId, f64)]) -> Vec<u32> {
+        topk.iter().map(|(o, _)| o.0).collect()
+    }
+
+    #[test]
+    fn test_topk() {
+        let games = [
+            game(dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5)),
+            game(dnf!(1 2 5 + 1 2 6 + 1 3 5 + 1 3 6 + 4 5 + 4 6)),
+            game(dnf!(1 2 + 1 3 + 2 3 + 4 5 + 6 7 8)),
+            Game::new(
+                dnf!(1 2 + 1 3 + 4).map_variable(|id| OwnerId(*id)),
+                OwnerSet::from_iter(1..=6),
+            )
+            .unwrap(),
+        ];
+        for game in games {
+            let expect = cal_sv_recursive_decompose(&game);
+            for k in 0..=expect.len() + 1 {
+                let actual = cal_sv_topk(&game, k);
+                assert_eq!(k.min(expect.len()), actual.len());
+                for (o, u) in &actual {
+                    assert_f64_eq(expect[o], *u);
+                }
+                for w in actual.windows(2) {
+                    assert!(w[0].1 >= w[1].1 - BOUND_EPS);
+                }
+                // the others are no more than the returned ones
+                if let Some((_, min)) = actual.last() {
+                    for (o, u) in &expect {
+                        if !actual.iter().any(|(p, _)| p == o) {
+                            assert!(*u <= min + BOUND_EPS);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ties() {
+        // 1, 2 and 3 tie, and so do 4, 5 and 6
+        let game = game(dnf!(1 + 2 + 3 + 4 5 6));
+        assert_eq!(vec![1, 2], ids(&cal_sv_topk(&game, 2)));
+        assert_eq!(vec![1, 2, 3, 4], ids(&cal_sv_topk(&game, 4)));
+        let topk = cal_sv_topk(&game, 6);
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], ids(&topk));
+        assert_f64_eq(19. / 60., topk[0].1);
+        assert_f64_eq(1. / 60., topk[5].1);
+    }
+
+    #[test]
+    fn test_pruning() {
+        // a dominant owner, a pair, and two groups of symmetric low-value owners
+        let game = game(dnf!(1 + 2 3 + 4 5 6 7 + 8 9 10 11));
+        let expect = cal_sv_recursive_decompose(&game);
+
+        let (topk, stats) = cal_sv_topk_with_stats(&game, 1);
+        assert_eq!(vec![1], ids(&topk));
+        assert_f64_eq(expect[&OwnerId(1)], topk[0].1);
+        // the root and owner 1, while the other three children of the root are pruned
+        assert_eq!(
+            TopkStats {
+                expanded: 2,
+                pruned: 3
+            },
+            stats
+        );
+
+        let (topk, stats) = cal_sv_topk_with_stats(&game, 3);
+        assert_eq!(vec![1, 2, 3], ids(&topk));
+        assert_eq!(
+            TopkStats {
+                expanded: 5,
+                pruned: 2
+            },
+            stats
+        );
+
+        // all owners are needed
+        let (topk, stats) = cal_sv_topk_with_stats(&game, 11);
+        assert_eq!(11, topk.len());
+        assert_eq!(0, stats.pruned);
+    }
+}
@@ -1,6 +1,5 @@
-use crate::{alg::subset_utility::subset_utility, Game, OwnerSet, ShapleyValues};
+use crate::{alg::subset_utility::subset_utility, par::prelude::*, Game, OwnerSet, ShapleyValues};
 use itertools::Itertools;
-use rayon::prelude::*;
 
 pub fn traditional_method(game: &Game) -> ShapleyValues {
     // info!("traditional method...");
@@ -47,6 +46,6 @@ mod tests {
 
     #[test]
     fn test() {
-        test_method(|game| traditional_method(game), true);
+        test_method(traditional_method, true);
     }
 }
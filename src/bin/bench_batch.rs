@@ -0,0 +1,83 @@
+use anyhow::{ensure, Result};
+use clap::Parser;
+use rayon::prelude::*;
+use shapley_value_decomposition::{
+    alg::synthesis_sv::{
+        batch::cal_sv_batch,
+        recursive_decompose_ablation::{cal_sv_recursive_decompose_ablation, AblationType},
+    },
+    gen::GameGenerator,
+    *,
+};
+use std::time::Instant;
+
+/// Compare `cal_sv_batch` against computing many small random games one by one, each with its own
+/// internal parallelism.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Number of games
+    #[clap(short, long, default_value_t = 10000)]
+    games: usize,
+
+    /// Number of owners in a game
+    #[clap(short = 'n', long, default_value_t = 4)]
+    owners: usize,
+
+    /// Number of implicants in a game before minimization
+    #[clap(short = 'm', long, default_value_t = 3)]
+    implicants: usize,
+
+    /// Kind of decomposition to disable
+    #[clap(short, long, value_enum, default_value = "no-hybrid")]
+    ablation_type: AblationType,
+
+    /// Random seed
+    #[clap(short, long, default_value_t = 0)]
+    seed: u64,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    ensure!(args.owners > 0, "no owners");
+
+    let gen = GameGenerator {
+        num_owners: args.owners,
+        num_implicants: args.implicants,
+        seed: args.seed,
+        ..Default::default()
+    };
+    let games: Vec<Game> = gen.games().take(args.games).collect();
+
+    let begin = Instant::now();
+    let one_by_one: Vec<ShapleyValues> = games
+        .iter()
+        .map(|game| cal_sv_recursive_decompose_ablation(game, args.ablation_type))
+        .collect();
+    let one_by_one_time = begin.elapsed();
+
+    let begin = Instant::now();
+    let outer: Vec<ShapleyValues> = games
+        .par_iter()
+        .map(|game| cal_sv_recursive_decompose_ablation(game, args.ablation_type))
+        .collect();
+    let outer_time = begin.elapsed();
+
+    let begin = Instant::now();
+    let batch = cal_sv_batch(&games, args.ablation_type);
+    let batch_time = begin.elapsed();
+
+    let mut max_diff = 0_f64;
+    for ((a, b), c) in one_by_one.iter().zip(&outer).zip(&batch) {
+        for (owner_id, u) in a.iter() {
+            max_diff = max_diff
+                .max((u - b.get_or_zero(owner_id)).abs())
+                .max((u - c.get_or_zero(owner_id)).abs());
+        }
+    }
+
+    println!("one by one:           {one_by_one_time:?}");
+    println!("parallel over games:  {outer_time:?}");
+    println!("cal_sv_batch:         {batch_time:?}");
+    println!("max difference: {max_diff:e}");
+    Ok(())
+}
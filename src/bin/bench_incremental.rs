@@ -0,0 +1,81 @@
+use anyhow::{ensure, Result};
+use clap::Parser;
+use rand::{prelude::*, rngs::StdRng};
+use shapley_value_decomposition::{
+    alg::synthesis_sv::{recursive_decompose::cal_sv_recursive_decompose, session::SvSession},
+    *,
+};
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+/// Compare recomputing the Shapley values incrementally with `SvSession` against from scratch,
+/// when random implicants are added to a game one at a time.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Number of owners
+    #[clap(short = 'n', long, default_value_t = 200)]
+    owners: u32,
+
+    /// Number of implicants to add
+    #[clap(short = 'm', long, default_value_t = 100)]
+    implicants: usize,
+
+    /// Maximum number of owners in an implicant
+    #[clap(short = 'k', long, default_value_t = 3)]
+    max_len: usize,
+
+    /// Random seed
+    #[clap(short, long, default_value_t = 0)]
+    seed: u64,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    ensure!(args.owners > 0 && args.max_len > 0, "empty implicants");
+
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    let owners: Vec<OwnerId> = (0..args.owners).map(OwnerId).collect();
+    let imps: Vec<BTreeSet<OwnerId>> = (0..args.implicants)
+        .map(|_| {
+            let len = rng.gen_range(1..=args.max_len.min(owners.len()));
+            owners.choose_multiple(&mut rng, len).copied().collect()
+        })
+        .collect();
+
+    // start from no winning coalition at all
+    let mut game = Game {
+        dnf: Dnf::new(),
+        owner_set: owners.iter().copied().collect(),
+    };
+    let mut session = SvSession::new(game.clone());
+    let (mut full_time, mut incremental_time) = (Duration::ZERO, Duration::ZERO);
+    let mut max_diff = 0_f64;
+
+    for imp in imps {
+        let begin = Instant::now();
+        game = game.with_added_implicant(imp.clone());
+        let expect = cal_sv_recursive_decompose(&game);
+        full_time += begin.elapsed();
+
+        let begin = Instant::now();
+        session.add_implicant(imp.into())?;
+        let actual = session.recompute();
+        incremental_time += begin.elapsed();
+
+        for (owner_id, u) in expect.iter() {
+            max_diff = max_diff.max((u - actual.get_or_zero(owner_id)).abs());
+        }
+    }
+
+    println!("implicants in the final DNF: {}", game.dnf.len());
+    println!("full recompute: {full_time:?}");
+    println!("incremental:    {incremental_time:?}");
+    println!(
+        "speedup: {:.2}x",
+        full_time.as_secs_f64() / incremental_time.as_secs_f64()
+    );
+    println!("max difference: {max_diff:e}");
+    Ok(())
+}
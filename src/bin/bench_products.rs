@@ -0,0 +1,59 @@
+use anyhow::{ensure, Result};
+use clap::Parser;
+use rand::prelude::*;
+use shapley_value_decomposition::{
+    alg::synthesis_sv::iec::{vertical_identity, vertical_op_into, IECoeffs},
+    product_tree::{try_all_products_linear, ProductTree},
+};
+use std::time::{Duration, Instant};
+
+/// Time the products of all the children but one of an And node, by `ProductTree::all_products`
+/// and by `all_products_linear`. The children are random monomials, as for unanimity games of
+/// different sizes, so the coefficients never overflow.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Number of runs
+    #[clap(short, long, default_value_t = 20)]
+    runs: usize,
+
+    /// Numbers of children
+    #[clap(short, long, default_values_t = [4, 16, 64, 256, 1024, 4096])]
+    children: Vec<usize>,
+
+    /// Random seed
+    #[clap(short, long, default_value_t = 0)]
+    seed: u64,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    ensure!(args.runs > 0, "no run");
+
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    println!("children       tree     linear");
+    for &n in &args.children {
+        let input: Vec<_> = (0..n)
+            .map(|_| IECoeffs::monomial(rng.gen_range(1..4), 1))
+            .collect();
+        let refs: Vec<_> = input.iter().collect();
+
+        let (mut tree_time, mut linear_time) = (Duration::ZERO, Duration::ZERO);
+        for _ in 0..args.runs {
+            let begin = Instant::now();
+            let tree = ProductTree::try_new(refs.clone(), vertical_op_into, false)?;
+            let expect = tree.try_all_products(vertical_identity, vertical_op_into)?;
+            tree_time += begin.elapsed();
+
+            let begin = Instant::now();
+            let (actual, _) = try_all_products_linear(&refs, vertical_identity, vertical_op_into)?;
+            linear_time += begin.elapsed();
+            ensure!(expect == actual, "different products of {n} children");
+        }
+        println!(
+            "{n:>8} {:>10.2?} {:>10.2?}",
+            tree_time / args.runs as u32,
+            linear_time / args.runs as u32
+        );
+    }
+    Ok(())
+}
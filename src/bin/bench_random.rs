@@ -0,0 +1,82 @@
+use anyhow::{ensure, Result};
+use clap::Parser;
+use shapley_value_decomposition::{
+    alg::synthesis_sv::recursive_decompose::cal_sv_recursive_decompose,
+    gen::{GameGenerator, Structure},
+};
+use std::time::{Duration, Instant};
+
+/// Time generating random games with `GameGenerator` and computing their Shapley values.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Number of games
+    #[clap(short, long, default_value_t = 10)]
+    games: usize,
+
+    /// Number of owners
+    #[clap(short = 'n', long, default_value_t = 50)]
+    owners: usize,
+
+    /// Number of implicants before minimization
+    #[clap(short = 'm', long, default_value_t = 50)]
+    implicants: usize,
+
+    /// Minimum number of owners in an implicant
+    #[clap(long, default_value_t = 1)]
+    min_len: usize,
+
+    /// Maximum number of owners in an implicant
+    #[clap(short = 'k', long, default_value_t = 3)]
+    max_len: usize,
+
+    /// Split the owners into this many blocks, or 0 for no block structure
+    #[clap(short, long, default_value_t = 0)]
+    blocks: usize,
+
+    /// Random seed
+    #[clap(short, long, default_value_t = 0)]
+    seed: u64,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    ensure!(
+        0 < args.min_len && args.min_len <= args.max_len,
+        "invalid implicant lengths"
+    );
+    ensure!(args.blocks <= args.owners, "more blocks than owners");
+
+    let gen = GameGenerator {
+        num_owners: args.owners,
+        num_implicants: args.implicants,
+        implicant_size: args.min_len..args.max_len + 1,
+        structure: match args.blocks {
+            0 => Structure::Random,
+            m => Structure::Blocks(m),
+        },
+        seed: args.seed,
+    };
+
+    let (mut gen_time, mut solve_time) = (Duration::ZERO, Duration::ZERO);
+    let mut total_implicants = 0;
+    let mut games = gen.games();
+    for _ in 0..args.games {
+        let begin = Instant::now();
+        let game = games.next().expect("endless games");
+        gen_time += begin.elapsed();
+        total_implicants += game.dnf.len();
+
+        let begin = Instant::now();
+        let sv = cal_sv_recursive_decompose(&game);
+        solve_time += begin.elapsed();
+        sv.assert_efficient(game.expected_sv_sum(), 1e-6);
+    }
+
+    println!(
+        "implicants per game after minimization: {:.1}",
+        total_implicants as f64 / args.games.max(1) as f64
+    );
+    println!("generation: {gen_time:?}");
+    println!("solve:      {solve_time:?}");
+    Ok(())
+}
@@ -5,7 +5,7 @@ use anyhow::{Context, Ok, Result};
 use clap::{Parser, ValueEnum};
 use rayon::prelude::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
 use serde_json::json;
-use shapley_value_decomposition::{utils::hashmap_reduce, *};
+use shapley_value_decomposition::*;
 use std::{fs::File, io::BufWriter, path::PathBuf, time::Instant};
 
 #[derive(Debug, Parser)]
@@ -44,6 +44,7 @@ struct Args {
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
+#[allow(clippy::upper_case_acronyms)]
 enum Method {
     /// Traditional method
     #[clap(alias("trad"))]
@@ -95,7 +96,7 @@ fn main() -> Result<()> {
                     }
                 }
             })
-            .reduce(ShapleyValues::default, hashmap_reduce);
+            .reduce(ShapleyValues::default, ShapleyValues::merge);
 
         let sv_cal_time = Instant::now() - begin_cal;
         info!("time in sv_cal {:?}", sv_cal_time);
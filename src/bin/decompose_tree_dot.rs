@@ -0,0 +1,37 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use shapley_value_decomposition::{
+    alg::synthesis_sv::decompose_tree::{AblationType, DecomposeTree},
+    *,
+};
+use std::{fs, path::PathBuf};
+
+/// Write the decomposition tree of a DNF in Graphviz DOT format.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Input DNF, e.g., "1 2 + 1 3 + 2 3"
+    #[clap(short = 'e', long, value_parser)]
+    dnf: String,
+
+    /// Output DOT file
+    #[clap(short, long, value_parser)]
+    output: PathBuf,
+
+    /// Type of Ablation
+    #[clap(long, value_enum)]
+    ablation: Option<AblationType>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let mut exp = Dnf::parse_with(&args.dnf, |t| t.parse().map(OwnerId)).context("invalid DNF")?;
+    exp.minimize();
+    let game = Game::from_dnf(exp);
+    game.validate()?;
+    let tree = DecomposeTree::from_game(&game, args.ablation);
+
+    fs::write(&args.output, tree.to_dot())
+        .with_context(|| format!("failed to write {}", args.output.display()))?;
+    Ok(())
+}
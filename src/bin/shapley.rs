@@ -0,0 +1,100 @@
+use anyhow::{ensure, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use shapley_value_decomposition::{
+    alg::{algorithm::Algorithm, synthesis_sv::recursive_decompose_ablation::AblationType},
+    *,
+};
+use std::{fs, path::PathBuf};
+
+/// Tolerance of the sum of the Shapley values before warning that it is not 1.
+const EFFICIENCY_TOLERANCE: f64 = 1e-6;
+
+/// Shapley values of simple games given as monotone DNFs.
+#[derive(Debug, Parser)]
+struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Compute the Shapley value of each owner
+    Compute(ComputeArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct ComputeArgs {
+    /// Input file with one implicant per line, e.g., "1 2 4", or a DNF string, e.g.,
+    /// "1 2 4 + 4 5", and an optional first line like "owners: 1 2 3 4 5"
+    #[clap(short, long, value_parser)]
+    input: PathBuf,
+
+    /// Algorithm
+    #[clap(short, long, value_enum, default_value = "recursive-decompose")]
+    algorithm: Algorithm,
+
+    /// Type of Ablation (for recursive-decompose)
+    #[clap(long, value_enum)]
+    ablation: Option<AblationType>,
+
+    /// Sample size (for permutation-sampling)
+    #[clap(short, long, default_value = "10000")]
+    samples: usize,
+
+    /// Random seed (for permutation-sampling)
+    #[clap(long, default_value = "0")]
+    seed: u64,
+
+    /// Output format
+    #[clap(short, long, alias = "format", value_enum, default_value = "table")]
+    output: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable table sorted by value in descending order
+    Table,
+    Csv,
+    Json,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    match args.command {
+        Command::Compute(args) => compute(args),
+    }
+}
+
+fn compute(args: ComputeArgs) -> Result<()> {
+    ensure!(
+        args.ablation.is_none() || args.algorithm == Algorithm::RecursiveDecompose,
+        "--ablation only applies to recursive-decompose"
+    );
+
+    let input = fs::read_to_string(&args.input)
+        .with_context(|| format!("failed to read {}", args.input.display()))?;
+    let game = Game::parse_implicant_lines(&input)
+        .with_context(|| format!("invalid game in {}", args.input.display()))?;
+
+    let sv = args
+        .algorithm
+        .with_params(args.ablation, args.samples, args.seed)
+        .compute(&game)?;
+
+    let expected = game.expected_sv_sum();
+    if let Err(sum) = sv.check_efficiency(expected, EFFICIENCY_TOLERANCE) {
+        eprintln!("warning: the Shapley values sum up to {sum} rather than {expected}");
+    }
+
+    match args.output {
+        OutputFormat::Table => {
+            println!("owner\tvalue");
+            for (owner, value) in sv.sorted_desc() {
+                println!("{owner}\t{value:.6}");
+            }
+        }
+        OutputFormat::Csv => print!("{}", sv.to_csv_string()?),
+        OutputFormat::Json => println!("{}", sv.to_json_string()?),
+    }
+    Ok(())
+}
@@ -1,18 +1,27 @@
 //! Ref: Jan C. Bioch, Modular Decomposition of Boolean Functions, 2002
 #![allow(clippy::module_inception)]
 
+mod cnf;
 mod decompose;
+pub(crate) mod dense;
 mod dnf;
 mod implicant;
+mod literal;
 mod modular_closure;
+mod parse;
 pub(crate) mod recursive_decompose;
 mod unionfind;
 mod utils;
 
+pub use cnf::Cnf;
 pub use decompose::{decompose, Decompose, SubExp};
 pub use dnf::Dnf;
 pub use implicant::Implicant;
-pub use recursive_decompose::{recursive_decompose, RecursiveDecompose};
+pub use literal::{GeneralDnf, Literal};
+pub use parse::DnfParseError;
+pub use recursive_decompose::{
+    recursive_decompose, recursive_decompose_to_dot, RecursiveDecompose,
+};
 
 /// Trait for boolean expression variable.
 pub trait Var: Clone + Ord + Eq + Sync + Send {}
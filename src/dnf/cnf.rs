@@ -0,0 +1,324 @@
+use super::{utils::*, Dnf, DnfParseError, Implicant, Var};
+use crate::par::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt, mem, str::FromStr};
+
+/// A boolean expression in CNF, i.e., a conjunction of clauses, each of which is the disjunction
+/// of its variables.
+///
+/// Clauses are stored as `Implicant`s, i.e., sets of variables. If the underlying set is empty,
+/// the expression is considered as TRUE. If it contains an empty clause, the expression is
+/// considered as FALSE.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    derive_more::Deref,
+    derive_more::DerefMut,
+    derive_more::AsRef,
+    derive_more::AsMut,
+    derive_more::From,
+    Serialize,
+    Deserialize,
+)]
+#[as_ref(forward)]
+#[as_mut(forward)]
+#[from(forward)]
+#[serde(transparent)]
+pub struct Cnf<T: Var>(pub BTreeSet<Implicant<T>>);
+
+impl<T: Var> Cnf<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn true_exp() -> Self {
+        Self::new()
+    }
+
+    pub fn false_exp() -> Self {
+        Self::from([Implicant::new()])
+    }
+
+    pub fn is_true(&self) -> bool {
+        self.is_empty()
+    }
+
+    pub fn is_false(&self) -> bool {
+        self.contains(&Implicant::new())
+    }
+
+    /// Return a set of all variables.
+    pub fn all_variables(&self) -> BTreeSet<T> {
+        let mut ans = BTreeSet::new();
+        for c in self.iter() {
+            ans.extend(c.iter().cloned());
+        }
+        ans
+    }
+
+    /// Remove clauses absorbed by (i.e., a superset of) another clause.
+    pub fn minimize(&mut self) {
+        let mut exp = Dnf::from(mem::take(&mut self.0));
+        exp.minimize();
+        self.0 = exp.0;
+    }
+
+    /// Eval to TRUE or FALSE.
+    pub fn eval(&self, input_set: &BTreeSet<T>, input_is_true: bool) -> bool {
+        self.par_iter()
+            .all(|c| c.iter().any(|var| input_set.contains(var) == input_is_true))
+    }
+
+    /// Convert to the equivalent minimized DNF by distributing the clauses over each other.
+    ///
+    /// Absorbed clauses are dropped first. An implicant already satisfying the next clause is kept
+    /// as is rather than being extended with each variable of the clause, and the intermediate DNF
+    /// is minimized after each clause, which keeps redundant implicants from piling up.
+    pub fn to_dnf(&self) -> Dnf<T> {
+        let mut cnf = self.clone();
+        cnf.minimize();
+
+        let mut ans = Dnf::true_exp();
+        for clause in cnf.iter() {
+            let next: BTreeSet<_> = ans
+                .par_iter()
+                .flat_map_iter(|t| {
+                    if has_intersection(&t.0, &clause.0) {
+                        vec![t.clone()]
+                    } else {
+                        clause
+                            .iter()
+                            .map(|var| {
+                                let mut t = t.clone();
+                                t.insert(var.clone());
+                                t
+                            })
+                            .collect()
+                    }
+                })
+                .collect();
+            ans = Dnf::from(next);
+            ans.minimize();
+        }
+        ans
+    }
+
+    /// Apply f to every variable in the CNF.
+    pub fn map_variable<U: Var>(&self, f: impl Fn(&T) -> U) -> Cnf<U> {
+        self.iter().map(|c| c.map_variable(&f)).collect()
+    }
+
+    /// Parse a CNF from a string in the same format as its `Display`, e.g., `(1 + 2) (3 + 4)`, with
+    /// each variable token converted by `f`.
+    ///
+    /// Each clause is enclosed in parentheses with its variables separated by `+`, and `(FALSE)` is
+    /// the empty clause. `TRUE` and `FALSE` stand for the constant expressions. The result is not
+    /// minimized.
+    pub fn parse_with<E>(
+        s: &str,
+        mut f: impl FnMut(&str) -> Result<T, E>,
+    ) -> Result<Self, DnfParseError> {
+        match s.trim() {
+            "TRUE" => return Ok(Self::true_exp()),
+            "FALSE" => return Ok(Self::false_exp()),
+            _ => {}
+        }
+
+        let mut exp = Cnf::new();
+        let mut offset = 0;
+        loop {
+            offset += s[offset..].len() - s[offset..].trim_start().len();
+            let rest = &s[offset..];
+            if rest.is_empty() {
+                break;
+            }
+            if !rest.starts_with('(') {
+                return Err(DnfParseError::new(offset, "expect `(` before a clause"));
+            }
+            let Some(end) = rest.find(')') else {
+                return Err(DnfParseError::new(offset, "unclosed `(`"));
+            };
+
+            let clause = Dnf::parse_with(&rest[1..end], &mut f)
+                .map_err(|e| DnfParseError::new(offset + 1 + e.offset, e.message))?;
+            if clause.iter().any(|t| t.len() != 1) {
+                return Err(DnfParseError::new(
+                    offset,
+                    "variables in a clause should be separated by `+`",
+                ));
+            }
+            exp.insert(clause.into_iter().flat_map(|t| t.0).collect());
+            offset += end + 1;
+        }
+
+        if exp.is_empty() {
+            return Err(DnfParseError::new(0, "empty expression"));
+        }
+        Ok(exp)
+    }
+}
+
+impl<T: Var + FromStr> Cnf<T> {
+    /// Parse a CNF from a string like `(1 + 2) (3 + 4)`. See `parse_with` for the format.
+    pub fn parse(s: &str) -> Result<Self, DnfParseError> {
+        Self::parse_with(s, T::from_str)
+    }
+}
+
+impl<T: Var + FromStr> FromStr for Cnf<T> {
+    type Err = DnfParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl<T: Var> Default for Cnf<T> {
+    fn default() -> Self {
+        Self(BTreeSet::new())
+    }
+}
+
+impl<T: Var> FromIterator<Implicant<T>> for Cnf<T> {
+    fn from_iter<I: IntoIterator<Item = Implicant<T>>>(iter: I) -> Self {
+        Self(BTreeSet::from_iter(iter))
+    }
+}
+
+impl<T: Var> IntoIterator for Cnf<T> {
+    type Item = <BTreeSet<Implicant<T>> as IntoIterator>::Item;
+    type IntoIter = <BTreeSet<Implicant<T>> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<T: Var + fmt::Display> fmt::Display for Cnf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "TRUE");
+        }
+
+        for (i, c) in self.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            if c.is_empty() {
+                write!(f, "(FALSE)")?;
+                continue;
+            }
+            write!(f, "(")?;
+            for (j, v) in c.iter().enumerate() {
+                if j != 0 {
+                    write!(f, " + ")?;
+                }
+                write!(f, "{v}")?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+#[macro_export]
+macro_rules! cnf {
+    () => {
+        $crate::dnf::Cnf::<i32>::true_exp()
+    };
+    (true) => {
+        $crate::dnf::Cnf::<i32>::true_exp()
+    };
+    (false) => {{
+        $crate::dnf::Cnf::<i32>::false_exp()
+    }};
+    ($(($x: literal $(+ $y: literal)*))+) => {{
+        let mut exp = $crate::dnf::Cnf::new();
+        $(
+            exp.insert($crate::implicant!($x $($y)*));
+        )+
+        exp
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dnf, implicant};
+
+    #[test]
+    fn test_display() {
+        assert_eq!("TRUE", format!("{}", cnf!()));
+        assert_eq!("(FALSE)", format!("{}", cnf!(false)));
+        assert_eq!("(1)", format!("{}", cnf!((1))));
+        assert_eq!("(3) (1 + 2)", format!("{}", cnf!((1 + 2)(3))));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(cnf!((1 + 2)(3))), "(1 + 2) (3)".parse::<Cnf<i32>>());
+        assert_eq!(Ok(cnf!((1 + 2)(3))), " (1+2)(3) ".parse::<Cnf<i32>>());
+        assert_eq!(Ok(cnf!(true)), "TRUE".parse::<Cnf<i32>>());
+        assert_eq!(Ok(cnf!(false)), "FALSE".parse::<Cnf<i32>>());
+
+        for exp in [cnf!(true), cnf!(false), cnf!((1 + 2)(3)), cnf!((1)(1 + 2))] {
+            assert_eq!(Ok(exp.clone()), exp.to_string().parse::<Cnf<i32>>());
+        }
+
+        let err = "(1 + 2) 3".parse::<Cnf<i32>>().unwrap_err();
+        assert_eq!(8, err.offset);
+        let err = "(1 + 2".parse::<Cnf<i32>>().unwrap_err();
+        assert_eq!("unclosed `(`", err.message);
+        let err = "(1 2)".parse::<Cnf<i32>>().unwrap_err();
+        assert_eq!(0, err.offset);
+        let err = "(1 + x)".parse::<Cnf<i32>>().unwrap_err();
+        assert_eq!(5, err.offset);
+    }
+
+    #[test]
+    fn test_to_dnf() {
+        assert_eq!(dnf!(true), cnf!(true).to_dnf());
+        assert_eq!(dnf!(false), cnf!(false).to_dnf());
+        assert_eq!(dnf!(1 + 2), cnf!((1 + 2)).to_dnf());
+        assert_eq!(dnf!(1 2), cnf!((1)(2)).to_dnf());
+        assert_eq!(dnf!(1 3 + 1 4 + 2 3 + 2 4), cnf!((1 + 2)(3 + 4)).to_dnf());
+        assert_eq!(dnf!(1 + 2 3), cnf!((1 + 2)(1 + 3)).to_dnf());
+
+        // repeated clause
+        let exp = Cnf::from_iter([implicant!(1 2), implicant!(2 1), implicant!(3)]);
+        assert_eq!(dnf!(1 3 + 2 3), exp.to_dnf());
+
+        // clause subsuming another
+        assert_eq!(dnf!(1 3 + 1 4), cnf!((1)(1 + 2)(3 + 4)).to_dnf());
+
+        // empty clause
+        let mut exp = cnf!((1 + 2)(3 + 4));
+        exp.insert(Implicant::new());
+        assert!(exp.is_false());
+        assert_eq!(dnf!(false), exp.to_dnf());
+    }
+
+    #[test]
+    fn test_to_dnf_eval() {
+        let exps = [
+            cnf!((1 + 2)(3 + 4)(1 + 4)),
+            cnf!((1 + 2 + 3)(2 + 3 + 4)(1 + 5)(4 + 5)),
+            cnf!((1)(2 + 3)(2 + 4)(3 + 4)),
+        ];
+        for exp in exps {
+            let vars: Vec<_> = exp.all_variables().into_iter().collect();
+            let dnf = exp.to_dnf();
+            for mask in 0..1_usize << vars.len() {
+                let input_set = (0..vars.len())
+                    .filter(|i| mask >> i & 1 == 1)
+                    .map(|i| vars[i])
+                    .collect();
+                assert_eq!(exp.eval(&input_set, true), dnf.eval(&input_set, true));
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
-use super::{modular_closure::compute_modular_closure, unionfind::UnionFind, utils::*, Dnf, Var};
-use rayon::prelude::*;
-use std::collections::{BTreeSet, HashMap};
+use super::{
+    modular_closure::compute_modular_closure, unionfind::UnionFind, utils::*, Dnf, Implicant, Var,
+};
+use crate::par::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
 
 /// Sub-expression for the decomposition result
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -124,7 +126,19 @@ pub(crate) fn decompose_inner<T: Var>(
             return ans;
         }
     }
+    if let Some(ans) = decompose_using_veto(exp, all_variables) {
+        return ans;
+    }
+
+    decompose_using_modular_sets(exp, all_variables)
+}
 
+/// Decompose by the maximal modular sets of `exp`, which computes a modular closure for every
+/// variable, each a few passes over `exp`.
+fn decompose_using_modular_sets<T: Var>(
+    exp: &Dnf<T>,
+    all_variables: &BTreeSet<T>,
+) -> (Decompose<T>, Vec<BTreeSet<T>>) {
     let (modular_set_list, is_prime) = compute_all_disjoint_modular_set(exp, all_variables);
 
     let ans = if is_prime && modular_set_list.len() > 2 {
@@ -286,6 +300,54 @@ fn compute_all_disjoint_modular_set<T: Var>(
     }
 }
 
+/// Decompose `exp` as the And of the variables in all of its implicants and the rest of it, if
+/// there are any such variables. The rest is split further if it is an And itself, so the result
+/// is the same as by `decompose_using_modular_sets`, but takes a single pass over `exp`, e.g.,
+/// for each level of `x1 (x2 + x3 (x4 + ...))`.
+fn decompose_using_veto<T: Var>(
+    exp: &Dnf<T>,
+    all_variables: &BTreeSet<T>,
+) -> Option<(Decompose<T>, Vec<BTreeSet<T>>)> {
+    let mut imps = exp.iter();
+    let mut veto = imps.next()?.0.clone();
+    for t in imps {
+        veto.retain(|v| t.contains(v));
+        if veto.is_empty() {
+            return None;
+        }
+    }
+
+    let (mut sub_exps, mut modular_set_list): (Vec<_>, Vec<_>) = veto
+        .iter()
+        .map(|v| (SubExp::Var(v.clone()), BTreeSet::from([v.clone()])))
+        .unzip();
+    let rest_variables: BTreeSet<T> = all_variables.difference(&veto).cloned().collect();
+    match rest_variables.len() {
+        // a single implicant
+        0 => {}
+        // not minimized, e.g., `1 2 + 1 2 3`
+        1 => return None,
+        _ => {
+            // still minimized without the variables in all implicants
+            let rest: Dnf<T> = exp
+                .iter()
+                .map(|t| Implicant(t.difference(&veto).cloned().collect()))
+                .collect();
+            match decompose_inner(&rest, &rest_variables, true) {
+                (Decompose::And(list), set_list) => {
+                    sub_exps.extend(list);
+                    modular_set_list.extend(set_list);
+                }
+                _ => {
+                    sub_exps.push(SubExp::Exp(rest));
+                    modular_set_list.push(rest_variables);
+                }
+            }
+        }
+    }
+    Some((Decompose::And(sub_exps), modular_set_list))
+}
+
 fn decompose_using_cc<T: Var>(exp: &Dnf<T>) -> Option<(Decompose<T>, Vec<BTreeSet<T>>)> {
     let imps: Vec<_> = exp.iter().collect();
     let mut union = UnionFind::new(imps.len());
@@ -297,7 +359,7 @@ fn decompose_using_cc<T: Var>(exp: &Dnf<T>) -> Option<(Decompose<T>, Vec<BTreeSe
         }
     }
     let labels = union.into_labeling();
-    let mut label_map: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut label_map: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
     for (i, l) in labels.into_iter().enumerate() {
         label_map.entry(l).or_default().push(i);
     }
@@ -335,7 +397,7 @@ fn decompose_using_cc<T: Var>(exp: &Dnf<T>) -> Option<(Decompose<T>, Vec<BTreeSe
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::dnf;
+    use crate::{dnf, tests::monotone_dnf, OwnerId};
     use proptest::prelude::*;
 
     #[test]
@@ -424,6 +486,29 @@ mod tests {
         assert_eq!(exp, d.expand());
     }
 
+    proptest! {
+        #[test]
+        fn test_decompose_using_veto(
+            exp in monotone_dnf(8, 6),
+            veto in prop::collection::btree_set(9..=10u32, 1..=2),
+        ) {
+            let veto = Implicant(veto.into_iter().map(OwnerId).collect());
+            let exp = exp & Dnf::from([veto]);
+            let all_variables = exp.all_variables();
+            let sorted = |(d, modular_set_list): (Decompose<OwnerId>, Vec<_>)| {
+                let Decompose::And(sub_exps) = d else {
+                    panic!("not an And: {d:?}");
+                };
+                let mut pairs: Vec<_> = sub_exps.into_iter().zip(modular_set_list).collect();
+                pairs.sort_unstable();
+                pairs
+            };
+            let expect = sorted(decompose_using_modular_sets(&exp, &all_variables));
+            let actual = sorted(decompose_using_veto(&exp, &all_variables).unwrap());
+            prop_assert_eq!(expect, actual);
+        }
+    }
+
     #[derive(
         Debug,
         Clone,
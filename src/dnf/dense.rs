@@ -0,0 +1,53 @@
+//! Sets of at most 128 variables as the bits of a `u128`, so that unions and subset checks are a
+//! few bit operations instead of merging `BTreeSet`s.
+
+use super::Var;
+use std::collections::BTreeSet;
+
+/// Maximum number of variables of a `DenseIndex`.
+pub(crate) const MAX_DENSE_VARIABLES: usize = u128::BITS as usize;
+
+/// Bit positions of the variables of an expression, which are their ranks.
+#[derive(Debug, Clone)]
+pub(crate) struct DenseIndex<T: Var>(Vec<T>);
+
+impl<T: Var> DenseIndex<T> {
+    /// None if there are more than `max_len` variables, or more than `MAX_DENSE_VARIABLES`.
+    pub(crate) fn new(vars: BTreeSet<T>, max_len: usize) -> Option<Self> {
+        (vars.len() <= max_len.min(MAX_DENSE_VARIABLES)).then(|| Self(vars.into_iter().collect()))
+    }
+
+    /// Bits of a set of variables.
+    ///
+    /// Panic if a variable is not in the index.
+    pub(crate) fn bits<'a>(&self, set: impl IntoIterator<Item = &'a T>) -> u128
+    where
+        T: 'a,
+    {
+        set.into_iter().fold(0, |bits, var| {
+            let i = self
+                .0
+                .binary_search(var)
+                .unwrap_or_else(|_| panic!("variable not in the dense index"));
+            bits | 1 << i
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits() {
+        let index = DenseIndex::new(BTreeSet::from([3, 7, 10]), MAX_DENSE_VARIABLES).unwrap();
+        assert_eq!(0, index.bits(&[]));
+        assert_eq!(0b101, index.bits(&[10, 3]));
+        assert_eq!(0b111, index.bits(&BTreeSet::from([3, 7, 10])));
+        assert!(DenseIndex::new(BTreeSet::from([3, 7, 10]), 2).is_none());
+
+        let index = DenseIndex::new((0..128).collect(), usize::MAX).unwrap();
+        assert_eq!(1 << 127, index.bits(&[127]));
+        assert!(DenseIndex::new((0..129).collect(), usize::MAX).is_none());
+    }
+}
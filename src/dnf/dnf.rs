@@ -1,5 +1,11 @@
-use super::{utils::*, Implicant, Var};
-use rayon::prelude::*;
+use super::{
+    dense::{DenseIndex, MAX_DENSE_VARIABLES},
+    utils::*,
+    Implicant, Var,
+};
+use crate::par::prelude::*;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeSet,
     fmt, mem,
@@ -10,11 +16,16 @@ use std::{
 ///
 /// If the underlying set is empty, the expression is considered as FALSE.
 /// If it contains a empty implicant, the expression is considered as TRUE.
+///
+/// Equality and hashing are those of the set of implicants, so they ignore the order and
+/// duplicates of implicants but not absorbed ones, e.g., `1 + 1 2` differs from `1`. Compare the
+/// `canonical` forms to tell whether two DNFs are the same monotone function.
 #[derive(
     Debug,
     Clone,
     PartialEq,
     Eq,
+    Hash,
     PartialOrd,
     Ord,
     derive_more::Deref,
@@ -22,11 +33,14 @@ use std::{
     derive_more::AsRef,
     derive_more::AsMut,
     derive_more::From,
+    Serialize,
+    Deserialize,
 )]
 #[cfg_attr(test, derive(proptest_derive::Arbitrary))]
 #[as_ref(forward)]
 #[as_mut(forward)]
 #[from(forward)]
+#[serde(transparent)]
 pub struct Dnf<T: Var>(pub BTreeSet<Implicant<T>>);
 
 impl<T: Var> Dnf<T> {
@@ -63,24 +77,39 @@ impl<T: Var> Dnf<T> {
         ans
     }
 
+    /// Iterate over the variable sets of the implicants, borrowing the underlying storage.
+    pub fn implicants(&self) -> impl Iterator<Item = &BTreeSet<T>> + '_ {
+        self.iter().map(|t| &t.0)
+    }
+
+    /// Number of distinct variables, i.e., `all_variables().len()`, counted by merging the sorted
+    /// implicants without collecting the variables.
+    pub fn variable_count(&self) -> usize {
+        self.iter().map(|t| t.iter()).kmerge().dedup().count()
+    }
+
     /// Remove non-hybrid implicants in DNF.
+    ///
+    /// The variables are never negated, so this gives the unique minimal DNF of the same function,
+    /// and the consensus steps of Quine-McCluskey, which merge implicants differing in the sign of
+    /// a variable, have nothing to do. The subset checks are done on bits when there are at most
+    /// 128 variables.
     pub fn minimize(&mut self) {
-        let mut skips = vec![false; self.len()];
-        for (i, term_i) in self.iter().enumerate() {
-            if skips[i] {
-                continue;
-            }
-
-            for (j, term_j) in self.iter().enumerate().skip(i + 1) {
-                if skips[j] {
-                    continue;
-                }
+        self.minimize_with(MAX_DENSE_VARIABLES);
+    }
 
-                if term_i.is_subset(term_j) {
-                    skips[j] = true;
-                }
+    /// `minimize` with dense sets of at most `max_dense` variables.
+    fn minimize_with(&mut self, max_dense: usize) {
+        let skips = match DenseIndex::new(self.all_variables(), max_dense) {
+            Some(index) => {
+                let bits: Vec<_> = self.iter().map(|t| index.bits(t.iter())).collect();
+                absorbed_terms(bits.len(), |i, j| bits[i] & !bits[j] == 0)
             }
-        }
+            None => {
+                let terms: Vec<_> = self.iter().collect();
+                absorbed_terms(terms.len(), |i, j| terms[i].is_subset(terms[j]))
+            }
+        };
 
         let original = mem::take(&mut self.0);
         let ans: Dnf<T> = original
@@ -91,12 +120,40 @@ impl<T: Var> Dnf<T> {
         *self = ans;
     }
 
+    /// Return a copy with absorbed implicants (i.e., supersets of another implicant) removed.
+    ///
+    /// Duplicated implicants are already merged since the implicants are kept in a set, so the
+    /// result is the unique minimal DNF of the same monotone function.
+    pub fn minimized(&self) -> Dnf<T> {
+        let mut ans = self.clone();
+        ans.minimize();
+        ans
+    }
+
+    /// Canonical form of the DNF, which is equal (and hashes the same) for all the DNFs of the
+    /// same monotone function. The implicants are kept sorted in a set anyway, so it is
+    /// `minimized`.
+    pub fn canonical(&self) -> Dnf<T> {
+        self.minimized()
+    }
+
+    /// Return the implicants not absorbed by (i.e., not a superset of) any other implicant.
+    pub fn minimal_implicants(&self) -> Vec<BTreeSet<T>> {
+        self.minimized().0.into_iter().map(|t| t.0).collect()
+    }
+
     /// Eval to TRUE or FALSE.
     pub fn eval(&self, input_set: &BTreeSet<T>, input_is_true: bool) -> bool {
         self.par_iter().any(|t| t.eval(input_set, input_is_true))
     }
 
-    /// Partially eval the expression with variables in `input_set` set to be `input_is_true`.
+    /// Partially eval the expression with variables in `input_set` set to be `input_is_true`, and
+    /// return the minimized residual over the other variables.
+    ///
+    /// With TRUE, the variables are dropped from every implicant, e.g., `1 2 + 1 3` becomes `1`
+    /// with `{2, 3}`, i.e., the coalitions winning together with the set. With FALSE, the
+    /// implicants with any of the variables are dropped, i.e., the coalitions winning without the
+    /// set. The result may be TRUE or FALSE.
     pub fn partial_eval(&self, input_set: &BTreeSet<T>, input_is_true: bool) -> Dnf<T> {
         let ans: BTreeSet<_> = self
             .par_iter()
@@ -132,7 +189,11 @@ impl<T: Var> Dnf<T> {
         Dnf::from(ans)
     }
 
-    /// A DNF with implicants who do not have intersection with input_set. Equivalent to `partial_eval(input_set, false)`
+    /// A DNF with implicants who do not have intersection with input_set, i.e., the complement of
+    /// `partial_exp` and the residual of the variables outside `input_set`.
+    ///
+    /// It is the same as `partial_eval(input_set, false)` but without minimizing, so the result
+    /// is minimized if `self` is, and the two add up to `self` with `|`.
     pub fn partial_exp_complement(&self, input_set: &BTreeSet<T>) -> Dnf<T> {
         let ans: BTreeSet<_> = self
             .par_iter()
@@ -175,6 +236,8 @@ impl<T: Var> IntoIterator for Dnf<T> {
     }
 }
 
+/// Without the `parallel` feature, `into_par_iter` goes through `IntoIterator` instead.
+#[cfg(feature = "parallel")]
 impl<T: Var> IntoParallelIterator for Dnf<T> {
     type Iter = <BTreeSet<Implicant<T>> as IntoParallelIterator>::Iter;
     type Item = <BTreeSet<Implicant<T>> as IntoParallelIterator>::Item;
@@ -208,7 +271,7 @@ impl<T: Var> BitAnd for Dnf<T> {
     }
 }
 
-impl<'a, 'b, T: Var> BitAnd<&'b Dnf<T>> for &'a Dnf<T> {
+impl<'b, T: Var> BitAnd<&'b Dnf<T>> for &Dnf<T> {
     type Output = Dnf<T>;
 
     fn bitand(self, rhs: &'b Dnf<T>) -> Self::Output {
@@ -249,7 +312,7 @@ impl<T: Var> BitOr for Dnf<T> {
     }
 }
 
-impl<'a, 'b, T: Var> BitOr<&'b Dnf<T>> for &'a Dnf<T> {
+impl<'b, T: Var> BitOr<&'b Dnf<T>> for &Dnf<T> {
     type Output = Dnf<T>;
 
     fn bitor(self, rhs: &'b Dnf<T>) -> Self::Output {
@@ -273,6 +336,24 @@ impl<'a, T: Var> BitOrAssign<&'a Dnf<T>> for Dnf<T> {
     }
 }
 
+/// Whether each of `len` implicants in the order of `Dnf` is absorbed by an earlier one, given
+/// whether the `i`-th one is a subset of the `j`-th one.
+fn absorbed_terms(len: usize, is_subset: impl Fn(usize, usize) -> bool) -> Vec<bool> {
+    let mut skips = vec![false; len];
+    for i in 0..len {
+        if skips[i] {
+            continue;
+        }
+
+        for (j, skip) in skips.iter_mut().enumerate().skip(i + 1) {
+            if !*skip && is_subset(i, j) {
+                *skip = true;
+            }
+        }
+    }
+    skips
+}
+
 #[macro_export]
 macro_rules! dnf {
     () => {
@@ -297,7 +378,12 @@ macro_rules! dnf {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::dnf;
+    use crate::{implicant, tests::sixteen_implicants};
+    use proptest::prelude::*;
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
 
     #[test]
     fn test_display() {
@@ -309,6 +395,28 @@ mod tests {
         assert_eq!("1 + 2 3", format!("{}", dnf!(1 + 2 3)));
     }
 
+    #[test]
+    fn test_canonical() {
+        fn hash<T: Hash>(t: &T) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            t.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        assert_eq!(dnf!(1 2), dnf!(1 2 + 2 1).canonical());
+        assert_eq!(dnf!(1 2).canonical(), dnf!(2 1 + 1 2).canonical());
+        assert_ne!(dnf!(1 + 1 2), dnf!(1));
+        assert_eq!(dnf!(1 + 1 2).canonical(), dnf!(1).canonical());
+        assert_eq!(
+            hash(&dnf!(3 + 1 2 + 2 3).canonical()),
+            hash(&dnf!(2 1 + 3 + 1 2 3).canonical())
+        );
+        assert_ne!(dnf!(1 2).canonical(), dnf!(1 + 2).canonical());
+        let mut exp = dnf!(1);
+        exp.insert(implicant!());
+        assert_eq!(dnf!(true), exp.canonical());
+    }
+
     #[test]
     fn test_is_true_is_false() {
         assert!(dnf!(true).is_true());
@@ -325,6 +433,62 @@ mod tests {
         assert_eq!(actual, expect);
     }
 
+    proptest! {
+        #[test]
+        fn test_minimize_dense(
+            sets in prop::collection::vec(prop::collection::btree_set(0u8..160, 0..6), 0..30)
+        ) {
+            let exp: Dnf<u8> = sets.into_iter().map(Implicant::from).collect();
+            let mut sparse = exp.clone();
+            sparse.minimize_with(0);
+            let mut dense = exp;
+            dense.minimize();
+            prop_assert_eq!(sparse, dense);
+        }
+    }
+
+    #[test]
+    fn test_implicants_and_variable_count() {
+        let exp = dnf!(1 2 + 2 3 4 + 5);
+        assert_eq!(
+            vec![
+                &BTreeSet::from([5]),
+                &BTreeSet::from([1, 2]),
+                &BTreeSet::from([2, 3, 4])
+            ],
+            exp.implicants().collect::<Vec<_>>()
+        );
+        assert_eq!(exp.iter().count(), exp.implicants().count());
+
+        for exp in [dnf!(true), dnf!(false), dnf!(1), dnf!(1 2 + 2 3 4 + 5)] {
+            assert_eq!(exp.all_variables().len(), exp.variable_count());
+        }
+        let exp = sixteen_implicants().dnf;
+        assert_eq!(exp.all_variables().len(), exp.variable_count());
+    }
+
+    #[test]
+    fn test_minimized() {
+        // the duplicated `1 2` is merged on insertion
+        let exp = dnf!(1 2 + 1 2 + 1 2 3 + 2 3 + 1 2 3 4);
+        assert_eq!(4, exp.len());
+        assert_eq!(dnf!(1 2 + 2 3), exp.minimized());
+        assert_eq!(dnf!(1 2 + 2 3), exp.minimized().minimized());
+        assert_eq!(4, exp.len());
+    }
+
+    #[test]
+    fn test_minimal_implicants() {
+        assert_eq!(
+            vec![BTreeSet::from([1, 2])],
+            dnf!(1 2 + 1 2 3).minimal_implicants()
+        );
+        assert_eq!(
+            vec![BTreeSet::from([1]), BTreeSet::from([2, 3])],
+            dnf!(1 + 1 2 + 2 3 + 1 2 3).minimal_implicants()
+        );
+    }
+
     #[test]
     fn test_arithmetic() {
         assert_eq!(dnf!(1 2), dnf!(1) & dnf!(2));
@@ -403,4 +567,31 @@ mod tests {
         assert_eq!(dnf!(4 6), exp.partial_exp_complement(&input_set));
         assert_eq!(dnf!(1 2 + 2 3), exp.contraction_exp(&input_set));
     }
+
+    #[test]
+    fn test_partial_eval_expansion() {
+        // Shannon expansion: f = x f(x = 1) + f(x = 0)
+        let exp = dnf!(1 2 + 1 3 + 2 3 + 4);
+        let x = BTreeSet::from([1]);
+        let positive = exp.partial_eval(&x, true);
+        let negative = exp.partial_eval(&x, false);
+        assert_eq!(dnf!(2 + 3 + 4), positive);
+        assert_eq!(dnf!(2 3 + 4), negative);
+        assert_eq!(negative, exp.partial_exp_complement(&x));
+        assert_eq!(exp, dnf!(1) & positive | negative);
+
+        // partial_exp and partial_exp_complement split the implicants
+        let input_set = BTreeSet::from([1, 4]);
+        let complement = exp.partial_exp_complement(&input_set);
+        assert_eq!(dnf!(2 3), complement);
+        assert_eq!(exp, exp.partial_exp(&input_set) | complement);
+
+        // unlike partial_eval, it keeps absorbed implicants
+        let exp = dnf!(1 2 + 1 2 3 + 4);
+        assert_eq!(
+            dnf!(1 2 + 1 2 3),
+            exp.partial_exp_complement(&BTreeSet::from([4]))
+        );
+        assert_eq!(dnf!(1 2), exp.partial_eval(&BTreeSet::from([4]), false));
+    }
 }
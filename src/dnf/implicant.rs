@@ -1,4 +1,5 @@
 use super::{utils::*, Dnf, Var};
+use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeSet,
     fmt,
@@ -13,16 +14,20 @@ use std::{
     Clone,
     PartialEq,
     Eq,
+    Hash,
     derive_more::Deref,
     derive_more::DerefMut,
     derive_more::AsRef,
     derive_more::AsMut,
     derive_more::From,
+    Serialize,
+    Deserialize,
 )]
 #[cfg_attr(test, derive(proptest_derive::Arbitrary))]
 #[as_ref(forward)]
 #[as_mut(forward)]
 #[from(forward)]
+#[serde(transparent)]
 pub struct Implicant<T: Var>(pub BTreeSet<T>);
 
 impl<T: Var> Implicant<T> {
@@ -143,7 +148,7 @@ impl<T: Var> BitAnd for Implicant<T> {
     }
 }
 
-impl<'a, 'b, T: Var> BitAnd<&'b Implicant<T>> for &'a Implicant<T> {
+impl<'b, T: Var> BitAnd<&'b Implicant<T>> for &Implicant<T> {
     type Output = Implicant<T>;
 
     fn bitand(self, rhs: &'b Implicant<T>) -> Self::Output {
@@ -173,7 +178,7 @@ impl<T: Var> BitOr for Implicant<T> {
     }
 }
 
-impl<'a, 'b, T: Var> BitOr<&'b Implicant<T>> for &'a Implicant<T> {
+impl<'b, T: Var> BitOr<&'b Implicant<T>> for &Implicant<T> {
     type Output = Dnf<T>;
 
     fn bitor(self, rhs: &'b Implicant<T>) -> Self::Output {
@@ -199,7 +204,7 @@ macro_rules! implicant {
 
 #[cfg(test)]
 mod tests {
-    use crate::{dnf, implicant};
+    use crate::dnf;
 
     #[test]
     fn test_display() {
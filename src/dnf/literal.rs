@@ -0,0 +1,129 @@
+use super::{Dnf, Var};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+/// A variable or its negation, e.g., "owner 2 must be absent" in a winning condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Literal<T> {
+    pub var: T,
+    pub negated: bool,
+}
+
+impl<T> Literal<T> {
+    pub fn pos(var: T) -> Self {
+        Self {
+            var,
+            negated: false,
+        }
+    }
+
+    pub fn neg(var: T) -> Self {
+        Self { var, negated: true }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Literal<U> {
+        Literal {
+            var: f(self.var),
+            negated: self.negated,
+        }
+    }
+
+    /// Whether the literal is true when exactly the variables in `true_set` are true.
+    pub fn eval(&self, true_set: &BTreeSet<T>) -> bool
+    where
+        T: Ord,
+    {
+        true_set.contains(&self.var) != self.negated
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Literal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            write!(f, "!")?;
+        }
+        write!(f, "{}", self.var)
+    }
+}
+
+/// A variable, negated if prefixed by `!` or `¬`.
+impl<T: FromStr> FromStr for Literal<T> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('!').or_else(|| s.strip_prefix('¬')) {
+            Some(var) => var.parse().map(Self::neg),
+            None => s.parse().map(Self::pos),
+        }
+    }
+}
+
+/// A DNF whose literals may be negated, i.e., of any boolean function rather than only monotone
+/// ones. It is parsed and displayed as a `Dnf`, e.g., `1 !2 + 3`.
+///
+/// Only evaluation and the conversion to a monotone `Dnf` are specific to it. The decomposition
+/// and the other algorithms of `Dnf` assume monotone expressions.
+pub type GeneralDnf<T> = Dnf<Literal<T>>;
+
+impl<T: Var> Dnf<Literal<T>> {
+    /// Whether the expression is true when exactly the variables in `true_set` are true.
+    pub fn is_satisfied_by(&self, true_set: &BTreeSet<T>) -> bool {
+        self.iter()
+            .any(|imp| imp.iter().all(|literal| literal.eval(true_set)))
+    }
+
+    /// Whether no literal is negated.
+    pub fn is_monotone(&self) -> bool {
+        self.iter().flat_map(|imp| imp.iter()).all(|l| !l.negated)
+    }
+
+    /// The same expression as a `Dnf` if no literal is negated.
+    pub fn to_monotone(&self) -> Option<Dnf<T>> {
+        self.is_monotone()
+            .then(|| self.map_variable(|literal| literal.var.clone()))
+    }
+
+    /// All the variables, negated or not.
+    pub fn literal_variables(&self) -> BTreeSet<T> {
+        self.iter()
+            .flat_map(|imp| imp.iter().map(|literal| literal.var.clone()))
+            .collect()
+    }
+
+    /// The same expression as a monotone `exp`, without any negated literal.
+    pub fn from_monotone(exp: &Dnf<T>) -> Self {
+        exp.map_variable(|var| Literal::pos(var.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dnf;
+
+    #[test]
+    fn test_eval() {
+        // x1 and not x2
+        let exp: GeneralDnf<u32> = "1 !2".parse().unwrap();
+        assert_eq!("1 !2", exp.to_string());
+        assert!(!exp.is_monotone());
+        assert_eq!(None, exp.to_monotone());
+        assert!(!exp.is_satisfied_by(&BTreeSet::new()));
+        assert!(exp.is_satisfied_by(&BTreeSet::from([1])));
+        assert!(!exp.is_satisfied_by(&BTreeSet::from([2])));
+        assert!(!exp.is_satisfied_by(&BTreeSet::from([1, 2])));
+        assert!(exp.is_satisfied_by(&BTreeSet::from([1, 3])));
+
+        let exp: GeneralDnf<u32> = "¬1 + 2 3".parse().unwrap();
+        assert_eq!(BTreeSet::from([1, 2, 3]), exp.literal_variables());
+        assert!(exp.is_satisfied_by(&BTreeSet::new()));
+        assert!(!exp.is_satisfied_by(&BTreeSet::from([1, 2])));
+        assert!(exp.is_satisfied_by(&BTreeSet::from([1, 2, 3])));
+
+        let monotone = dnf!(1 2 + 3);
+        let exp = GeneralDnf::from_monotone(&monotone);
+        assert_eq!("3 + 1 2", exp.to_string());
+        assert_eq!(Some(monotone), exp.to_monotone());
+        assert!("1 !x".parse::<GeneralDnf<u32>>().is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use super::{utils::*, Dnf, Var};
-use rayon::prelude::*;
+use crate::par::prelude::*;
 use std::{cmp::Ordering, collections::BTreeSet};
 
 type SList<T> = Vec<(BTreeSet<T>, BTreeSet<T>)>;
@@ -0,0 +1,183 @@
+use super::{Dnf, Implicant, Var};
+use std::{error, fmt, mem, str::FromStr};
+
+/// Error when parsing a DNF or CNF from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnfParseError {
+    /// Byte offset of the input where the error occurs.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl DnfParseError {
+    pub(crate) fn new(offset: usize, message: impl Into<String>) -> Self {
+        Self {
+            offset,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for DnfParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at byte {})", self.message, self.offset)
+    }
+}
+
+impl error::Error for DnfParseError {}
+
+impl<T: Var> Dnf<T> {
+    /// Parse a DNF from a string in the same format as its `Display`, e.g., `1 2 3 + 1 2 4`, with
+    /// each variable token converted by `f`.
+    ///
+    /// Variables in an implicant are separated by whitespaces, and implicants are separated by `+`.
+    /// `TRUE` and `FALSE` stand for the constant expressions. The result is not minimized.
+    pub fn parse_with<E>(
+        s: &str,
+        mut f: impl FnMut(&str) -> Result<T, E>,
+    ) -> Result<Self, DnfParseError> {
+        match s.trim() {
+            "TRUE" => return Ok(Self::true_exp()),
+            "FALSE" => return Ok(Self::false_exp()),
+            _ => {}
+        }
+
+        let mut exp = Dnf::new();
+        let mut imp = Implicant::new();
+        let mut last_plus = None;
+        for token in tokenize(s) {
+            match token {
+                Token::Plus(offset) => {
+                    if imp.is_empty() {
+                        return Err(DnfParseError::new(offset, "empty implicant before `+`"));
+                    }
+                    exp.insert(mem::take(&mut imp));
+                    last_plus = Some(offset);
+                }
+                Token::Var(offset, text) => {
+                    let var = f(text).map_err(|_| {
+                        DnfParseError::new(offset, format!("invalid variable `{text}`"))
+                    })?;
+                    imp.insert(var);
+                }
+            }
+        }
+
+        if imp.is_empty() {
+            return Err(match last_plus {
+                Some(offset) => DnfParseError::new(offset, "trailing `+`"),
+                None => DnfParseError::new(0, "empty expression"),
+            });
+        }
+        exp.insert(imp);
+        Ok(exp)
+    }
+}
+
+impl<T: Var + FromStr> Dnf<T> {
+    /// Parse a DNF from a string like `1 2 3 + 1 2 4`. See `parse_with` for the format.
+    pub fn parse(s: &str) -> Result<Self, DnfParseError> {
+        Self::parse_with(s, T::from_str)
+    }
+}
+
+impl<T: Var + FromStr> FromStr for Dnf<T> {
+    type Err = DnfParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+enum Token<'a> {
+    Plus(usize),
+    Var(usize, &'a str),
+}
+
+fn tokenize(s: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut var_begin = None;
+    for (i, c) in s.char_indices() {
+        if c.is_whitespace() || c == '+' {
+            if let Some(begin) = var_begin.take() {
+                tokens.push(Token::Var(begin, &s[begin..i]));
+            }
+            if c == '+' {
+                tokens.push(Token::Plus(i));
+            }
+        } else if var_begin.is_none() {
+            var_begin = Some(i);
+        }
+    }
+    if let Some(begin) = var_begin {
+        tokens.push(Token::Var(begin, &s[begin..]));
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dnf, OwnerId};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(dnf!(1 2 3 + 1 2 4)), "1 2 3 + 1 2 4".parse::<Dnf<i32>>());
+        assert_eq!(Ok(dnf!(1 2 + 3)), "  1\t2+3 ".parse::<Dnf<i32>>());
+        assert_eq!(Ok(dnf!(true)), "TRUE".parse::<Dnf<i32>>());
+        assert_eq!(Ok(dnf!(false)), "FALSE".parse::<Dnf<i32>>());
+    }
+
+    #[test]
+    fn test_parse_with() {
+        assert_eq!(
+            Ok(dnf!(1 2 + 3).map_variable(|id| *id as u64)),
+            "1 2 + 3".parse::<Dnf<u64>>()
+        );
+        assert_eq!(
+            Ok(dnf!(1 2 + 3).map_variable(|id| OwnerId(*id as u32))),
+            Dnf::parse_with("1 2 + 3", |t| t.parse().map(OwnerId))
+        );
+
+        let exp = Dnf::parse_with("alice bob + carol", |t| Ok::<_, ()>(t.to_string())).unwrap();
+        assert_eq!(
+            BTreeSet::from(["alice".to_string(), "bob".to_string(), "carol".to_string()]),
+            exp.all_variables()
+        );
+        assert_eq!("carol + alice bob", exp.to_string());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for exp in [
+            dnf!(true),
+            dnf!(false),
+            dnf!(1),
+            dnf!(1 + 2 3),
+            dnf!(1 2 4 + 4 5),
+        ] {
+            assert_eq!(Ok(exp.clone()), exp.to_string().parse::<Dnf<i32>>());
+        }
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = "1 x".parse::<Dnf<i32>>().unwrap_err();
+        assert_eq!(2, err.offset);
+        assert_eq!("invalid variable `x` (at byte 2)", err.to_string());
+
+        let err = "+ 1".parse::<Dnf<i32>>().unwrap_err();
+        assert_eq!(0, err.offset);
+
+        let err = "1 + + 2".parse::<Dnf<i32>>().unwrap_err();
+        assert_eq!(4, err.offset);
+
+        let err = "1 2 +".parse::<Dnf<i32>>().unwrap_err();
+        assert_eq!(4, err.offset);
+        assert_eq!("trailing `+`", err.message);
+
+        let err = " ".parse::<Dnf<i32>>().unwrap_err();
+        assert_eq!("empty expression", err.message);
+    }
+}
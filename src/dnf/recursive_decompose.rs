@@ -3,9 +3,14 @@ use super::{
     utils::*,
     Dnf, Var,
 };
+use crate::par::prelude::*;
 use ptree::{Style, TreeItem};
-use rayon::prelude::*;
-use std::{borrow::Cow, collections::BTreeSet, fmt::Display, io};
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    fmt::{Display, Write},
+    io,
+};
 
 #[derive(Debug, Clone, PartialOrd, Ord)]
 pub enum RecursiveDecompose<T: Var> {
@@ -30,7 +35,7 @@ impl<T: Var + Display> TreeItem for RecursiveDecompose<T> {
         }
     }
 
-    fn children(&self) -> Cow<[Self::Child]> {
+    fn children(&self) -> Cow<'_, [Self::Child]> {
         match self {
             Self::Var(_) => Cow::from(vec![]),
             Self::And(list) | Self::Or(list) | Self::Hybrid { sub_exps: list, .. } => {
@@ -107,9 +112,79 @@ impl<T: Var> RecursiveDecompose<T> {
     }
 }
 
+/// Render a recursive decomposition in Graphviz DOT format, e.g., for `dot -Tpng`.
+///
+/// Var leaves are drawn as ellipses, And nodes as boxes, Or nodes as diamonds, and Hybrid nodes as
+/// double octagons labeled with their `hybrid_exp`. Edges out of a Hybrid node are labeled with the
+/// index of the sub-expression referred to by `hybrid_exp`.
+pub fn recursive_decompose_to_dot<T: Var + Display>(d: &RecursiveDecompose<T>) -> String {
+    let mut out = String::from("digraph RecursiveDecompose {\n");
+    // nodes are numbered in pre-order, and the edge to a child follows its subtree
+    let mut next_id = 0;
+    let mut stack = vec![DotStep::Node(d, None)];
+    while let Some(step) = stack.pop() {
+        match step {
+            DotStep::Node(d, parent) => {
+                let id = next_id;
+                next_id += 1;
+                write_dot_node(d, id, &mut out);
+                if let Some(parent) = parent {
+                    stack.push(DotStep::Edge(parent, id));
+                }
+                // edges out of a Hybrid node are labeled with the index of the child
+                let (children, is_hybrid) = match d {
+                    RecursiveDecompose::Var(_) => (&[][..], false),
+                    RecursiveDecompose::And(list) | RecursiveDecompose::Or(list) => {
+                        (&list[..], false)
+                    }
+                    RecursiveDecompose::Hybrid { sub_exps, .. } => (&sub_exps[..], true),
+                };
+                stack.extend(
+                    children
+                        .iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(i, c)| DotStep::Node(c, Some((id, is_hybrid.then_some(i))))),
+                );
+            }
+            DotStep::Edge((parent, Some(i)), child) => {
+                writeln!(out, "    n{parent} -> n{child} [label=\"{i}\"];").unwrap();
+            }
+            DotStep::Edge((parent, None), child) => {
+                writeln!(out, "    n{parent} -> n{child};").unwrap();
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// A step of `recursive_decompose_to_dot`: write a node with the id of its parent and the label
+/// of the edge to it, or such an edge to a child.
+enum DotStep<'a, T: Var> {
+    Node(&'a RecursiveDecompose<T>, Option<(usize, Option<usize>)>),
+    Edge((usize, Option<usize>), usize),
+}
+
+fn write_dot_node<T: Var + Display>(d: &RecursiveDecompose<T>, id: usize, out: &mut String) {
+    let (label, shape) = match d {
+        RecursiveDecompose::Var(var) => (var.to_string().replace('"', "\\\""), "ellipse"),
+        RecursiveDecompose::And(_) => ("And".to_string(), "box"),
+        RecursiveDecompose::Or(_) => ("Or".to_string(), "diamond"),
+        RecursiveDecompose::Hybrid { hybrid_exp, .. } => {
+            (format!("Hybrid\\n{hybrid_exp}"), "doubleoctagon")
+        }
+    };
+    writeln!(out, "    n{id} [label=\"{label}\", shape={shape}];").unwrap();
+}
+
 /// Recursively decompose a DNF.
 ///
 /// The input requires to be already minimized. It cannot be true or false.
+///
+/// The sub-expressions are decomposed level by level from the top down, where those of a level are
+/// decomposed in parallel. The levels are explicit lists rather than a recursion, which would
+/// overflow the stack on a deep decomposition, e.g., of `x1 (x2 + x3 (x4 + ...))`.
 pub fn recursive_decompose<T: Var>(
     exp: &Dnf<T>,
     all_variables: &BTreeSet<T>,
@@ -121,55 +196,104 @@ pub fn recursive_decompose<T: Var>(
         return RecursiveDecompose::Var(v);
     }
 
-    recursive_decompose_inner(exp, all_variables, true)
+    // the children of the nodes of a level are consecutive in the next level, in order
+    let (root, mut next) = split(exp, all_variables, true);
+    let mut levels = vec![vec![root]];
+    while !next.is_empty() {
+        let decomposed: Vec<_> = next
+            .into_par_iter()
+            .map(|(sub_exp, var_set, try_cc)| match sub_exp {
+                SubExp::Exp(sub) => split(&sub, &var_set, try_cc),
+                SubExp::Var(var) => (PendingNode::Var(var), vec![]),
+            })
+            .collect();
+        let mut level = Vec::with_capacity(decomposed.len());
+        next = vec![];
+        for (node, children) in decomposed {
+            level.push(node);
+            next.extend(children);
+        }
+        levels.push(level);
+    }
+
+    let mut built: Vec<RecursiveDecompose<T>> = vec![];
+    for level in levels.into_iter().rev() {
+        let mut children = built.into_iter();
+        built = level
+            .into_iter()
+            .map(|node| node.build(&mut children))
+            .collect();
+    }
+    built.pop().expect("the root is built")
+}
+
+/// A node of `RecursiveDecompose` whose children are not decomposed yet.
+enum PendingNode<T: Var> {
+    Var(T),
+    And(usize),
+    Or(usize),
+    Hybrid {
+        hybrid_exp: Dnf<usize>,
+        children_len: usize,
+    },
+}
+
+impl<T: Var> PendingNode<T> {
+    /// Take the children of the node from the decomposed nodes of the next level.
+    fn build(
+        self,
+        children: &mut impl Iterator<Item = RecursiveDecompose<T>>,
+    ) -> RecursiveDecompose<T> {
+        match self {
+            Self::Var(var) => RecursiveDecompose::Var(var),
+            Self::And(len) => RecursiveDecompose::And(children.take(len).collect()),
+            Self::Or(len) => RecursiveDecompose::Or(children.take(len).collect()),
+            Self::Hybrid {
+                hybrid_exp,
+                children_len,
+            } => RecursiveDecompose::Hybrid {
+                hybrid_exp,
+                sub_exps: children.take(children_len).collect(),
+            },
+        }
+    }
 }
 
-fn recursive_decompose_inner<T: Var>(
+/// A sub-expression to decompose, with its variables and whether to try splitting it into
+/// connected components.
+type PendingSubExp<T> = (SubExp<T>, BTreeSet<T>, bool);
+
+/// Decompose `exp` by a single level, into the node and its sub-expressions.
+fn split<T: Var>(
     exp: &Dnf<T>,
     all_variables: &BTreeSet<T>,
     try_cc: bool,
-) -> RecursiveDecompose<T> {
+) -> (PendingNode<T>, Vec<PendingSubExp<T>>) {
     let (d, modular_set_list) = decompose_inner(exp, all_variables, try_cc);
 
-    match d {
-        Decompose::Var(var) => RecursiveDecompose::Var(var),
-        Decompose::And(list) => {
-            let list = sub_exp_list_to_recursive_decompose_list(list, modular_set_list, true);
-            RecursiveDecompose::And(list)
-        }
-        Decompose::Or(list) => {
-            let list = sub_exp_list_to_recursive_decompose_list(list, modular_set_list, false);
-            RecursiveDecompose::Or(list)
-        }
+    // the components of an Or are split already
+    let (node, sub_exps, try_cc_in_recursive) = match d {
+        Decompose::Var(var) => return (PendingNode::Var(var), vec![]),
+        Decompose::And(list) => (PendingNode::And(list.len()), list, true),
+        Decompose::Or(list) => (PendingNode::Or(list.len()), list, false),
         Decompose::Hybrid {
             hybrid_exp,
             sub_exps,
-        } => {
-            let sub_exps =
-                sub_exp_list_to_recursive_decompose_list(sub_exps, modular_set_list, true);
-            RecursiveDecompose::Hybrid {
+        } => (
+            PendingNode::Hybrid {
                 hybrid_exp,
-                sub_exps,
-            }
-        }
-    }
-}
-
-fn sub_exp_list_to_recursive_decompose_list<T: Var>(
-    list: Vec<SubExp<T>>,
-    modular_set_list: Vec<BTreeSet<T>>,
-    try_cc_in_recursive: bool,
-) -> Vec<RecursiveDecompose<T>> {
-    list.into_par_iter()
-        .enumerate()
-        .map(|(i, sub_exp)| match sub_exp {
-            SubExp::Exp(sub) => {
-                let var_set = &modular_set_list[i];
-                recursive_decompose_inner(&sub, var_set, try_cc_in_recursive)
-            }
-            SubExp::Var(var) => RecursiveDecompose::Var(var),
-        })
-        .collect()
+                children_len: sub_exps.len(),
+            },
+            sub_exps,
+            true,
+        ),
+    };
+    let children = sub_exps
+        .into_iter()
+        .zip(modular_set_list)
+        .map(|(sub_exp, var_set)| (sub_exp, var_set, try_cc_in_recursive))
+        .collect();
+    (node, children)
 }
 
 #[cfg(test)]
@@ -249,6 +373,29 @@ mod tests {
         assert_eq!(exp, d.expand());
     }
 
+    #[test]
+    fn test_to_dot() {
+        let exp = dnf!(1 2 4 + 1 3 4 + 2 3 4 + 1 2 5 6 + 1 3 5 6 + 2 3 5 6 + 4 5 6 + 1 2 7 + 1 3 7 + 2 3 7 + 4 7);
+        let all_variables = exp.all_variables();
+        let d = recursive_decompose(&exp, &all_variables);
+        let dot = recursive_decompose_to_dot(&d);
+
+        assert!(dot.starts_with("digraph RecursiveDecompose {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches('{').count(), dot.matches('}').count());
+
+        // Hybrid, Hybrid, 1, 2, 3, 4, Or, And, 5, 6, 7
+        let nodes = dot
+            .lines()
+            .filter(|l| l.contains("[label=") && !l.contains("->"));
+        assert_eq!(11, nodes.count());
+        assert_eq!(10, dot.matches("->").count());
+        assert_eq!(2, dot.matches("shape=doubleoctagon").count());
+        assert_eq!(7, dot.matches("shape=ellipse").count());
+        assert!(dot.contains("n0 [label=\"Hybrid\\n0 1 + 0 2 + 1 2\", shape=doubleoctagon];"));
+        assert!(dot.contains("n0 -> n1 [label=\"0\"];"));
+    }
+
     #[test]
     fn build_tree() {
         let exp = dnf!(1 2 4 + 1 3 4 + 2 3 4 + 1 2 5 6 + 1 3 5 6 + 2 3 5 6 + 4 5 6 + 1 2 7 + 1 3 7 + 2 3 7 + 4 7);
@@ -0,0 +1,281 @@
+//! C API of games and their Shapley values, with the `ffi` feature, e.g., to embed the computation
+//! in a query engine in C or C++.
+//!
+//! The build generates the header `shapley_sv.h` with cbindgen into `$OUT_DIR/include`, and
+//! `cargo rustc --release --features ffi --crate-type staticlib` builds a library to link with.
+//! No panic crosses the boundary: a function returning `SvStatus` reports it as
+//! `SV_STATUS_PANIC`, and one returning a pointer returns null.
+
+use crate::{
+    alg::synthesis_sv::{
+        decompose_tree::{try_cal_sv_with, AblationType},
+        SvError, SvOptions,
+    },
+    dnf::Implicant,
+    Dnf, Game, OwnerId, OwnerSet,
+};
+use std::{
+    os::raw::c_int,
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+/// Ablation of `sv_compute`: the full decomposition.
+pub const SV_ABLATION_NONE: c_int = 0;
+/// Ablation of `sv_compute`: `AblationType::NoHorizontal`.
+pub const SV_ABLATION_NO_HORIZONTAL: c_int = 1;
+/// Ablation of `sv_compute`: `AblationType::NoVertical`.
+pub const SV_ABLATION_NO_VERTICAL: c_int = 2;
+/// Ablation of `sv_compute`: `AblationType::NoHybrid`.
+pub const SV_ABLATION_NO_HYBRID: c_int = 3;
+
+/// Result of a function of the C API.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvStatus {
+    Ok = 0,
+    /// A pointer argument is null.
+    NullPointer = 1,
+    /// The ablation is none of the `SV_ABLATION_*` constants.
+    InvalidAblation = 2,
+    /// An IE coefficient overflows, see `SvError::Overflow`.
+    Overflow = 3,
+    /// A leaf has too many unions of implicants, see `SvError::LeafTooLarge` and
+    /// `SvError::TooManyCombinations`.
+    TooLarge = 4,
+    Cancelled = 5,
+    /// A bug, caught at the boundary.
+    Panic = 6,
+}
+
+impl From<SvError> for SvStatus {
+    fn from(e: SvError) -> Self {
+        match e {
+            SvError::Overflow { .. } => Self::Overflow,
+            SvError::LeafTooLarge { .. } | SvError::TooManyCombinations { .. } => Self::TooLarge,
+            SvError::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+/// A validated game, created by `sv_game_new` and freed by `sv_free_game`.
+#[derive(Debug)]
+pub struct SvGame(Game);
+
+/// Shapley values of a game sorted by owners, created by `sv_compute` and freed by
+/// `sv_free_result`.
+#[derive(Debug)]
+pub struct SvResult {
+    owners: Vec<u64>,
+    values: Vec<f64>,
+}
+
+/// Game of `n_imps` implicants over `n_owners` owners, or null if it is invalid as in `Game::new`
+/// (absorbed implicants aside, which are removed), or an owner does not fit in 32 bits.
+///
+/// Implicant `i` is `flat_implicants[offsets[i]..offsets[i + 1]]`, so `offsets` has `n_imps + 1`
+/// increasing entries, starting with 0.
+///
+/// # Safety
+///
+/// `offsets` points to `n_imps + 1` entries, `flat_implicants` to `offsets[n_imps]` entries, and
+/// `owners` to `n_owners` entries. Any of them may be null if it has no entries.
+#[no_mangle]
+pub unsafe extern "C" fn sv_game_new(
+    flat_implicants: *const u64,
+    offsets: *const usize,
+    n_imps: usize,
+    owners: *const u64,
+    n_owners: usize,
+) -> *mut SvGame {
+    catch_panic(|| {
+        let offsets = slice_or_empty(offsets, n_imps + 1);
+        let flat_implicants = slice_or_empty(flat_implicants, offsets.last().copied().unwrap_or(0));
+        let dnf = offsets
+            .windows(2)
+            .map(|w| {
+                let imp = flat_implicants.get(w[0]..w[1])?;
+                imp.iter()
+                    .map(|&id| owner_id(id))
+                    .collect::<Option<Implicant<OwnerId>>>()
+            })
+            .collect::<Option<Dnf<OwnerId>>>()?;
+        let owner_set = slice_or_empty(owners, n_owners)
+            .iter()
+            .map(|&id| owner_id(id))
+            .collect::<Option<OwnerSet>>()?;
+        let game = Game::new(dnf.minimized(), owner_set).ok()?;
+        Some(Box::into_raw(Box::new(SvGame(game))))
+    })
+    .flatten()
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Compute the Shapley values of `game` with one of the `SV_ABLATION_*` constants, and store them
+/// in `*result` if `SV_STATUS_OK` is returned.
+///
+/// # Safety
+///
+/// `game` is null or from `sv_game_new` and not freed, and `result` is null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sv_compute(
+    game: *const SvGame,
+    ablation: c_int,
+    result: *mut *mut SvResult,
+) -> SvStatus {
+    if game.is_null() || result.is_null() {
+        return SvStatus::NullPointer;
+    }
+    let ablation_type = match ablation {
+        SV_ABLATION_NONE => None,
+        SV_ABLATION_NO_HORIZONTAL => Some(AblationType::NoHorizontal),
+        SV_ABLATION_NO_VERTICAL => Some(AblationType::NoVertical),
+        SV_ABLATION_NO_HYBRID => Some(AblationType::NoHybrid),
+        _ => return SvStatus::InvalidAblation,
+    };
+    let game = &(*game).0;
+    let sv = match catch_panic(|| try_cal_sv_with(game, ablation_type, &SvOptions::default())) {
+        Some(Ok(sv)) => sv,
+        Some(Err(e)) => return e.into(),
+        None => return SvStatus::Panic,
+    };
+    let (owners, values) = game
+        .owner_set
+        .iter()
+        .map(|owner_id| {
+            (
+                u64::from(owner_id.0),
+                sv.get(owner_id).copied().unwrap_or(0.),
+            )
+        })
+        .unzip();
+    *result = Box::into_raw(Box::new(SvResult { owners, values }));
+    SvStatus::Ok
+}
+
+/// Number of owners of `result`, or 0 if it is null.
+///
+/// # Safety
+///
+/// `result` is null or from `sv_compute` and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn sv_result_len(result: *const SvResult) -> usize {
+    result.as_ref().map_or(0, |r| r.owners.len())
+}
+
+/// The `sv_result_len` owners of `result` in increasing order, valid until it is freed, or null
+/// if it is null.
+///
+/// # Safety
+///
+/// `result` is null or from `sv_compute` and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn sv_result_owners(result: *const SvResult) -> *const u64 {
+    result.as_ref().map_or(ptr::null(), |r| r.owners.as_ptr())
+}
+
+/// The `sv_result_len` Shapley values of `result`, in the order of `sv_result_owners`, valid
+/// until it is freed, or null if it is null.
+///
+/// # Safety
+///
+/// `result` is null or from `sv_compute` and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn sv_result_values(result: *const SvResult) -> *const f64 {
+    result.as_ref().map_or(ptr::null(), |r| r.values.as_ptr())
+}
+
+/// Free a game. Nothing happens if it is null.
+///
+/// # Safety
+///
+/// `game` is null or from `sv_game_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sv_free_game(game: *mut SvGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Free a result. Nothing happens if it is null.
+///
+/// # Safety
+///
+/// `result` is null or from `sv_compute` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sv_free_result(result: *mut SvResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
+
+fn owner_id(id: u64) -> Option<OwnerId> {
+    u32::try_from(id).ok().map(OwnerId)
+}
+
+/// The `len` entries at `data`, or none if it is null.
+unsafe fn slice_or_empty<'a, T>(data: *const T, len: usize) -> &'a [T] {
+    if data.is_null() {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    }
+}
+
+/// Run `f`, or return None if it panics.
+fn catch_panic<R>(f: impl FnOnce() -> R) -> Option<R> {
+    panic::catch_unwind(AssertUnwindSafe(f)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[link(name = "sv_ffi_test", kind = "static")]
+    extern "C" {
+        /// The C test program `tests/ffi/sv_ffi_test.c`, compiled by the build script.
+        fn sv_ffi_test_main() -> c_int;
+    }
+
+    #[test]
+    fn test_c_program() {
+        assert_eq!(0, unsafe { sv_ffi_test_main() });
+    }
+
+    #[test]
+    fn test_invalid() {
+        unsafe {
+            // owner 2 is not in the owner set
+            let flat = [1u64, 2];
+            let offsets = [0usize, 2];
+            let owners = [1u64];
+            let game = sv_game_new(flat.as_ptr(), offsets.as_ptr(), 1, owners.as_ptr(), 1);
+            assert!(game.is_null());
+
+            // an owner beyond 32 bits
+            let owners = [1u64, 1 << 32];
+            let game = sv_game_new(flat.as_ptr(), offsets.as_ptr(), 1, owners.as_ptr(), 2);
+            assert!(game.is_null());
+
+            // offsets beyond the implicants
+            let owners = [1u64, 2];
+            let offsets = [0usize, 2, 1];
+            let game = sv_game_new(flat.as_ptr(), offsets.as_ptr(), 2, owners.as_ptr(), 2);
+            assert!(game.is_null());
+
+            let offsets = [0usize, 2];
+            let game = sv_game_new(flat.as_ptr(), offsets.as_ptr(), 1, owners.as_ptr(), 2);
+            assert!(!game.is_null());
+            let mut result = ptr::null_mut();
+            assert_eq!(SvStatus::InvalidAblation, sv_compute(game, 9, &mut result));
+            assert_eq!(
+                SvStatus::NullPointer,
+                sv_compute(ptr::null(), SV_ABLATION_NONE, &mut result)
+            );
+            assert!(result.is_null());
+            assert_eq!(0, sv_result_len(result));
+            sv_free_game(game);
+            sv_free_result(result);
+        }
+    }
+}
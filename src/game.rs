@@ -1,28 +1,133 @@
+#[cfg(feature = "data")]
 use crate::{
-    alg::join::join,
-    dnf::{Dnf, Implicant},
+    alg::join::join, par::prelude::*, utils::cartesian_product, DataSet, RowId, PLANS,
+    ROW_ID_COL_NAME,
+};
+use crate::{
+    alg::synthesis_sv::recursive_decompose::{cal_sv_for, cal_swing_counts},
+    dnf::{Cnf, Dnf, GeneralDnf, Implicant},
+    gen::{GameGenerator, Structure},
     owner::{OwnerId, OwnerSet},
-    utils::{cartesian_product, dnf_to_syns},
-    DataSet, RowId, PLANS, ROW_ID_COL_NAME,
+    utils::dnf_to_syns,
+    BlocPower,
 };
-use anyhow::{Context, Error, Ok, Result};
+use anyhow::{ensure, Context, Error, Result};
+#[cfg(feature = "data")]
 use polars_core::{
     prelude::{AnyValue, DataFrame, NamedFrom},
     series::{ChunkCompare, Series},
 };
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeSet, str::FromStr};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap},
+    error, fmt,
+    ops::Range,
+    str::FromStr,
+};
 
 /// A simple game among data owners.
-#[derive(Debug, Clone)]
+///
+/// Use `Game::new` to construct a validated game. The fields are public so that a game can still
+/// be built directly, in which case it is up to the caller to keep it valid.
+///
+/// It is (de)serialized as `{"dnf": [[1, 2], [3]], "owner_set": [1, 2, 3]}`, and deserialization
+/// fails if the DNF refers to owners outside the owner set.
+///
+/// As with `Dnf`, equality and hashing are structural. Use the `canonical` form as a key to
+/// memoize results across games that only differ by absorbed implicants.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedGame")]
 pub struct Game {
     pub dnf: Dnf<OwnerId>,
     pub owner_set: OwnerSet,
 }
 
+#[derive(Deserialize)]
+struct UncheckedGame {
+    dnf: Dnf<OwnerId>,
+    owner_set: OwnerSet,
+}
+
+impl TryFrom<UncheckedGame> for Game {
+    type Error = GameError;
+
+    fn try_from(game: UncheckedGame) -> Result<Self, Self::Error> {
+        let game = Self {
+            dnf: game.dnf,
+            owner_set: game.owner_set,
+        };
+        let unknown_owners = game.unknown_owners();
+        if !unknown_owners.is_empty() {
+            return Err(GameError::UnknownOwners(unknown_owners));
+        }
+        Ok(game)
+    }
+}
+
+/// Error of an invalid game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    /// The owner set is empty.
+    EmptyOwnerSet,
+    /// The DNF has no implicant, i.e., no coalition is winning.
+    EmptyDnf,
+    /// Owners in the DNF but not in the owner set.
+    UnknownOwners(Vec<OwnerId>),
+    /// An implicant duplicating (i.e., absorbed by) another one, which is not allowed since the
+    /// decomposition requires a minimized DNF.
+    RedundantImplicant(Vec<OwnerId>),
+    /// The CNF has an empty clause, i.e., no coalition is winning.
+    EmptyClause,
+    /// Members of a bloc not in the owner set.
+    UnknownMembers(Vec<OwnerId>),
+    /// A winning coalition whose superset with one more owner is losing, so the characteristic
+    /// function is not monotone.
+    NonMonotone {
+        winning: Vec<OwnerId>,
+        losing: Vec<OwnerId>,
+    },
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyOwnerSet => write!(f, "the owner set is empty"),
+            Self::EmptyDnf => write!(f, "the DNF is empty"),
+            Self::UnknownOwners(owners) => {
+                write!(f, "owners {owners:?} in the DNF are not in the owner set")
+            }
+            Self::RedundantImplicant(imp) => {
+                write!(f, "implicant {imp:?} is absorbed by another implicant")
+            }
+            Self::EmptyClause => write!(f, "the CNF has an empty clause"),
+            Self::UnknownMembers(owners) => {
+                write!(f, "members {owners:?} of the bloc are not in the owner set")
+            }
+            Self::NonMonotone { winning, losing } => {
+                write!(
+                    f,
+                    "coalition {winning:?} wins but its superset {losing:?} loses"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for GameError {}
+
 impl Game {
-    pub fn new(exp: Dnf<OwnerId>) -> Self {
+    /// Create a game among `owner_set`, where the coalitions satisfying `dnf` are winning.
+    ///
+    /// Owners in `owner_set` but not in `dnf` are null players. See `validate` for the errors.
+    pub fn new(dnf: Dnf<OwnerId>, owner_set: OwnerSet) -> Result<Self, GameError> {
+        let game = Self { dnf, owner_set };
+        game.validate()?;
+        Ok(game)
+    }
+
+    /// Create a game among exactly the owners appearing in `exp`, without validation.
+    pub fn from_dnf(exp: Dnf<OwnerId>) -> Self {
         let owner_set = exp.all_variables().into();
         Self {
             dnf: exp,
@@ -30,14 +135,499 @@ impl Game {
         }
     }
 
+    /// The same game with its DNF in `Dnf::canonical` form, so that all the DNFs of the same
+    /// winning coalitions give equal games.
+    pub fn canonical(&self) -> Game {
+        Game {
+            dnf: self.dnf.canonical(),
+            owner_set: self.owner_set.clone(),
+        }
+    }
+
+    /// Create a game among `owner_set`, where the coalitions satisfying `cnf` are winning.
+    ///
+    /// The CNF is converted to a minimized DNF by `Cnf::to_dnf`. See `validate` for the other
+    /// errors.
+    pub fn from_cnf(cnf: Cnf<OwnerId>, owner_set: OwnerSet) -> Result<Self, GameError> {
+        if cnf.is_false() {
+            return Err(GameError::EmptyClause);
+        }
+        Self::new(cnf.to_dnf(), owner_set)
+    }
+
+    /// Same as `from_cnf`, with the CNF given as its clauses, each of which is satisfied by any of
+    /// its owners.
+    pub fn from_cnf_clauses(
+        clauses: &[BTreeSet<OwnerId>],
+        owner_set: OwnerSet,
+    ) -> Result<Self, GameError> {
+        let cnf = clauses.iter().cloned().map(Implicant::from).collect();
+        Self::from_cnf(cnf, owner_set)
+    }
+
+    /// Create the weighted voting game where a coalition wins if its total weight reaches `quota`.
+    ///
+    /// The DNF consists of the minimal winning coalitions. It is empty if the total weight is
+    /// below `quota`.
+    pub fn from_weighted_voting(weights: &[(OwnerId, u64)], quota: u64) -> Self {
+        let mut players = weights.to_vec();
+        // with the heaviest players first, the last one added to a coalition is the lightest
+        players.sort_unstable_by_key(|&(_, w)| Reverse(w));
+        let mut remaining: Vec<u64> = players
+            .iter()
+            .rev()
+            .scan(0, |acc, (_, w)| {
+                *acc += w;
+                Some(*acc)
+            })
+            .collect();
+        remaining.reverse();
+        remaining.push(0);
+
+        let mut dnf = Dnf::new();
+        let mut coalition = Implicant::new();
+        collect_minimal_winning(&players, &remaining, quota, 0, 0, &mut coalition, &mut dnf);
+
+        Self {
+            dnf,
+            owner_set: weights.iter().map(|(o, _)| *o).collect(),
+        }
+    }
+
+    /// Create the game among owners `0..num_owners` whose winning coalitions are those satisfying
+    /// `is_winning`, e.g., to test the algorithms on an arbitrary small game. The DNF consists of
+    /// the minimal winning coalitions.
+    ///
+    /// `is_winning` is called on each of the `2^num_owners` coalitions once. Fail with
+    /// `GameError::NonMonotone` if a winning coalition has a losing superset, and see `validate`
+    /// for the other errors, e.g., `GameError::EmptyDnf` if no coalition wins.
+    ///
+    /// Panic if `num_owners` is 32 or more.
+    pub fn from_truth_table(
+        num_owners: usize,
+        is_winning: impl Fn(&BTreeSet<OwnerId>) -> bool,
+    ) -> Result<Self, GameError> {
+        assert!(
+            num_owners < 32,
+            "too many owners ({num_owners}) for a truth table"
+        );
+        let coalition = |mask: usize| -> BTreeSet<OwnerId> {
+            (0..num_owners)
+                .filter(|i| mask & 1 << i != 0)
+                .map(|i| OwnerId(i as u32))
+                .collect()
+        };
+        let wins: Vec<bool> = (0..1usize << num_owners)
+            .map(|mask| is_winning(&coalition(mask)))
+            .collect();
+
+        let mut dnf = Dnf::new();
+        for (mask, _) in wins.iter().enumerate().filter(|(_, win)| **win) {
+            // a losing superset with one more owner is enough, as supersets are reached by steps
+            if let Some(i) = (0..num_owners).find(|i| !wins[mask | 1 << i]) {
+                return Err(GameError::NonMonotone {
+                    winning: coalition(mask).into_iter().collect(),
+                    losing: coalition(mask | 1 << i).into_iter().collect(),
+                });
+            }
+            if (0..num_owners).all(|i| mask & 1 << i == 0 || !wins[mask & !(1 << i)]) {
+                dnf.insert(Implicant::from(coalition(mask)));
+            }
+        }
+        Self::new(dnf, (0..num_owners as u32).map(OwnerId).collect())
+    }
+
+    /// Parse a game with one implicant per line, e.g., `1 2 4`, and an optional first line like
+    /// `owners: 1 2 3 4 5` listing the owner set. Empty lines and lines starting with `#` are
+    /// skipped.
+    ///
+    /// A line may also hold several implicants in the DNF string format, e.g., `1 2 4 + 4 5`, so
+    /// the whole DNF can be given in a single line.
+    pub fn parse_implicant_lines(s: &str) -> Result<Self> {
+        let parse_owners = |line: &str| -> Result<BTreeSet<OwnerId>> {
+            line.split_whitespace()
+                .map(|t| {
+                    t.parse()
+                        .map(OwnerId)
+                        .with_context(|| format!("invalid owner `{t}`"))
+                })
+                .collect()
+        };
+
+        let mut owner_set = None;
+        let mut dnf = Dnf::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(owners) = line.strip_prefix("owners:") {
+                ensure!(
+                    owner_set.is_none() && dnf.is_empty(),
+                    "line {}: `owners:` must be the first line",
+                    i + 1
+                );
+                owner_set = Some(OwnerSet(parse_owners(owners)?));
+            } else {
+                for imp in line.split('+') {
+                    let imp = parse_owners(imp).with_context(|| format!("line {}", i + 1))?;
+                    ensure!(!imp.is_empty(), "line {}: empty implicant", i + 1);
+                    dnf.insert(Implicant(imp));
+                }
+            }
+        }
+
+        let owner_set = owner_set.unwrap_or_else(|| dnf.all_variables().into());
+        Ok(Self::new(dnf, owner_set)?)
+    }
+
+    /// A random game among owners `0..num_owners` with `num_implicants` sampled implicants, whose
+    /// sizes are uniform in `implicant_size_range` (capped by `num_owners`), for benchmarking and
+    /// fuzzing. The same seed always gives the same game. See `GameGenerator` for more options.
+    ///
+    /// The DNF is minimized, so it may end up with fewer implicants when some of them are
+    /// duplicated or absorbed. Owners in no implicant are null players.
+    ///
+    /// Panic if `implicant_size_range` is empty or contains 0, or there are implicants but no
+    /// owners.
+    pub fn random(
+        num_owners: usize,
+        num_implicants: usize,
+        implicant_size_range: Range<usize>,
+        seed: u64,
+    ) -> Game {
+        GameGenerator {
+            num_owners,
+            num_implicants,
+            implicant_size: implicant_size_range,
+            structure: Structure::Random,
+            seed,
+        }
+        .generate()
+    }
+
     pub fn owner_len(&self) -> usize {
         self.owner_set.len()
     }
 
+    /// Return the minimal winning coalitions, i.e., the minimal implicants of the DNF.
+    pub fn minimal_winning_coalitions(&self) -> Vec<OwnerSet> {
+        self.dnf
+            .minimal_implicants()
+            .into_iter()
+            .map(OwnerSet::from)
+            .collect()
+    }
+
+    /// Return the null players, i.e., owners in the owner set that are in no minimal winning
+    /// coalition. They are never swing players, so their Shapley values are 0, e.g., owner 2 in
+    /// `1 2 + 1`.
+    pub fn null_players(&self) -> BTreeSet<OwnerId> {
+        let mut ans = self.owner_set.0.clone();
+        for imp in self.dnf.minimal_implicants() {
+            for owner in &imp {
+                ans.remove(owner);
+            }
+        }
+        ans
+    }
+
+    /// Alias of `null_players`.
+    pub fn dummy_players(&self) -> BTreeSet<OwnerId> {
+        self.null_players()
+    }
+
+    /// Return the veto players, i.e., owners in every minimal winning coalition. No coalition wins
+    /// without them.
+    pub fn veto_players(&self) -> BTreeSet<OwnerId> {
+        let mut imps = self.dnf.minimal_implicants().into_iter();
+        let Some(mut ans) = imps.next() else {
+            return BTreeSet::new();
+        };
+        for imp in imps {
+            ans.retain(|owner| imp.contains(owner));
+        }
+        ans
+    }
+
+    /// Return the dictator, i.e., the owner who wins alone and is a veto player.
+    pub fn dictator(&self) -> Option<OwnerId> {
+        match &self.dnf.minimal_implicants()[..] {
+            [imp] if imp.len() == 1 => imp.first().copied(),
+            _ => None,
+        }
+    }
+
+    /// Group the owners that play identical roles, i.e., swapping any two owners of a group maps
+    /// the game onto itself, so they get the same Shapley value, e.g., `[{1, 2, 3}, {4, 5}]` in
+    /// `1 4 5 + 2 4 5 + 3 4 5`. Null players are all in a group.
+    ///
+    /// Every owner is in a group, possibly alone, and the groups are ordered by their smallest
+    /// owners. Owners may get the same value without being interchangeable, e.g., 1 and 4 in
+    /// `1 2 + 2 3 + 3 4`, where 2 and 3 have to be swapped as well.
+    pub fn symmetry_classes(&self) -> Vec<BTreeSet<OwnerId>> {
+        let dnf = self.dnf.minimized();
+        // the lengths of the implicants of each owner, in order, which must match for a swap
+        let mut signatures: HashMap<OwnerId, Vec<usize>> = HashMap::new();
+        for imp in dnf.iter() {
+            for owner in imp.iter() {
+                signatures.entry(*owner).or_default().push(imp.len());
+            }
+        }
+        let swapped = |a: OwnerId, b: OwnerId| {
+            dnf.map_variable(|o| match *o {
+                o if o == a => b,
+                o if o == b => a,
+                o => o,
+            })
+        };
+
+        let mut classes: Vec<BTreeSet<OwnerId>> = vec![];
+        // indices of the classes by signature, whose first owners are tried in turn
+        let mut candidates: HashMap<&[usize], Vec<usize>> = HashMap::new();
+        for owner in self.owner_set.iter() {
+            let signature = signatures.get(owner).map_or(&[][..], |s| &s[..]);
+            let indices = candidates.entry(signature).or_default();
+            let found = indices.iter().find(|i| {
+                let first = *classes[**i].first().unwrap();
+                signature.is_empty() || swapped(first, *owner) == dnf
+            });
+            match found {
+                Some(i) => {
+                    classes[*i].insert(*owner);
+                }
+                None => {
+                    indices.push(classes.len());
+                    classes.push(BTreeSet::from([*owner]));
+                }
+            }
+        }
+        classes
+    }
+
+    /// Whether `coalition` is winning, i.e., it contains all the owners of some implicant.
+    pub fn is_winning(&self, coalition: &BTreeSet<OwnerId>) -> bool {
+        self.dnf.iter().any(|imp| imp.is_subset(coalition))
+    }
+
+    /// A copy of the game with `imp` added as a winning coalition, removing the implicants absorbed
+    /// by it. Owners of `imp` not in the owner set are added to it.
+    ///
+    /// The DNF is unchanged if `imp` is already absorbed by it. To recompute the Shapley values
+    /// after each added implicant, `SvSession::add_implicant` reuses the decomposition of the
+    /// components of the DNF not sharing owners with `imp`.
+    pub fn with_added_implicant(&self, imp: BTreeSet<OwnerId>) -> Game {
+        let mut game = self.clone();
+        game.owner_set.extend(imp.iter().copied());
+        if !game.dnf.iter().any(|t| t.is_subset(&imp)) {
+            game.dnf.retain(|t| !imp.is_subset(t));
+            game.dnf.insert(Implicant::from(imp));
+        }
+        game
+    }
+
+    /// The game among the owners in `coalition` only, where the others are absent, i.e., the
+    /// implicants with any of them are dropped. See `Dnf::restrict_to`.
+    ///
+    /// The DNF is empty if no implicant is within `coalition`, in which case the Shapley values
+    /// are all zeros. The owners of `coalition` not in the owner set are ignored.
+    pub fn subgame(&self, coalition: &OwnerSet) -> Game {
+        Game {
+            dnf: self.dnf.restrict_to(coalition),
+            owner_set: self.owners_within(coalition),
+        }
+    }
+
+    /// The dual of `subgame`, where the owners outside `coalition` are always present, i.e., they
+    /// are projected out of the implicants by `partial_eval`. See `Dnf::project_to`.
+    ///
+    /// The DNF is TRUE if an implicant only has owners outside `coalition`, in which case the
+    /// Shapley values are all zeros.
+    pub fn marginal_game(&self, coalition: &OwnerSet) -> Game {
+        Game {
+            dnf: self.dnf.project_to(coalition),
+            owner_set: self.owners_within(coalition),
+        }
+    }
+
+    /// The dual game among the same owners, where a coalition wins iff its complement loses in
+    /// this game, i.e., iff it meets every minimal winning coalition.
+    ///
+    /// Its DNF is that of the implicants read as the clauses of a CNF, minimized by
+    /// `Cnf::to_dnf`, which may have exponentially many implicants. The dual of a game that never
+    /// wins always wins and vice versa, and the dual of the dual is the game itself. A game and
+    /// its dual have the same Shapley values.
+    pub fn dual(&self) -> Game {
+        let cnf: Cnf<OwnerId> = self.dnf.minimized().iter().cloned().collect();
+        Game {
+            dnf: cnf.to_dnf(),
+            owner_set: self.owner_set.clone(),
+        }
+    }
+
+    fn owners_within(&self, coalition: &OwnerSet) -> OwnerSet {
+        self.owner_set.intersection(coalition).copied().collect()
+    }
+
+    /// A copy of the game where the owners in `group` are merged into a single owner `new_id`,
+    /// which wins wherever the whole group does, e.g., to value a whole data source.
+    ///
+    /// Implicants that become identical are deduplicated, and those absorbed by others are
+    /// removed. Owners of `group` not in the owner set are ignored.
+    ///
+    /// Panic if `new_id` is an owner outside `group`.
+    pub fn merge_owners(&self, group: &OwnerSet, new_id: OwnerId) -> Game {
+        assert!(
+            group.contains(&new_id) || !self.owner_set.contains(&new_id),
+            "owner {new_id} is already in the game"
+        );
+        let mut dnf = self
+            .dnf
+            .map_variable(|o| if group.contains(o) { new_id } else { *o });
+        dnf.minimize();
+        let mut owner_set: OwnerSet = self
+            .owner_set
+            .iter()
+            .filter(|o| !group.contains(*o))
+            .copied()
+            .collect();
+        owner_set.insert(new_id);
+        Game { dnf, owner_set }
+    }
+
+    /// The characteristic function, i.e., whether `coalition` is winning with its owners present
+    /// and everyone else absent. Same as `is_winning`.
+    pub fn value(&self, coalition: &OwnerSet) -> bool {
+        self.is_winning(coalition)
+    }
+
+    /// Same as `value`, but as 1 or 0.
+    pub fn value01(&self, coalition: &OwnerSet) -> f64 {
+        if self.value(coalition) {
+            1.
+        } else {
+            0.
+        }
+    }
+
+    /// Whether `coalition` is winning but none of its proper subsets is, i.e., it is a minimal
+    /// implicant of the DNF.
+    ///
+    /// As the game is monotone, this holds iff every implicant within `coalition` is `coalition`
+    /// itself, which is checked directly on the implicants without minimizing the DNF.
+    pub fn is_minimal_winning(&self, coalition: &BTreeSet<OwnerId>) -> bool {
+        let mut within = self
+            .dnf
+            .iter()
+            .filter(|imp| imp.is_subset(coalition))
+            .peekable();
+        within.peek().is_some() && within.all(|imp| imp.len() == coalition.len())
+    }
+
+    /// Number of coalitions in which each owner is a swing player, i.e., the coalition wins but
+    /// loses without the owner, computed by the decomposition. See `cal_swing_counts`.
+    pub fn swing_counts(&self) -> HashMap<OwnerId, u128> {
+        cal_swing_counts(self)
+    }
+
+    /// The sum of the Shapley values of all the owners by the efficiency axiom, i.e.,
+    /// `v(N) - v({})`, which is 1 if the grand coalition wins (the DNF is not empty) and the empty
+    /// coalition does not (the DNF is not TRUE), and 0 otherwise.
+    pub fn expected_sv_sum(&self) -> f64 {
+        self.value01(&self.owner_set) - self.value01(&OwnerSet::default())
+    }
+
+    /// Total Shapley value of the allied owners in `members`, and its share of the total value,
+    /// which is 1 unless the game always or never wins. Only the values of the members are
+    /// computed.
+    pub fn bloc_power(&self, members: &BTreeSet<OwnerId>) -> Result<BlocPower, GameError> {
+        let unknown_members: Vec<_> = members
+            .iter()
+            .filter(|o| !self.owner_set.contains(*o))
+            .copied()
+            .collect();
+        if !unknown_members.is_empty() {
+            return Err(GameError::UnknownMembers(unknown_members));
+        }
+
+        let power = cal_sv_for(self, members).coalition_sum(members);
+        let total = self.expected_sv_sum();
+        Ok(BlocPower {
+            power,
+            fraction: if total == 0. { 0. } else { power / total },
+        })
+    }
+
+    /// Marginal contribution (0 or 1) of each owner when the owners join one by one in `order`,
+    /// i.e., whether the owner turns the coalition of the owners before it into a winning one.
+    ///
+    /// The Shapley value of an owner is the average of its marginal contributions over all the
+    /// orders of the owner set. Owners not in `order` are left out.
+    pub fn marginal_contributions(&self, order: &[OwnerId]) -> Vec<(OwnerId, f64)> {
+        let mut coalition = BTreeSet::new();
+        let mut was_winning = self.is_winning(&coalition);
+        order
+            .iter()
+            .map(|owner| {
+                coalition.insert(*owner);
+                // a winning coalition stays winning as the game is monotone
+                let is_winning = was_winning || self.is_winning(&coalition);
+                let marginal = if is_winning && !was_winning { 1. } else { 0. };
+                was_winning = is_winning;
+                (*owner, marginal)
+            })
+            .collect()
+    }
+
+    /// Check that the owner set and the DNF are non-empty, that the DNF is minimized, and that it
+    /// only refers to owners in the owner set.
+    pub fn validate(&self) -> Result<(), GameError> {
+        if self.owner_set.is_empty() {
+            return Err(GameError::EmptyOwnerSet);
+        }
+
+        if self.dnf.is_false() {
+            return Err(GameError::EmptyDnf);
+        }
+
+        let unknown_owners = self.unknown_owners();
+        if !unknown_owners.is_empty() {
+            return Err(GameError::UnknownOwners(unknown_owners));
+        }
+
+        // implicants are ordered by their lengths, so a subset always comes first
+        for (i, imp_i) in self.dnf.iter().enumerate() {
+            if let Some(imp_j) = self
+                .dnf
+                .iter()
+                .skip(i + 1)
+                .find(|imp_j| imp_i.is_subset(imp_j))
+            {
+                return Err(GameError::RedundantImplicant(
+                    imp_j.iter().copied().collect(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn unknown_owners(&self) -> Vec<OwnerId> {
+        self.dnf
+            .all_variables()
+            .into_iter()
+            .filter(|o| !self.owner_set.contains(o))
+            .collect()
+    }
+
     pub fn to_syns(&self) -> Vec<&'_ OwnerSet> {
         dnf_to_syns(&self.dnf)
     }
+}
 
+/// Games of the tuples of a dataset, joined by polars.
+#[cfg(feature = "data")]
+impl Game {
     pub fn generate_games(dataset: &DataSet) -> Result<Vec<Self>> {
         if dataset.owner_set.is_empty() {
             Self::generate_games_without_assignment(dataset)
@@ -146,6 +736,7 @@ impl Game {
         Ok(games)
     }
 
+    #[allow(deprecated)]
     fn join_df(dataset: &DataSet) -> Result<DataFrame> {
         info!("join...");
         let mut join_df = join(
@@ -209,9 +800,9 @@ impl Game {
 
         let winner = home_score
             .into_iter()
-            .zip(away_score.into_iter())
-            .zip(home_team_name.into_iter())
-            .zip(away_team_name.into_iter())
+            .zip(away_score)
+            .zip(home_team_name)
+            .zip(away_team_name)
             .map(
                 |(((home_score, away_score), home_team_name), away_team_name)| {
                     if home_score > away_score {
@@ -264,9 +855,89 @@ impl Game {
     }
 }
 
+/// Add the minimal winning coalitions extending `coalition` with players from `start`.
+///
+/// `remaining[i]` is the total weight of `players[i..]`.
+fn collect_minimal_winning(
+    players: &[(OwnerId, u64)],
+    remaining: &[u64],
+    quota: u64,
+    start: usize,
+    weight: u64,
+    coalition: &mut Implicant<OwnerId>,
+    ans: &mut Dnf<OwnerId>,
+) {
+    if weight >= quota {
+        // removing the lightest (i.e., last added) player makes it losing, so it is minimal
+        ans.insert(coalition.clone());
+        return;
+    }
+
+    for (i, (owner, w)) in players.iter().enumerate().skip(start) {
+        if weight + remaining[i] < quota {
+            break;
+        }
+        coalition.insert(*owner);
+        collect_minimal_winning(players, remaining, quota, i + 1, weight + w, coalition, ans);
+        coalition.remove(owner);
+    }
+}
+
+/// A game whose winning condition may require some owners to be absent, i.e., a DNF with negated
+/// literals. It is not a simple game unless no literal is negated, as an owner joining a winning
+/// coalition may make it lose.
+///
+/// Its Shapley values are computed by `cal_sv_general`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneralGame {
+    pub dnf: GeneralDnf<OwnerId>,
+    pub owner_set: OwnerSet,
+}
+
+impl GeneralGame {
+    /// Create a game among `owner_set`, where the coalitions satisfying `dnf` are winning.
+    ///
+    /// Fail with `GameError::UnknownOwners` if a literal is of an owner outside `owner_set`.
+    pub fn new(dnf: GeneralDnf<OwnerId>, owner_set: OwnerSet) -> Result<Self, GameError> {
+        let unknown_owners: Vec<_> = dnf
+            .literal_variables()
+            .into_iter()
+            .filter(|o| !owner_set.contains(o))
+            .collect();
+        if !unknown_owners.is_empty() {
+            return Err(GameError::UnknownOwners(unknown_owners));
+        }
+        Ok(Self { dnf, owner_set })
+    }
+
+    /// Whether `coalition` is winning, i.e., it satisfies some implicant, with the owners of its
+    /// negated literals absent.
+    pub fn is_winning(&self, coalition: &BTreeSet<OwnerId>) -> bool {
+        self.dnf.is_satisfied_by(coalition)
+    }
+
+    /// The same game as a simple game if no literal is negated.
+    pub fn to_game(&self) -> Option<Game> {
+        Some(Game {
+            dnf: self.dnf.to_monotone()?,
+            owner_set: self.owner_set.clone(),
+        })
+    }
+}
+
+impl From<&Game> for GeneralGame {
+    fn from(game: &Game) -> Self {
+        Self {
+            dnf: GeneralDnf::from_monotone(&game.dnf),
+            owner_set: game.owner_set.clone(),
+        }
+    }
+}
+
 /// A boolean expression
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(untagged)]
+#[allow(dead_code)]
 enum BoolExp<T> {
     And { and: Vec<BoolExp<T>> },
     Or { or: Vec<BoolExp<T>> },
@@ -283,3 +954,849 @@ where
         Ok(serde_json::from_str(s)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::{
+            exact::{cal_banzhaf_swings_exact, cal_sv_brute_force, cal_sv_exact},
+            synthesis_sv::recursive_decompose::cal_sv_recursive_decompose,
+        },
+        cnf, dnf,
+        tests::assert_f64_eq,
+    };
+    use itertools::Itertools;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_serde() {
+        let game = Game::from_dnf(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+        );
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(
+            r#"{"dnf":[[4,5],[1,2,4],[1,2,5],[2,3,4],[2,3,5]],"owner_set":[1,2,3,4,5]}"#,
+            json
+        );
+        assert_eq!(game, serde_json::from_str::<Game>(&json).unwrap());
+
+        let json = r#"{"dnf":[[1,2]],"owner_set":[1]}"#;
+        assert!(serde_json::from_str::<Game>(json).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let game = Game::from_dnf(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+        );
+        assert_eq!(Ok(()), game.validate());
+
+        let game = Game {
+            dnf: Dnf::false_exp(),
+            owner_set: OwnerSet::from_iter([1, 2]),
+        };
+        assert_eq!(Err(GameError::EmptyDnf), game.validate());
+
+        let game = Game {
+            dnf: dnf!(1 2 + 3 4).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([1, 2]),
+        };
+        assert_eq!(
+            Err(GameError::UnknownOwners(vec![OwnerId(3), OwnerId(4)])),
+            game.validate()
+        );
+
+        let game = Game::from_dnf(dnf!(1 2 + 1 2 3).map_variable(|id| OwnerId(*id)));
+        assert_eq!(
+            Err(GameError::RedundantImplicant(vec![
+                OwnerId(1),
+                OwnerId(2),
+                OwnerId(3)
+            ])),
+            game.validate()
+        );
+    }
+
+    #[test]
+    fn test_new() {
+        let exp = dnf!(1 2 + 2 3).map_variable(|id| OwnerId(*id));
+        let game = Game::new(exp.clone(), OwnerSet::from_iter([1, 2, 3, 4])).unwrap();
+        assert_eq!(exp, game.dnf);
+        assert_eq!(OwnerSet::from_iter([1, 2, 3, 4]), game.owner_set);
+
+        assert_eq!(
+            Err(GameError::EmptyOwnerSet),
+            Game::new(Dnf::true_exp(), OwnerSet::default())
+        );
+        assert_eq!(
+            Err(GameError::EmptyDnf),
+            Game::new(Dnf::false_exp(), OwnerSet::from_iter([1, 2]))
+        );
+        assert_eq!(
+            Err(GameError::UnknownOwners(vec![OwnerId(3)])),
+            Game::new(exp, OwnerSet::from_iter([1, 2]))
+        );
+        assert_eq!(
+            Err(GameError::RedundantImplicant(vec![OwnerId(1), OwnerId(2)])),
+            Game::new(
+                dnf!(1 + 1 2).map_variable(|id| OwnerId(*id)),
+                OwnerSet::from_iter([1, 2])
+            )
+        );
+    }
+
+    #[test]
+    fn test_canonical() {
+        let owner_set = OwnerSet::from_iter(1..=3);
+        // not `Game::new`, which rejects the redundant implicants that the canonical form drops
+        let game = |exp: Dnf<u32>| Game {
+            dnf: exp.map_variable(|id| OwnerId(*id)),
+            owner_set: owner_set.clone(),
+        };
+        let mut memo = HashMap::new();
+        memo.insert(game(dnf!(1 2 + 3)).canonical(), 1);
+        assert_eq!(Some(&1), memo.get(&game(dnf!(3 + 2 1 + 1 2 3)).canonical()));
+        assert_eq!(None, memo.get(&game(dnf!(1 2 + 2 3)).canonical()));
+
+        // the null players still matter
+        let other = Game::new(game(dnf!(1 2 + 3)).dnf, OwnerSet::from_iter(1..=4)).unwrap();
+        assert_eq!(None, memo.get(&other.canonical()));
+    }
+
+    #[test]
+    fn test_from_cnf() {
+        let cnf = cnf!((1 + 2)(1 + 3)(1 + 2 + 4)).map_variable(|id| OwnerId(*id));
+        let game = Game::from_cnf(cnf, OwnerSet::from_iter([1, 2, 3, 4])).unwrap();
+        assert_eq!(dnf!(1 + 2 3).map_variable(|id| OwnerId(*id)), game.dnf);
+        let sv = cal_sv_exact(&game);
+        assert_f64_eq(2. / 3., sv[&OwnerId(1)]);
+        assert_f64_eq(0., sv[&OwnerId(4)]);
+
+        let mut cnf = cnf!((1 + 2)).map_variable(|id| OwnerId(*id));
+        cnf.insert(Implicant::new());
+        assert_eq!(
+            Err(GameError::EmptyClause),
+            Game::from_cnf(cnf, OwnerSet::from_iter([1, 2]))
+        );
+    }
+
+    #[test]
+    fn test_from_cnf_clauses() {
+        // (1 or 2) and 3
+        let clauses = [
+            BTreeSet::from([OwnerId(1), OwnerId(2)]),
+            BTreeSet::from([OwnerId(3)]),
+        ];
+        let game = Game::from_cnf_clauses(&clauses, OwnerSet::from_iter([1, 2, 3])).unwrap();
+        assert_eq!(dnf!(1 3 + 2 3).map_variable(|id| OwnerId(*id)), game.dnf);
+
+        // absorbed clauses do not multiply the implicants
+        let clauses = [
+            BTreeSet::from([OwnerId(1), OwnerId(2)]),
+            BTreeSet::from([OwnerId(1)]),
+            BTreeSet::from([OwnerId(1), OwnerId(3)]),
+        ];
+        let game = Game::from_cnf_clauses(&clauses, OwnerSet::from_iter([1, 2, 3])).unwrap();
+        assert_eq!(dnf!(1).map_variable(|id| OwnerId(*id)), game.dnf);
+
+        assert_eq!(
+            Err(GameError::EmptyClause),
+            Game::from_cnf_clauses(&[BTreeSet::new()], OwnerSet::from_iter([1]))
+        );
+        assert_eq!(
+            Err(GameError::UnknownOwners(vec![OwnerId(4)])),
+            Game::from_cnf_clauses(&[BTreeSet::from([OwnerId(4)])], OwnerSet::from_iter([1]))
+        );
+    }
+
+    #[test]
+    fn test_from_truth_table() {
+        // majority of 3
+        let game = Game::from_truth_table(3, |coalition| coalition.len() >= 2).unwrap();
+        assert_eq!(
+            dnf!(0 1 + 0 2 + 1 2).map_variable(|id| OwnerId(*id)),
+            game.dnf
+        );
+        assert_eq!(OwnerSet::from_iter(0..3), game.owner_set);
+
+        // the truth table of a game gives the game back, with its null players
+        let expect = Game::new(
+            dnf!(0 1 3 + 0 1 4 + 1 2 3 + 1 2 4 + 3 4).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(0..6),
+        )
+        .unwrap();
+        let game = Game::from_truth_table(6, |coalition| expect.is_winning(coalition)).unwrap();
+        assert_eq!(expect.canonical(), game.canonical());
+        let sv = cal_sv_recursive_decompose(&game);
+        assert_f64_eq(0.23333333333, sv[&OwnerId(1)]);
+
+        assert_eq!(
+            Err(GameError::EmptyDnf),
+            Game::from_truth_table(2, |_| false)
+        );
+    }
+
+    #[test]
+    fn test_from_truth_table_non_monotone() {
+        // exactly one of 0 and 1, so {0} wins but {0, 1} loses
+        let err = Game::from_truth_table(2, |coalition| coalition.len() == 1).unwrap_err();
+        assert_eq!(
+            GameError::NonMonotone {
+                winning: vec![OwnerId(0)],
+                losing: vec![OwnerId(0), OwnerId(1)],
+            },
+            err
+        );
+        assert_eq!(
+            "coalition [OwnerId(0)] wins but its superset [OwnerId(0), OwnerId(1)] loses",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_marginal_contributions() {
+        let game = Game::from_dnf(dnf!(1 2 3).map_variable(|id| OwnerId(*id)));
+        let owners: Vec<_> = game.owner_set.iter().copied().collect();
+        for order in owners.iter().copied().permutations(owners.len()) {
+            let marginals = game.marginal_contributions(&order);
+            assert_eq!(order, marginals.iter().map(|(o, _)| *o).collect_vec());
+            // only the last owner completes the implicant
+            assert_eq!(
+                vec![0., 0., 1.],
+                marginals.iter().map(|(_, m)| *m).collect_vec()
+            );
+        }
+
+        // the average over all the orders is the Shapley value
+        let game = Game::new(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=6),
+        )
+        .unwrap();
+        let owners: Vec<_> = game.owner_set.iter().copied().collect();
+        let mut sums: BTreeMap<OwnerId, f64> = BTreeMap::new();
+        let mut orders = 0;
+        for order in owners.iter().copied().permutations(owners.len()) {
+            let marginals = game.marginal_contributions(&order);
+            assert_eq!(1., marginals.iter().map(|(_, m)| m).sum::<f64>());
+            for (owner, marginal) in marginals {
+                *sums.entry(owner).or_default() += marginal;
+            }
+            orders += 1;
+        }
+        let sv = cal_sv_exact(&game);
+        for (owner, sum) in sums {
+            assert_f64_eq(sv[&owner], sum / orders as f64);
+        }
+
+        // a partial order
+        assert_eq!(
+            vec![(OwnerId(4), 0.), (OwnerId(5), 1.)],
+            game.marginal_contributions(&[OwnerId(4), OwnerId(5)])
+        );
+    }
+
+    #[test]
+    fn test_is_winning() {
+        let game = Game::from_dnf(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+        );
+        assert!(!game.is_winning(&BTreeSet::new()));
+        assert!(game.is_winning(&game.owner_set));
+        assert!(game.is_winning(&OwnerSet::from_iter([4, 5])));
+        assert!(game.is_winning(&OwnerSet::from_iter([1, 2, 4])));
+        assert!(!game.is_winning(&OwnerSet::from_iter([1, 2, 3])));
+        assert!(!game.is_winning(&OwnerSet::from_iter([1, 4])));
+
+        let game = Game::from_dnf(dnf!(1).map_variable(|id| OwnerId(*id)));
+        assert!(!game.is_winning(&BTreeSet::new()));
+        assert!(game.is_winning(&game.owner_set));
+    }
+
+    #[test]
+    fn test_with_added_implicant() {
+        let game = Game::new(
+            dnf!(1 2 4 + 1 2 5 + 3 4).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=5),
+        )
+        .unwrap();
+
+        // absorb `1 2 4` and `1 2 5`
+        let actual = game.with_added_implicant(BTreeSet::from([OwnerId(1), OwnerId(2)]));
+        assert_eq!(dnf!(1 2 + 3 4).map_variable(|id| OwnerId(*id)), actual.dnf);
+        assert_eq!(game.owner_set, actual.owner_set);
+        assert_eq!(Ok(()), actual.validate());
+
+        // already absorbed
+        let actual =
+            game.with_added_implicant(BTreeSet::from([OwnerId(3), OwnerId(4), OwnerId(5)]));
+        assert_eq!(game, actual);
+
+        // a new owner
+        let actual = game.with_added_implicant(BTreeSet::from([OwnerId(6)]));
+        assert_eq!(
+            dnf!(1 2 4 + 1 2 5 + 3 4 + 6).map_variable(|id| OwnerId(*id)),
+            actual.dnf
+        );
+        assert_eq!(OwnerSet::from_iter(1..=6), actual.owner_set);
+        assert_eq!(Ok(()), actual.validate());
+    }
+
+    #[test]
+    fn test_subgame() {
+        let game = Game::new(
+            dnf!(1 2 + 2 3 + 4).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=5),
+        )
+        .unwrap();
+
+        // 4 is absent, so only `1 2` and `2 3` may win
+        let subgame = game.subgame(&OwnerSet::from_iter([1, 2, 3]));
+        assert_eq!(dnf!(1 2 + 2 3).map_variable(|id| OwnerId(*id)), subgame.dnf);
+        assert_eq!(OwnerSet::from_iter([1, 2, 3]), subgame.owner_set);
+        let sv = cal_sv_recursive_decompose(&subgame);
+        assert_f64_eq(1. / 6., sv[&OwnerId(1)]);
+        assert_f64_eq(2. / 3., sv[&OwnerId(2)]);
+
+        // 4 is present, so the others never matter
+        let marginal = game.marginal_game(&OwnerSet::from_iter([1, 2, 3]));
+        assert!(marginal.dnf.is_true());
+        assert_eq!(OwnerSet::from_iter([1, 2, 3]), marginal.owner_set);
+        let sv = cal_sv_recursive_decompose(&marginal);
+        assert_eq!(3, sv.len());
+        assert!(sv.values().all(|u| *u == 0.));
+
+        // 2 is present, so either 1 or 3 wins
+        let marginal = game.marginal_game(&OwnerSet::from_iter([1, 3, 4, 5]));
+        assert_eq!(
+            dnf!(1 + 3 + 4).map_variable(|id| OwnerId(*id)),
+            marginal.dnf
+        );
+        let sv = cal_sv_recursive_decompose(&marginal);
+        assert_f64_eq(1. / 3., sv[&OwnerId(1)]);
+        assert_f64_eq(0., sv[&OwnerId(5)]);
+        // while 2 is absent in the subgame
+        let subgame = game.subgame(&OwnerSet::from_iter([1, 3, 4, 5]));
+        assert_eq!(dnf!(4).map_variable(|id| OwnerId(*id)), subgame.dnf);
+
+        // unsatisfiable
+        let subgame = game.subgame(&OwnerSet::from_iter([1, 3, 5]));
+        assert!(subgame.dnf.is_false());
+        assert_eq!(Err(GameError::EmptyDnf), subgame.validate());
+        for sv in [
+            cal_sv_recursive_decompose(&subgame),
+            cal_sv_exact(&subgame),
+            cal_sv_brute_force(&subgame),
+        ] {
+            assert_eq!(3, sv.len());
+            assert!(sv.values().all(|u| *u == 0.));
+        }
+    }
+
+    #[test]
+    fn test_dual() {
+        let game = Game::from_dnf(dnf!(1 + 2 + 3).map_variable(|id| OwnerId(*id)));
+        assert_eq!(dnf!(1 2 3).map_variable(|id| OwnerId(*id)), game.dual().dnf);
+        assert_eq!(game, game.dual().dual());
+
+        let games = [
+            Game::from_dnf(
+                dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            ),
+            Game::new(
+                dnf!(1 2 + 1 3 + 4).map_variable(|id| OwnerId(*id)),
+                OwnerSet::from_iter(1..=5),
+            )
+            .unwrap(),
+        ];
+        for game in games {
+            let dual = game.dual();
+            assert_eq!(game.owner_set, dual.owner_set);
+            for coalition in game.owner_set.iter().copied().powerset() {
+                let coalition = BTreeSet::from_iter(coalition);
+                let complement = game.owner_set.difference(&coalition).copied().collect();
+                assert_eq!(!game.is_winning(&complement), dual.is_winning(&coalition));
+            }
+            assert_eq!(game.canonical(), dual.dual().canonical());
+
+            let expect = cal_sv_recursive_decompose(&game);
+            let actual = cal_sv_recursive_decompose(&dual);
+            for (o, u) in &expect {
+                assert_f64_eq(*u, actual[o]);
+            }
+        }
+
+        // majority of 3 is self-dual
+        let game = Game::from_dnf(dnf!(1 2 + 1 3 + 2 3).map_variable(|id| OwnerId(*id)));
+        assert_eq!(game.canonical(), game.dual().canonical());
+
+        let never = Game {
+            dnf: Dnf::new(),
+            owner_set: OwnerSet::from_iter([1, 2]),
+        };
+        assert!(never.dual().dnf.is_true());
+        assert!(never.dual().dual().dnf.is_false());
+    }
+
+    #[test]
+    fn test_merge_owners() {
+        let game = Game::new(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=6),
+        )
+        .unwrap();
+
+        // `1 2 4` and `2 3 4` become identical, and so do `1 2 5` and `2 3 5`
+        let merged = game.merge_owners(&OwnerSet::from_iter([1, 3]), OwnerId(0));
+        assert_eq!(
+            dnf!(0 2 4 + 0 2 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            merged.dnf
+        );
+        assert_eq!(OwnerSet::from_iter([0, 2, 4, 5, 6]), merged.owner_set);
+        assert_eq!(Ok(()), merged.validate());
+
+        // `1 2 5` and `2 3 5` are absorbed by `1 2` and `2 3`
+        let merged = game.merge_owners(&OwnerSet::from_iter([2, 4]), OwnerId(2));
+        assert_eq!(
+            dnf!(1 2 + 2 3 + 2 5).map_variable(|id| OwnerId(*id)),
+            merged.dnf
+        );
+        assert_eq!(OwnerSet::from_iter([1, 2, 3, 5, 6]), merged.owner_set);
+        assert_eq!(Ok(()), merged.validate());
+
+        // the whole owner set
+        let merged = game.merge_owners(&game.owner_set, OwnerId(1));
+        assert_eq!(dnf!(1).map_variable(|id| OwnerId(*id)), merged.dnf);
+        assert_eq!(OwnerSet::from_iter([1]), merged.owner_set);
+    }
+
+    #[test]
+    #[should_panic(expected = "already in the game")]
+    fn test_merge_owners_conflict() {
+        let game = Game::from_dnf(dnf!(1 2 + 3).map_variable(|id| OwnerId(*id)));
+        game.merge_owners(&OwnerSet::from_iter([1, 2]), OwnerId(3));
+    }
+
+    #[test]
+    fn test_random() {
+        let game = Game::random(20, 30, 2..5, 42);
+        assert_eq!(Ok(()), game.validate());
+        assert_eq!(OwnerSet::from_iter(0..20), game.owner_set);
+        assert!(!game.dnf.is_empty() && game.dnf.len() <= 30);
+        assert!(game.dnf.iter().all(|imp| (2..5).contains(&imp.len())));
+
+        // reproducible from the seed
+        assert_eq!(game, Game::random(20, 30, 2..5, 42));
+        assert_ne!(game, Game::random(20, 30, 2..5, 43));
+
+        // implicants larger than the owner set are capped
+        let game = Game::random(3, 10, 2..10, 0);
+        assert_eq!(Ok(()), game.validate());
+        assert!(game.dnf.iter().all(|imp| (2..=3).contains(&imp.len())));
+
+        let game = Game::random(5, 0, 1..2, 0);
+        assert!(game.dnf.is_empty());
+        assert_eq!(5, game.owner_len());
+    }
+
+    #[test]
+    fn test_swing_counts() {
+        let exps = [
+            dnf!(1 2 + 1 3 + 2 3),
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5),
+            dnf!(1 3 6 8 + 3 5 6 8 + 3 4 6 8 9),
+            dnf!(1 2 + 1 3 + 4),
+        ];
+        for exp in exps {
+            let mut game = Game::from_dnf(exp.map_variable(|id| OwnerId(*id)));
+            game.owner_set.insert(OwnerId(100));
+            let expect = cal_banzhaf_swings_exact(&game);
+            let actual = game.swing_counts();
+            assert_eq!(game.owner_len(), actual.len());
+            for (o, swings) in actual {
+                assert_eq!(expect[&o], swings as f64);
+            }
+        }
+
+        // beyond the enumeration, with 77 null owners
+        let game = Game::new(
+            dnf!(1 2 + 3).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter(1..=80),
+        )
+        .unwrap();
+        let swings = game.swing_counts();
+        assert_eq!(1 << 77, swings[&OwnerId(1)]);
+        assert_eq!(3 << 77, swings[&OwnerId(3)]);
+        assert_eq!(0, swings[&OwnerId(80)]);
+    }
+
+    #[test]
+    fn test_expected_sv_sum() {
+        let game = Game::from_dnf(dnf!(1 2 + 3).map_variable(|id| OwnerId(*id)));
+        assert_eq!(1., game.expected_sv_sum());
+        assert_eq!(Ok(()), cal_sv_exact(&game).check_efficiency(1., 1e-9));
+
+        for dnf in [Dnf::false_exp(), Dnf::true_exp()] {
+            let game = Game {
+                dnf,
+                owner_set: OwnerSet::from_iter([1, 2]),
+            };
+            assert_eq!(0., game.expected_sv_sum());
+            assert_f64_eq(0., cal_sv_recursive_decompose(&game).sum());
+            assert_eq!(
+                Ok(()),
+                cal_sv_recursive_decompose(&game).check_efficiency(game.expected_sv_sum(), 1e-9)
+            );
+        }
+    }
+
+    #[test]
+    fn test_bloc_power() {
+        let game = Game::new(
+            dnf!(1 2 + 1 3 + 2 3).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter([1, 2, 3, 4]),
+        )
+        .unwrap();
+        let bloc = game
+            .bloc_power(&BTreeSet::from([OwnerId(1), OwnerId(2)]))
+            .unwrap();
+        assert_f64_eq(2. / 3., bloc.power);
+        assert_f64_eq(2. / 3., bloc.fraction);
+
+        // the null player adds nothing
+        let bloc = game
+            .bloc_power(&BTreeSet::from([OwnerId(1), OwnerId(4)]))
+            .unwrap();
+        assert_f64_eq(1. / 3., bloc.power);
+
+        let sv = cal_sv_exact(&game);
+        let members = OwnerSet::from_iter([1, 2, 3, 4]).0;
+        assert_f64_eq(sv.sum(), game.bloc_power(&members).unwrap().power);
+
+        assert_eq!(
+            Err(GameError::UnknownMembers(vec![OwnerId(5)])),
+            game.bloc_power(&BTreeSet::from([OwnerId(1), OwnerId(5)]))
+        );
+
+        // nobody has any power if no coalition wins
+        let game = Game {
+            dnf: Dnf::new(),
+            owner_set: OwnerSet::from_iter([1, 2]),
+        };
+        let bloc = game.bloc_power(&BTreeSet::from([OwnerId(1)])).unwrap();
+        assert_eq!(
+            BlocPower {
+                power: 0.,
+                fraction: 0.
+            },
+            bloc
+        );
+    }
+
+    #[test]
+    fn test_value() {
+        let games = [
+            Game::from_dnf(
+                dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+            ),
+            Game::new(
+                dnf!(1 2 + 1 3 + 4).map_variable(|id| OwnerId(*id)),
+                OwnerSet::from_iter(1..=5),
+            )
+            .unwrap(),
+            // not minimized
+            Game {
+                dnf: dnf!(1 2 + 1 2 3 + 3 4).map_variable(|id| OwnerId(*id)),
+                owner_set: OwnerSet::from_iter(1..=4),
+            },
+        ];
+        for game in games {
+            let mwcs = game.minimal_winning_coalitions();
+            for coalition in game.owner_set.iter().copied().powerset() {
+                let coalition = OwnerSet::from_iter(coalition);
+                let expect = game.dnf.iter().any(|imp| imp.is_subset(&coalition));
+                assert_eq!(expect, game.value(&coalition));
+                assert_eq!(expect, game.is_winning(&coalition));
+                assert_eq!(if expect { 1. } else { 0. }, game.value01(&coalition));
+                assert_eq!(
+                    mwcs.contains(&coalition),
+                    game.is_minimal_winning(&coalition),
+                    "{coalition:?}"
+                );
+            }
+        }
+
+        let game = Game::from_dnf(dnf!(1 2 + 1 2 3 + 3 4).map_variable(|id| OwnerId(*id)));
+        assert!(game.is_minimal_winning(&OwnerSet::from_iter([1, 2])));
+        assert!(!game.is_minimal_winning(&OwnerSet::from_iter([1, 2, 3])));
+        assert!(!game.is_minimal_winning(&OwnerSet::from_iter([1, 3])));
+        assert!(!game.is_minimal_winning(&OwnerSet::default()));
+    }
+
+    #[test]
+    fn test_minimal_winning_coalitions() {
+        let game = Game::from_dnf(dnf!(1 2 + 1 2 3 + 3 4).map_variable(|id| OwnerId(*id)));
+        assert_eq!(
+            vec![OwnerSet::from_iter([1, 2]), OwnerSet::from_iter([3, 4])],
+            game.minimal_winning_coalitions()
+        );
+    }
+
+    #[test]
+    fn test_dummy_players() {
+        let game = Game::new(
+            dnf!(1 2 + 1 2 3 + 2 4).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter([1, 2, 3, 4, 5]),
+        );
+        assert_eq!(
+            Err(GameError::RedundantImplicant(vec![
+                OwnerId(1),
+                OwnerId(2),
+                OwnerId(3)
+            ])),
+            game
+        );
+
+        let game = Game {
+            dnf: dnf!(1 2 + 1 2 3 + 2 4).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([1, 2, 3, 4, 5]),
+        };
+        let dummy_players = game.dummy_players();
+        assert_eq!(BTreeSet::from([OwnerId(3), OwnerId(5)]), dummy_players);
+
+        let sv = cal_sv_exact(&game);
+        for owner in &dummy_players {
+            assert_eq!(0., sv[owner]);
+        }
+
+        let game = Game::from_dnf(
+            dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id)),
+        );
+        assert!(game.dummy_players().is_empty());
+    }
+
+    #[test]
+    fn test_null_players() {
+        // 1 absorbs 1 2
+        let game = Game {
+            dnf: dnf!(1 2 + 1).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([1, 2]),
+        };
+        assert_eq!(BTreeSet::from([OwnerId(2)]), game.null_players());
+        assert_eq!(BTreeSet::from([OwnerId(1)]), game.veto_players());
+        assert_eq!(Some(OwnerId(1)), game.dictator());
+
+        // all three at once: 1 is a dictator and thus the only veto player, and the others are
+        // null players
+        let game = Game {
+            dnf: dnf!(1 + 1 2 + 1 3 4).map_variable(|id| OwnerId(*id)),
+            owner_set: OwnerSet::from_iter([1, 2, 3, 4, 5]),
+        };
+        let null_players = game.null_players();
+        assert_eq!(OwnerSet::from_iter([2, 3, 4, 5]).0, null_players);
+        assert_eq!(BTreeSet::from([OwnerId(1)]), game.veto_players());
+        assert_eq!(Some(OwnerId(1)), game.dictator());
+
+        let sv = cal_sv_recursive_decompose(&game);
+        assert_eq!(1., sv[&OwnerId(1)]);
+        for owner in &null_players {
+            assert_eq!(0., sv[owner]);
+        }
+
+        // veto players without a dictator, and a null player outside the DNF
+        let game = Game::new(
+            dnf!(1 2 3 + 1 2 4).map_variable(|id| OwnerId(*id)),
+            OwnerSet::from_iter([1, 2, 3, 4, 5]),
+        )
+        .unwrap();
+        assert_eq!(BTreeSet::from([OwnerId(5)]), game.null_players());
+        assert_eq!(
+            BTreeSet::from([OwnerId(1), OwnerId(2)]),
+            game.veto_players()
+        );
+        assert_eq!(None, game.dictator());
+    }
+
+    #[test]
+    fn test_general_game() {
+        // x1 and not x2
+        let exp = "1 !2"
+            .parse::<GeneralDnf<u32>>()
+            .unwrap()
+            .map_variable(|l| l.map(OwnerId));
+        assert_eq!(
+            Err(GameError::UnknownOwners(vec![OwnerId(2)])),
+            GeneralGame::new(exp.clone(), OwnerSet::from_iter([1]))
+        );
+        let game = GeneralGame::new(exp, OwnerSet::from_iter([1, 2])).unwrap();
+        let winning: Vec<_> = [vec![], vec![1], vec![2], vec![1, 2]]
+            .into_iter()
+            .map(|c| game.is_winning(&c.into_iter().map(OwnerId).collect()))
+            .collect();
+        assert_eq!(vec![false, true, false, false], winning);
+        assert_eq!(None, game.to_game());
+
+        let simple = Game::from_dnf(dnf!(1 2 + 3).map_variable(|id| OwnerId(*id)));
+        assert_eq!(Some(&simple), GeneralGame::from(&simple).to_game().as_ref());
+    }
+
+    #[test]
+    fn test_minimized_sv() {
+        // 1 2 + 3 4 + 5 + 6 7 8 with 6 absorbed implicants
+        let exp = dnf!(1 2 + 3 4 + 5 + 6 7 8 + 1 2 3 + 1 2 4 6 + 3 4 5 + 5 6 + 5 7 8 + 3 4 6 7 8)
+            .map_variable(|id| OwnerId(*id));
+        assert_eq!(10, exp.len());
+        let minimized = exp.minimized();
+        assert_eq!(
+            dnf!(1 2 + 3 4 + 5 + 6 7 8).map_variable(|id| OwnerId(*id)),
+            minimized
+        );
+
+        let expect = cal_sv_brute_force(&Game::from_dnf(exp));
+        let actual = cal_sv_recursive_decompose(&Game::from_dnf(minimized));
+        assert_eq!(expect.len(), actual.len());
+        for (o, u) in &actual {
+            assert_f64_eq(expect[o], *u);
+        }
+    }
+
+    #[test]
+    fn test_symmetry_classes() {
+        // not `Game::new`, which rejects an absorbed implicant and a game that never wins
+        let classes = |exp: Dnf<i32>, owners: &[u32]| {
+            Game {
+                dnf: exp.map_variable(|id| OwnerId(*id as u32)),
+                owner_set: OwnerSet::from_iter(owners.iter().copied()),
+            }
+            .symmetry_classes()
+            .into_iter()
+            .map(|c| c.into_iter().map(|o| o.0).collect())
+            .collect::<Vec<BTreeSet<_>>>()
+        };
+        let set = |owners: &[u32]| BTreeSet::from_iter(owners.iter().copied());
+
+        assert_eq!(
+            vec![set(&[1, 2, 3]), set(&[4, 5])],
+            classes(dnf!(1 4 5 + 2 4 5 + 3 4 5), &[1, 2, 3, 4, 5])
+        );
+        assert_eq!(
+            vec![set(&[1, 3]), set(&[2])],
+            classes(dnf!(1 2 + 2 3), &[1, 2, 3])
+        );
+        // 1 and 4 have the same value by swapping 1 with 4 and 2 with 3 at once, but are not
+        // interchangeable alone
+        assert_eq!(
+            vec![set(&[1]), set(&[2]), set(&[3]), set(&[4])],
+            classes(dnf!(1 2 + 2 3 + 3 4), &[1, 2, 3, 4])
+        );
+        assert_eq!(
+            vec![set(&[1, 4]), set(&[2, 3])],
+            classes(dnf!(1 2 + 1 3 + 2 4 + 3 4), &[1, 2, 3, 4])
+        );
+        // null players, including the absorbed 3, and a symmetric majority game
+        assert_eq!(
+            vec![set(&[1, 2, 4]), set(&[3, 5, 6])],
+            classes(dnf!(1 2 + 1 4 + 2 4 + 1 2 3), &[1, 2, 3, 4, 5, 6])
+        );
+        assert!(classes(dnf!(false), &[]).is_empty());
+
+        let game = Game::from_dnf(dnf!(1 4 5 + 2 4 5 + 3 4 5 + 6).map_variable(|id| OwnerId(*id)));
+        let sv = cal_sv_recursive_decompose(&game);
+        for class in game.symmetry_classes() {
+            let first = class.first().unwrap();
+            for owner in &class {
+                assert_f64_eq(sv[first], sv[owner]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_veto_players_and_dictator() {
+        let game = Game::from_dnf(dnf!(1 2 3).map_variable(|id| OwnerId(*id)));
+        assert_eq!(
+            BTreeSet::from([OwnerId(1), OwnerId(2), OwnerId(3)]),
+            game.veto_players()
+        );
+        assert_eq!(None, game.dictator());
+
+        let game = Game::from_dnf(dnf!(1 + 1 2).map_variable(|id| OwnerId(*id)));
+        assert_eq!(BTreeSet::from([OwnerId(1)]), game.veto_players());
+        assert_eq!(Some(OwnerId(1)), game.dictator());
+
+        let game = Game::from_dnf(dnf!(1 2 + 1 3).map_variable(|id| OwnerId(*id)));
+        assert_eq!(BTreeSet::from([OwnerId(1)]), game.veto_players());
+        assert_eq!(None, game.dictator());
+
+        let game = Game::from_dnf(dnf!(1 2 + 1 3 + 2 3).map_variable(|id| OwnerId(*id)));
+        assert!(game.veto_players().is_empty());
+        assert_eq!(None, game.dictator());
+    }
+
+    #[test]
+    fn test_from_weighted_voting() {
+        let weights = [(OwnerId(1), 4), (OwnerId(2), 2), (OwnerId(3), 1)];
+        let game = Game::from_weighted_voting(&weights, 4);
+        assert_eq!(dnf!(1).map_variable(|id| OwnerId(*id)), game.dnf);
+        assert_eq!(OwnerSet::from_iter([1, 2, 3]), game.owner_set);
+        assert_eq!(Some(OwnerId(1)), game.dictator());
+
+        let game = Game::from_weighted_voting(&weights, 5);
+        assert_eq!(dnf!(1 2 + 1 3).map_variable(|id| OwnerId(*id)), game.dnf);
+
+        let game = Game::from_weighted_voting(&weights, 6);
+        assert_eq!(dnf!(1 2).map_variable(|id| OwnerId(*id)), game.dnf);
+        assert_eq!(BTreeSet::from([OwnerId(3)]), game.dummy_players());
+
+        let weights = [
+            (OwnerId(1), 2),
+            (OwnerId(2), 2),
+            (OwnerId(3), 2),
+            (OwnerId(4), 0),
+        ];
+        let game = Game::from_weighted_voting(&weights, 4);
+        assert_eq!(
+            dnf!(1 2 + 1 3 + 2 3).map_variable(|id| OwnerId(*id)),
+            game.dnf
+        );
+        assert_eq!(Ok(()), game.validate());
+
+        // the total weight is below the quota
+        let game = Game::from_weighted_voting(&weights, 7);
+        assert!(game.dnf.is_false());
+        assert_eq!(OwnerSet::from_iter([1, 2, 3, 4]), game.owner_set);
+        assert_eq!(Err(GameError::EmptyDnf), game.validate());
+
+        let game = Game::from_weighted_voting(&weights, 0);
+        assert!(game.dnf.is_true());
+    }
+
+    #[test]
+    fn test_parse_implicant_lines() {
+        let game =
+            Game::parse_implicant_lines("# hybrid\n1 2 4\n1 2 5\n2 3 4\n\n2 3 5\n4 5\n").unwrap();
+        assert_eq!(
+            Game::from_dnf(
+                dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id))
+            ),
+            game
+        );
+
+        let game = Game::parse_implicant_lines("1 2 4 + 1 2 5\n2 3 4 + 2 3 5 + 4 5").unwrap();
+        assert_eq!(
+            Game::from_dnf(
+                dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5).map_variable(|id| OwnerId(*id))
+            ),
+            game
+        );
+
+        let game = Game::parse_implicant_lines("owners: 1 2 3\n1 2").unwrap();
+        assert_eq!(OwnerSet::from_iter([1, 2, 3]), game.owner_set);
+
+        assert!(Game::parse_implicant_lines("1 2\nowners: 1 2").is_err());
+        assert!(Game::parse_implicant_lines("1 x").is_err());
+        assert!(Game::parse_implicant_lines("owners: 1\n1 2").is_err());
+        assert!(Game::parse_implicant_lines("1 2\n1 2 3").is_err());
+        assert!(Game::parse_implicant_lines("1 2 + + 3").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Random games for benchmarking and fuzzing.
+
+use crate::{Dnf, Game, OwnerId};
+use rand::prelude::*;
+use std::{iter, ops::Range};
+
+/// How the implicants of a random game are laid out among the owners.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Structure {
+    /// Implicants of any owners.
+    #[default]
+    Random,
+    /// The owners are split into this many blocks of about the same size, and every implicant is
+    /// within a block. The game is an OR of the independent games of the blocks, which decomposes
+    /// well.
+    Blocks(usize),
+}
+
+/// Generator of random games among owners `0..num_owners`, whose DNFs are minimized, so they may
+/// end up with fewer than `num_implicants` implicants when some of them are duplicated or
+/// absorbed. Owners in no implicant are null players.
+///
+/// The same seed always gives the same games.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameGenerator {
+    pub num_owners: usize,
+    pub num_implicants: usize,
+    /// The sizes of the implicants are uniform in the range, capped by the size of a block.
+    pub implicant_size: Range<usize>,
+    pub structure: Structure,
+    pub seed: u64,
+}
+
+impl Default for GameGenerator {
+    fn default() -> Self {
+        Self {
+            num_owners: 10,
+            num_implicants: 10,
+            implicant_size: 1..4,
+            structure: Structure::Random,
+            seed: 0,
+        }
+    }
+}
+
+impl GameGenerator {
+    /// A game from `seed`.
+    pub fn generate(&self) -> Game {
+        self.generate_with(&mut StdRng::seed_from_u64(self.seed))
+    }
+
+    /// An endless sequence of different games from `seed`.
+    pub fn games(&self) -> impl Iterator<Item = Game> + '_ {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        iter::repeat_with(move || self.generate_with(&mut rng))
+    }
+
+    /// A game from `rng`, ignoring `seed`.
+    ///
+    /// Panic if `implicant_size` is empty or contains 0, there are implicants but no owners, or
+    /// the number of blocks is 0 or larger than the number of owners.
+    pub fn generate_with(&self, rng: &mut impl Rng) -> Game {
+        let size = &self.implicant_size;
+        assert!(
+            size.start > 0 && !size.is_empty(),
+            "invalid implicant size range {size:?}"
+        );
+        let n = self.num_owners;
+        assert!(
+            n > 0 || self.num_implicants == 0,
+            "no owner for the implicants"
+        );
+
+        let owners: Vec<OwnerId> = (0..n as u32).map(OwnerId).collect();
+        let blocks: Vec<&[OwnerId]> = match self.structure {
+            Structure::Random => vec![&owners[..]],
+            Structure::Blocks(m) => {
+                assert!(m > 0 && m <= n, "cannot split {n} owners into {m} blocks");
+                (0..m)
+                    .map(|i| &owners[i * n / m..(i + 1) * n / m])
+                    .collect()
+            }
+        };
+
+        let mut dnf: Dnf<OwnerId> = (0..self.num_implicants)
+            .map(|_| {
+                let block = blocks[rng.gen_range(0..blocks.len())];
+                let len =
+                    rng.gen_range(size.start.min(block.len())..=(size.end - 1).min(block.len()));
+                block.choose_multiple(rng, len).copied().collect()
+            })
+            .collect();
+        dnf.minimize();
+        Game {
+            dnf,
+            owner_set: owners.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alg::{
+            exact::cal_sv_brute_force,
+            synthesis_sv::recursive_decompose_ablation::{
+                cal_sv_recursive_decompose_ablation, AblationType,
+            },
+        },
+        tests::assert_f64_eq,
+        OwnerSet,
+    };
+
+    #[test]
+    fn test_generate() {
+        let gen = GameGenerator {
+            num_owners: 20,
+            num_implicants: 30,
+            implicant_size: 2..5,
+            ..Default::default()
+        };
+        let game = gen.generate();
+        assert_eq!(Ok(()), game.validate());
+        assert_eq!(OwnerSet::from_iter(0..20), game.owner_set);
+        assert!(game.dnf.iter().all(|imp| (2..5).contains(&imp.len())));
+
+        assert_eq!(game, gen.generate());
+        let games: Vec<_> = gen.games().take(2).collect();
+        assert_eq!(games, gen.games().take(2).collect::<Vec<_>>());
+        assert_ne!(games[0], games[1]);
+        let other = GameGenerator { seed: 1, ..gen };
+        assert_ne!(game, other.generate());
+    }
+
+    #[test]
+    fn test_blocks() {
+        let gen = GameGenerator {
+            num_owners: 9,
+            num_implicants: 20,
+            implicant_size: 2..10,
+            structure: Structure::Blocks(3),
+            ..Default::default()
+        };
+        for game in gen.games().take(10) {
+            assert_eq!(Ok(()), game.validate());
+            for imp in game.dnf.iter() {
+                // blocks of owners 0..3, 3..6 and 6..9
+                let blocks: Vec<_> = imp.iter().map(|o| o.0 / 3).collect();
+                assert!(blocks.iter().all(|b| *b == blocks[0]), "{imp}");
+                assert!((2..=3).contains(&imp.len()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for seed in 0..100 {
+            let num_owners = rng.gen_range(1..=10);
+            let gen = GameGenerator {
+                num_owners,
+                num_implicants: rng.gen_range(1..=8),
+                implicant_size: 1..5,
+                structure: if seed % 2 == 0 {
+                    Structure::Random
+                } else {
+                    Structure::Blocks(rng.gen_range(1..=num_owners))
+                },
+                seed,
+            };
+            let game = gen.generate();
+            let expect = cal_sv_brute_force(&game);
+            for ablation_type in [
+                AblationType::NoHorizontal,
+                AblationType::NoVertical,
+                AblationType::NoHybrid,
+            ] {
+                let actual = cal_sv_recursive_decompose_ablation(&game, ablation_type);
+                assert_eq!(expect.len(), actual.len());
+                for (o, u) in &actual {
+                    assert_f64_eq(expect[o], *u);
+                }
+            }
+        }
+    }
+}
@@ -1,33 +1,107 @@
+//! Shapley values of simple games by recursive decomposition.
+//!
+//! `ShapleyComputer` computes the Shapley values of a `Game` with any of the options.
+//!
+//! Features, of which `parallel` and `data` are on by default:
+//!
+//! - `parallel`: compute with rayon. Without it everything runs on the calling thread through
+//!   the sequential stand-ins of `par`, e.g., for `wasm32-unknown-unknown`.
+//! - `data`: load the datasets of the experiments and generate their games with polars, which is
+//!   by far the largest dependency.
+//! - `ffi`: C API in `ffi`, with a header generated by cbindgen.
+//! - `bigint`: IE coefficients in arbitrary precision, which never overflow.
+//! - `check-efficiency`: assert that the values sum up to 1 in debug builds.
+//! - `serde-index`: save and load decomposition trees with `SvIndex`.
+
+// proptest-derive 0.3 implements `Arbitrary` inside a constant
+#![cfg_attr(test, allow(non_local_definitions))]
+
+// wasm32-unknown-unknown has no threads nor filesystem, so rayon would panic when spawning its
+// workers and polars does not build. See the `wasm` crate, built without default features.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", feature = "parallel"))]
+compile_error!("the `parallel` feature needs threads; build with `default-features = false`");
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", feature = "data"))]
+compile_error!("the `data` feature needs polars; build with `default-features = false`");
+
+// only the loading of datasets logs
+#[cfg(feature = "data")]
 #[macro_use]
 extern crate tracing;
 
+use num_rational::BigRational;
+use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
 
 pub mod alg;
 pub mod dnf;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod game;
+pub mod gen;
 pub mod owner;
+pub mod par;
 pub mod product_tree;
+pub mod shapley_values;
 pub mod union_combination;
 pub mod utils;
 
+#[cfg(feature = "data")]
 pub mod table;
+#[cfg(feature = "data")]
 pub use table::*;
 
+#[cfg(feature = "data")]
 pub mod dataset;
+#[cfg(feature = "data")]
 pub use dataset::*;
 
+#[cfg(feature = "data")]
 pub mod join_plan;
+#[cfg(feature = "data")]
 pub use join_plan::*;
 
 #[cfg(test)]
 pub(crate) mod tests;
 
-pub use dnf::Dnf;
-pub use game::Game;
-pub use owner::{OwnerId, OwnerSet};
-pub type ShapleyValues = HashMap<OwnerId, f64>;
+pub use alg::synthesis_sv::{ShapleyComputer, SvError, SvOptions};
+pub use dnf::{Cnf, Dnf};
+pub use game::{Game, GameError, GeneralGame};
+pub use owner::{InternedGame, OwnerId, OwnerInterner, OwnerSet};
+pub use shapley_values::{BlocPower, ShapleyValues, ShapleyValuesF32};
+pub type BanzhafValues<O = OwnerId> = HashMap<O, f64>;
+
+/// Shapley values in exact rational numbers, ordered by owners.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    derive_more::Deref,
+    derive_more::DerefMut,
+    derive_more::From,
+    derive_more::IntoIterator,
+)]
+pub struct ExactShapleyValues(pub BTreeMap<OwnerId, BigRational>);
+
+impl ExactShapleyValues {
+    /// Round the values to the nearest `f64`.
+    pub fn to_f64(&self) -> ShapleyValues {
+        self.iter()
+            .map(|(owner_id, v)| (*owner_id, v.to_f64().unwrap_or(f64::NAN)))
+            .collect()
+    }
+}
+
+impl FromIterator<(OwnerId, BigRational)> for ExactShapleyValues {
+    fn from_iter<T: IntoIterator<Item = (OwnerId, BigRational)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct SVResult {
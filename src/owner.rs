@@ -1,5 +1,13 @@
+use crate::{
+    alg::synthesis_sv::recursive_decompose::cal_sv_recursive_decompose,
+    dnf::{Dnf, Var},
+    Game, GameError, ShapleyValues,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::{
+    collections::{BTreeSet, HashMap},
+    hash::Hash,
+};
 
 #[derive(
     Debug,
@@ -63,3 +71,263 @@ impl FromIterator<OwnerId> for OwnerSet {
         Self(iter.into_iter().collect())
     }
 }
+
+impl Dnf<OwnerId> {
+    /// Drop the implicants with any owner outside `allowed`, i.e., the removed owners never join
+    /// a coalition, so the coalitions they were needed for are no longer winning.
+    ///
+    /// The result only refers to owners in `allowed` and is minimized if `self` is.
+    pub fn restrict_to(&self, allowed: &OwnerSet) -> Dnf<OwnerId> {
+        self.partial_exp_complement(&self.disallowed(allowed))
+    }
+
+    /// Project the owners outside `allowed` out of the implicants, i.e., the removed owners are
+    /// always present, so the rest of an implicant suffices on its own. Unlike `restrict_to`, the
+    /// winning coalitions only grow, and the result is TRUE if an implicant only has removed
+    /// owners.
+    ///
+    /// The result is minimized.
+    pub fn project_to(&self, allowed: &OwnerSet) -> Dnf<OwnerId> {
+        self.partial_eval(&self.disallowed(allowed), true)
+    }
+
+    fn disallowed(&self, allowed: &OwnerSet) -> BTreeSet<OwnerId> {
+        self.all_variables()
+            .into_iter()
+            .filter(|o| !allowed.contains(o))
+            .collect()
+    }
+}
+
+/// Map owners of an arbitrary type `O` (e.g., string names) to dense `OwnerId`s and back.
+#[derive(Debug, Clone)]
+pub struct OwnerInterner<O> {
+    ids: HashMap<O, OwnerId>,
+    owners: Vec<O>,
+}
+
+impl<O> Default for OwnerInterner<O> {
+    fn default() -> Self {
+        Self {
+            ids: HashMap::new(),
+            owners: Vec::new(),
+        }
+    }
+}
+
+impl<O: Var + Hash> OwnerInterner<O> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.owners.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.owners.is_empty()
+    }
+
+    /// Return the id of `owner`, assigning the next unused id if it is new.
+    pub fn intern(&mut self, owner: &O) -> OwnerId {
+        if let Some(id) = self.ids.get(owner) {
+            return *id;
+        }
+        let id = OwnerId(self.owners.len() as u32);
+        self.ids.insert(owner.clone(), id);
+        self.owners.push(owner.clone());
+        id
+    }
+
+    pub fn id(&self, owner: &O) -> Option<OwnerId> {
+        self.ids.get(owner).copied()
+    }
+
+    pub fn owner(&self, id: OwnerId) -> Option<&O> {
+        self.owners.get(id.0 as usize)
+    }
+
+    /// Intern all the variables of `exp` and return the same DNF over their ids.
+    pub fn intern_dnf(&mut self, exp: &Dnf<O>) -> Dnf<OwnerId> {
+        for owner in exp.all_variables() {
+            self.intern(&owner);
+        }
+        exp.map_variable(|owner| self.ids[owner])
+    }
+
+    /// Intern a game among `owners`, where the coalitions satisfying `exp` are winning.
+    pub fn intern_game(
+        &mut self,
+        exp: &Dnf<O>,
+        owners: impl IntoIterator<Item = O>,
+    ) -> Result<Game, GameError> {
+        let dnf = self.intern_dnf(exp);
+        let owner_set = owners.into_iter().map(|o| self.intern(&o)).collect();
+        Game::new(dnf, owner_set)
+    }
+
+    /// Relabel the keys of per-owner values back to the original owners.
+    ///
+    /// Panic if some id is not assigned by this interner.
+    pub fn relabel<V>(&self, values: impl IntoIterator<Item = (OwnerId, V)>) -> HashMap<O, V> {
+        values
+            .into_iter()
+            .map(|(id, v)| (self.owners[id.0 as usize].clone(), v))
+            .collect()
+    }
+}
+
+/// A game among owners of an arbitrary type `O` (e.g., account names), interned to a `Game` over
+/// dense `OwnerId`s on which the algorithms run.
+#[derive(Debug, Clone)]
+pub struct InternedGame<O> {
+    game: Game,
+    interner: OwnerInterner<O>,
+}
+
+impl<O: Var + Hash> InternedGame<O> {
+    /// Create a game among `owners`, where the coalitions satisfying `exp` are winning.
+    pub fn new(exp: &Dnf<O>, owners: impl IntoIterator<Item = O>) -> Result<Self, GameError> {
+        let mut interner = OwnerInterner::new();
+        let game = interner.intern_game(exp, owners)?;
+        Ok(Self { game, interner })
+    }
+
+    /// Create a game among exactly the owners appearing in `exp`.
+    pub fn from_dnf(exp: &Dnf<O>) -> Result<Self, GameError> {
+        Self::new(exp, exp.all_variables())
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn interner(&self) -> &OwnerInterner<O> {
+        &self.interner
+    }
+
+    /// Compute per-owner values with `f` (e.g., `cal_sv_recursive_decompose`) keyed by the
+    /// original owners.
+    pub fn cal<V, R>(&self, f: impl FnOnce(&Game) -> R) -> HashMap<O, V>
+    where
+        R: IntoIterator<Item = (OwnerId, V)>,
+    {
+        self.interner.relabel(f(&self.game))
+    }
+
+    /// Shapley values by the recursive decomposition, keyed by the original owners.
+    pub fn cal_sv(&self) -> ShapleyValues<O> {
+        self.cal(cal_sv_recursive_decompose).into()
+    }
+}
+
+/// Compute per-owner values (e.g., with `cal_sv_recursive_decompose`) of a game among `owners`
+/// of an arbitrary type, where the coalitions satisfying `exp` are winning.
+pub fn cal_with_owners<O: Var + Hash, V, R>(
+    exp: &Dnf<O>,
+    owners: impl IntoIterator<Item = O>,
+    f: impl FnOnce(&Game) -> R,
+) -> Result<HashMap<O, V>, GameError>
+where
+    R: IntoIterator<Item = (OwnerId, V)>,
+{
+    Ok(InternedGame::new(exp, owners)?.cal(f))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{alg::exact::cal_sv_exact, dnf, tests::assert_f64_eq};
+
+    #[test]
+    fn test_restrict_to() {
+        let exp = dnf!(1 2 + 2 3 + 4).map_variable(|id| OwnerId(*id));
+
+        // owners 1 and 2 are gone, so are the implicants with them
+        let allowed = OwnerSet::from_iter([3, 4]);
+        let dropped = exp.restrict_to(&allowed);
+        assert_eq!(dnf!(4).map_variable(|id| OwnerId(*id)), dropped);
+        // owners 1 and 2 are always there, so 3 alone wins, and `1 2` becomes TRUE
+        let projected = exp.project_to(&allowed);
+        assert!(projected.is_true());
+
+        let allowed = OwnerSet::from_iter([2, 3, 4, 5]);
+        let dropped = exp.restrict_to(&allowed);
+        assert_eq!(dnf!(2 3 + 4).map_variable(|id| OwnerId(*id)), dropped);
+        let projected = exp.project_to(&allowed);
+        assert_eq!(dnf!(2 + 4).map_variable(|id| OwnerId(*id)), projected);
+
+        // the two semantics give different games
+        let game = Game::new(dropped, allowed.clone()).unwrap();
+        let sv = cal_sv_exact(&game);
+        assert_f64_eq(1. / 6., sv[&OwnerId(2)]);
+        let game = Game::new(projected, allowed).unwrap();
+        let sv = cal_sv_exact(&game);
+        assert_f64_eq(0.5, sv[&OwnerId(2)]);
+        assert_f64_eq(0., sv[&OwnerId(3)]);
+
+        // nothing is removed
+        let allowed = OwnerSet::from_iter(1..=4);
+        assert_eq!(exp, exp.restrict_to(&allowed));
+        assert_eq!(exp, exp.project_to(&allowed));
+    }
+
+    #[test]
+    fn test_interner() {
+        let mut interner = OwnerInterner::new();
+        assert_eq!(OwnerId(0), interner.intern(&"alice"));
+        assert_eq!(OwnerId(1), interner.intern(&"bob"));
+        assert_eq!(OwnerId(0), interner.intern(&"alice"));
+        assert_eq!(2, interner.len());
+        assert_eq!(Some(OwnerId(1)), interner.id(&"bob"));
+        assert_eq!(None, interner.id(&"carol"));
+        assert_eq!(Some(&"bob"), interner.owner(OwnerId(1)));
+        assert_eq!(None, interner.owner(OwnerId(2)));
+
+        let values = HashMap::from([(OwnerId(0), 1), (OwnerId(1), 2)]);
+        assert_eq!(
+            HashMap::from([("alice", 1), ("bob", 2)]),
+            interner.relabel(values)
+        );
+    }
+
+    #[test]
+    fn test_cal_with_owners() {
+        let exp =
+            Dnf::parse_with("alice bob + alice carol", |t| Ok::<_, ()>(t.to_string())).unwrap();
+        let owners = ["alice", "bob", "carol", "dave"].map(String::from);
+        let sv = cal_with_owners(&exp, owners, cal_sv_recursive_decompose).unwrap();
+        assert_eq!(4, sv.len());
+        assert_f64_eq(0.66666666666, sv["alice"]);
+        assert_f64_eq(0.16666666666, sv["bob"]);
+        assert_f64_eq(0.16666666666, sv["carol"]);
+        assert_f64_eq(0., sv["dave"]);
+
+        let owners = ["alice", "bob"].map(String::from);
+        assert_eq!(
+            Err(GameError::UnknownOwners(vec![OwnerId(2)])),
+            cal_with_owners(&exp, owners, cal_sv_recursive_decompose)
+        );
+    }
+
+    #[test]
+    fn test_interned_game() {
+        let exp = dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5);
+        let expect =
+            cal_sv_recursive_decompose(&Game::from_dnf(exp.map_variable(|id| OwnerId(*id))));
+
+        let game = InternedGame::from_dnf(&exp.map_variable(|id| format!("player{id}"))).unwrap();
+        assert_eq!(5, game.interner().len());
+        assert_eq!(5, game.game().owner_len());
+        let sv: ShapleyValues<String> = game.cal_sv();
+        assert_eq!(5, sv.len());
+        for (id, u) in expect {
+            assert_f64_eq(u, sv[&format!("player{id}")]);
+        }
+
+        let exp = exp.map_variable(|id| format!("player{id}"));
+        let owners = (1..=6).map(|id| format!("player{id}"));
+        let sv = InternedGame::new(&exp, owners).unwrap().cal(cal_sv_exact);
+        assert_f64_eq(0., sv["player6"]);
+    }
+}
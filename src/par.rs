@@ -0,0 +1,13 @@
+//! Parallel iterators and thread pools of rayon with the `parallel` feature, or sequential ones
+//! with the same API otherwise, e.g., for `wasm32-unknown-unknown`, which has no threads.
+//!
+//! The crate imports `crate::par::prelude::*` rather than `rayon::prelude::*`, so the algorithms
+//! are written once for both. The results are the same either way, as the reductions are exact.
+
+#[cfg(feature = "parallel")]
+pub use rayon::{prelude, ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+
+#[cfg(not(feature = "parallel"))]
+mod seq;
+#[cfg(not(feature = "parallel"))]
+pub use seq::*;
@@ -0,0 +1,470 @@
+//! Sequential stand-ins for the parts of rayon used by the crate, without the `parallel` feature.
+//!
+//! `SeqIter` wraps a plain iterator with the methods of rayon's parallel iterators, e.g., `reduce`
+//! with an identity and `fold` into partial results, so that calls written for rayon run in order
+//! on the calling thread. A thread pool runs its closures right away, and its options are ignored.
+
+use std::{error, fmt, iter, slice};
+
+pub mod prelude {
+    pub use super::{
+        IntoParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelBridge,
+        ParallelSlice, SeqIter,
+    };
+}
+
+/// A sequential iterator with the API of rayon's `ParallelIterator` and
+/// `IndexedParallelIterator`.
+#[derive(Debug, Clone)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct SeqIter<I>(I);
+
+impl<I: Iterator> SeqIter<I> {
+    pub fn map<R, F: FnMut(I::Item) -> R>(self, f: F) -> SeqIter<iter::Map<I, F>> {
+        SeqIter(self.0.map(f))
+    }
+
+    pub fn filter<P: FnMut(&I::Item) -> bool>(self, predicate: P) -> SeqIter<iter::Filter<I, P>> {
+        SeqIter(self.0.filter(predicate))
+    }
+
+    pub fn filter_map<R, F: FnMut(I::Item) -> Option<R>>(
+        self,
+        f: F,
+    ) -> SeqIter<iter::FilterMap<I, F>> {
+        SeqIter(self.0.filter_map(f))
+    }
+
+    pub fn flat_map<U: IntoIterator, F: FnMut(I::Item) -> U>(
+        self,
+        f: F,
+    ) -> SeqIter<iter::FlatMap<I, U, F>> {
+        SeqIter(self.0.flat_map(f))
+    }
+
+    pub fn flat_map_iter<U: IntoIterator, F: FnMut(I::Item) -> U>(
+        self,
+        f: F,
+    ) -> SeqIter<iter::FlatMap<I, U, F>> {
+        SeqIter(self.0.flat_map(f))
+    }
+
+    pub fn flatten(self) -> SeqIter<iter::Flatten<I>>
+    where
+        I::Item: IntoIterator,
+    {
+        SeqIter(self.0.flatten())
+    }
+
+    pub fn enumerate(self) -> SeqIter<iter::Enumerate<I>> {
+        SeqIter(self.0.enumerate())
+    }
+
+    pub fn zip<Z: IntoIterator>(self, other: Z) -> SeqIter<iter::Zip<I, Z::IntoIter>> {
+        SeqIter(self.0.zip(other))
+    }
+
+    pub fn chain<C: IntoIterator<Item = I::Item>>(
+        self,
+        other: C,
+    ) -> SeqIter<iter::Chain<I, C::IntoIter>> {
+        SeqIter(self.0.chain(other))
+    }
+
+    pub fn cloned<'a, T: 'a + Clone>(self) -> SeqIter<iter::Cloned<I>>
+    where
+        I: Iterator<Item = &'a T>,
+    {
+        SeqIter(self.0.cloned())
+    }
+
+    pub fn copied<'a, T: 'a + Copy>(self) -> SeqIter<iter::Copied<I>>
+    where
+        I: Iterator<Item = &'a T>,
+    {
+        SeqIter(self.0.copied())
+    }
+
+    /// Items in `Vec`s of `chunk_size`, except the last one which may be shorter.
+    pub fn chunks(self, chunk_size: usize) -> SeqIter<Chunks<I>> {
+        assert!(chunk_size != 0, "chunk_size must not be zero");
+        SeqIter(Chunks {
+            iter: self.0,
+            chunk_size,
+        })
+    }
+
+    pub fn with_min_len(self, _min: usize) -> Self {
+        self
+    }
+
+    pub fn with_max_len(self, _max: usize) -> Self {
+        self
+    }
+
+    pub fn for_each<F: FnMut(I::Item)>(self, f: F) {
+        self.0.for_each(f)
+    }
+
+    /// Stop at the first error, which is the error of rayon if there is a single one.
+    pub fn try_for_each<E, F: FnMut(I::Item) -> Result<(), E>>(mut self, f: F) -> Result<(), E> {
+        self.0.try_for_each(f)
+    }
+
+    pub fn count(self) -> usize {
+        self.0.count()
+    }
+
+    pub fn sum<S: iter::Sum<I::Item>>(self) -> S {
+        self.0.sum()
+    }
+
+    pub fn product<P: iter::Product<I::Item>>(self) -> P {
+        self.0.product()
+    }
+
+    pub fn min(self) -> Option<I::Item>
+    where
+        I::Item: Ord,
+    {
+        self.0.min()
+    }
+
+    pub fn max(self) -> Option<I::Item>
+    where
+        I::Item: Ord,
+    {
+        self.0.max()
+    }
+
+    pub fn min_by_key<K: Ord, F: FnMut(&I::Item) -> K>(self, f: F) -> Option<I::Item> {
+        self.0.min_by_key(f)
+    }
+
+    pub fn max_by_key<K: Ord, F: FnMut(&I::Item) -> K>(self, f: F) -> Option<I::Item> {
+        self.0.max_by_key(f)
+    }
+
+    pub fn any<P: FnMut(I::Item) -> bool>(mut self, predicate: P) -> bool {
+        self.0.any(predicate)
+    }
+
+    pub fn all<P: FnMut(I::Item) -> bool>(mut self, predicate: P) -> bool {
+        self.0.all(predicate)
+    }
+
+    /// The first item satisfying `predicate`, as any of them is a valid result of rayon.
+    pub fn find_any<P: FnMut(&I::Item) -> bool>(mut self, predicate: P) -> Option<I::Item> {
+        self.0.find(predicate)
+    }
+
+    pub fn find_first<P: FnMut(&I::Item) -> bool>(mut self, predicate: P) -> Option<I::Item> {
+        self.0.find(predicate)
+    }
+
+    pub fn find_map_any<R, F: FnMut(I::Item) -> Option<R>>(mut self, f: F) -> Option<R> {
+        self.0.find_map(f)
+    }
+
+    pub fn find_map_first<R, F: FnMut(I::Item) -> Option<R>>(mut self, f: F) -> Option<R> {
+        self.0.find_map(f)
+    }
+
+    /// Fold all the items into `identity()` by `op`, as a single partial result.
+    pub fn reduce<ID, OP>(self, identity: ID, op: OP) -> I::Item
+    where
+        ID: Fn() -> I::Item,
+        OP: FnMut(I::Item, I::Item) -> I::Item,
+    {
+        self.0.fold(identity(), op)
+    }
+
+    /// Same as `reduce`, but stop at the first error of an item or of `op`.
+    pub fn try_reduce<T, E, ID, OP>(self, identity: ID, mut op: OP) -> Result<T, E>
+    where
+        I: Iterator<Item = Result<T, E>>,
+        ID: Fn() -> T,
+        OP: FnMut(T, T) -> Result<T, E>,
+    {
+        let mut acc = identity();
+        for item in self.0 {
+            acc = op(acc, item?)?;
+        }
+        Ok(acc)
+    }
+
+    pub fn reduce_with<OP: FnMut(I::Item, I::Item) -> I::Item>(self, op: OP) -> Option<I::Item> {
+        self.0.reduce(op)
+    }
+
+    /// Fold all the items into a single partial result, which rayon does per job.
+    pub fn fold<T, ID, F>(self, identity: ID, f: F) -> SeqIter<iter::Once<T>>
+    where
+        ID: Fn() -> T,
+        F: FnMut(T, I::Item) -> T,
+    {
+        SeqIter(iter::once(self.0.fold(identity(), f)))
+    }
+
+    pub fn collect<C: FromIterator<I::Item>>(self) -> C {
+        self.0.collect()
+    }
+
+    /// Collect into `target`, replacing its items.
+    pub fn collect_into_vec(self, target: &mut Vec<I::Item>) {
+        target.clear();
+        target.extend(self.0);
+    }
+
+    pub fn unzip<A, B, FromA, FromB>(self) -> (FromA, FromB)
+    where
+        I: Iterator<Item = (A, B)>,
+        FromA: Default + Extend<A>,
+        FromB: Default + Extend<B>,
+    {
+        self.0.unzip()
+    }
+
+    /// Items satisfying `predicate` in the first collection and the others in the second.
+    pub fn partition<A, B, P>(self, mut predicate: P) -> (A, B)
+    where
+        A: Default + Extend<I::Item>,
+        B: Default + Extend<I::Item>,
+        P: FnMut(&I::Item) -> bool,
+    {
+        let mut left = A::default();
+        let mut right = B::default();
+        for item in self.0 {
+            if predicate(&item) {
+                left.extend(iter::once(item));
+            } else {
+                right.extend(iter::once(item));
+            }
+        }
+        (left, right)
+    }
+}
+
+impl<I: Iterator> IntoIterator for SeqIter<I> {
+    type Item = I::Item;
+    type IntoIter = I;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0
+    }
+}
+
+/// Iterator of `SeqIter::chunks`.
+#[derive(Debug, Clone)]
+pub struct Chunks<I> {
+    iter: I,
+    chunk_size: usize,
+}
+
+impl<I: Iterator> Iterator for Chunks<I> {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk: Vec<_> = self.iter.by_ref().take(self.chunk_size).collect();
+        (!chunk.is_empty()).then_some(chunk)
+    }
+}
+
+pub trait IntoParallelIterator {
+    type Iter;
+    type Item;
+
+    fn into_par_iter(self) -> Self::Iter;
+}
+
+impl<T: IntoIterator> IntoParallelIterator for T {
+    type Iter = SeqIter<T::IntoIter>;
+    type Item = T::Item;
+
+    fn into_par_iter(self) -> Self::Iter {
+        SeqIter(self.into_iter())
+    }
+}
+
+pub trait IntoParallelRefIterator<'data> {
+    type Iter;
+    type Item: 'data;
+
+    fn par_iter(&'data self) -> Self::Iter;
+}
+
+impl<'data, I: 'data + ?Sized> IntoParallelRefIterator<'data> for I
+where
+    &'data I: IntoIterator,
+{
+    type Iter = SeqIter<<&'data I as IntoIterator>::IntoIter>;
+    type Item = <&'data I as IntoIterator>::Item;
+
+    fn par_iter(&'data self) -> Self::Iter {
+        SeqIter(self.into_iter())
+    }
+}
+
+pub trait IntoParallelRefMutIterator<'data> {
+    type Iter;
+    type Item: 'data;
+
+    fn par_iter_mut(&'data mut self) -> Self::Iter;
+}
+
+impl<'data, I: 'data + ?Sized> IntoParallelRefMutIterator<'data> for I
+where
+    &'data mut I: IntoIterator,
+{
+    type Iter = SeqIter<<&'data mut I as IntoIterator>::IntoIter>;
+    type Item = <&'data mut I as IntoIterator>::Item;
+
+    fn par_iter_mut(&'data mut self) -> Self::Iter {
+        SeqIter(self.into_iter())
+    }
+}
+
+pub trait ParallelBridge: Iterator + Sized {
+    fn par_bridge(self) -> SeqIter<Self> {
+        SeqIter(self)
+    }
+}
+
+impl<I: Iterator> ParallelBridge for I {}
+
+pub trait ParallelSlice<T> {
+    fn par_chunks(&self, chunk_size: usize) -> SeqIter<slice::Chunks<'_, T>>;
+}
+
+impl<T> ParallelSlice<T> for [T] {
+    fn par_chunks(&self, chunk_size: usize) -> SeqIter<slice::Chunks<'_, T>> {
+        SeqIter(self.chunks(chunk_size))
+    }
+}
+
+/// A "pool" running everything on the calling thread.
+#[derive(Debug, Default)]
+pub struct ThreadPool {
+    _private: (),
+}
+
+impl ThreadPool {
+    pub fn install<R, OP: FnOnce() -> R>(&self, op: OP) -> R {
+        op()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ThreadPoolBuilder {
+    _private: (),
+}
+
+impl ThreadPoolBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn num_threads(self, _num_threads: usize) -> Self {
+        self
+    }
+
+    pub fn build(self) -> Result<ThreadPool, ThreadPoolBuildError> {
+        Ok(ThreadPool::default())
+    }
+
+    /// Nothing to set up, as there is no global pool.
+    pub fn build_global(self) -> Result<(), ThreadPoolBuildError> {
+        Ok(())
+    }
+}
+
+/// Never returned, as building a sequential pool cannot fail.
+#[derive(Debug)]
+pub struct ThreadPoolBuildError {
+    _private: (),
+}
+
+impl fmt::Display for ThreadPoolBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to build the thread pool")
+    }
+}
+
+impl error::Error for ThreadPoolBuildError {}
+
+#[cfg(test)]
+mod tests {
+    use super::prelude::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_seq_iter() {
+        let v = vec![1, 2, 3, 4, 5];
+        assert_eq!(15, v.par_iter().sum::<i32>());
+        assert_eq!(
+            vec![(0, 2), (1, 4)],
+            v.par_iter()
+                .map(|x| x * 2)
+                .enumerate()
+                .filter(|(i, _)| *i < 2)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            20,
+            v.par_iter()
+                .map(|x| x + 1)
+                .with_min_len(2)
+                .reduce(|| 0, |a, b| a + b)
+        );
+        assert_eq!(
+            vec![vec![1, 2], vec![3, 4], vec![5]],
+            v.par_iter().copied().chunks(2).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![3, 7, 5],
+            v.par_chunks(2)
+                .map(|c| c.iter().sum::<i32>())
+                .collect::<Vec<_>>()
+        );
+        let (even, odd): (Vec<i32>, Vec<i32>) = v.clone().into_par_iter().partition(|x| x % 2 == 0);
+        assert_eq!((vec![2, 4], vec![1, 3, 5]), (even, odd));
+        assert_eq!(
+            vec![15],
+            (1..=5)
+                .into_par_iter()
+                .fold(|| 0, |a, b| a + b)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(3),
+            v.par_iter().find_map_any(|x| (*x > 2).then_some(*x))
+        );
+
+        let mut m = HashMap::from([(1, 1.), (2, 2.)]);
+        m.par_iter_mut().for_each(|(_, u)| *u /= 2.);
+        assert_eq!(1.5, m.par_iter().map(|(_, u)| u).sum::<f64>());
+        let mut out = vec![0];
+        (0..3)
+            .par_bridge()
+            .flat_map(|i| vec![i; i])
+            .collect_into_vec(&mut out);
+        assert_eq!(vec![1, 2, 2], out);
+
+        let checked_add = |a: i32, b: i32| a.checked_add(b).ok_or(b);
+        assert_eq!(
+            Ok(15),
+            v.par_iter().map(|x| Ok(*x)).try_reduce(|| 0, checked_add)
+        );
+        assert_eq!(
+            Err(i32::MAX),
+            v.par_iter()
+                .map(|x| if *x == 3 { Ok(i32::MAX) } else { Ok(*x) })
+                .try_reduce(|| 0, checked_add)
+        );
+        assert_eq!(
+            Err(4),
+            v.par_iter()
+                .try_for_each(|x| if *x > 3 { Err(*x) } else { Ok(()) })
+        );
+    }
+}
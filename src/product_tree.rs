@@ -1,61 +1,110 @@
-use rayon::prelude::*;
+//! Products of all the inputs but each one, under any associative and commutative operation with
+//! an identity, e.g., the coefficients of the other children of a node in the decomposition.
+//!
+//! Unlike dividing the product of all the inputs by each one, this needs no inverse. The product
+//! operation writes into its third argument, so that buffers can be reused. The `try_` variants
+//! take an operation that may fail, e.g., on an overflow, and return one of its errors.
 
+use crate::par::prelude::*;
+use std::{borrow::Cow, convert::Infallible, mem};
+
+/// Binary tree of the products of the inputs, from which the products of all the inputs but each
+/// one are computed in O(n log n) operations.
+///
+/// The inputs are borrowed as the leaves, and the product operation writes into a buffer, which
+/// is reused along the path to each leaf in `all_products`. The operation must be associative and
+/// commutative, as the inputs are multiplied in the order of the tree.
+///
+/// ```
+/// use shapley_value_decomposition::product_tree::ProductTree;
+///
+/// let mul = |a: &i64, b: &i64, out: &mut i64| *out = a * b;
+/// let input = [2, 3, 5, 7];
+/// let tree = ProductTree::new(input.iter().collect(), mul, true);
+/// assert_eq!(vec![105, 70, 42, 30], tree.all_products(|| 1, mul));
+/// assert_eq!(210, tree.root());
+/// ```
 #[derive(Debug, Clone)]
-pub struct ProductTree<T> {
-    tree: Vec<Vec<T>>,
+pub struct ProductTree<'a, T: Clone> {
+    tree: Vec<Vec<Cow<'a, T>>>,
     tree_depth: usize,
     input_len: usize,
 }
 
-impl<T> ProductTree<T>
+impl<'a, T> ProductTree<'a, T>
 where
-    T: Sync + Send + Clone,
+    T: Sync + Send + Clone + Default,
 {
+    /// Build the tree, with the product of all the inputs if `comp_root`, which `all_products`
+    /// does not need.
     pub fn new(
-        input: Vec<T>,
-        product_op: impl Fn(&T, &T) -> T + Sync + Send,
+        input: Vec<&'a T>,
+        product_op: impl Fn(&T, &T, &mut T) + Sync + Send,
         comp_root: bool,
     ) -> Self {
+        infallible(Self::try_new(input, infallible_op(product_op), comp_root))
+    }
+
+    /// Same as `new`, but fail with an error of `product_op`.
+    pub fn try_new<E: Send>(
+        input: Vec<&'a T>,
+        product_op: impl Fn(&T, &T, &mut T) -> Result<(), E> + Sync + Send,
+        comp_root: bool,
+    ) -> Result<Self, E> {
         let len = input.len();
         // equivalent to len.log2_ceil()
         let mut tree_depth = (usize::BITS - 1 - len.next_power_of_two().leading_zeros()) as usize;
         if comp_root {
             tree_depth += 1;
         }
-        let mut product_tree = Vec::with_capacity(tree_depth);
-        product_tree.push(input);
+        // a single input is its own root
+        tree_depth = tree_depth.max(1);
+        let mut product_tree: Vec<Vec<Cow<'a, T>>> = Vec::with_capacity(tree_depth);
+        product_tree.push(input.into_iter().map(Cow::Borrowed).collect());
         for i in 0..tree_depth - 1 {
             let layer = product_tree[i]
                 .par_iter()
                 .chunks(2)
-                .map(|chunk| {
+                .map(|chunk| -> Result<_, E> {
                     if chunk.len() == 2 {
-                        product_op(chunk[0], chunk[1])
+                        let mut product = T::default();
+                        product_op(chunk[0], chunk[1], &mut product)?;
+                        Ok(Cow::Owned(product))
                     } else {
-                        chunk[0].clone()
+                        Ok(chunk[0].clone())
                     }
                 })
-                .collect();
+                .collect::<Result<_, _>>()?;
             product_tree.push(layer);
         }
         debug_assert_eq!(tree_depth, product_tree.len());
 
-        Self {
+        Ok(Self {
             tree: product_tree,
             tree_depth,
             input_len: len,
-        }
+        })
     }
 
+    /// The product of all the inputs but the `i`-th one for each `i`, which is `identity_op()` for
+    /// a single input.
     pub fn all_products(
         &self,
         identity_op: impl Fn() -> T + Sync + Send,
-        product_op: impl Fn(&T, &T) -> T + Sync + Send,
+        product_op: impl Fn(&T, &T, &mut T) + Sync + Send,
     ) -> Vec<T> {
-        let mut ans = Vec::with_capacity(self.input_len);
+        infallible(self.try_all_products(identity_op, infallible_op(product_op)))
+    }
+
+    /// Same as `all_products`, but fail with an error of `product_op`.
+    pub fn try_all_products<E: Send>(
+        &self,
+        identity_op: impl Fn() -> T + Sync + Send,
+        product_op: impl Fn(&T, &T, &mut T) -> Result<(), E> + Sync + Send,
+    ) -> Result<Vec<T>, E> {
         (0..self.input_len)
             .into_par_iter()
-            .map(|mut i| {
+            .map(|mut i| -> Result<T, E> {
                 let mut i_bits = Vec::with_capacity(self.tree_depth);
                 for _ in 0..self.tree_depth {
                     i_bits.push(i % 2);
@@ -63,42 +112,201 @@ where
                 }
 
                 let mut v = identity_op();
+                let mut buffer = T::default();
                 let mut index = 0;
                 for (depth, bit) in i_bits.into_iter().enumerate().rev() {
                     index = 2 * index + bit;
                     let neighbor_index = if bit == 0 { index + 1 } else { index - 1 };
                     let layer = &self.tree[depth];
                     if neighbor_index < layer.len() {
-                        v = product_op(&v, &layer[neighbor_index]);
+                        product_op(&v, &layer[neighbor_index], &mut buffer)?;
+                        mem::swap(&mut v, &mut buffer);
                     }
                 }
-                v
+                Ok(v)
             })
-            .collect_into_vec(&mut ans);
-        ans
+            .collect()
     }
 
+    /// The product of all the inputs.
+    ///
+    /// Panic if there is no input, or the tree is built without `comp_root`.
     pub fn root(mut self) -> T {
         let mut root = self.tree.pop().unwrap();
-        debug_assert_eq!(root.len(), 1);
-        root.pop().unwrap()
+        assert_eq!(root.len(), 1, "no root of {} inputs", self.input_len);
+        root.pop().unwrap().into_owned()
+    }
+}
+
+/// Same as `ProductTree::all_products`, but by prefix and suffix products in about 3n
+/// operations rather than n log n, along with the product of all the inputs. Only the last step
+/// is parallel, so it pays off for many inputs.
+///
+/// The product operation must be associative and commutative, as with the tree.
+///
+/// ```
+/// use shapley_value_decomposition::product_tree::all_products_linear;
+///
+/// let mul = |a: &i64, b: &i64, out: &mut i64| *out = a * b;
+/// let input = [2, 3, 5, 7];
+/// let (products, all) = all_products_linear(&input.iter().collect::<Vec<_>>(), || 1, mul);
+/// assert_eq!(vec![105, 70, 42, 30], products);
+/// assert_eq!(210, all);
+/// ```
+pub fn all_products_linear<T>(
+    input: &[&T],
+    identity_op: impl Fn() -> T + Sync + Send,
+    product_op: impl Fn(&T, &T, &mut T) + Sync + Send,
+) -> (Vec<T>, T)
+where
+    T: Sync + Send + Default,
+{
+    infallible(try_all_products_linear(
+        input,
+        identity_op,
+        infallible_op(product_op),
+    ))
+}
+
+/// Same as `all_products_linear`, but fail with an error of `product_op`.
+pub fn try_all_products_linear<T, E>(
+    input: &[&T],
+    identity_op: impl Fn() -> T + Sync + Send,
+    product_op: impl Fn(&T, &T, &mut T) -> Result<(), E> + Sync + Send,
+) -> Result<(Vec<T>, T), E>
+where
+    T: Sync + Send + Default,
+    E: Send,
+{
+    let scan = |input: &mut dyn Iterator<Item = &&T>| -> Result<Vec<T>, E> {
+        let mut ans = Vec::with_capacity(input.size_hint().0 + 1);
+        ans.push(identity_op());
+        for x in input {
+            let mut product = T::default();
+            product_op(ans.last().unwrap(), x, &mut product)?;
+            ans.push(product);
+        }
+        Ok(ans)
+    };
+    // the products of the first `i` inputs, and of the last `i` ones
+    let mut prefixes = scan(&mut input.iter())?;
+    let suffixes = scan(&mut input.iter().rev())?;
+
+    let len = input.len();
+    let products = (0..len)
+        .into_par_iter()
+        .map(|i| -> Result<T, E> {
+            let mut product = T::default();
+            product_op(&prefixes[i], &suffixes[len - 1 - i], &mut product)?;
+            Ok(product)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((products, prefixes.pop().unwrap()))
+}
+
+/// A product operation that never fails, for the `try_` variants.
+fn infallible_op<T>(
+    product_op: impl Fn(&T, &T, &mut T) + Sync + Send,
+) -> impl Fn(&T, &T, &mut T) -> Result<(), Infallible> + Sync + Send {
+    move |a, b, out| {
+        product_op(a, b, out);
+        Ok(())
+    }
+}
+
+fn infallible<R>(result: Result<R, Infallible>) -> R {
+    match result {
+        Ok(r) => r,
+        Err(e) => match e {},
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test() {
-        let product_op = |a: &i32, b: &i32| -> i32 { a * b };
+        let product_op = |a: &i32, b: &i32, out: &mut i32| *out = a * b;
         let identity_op = || -> i32 { 1 };
-        let input = vec![1, 2, 3, 4, 5];
-        let product_tree1 = ProductTree::new(input.clone(), product_op, true);
-        let product_tree2 = ProductTree::new(input, product_op, false);
+        let input = [1, 2, 3, 4, 5];
+        let product_tree1 = ProductTree::new(input.iter().collect(), product_op, true);
+        let product_tree2 = ProductTree::new(input.iter().collect(), product_op, false);
         let all_products1 = product_tree1.all_products(identity_op, product_op);
         let all_products2 = product_tree2.all_products(identity_op, product_op);
         assert_eq!(all_products1, all_products2);
         assert_eq!(product_tree1.root(), 120);
     }
+
+    #[test]
+    fn test_few_inputs() {
+        let product_op = |a: &i32, b: &i32, out: &mut i32| *out = a * b;
+        for comp_root in [false, true] {
+            let tree = ProductTree::new(vec![&3], product_op, comp_root);
+            assert_eq!(vec![1], tree.all_products(|| 1, product_op));
+            assert_eq!(3, tree.root());
+
+            let tree = ProductTree::new(vec![&3, &4], product_op, comp_root);
+            assert_eq!(vec![4, 3], tree.all_products(|| 1, product_op));
+
+            let tree = ProductTree::new(vec![], product_op, comp_root);
+            assert!(tree.all_products(|| 1, product_op).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_all_products_linear() {
+        let product_op = |a: &i32, b: &i32, out: &mut i32| *out = a * b;
+        let input = [1, 2, 3, 4, 5];
+        let (products, root) =
+            all_products_linear(&input.iter().collect::<Vec<_>>(), || 1, product_op);
+        assert_eq!(vec![120, 60, 40, 30, 24], products);
+        assert_eq!(120, root);
+
+        let (products, root) = all_products_linear(&[], || 1, product_op);
+        assert!(products.is_empty());
+        assert_eq!(1, root);
+    }
+
+    #[test]
+    fn test_try() {
+        // products above 50 fail
+        let product_op = |a: &i32, b: &i32, out: &mut i32| {
+            let p = a * b;
+            if p > 50 {
+                return Err(p);
+            }
+            *out = p;
+            Ok(())
+        };
+        let input = [5, 6, 7];
+        let refs: Vec<_> = input.iter().collect();
+        let tree = ProductTree::try_new(refs.clone(), product_op, false).unwrap();
+        assert_eq!(
+            Ok(vec![42, 35, 30]),
+            tree.try_all_products(|| 1, product_op)
+        );
+        // the root is 210
+        assert_eq!(
+            Err(210),
+            ProductTree::try_new(refs.clone(), product_op, true).map(|_| ())
+        );
+        assert_eq!(
+            Err(210),
+            try_all_products_linear(&refs, || 1, product_op).map(|_| ())
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_linear_against_tree(input in prop::collection::vec(any::<u64>(), 1..100)) {
+            let product_op = |a: &u64, b: &u64, out: &mut u64| *out = a.wrapping_mul(*b);
+            let refs: Vec<_> = input.iter().collect();
+            let tree = ProductTree::new(refs.clone(), product_op, true);
+            let (products, root) = all_products_linear(&refs, || 1, product_op);
+            prop_assert_eq!(tree.all_products(|| 1, product_op), products);
+            prop_assert_eq!(tree.root(), root);
+        }
+    }
 }
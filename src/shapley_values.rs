@@ -0,0 +1,605 @@
+#[cfg(feature = "parallel")]
+use crate::par::prelude::*;
+use crate::{
+    utils::{hashmap_reduce, stable_sum},
+    OwnerId,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map, BTreeMap, BTreeSet, HashMap},
+    fmt, fs,
+    hash::Hash,
+    io,
+    path::Path,
+};
+
+/// Total value of a bloc of owners. See `ShapleyValues::bloc_power`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BlocPower {
+    /// Sum of the values of the members.
+    pub power: f64,
+    /// Share of `power` in the sum of all the values, or 0 if the latter is 0.
+    pub fraction: f64,
+}
+
+/// Shapley value of each owner, in `f64` unless `F` is `f32` as in `ShapleyValuesF32`.
+///
+/// It derefs to the underlying map, so it can be indexed and iterated like one.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    derive_more::Deref,
+    derive_more::DerefMut,
+    derive_more::From,
+    Serialize,
+    Deserialize,
+)]
+#[serde(transparent)]
+#[serde(bound(
+    serialize = "O: Serialize, F: Serialize",
+    deserialize = "O: Deserialize<'de> + Eq + Hash, F: Deserialize<'de>"
+))]
+pub struct ShapleyValues<O: Eq + Hash = OwnerId, F = f64>(pub HashMap<O, F>);
+
+/// Shapley values in `f32`, whose entries take about half the memory of those of `ShapleyValues`
+/// with `OwnerId`, at the cost of precision, as an `f32` keeps about 7 significant digits. The
+/// values are still computed in `f64` and rounded by `ShapleyValues::to_f32`, so only the storage
+/// of the results is smaller.
+pub type ShapleyValuesF32<O = OwnerId> = ShapleyValues<O, f32>;
+
+impl<O: Eq + Hash> ShapleyValues<O> {
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Round the values to the nearest `f32`.
+    pub fn to_f32(&self) -> ShapleyValuesF32<O>
+    where
+        O: Clone,
+    {
+        self.iter().map(|(o, u)| (o.clone(), *u as f32)).collect()
+    }
+
+    /// Sum of the values, which is 1 for a game where the grand coalition wins and the empty one
+    /// does not. It is added up by `stable_sum`, so it is the same whatever the order of the map.
+    pub fn sum(&self) -> f64 {
+        stable_sum(self.values().copied())
+    }
+
+    /// Check that the values sum up to `expected` within `tolerance` as required by the efficiency
+    /// axiom, or return the actual sum.
+    ///
+    /// The values of a game sum up to `Game::expected_sv_sum`, which is 1 unless the game always
+    /// or never wins.
+    pub fn check_efficiency(&self, expected: f64, tolerance: f64) -> Result<(), f64> {
+        let sum = self.sum();
+        if (sum - expected).abs() <= tolerance {
+            Ok(())
+        } else {
+            Err(sum)
+        }
+    }
+
+    /// Panic if the values do not sum up to `expected` within `tolerance`, i.e., violate the
+    /// efficiency axiom. See `check_efficiency`.
+    pub fn assert_efficient(&self, expected: f64, tolerance: f64) {
+        if let Err(sum) = self.check_efficiency(expected, tolerance) {
+            panic!(
+                "Shapley values sum up to {sum} rather than {expected} (tolerance: {tolerance})"
+            );
+        }
+    }
+
+    /// Value of `owner`, or 0 if it is absent, e.g., a null player not in the game.
+    pub fn get_or_zero(&self, owner: &O) -> f64 {
+        self.get(owner).copied().unwrap_or(0.)
+    }
+
+    /// Sum of the values of `members` by `stable_sum`, treating those absent as 0.
+    pub fn coalition_sum(&self, members: &BTreeSet<O>) -> f64 {
+        stable_sum(members.iter().map(|owner| self.get_or_zero(owner)))
+    }
+
+    /// `coalition_sum` of `members` along with its share of the total.
+    pub fn bloc_power(&self, members: &BTreeSet<O>) -> BlocPower {
+        let power = self.coalition_sum(members);
+        let total = self.sum();
+        BlocPower {
+            power,
+            fraction: if total == 0. { 0. } else { power / total },
+        }
+    }
+
+    /// Add up the values of each owner in the two, e.g., to reduce the values of a batch of games.
+    pub fn merge(self, other: Self) -> Self {
+        Self(hashmap_reduce(self.0, other.0))
+    }
+}
+
+impl<O: Eq + Hash + Ord + Clone> ShapleyValues<O> {
+    /// Owners with their values in descending order of the values, with ties in ascending order
+    /// of the owners.
+    pub fn sorted_desc(&self) -> Vec<(O, f64)> {
+        let mut ans: Vec<_> = self.iter().map(|(o, u)| (o.clone(), *u)).collect();
+        ans.sort_by(|(o1, u1), (o2, u2)| u2.total_cmp(u1).then_with(|| o1.cmp(o2)));
+        ans
+    }
+
+    /// The first `k` owners of `sorted_desc`.
+    pub fn top_k(&self, k: usize) -> Vec<(O, f64)> {
+        let mut ans = self.sorted_desc();
+        ans.truncate(k);
+        ans
+    }
+}
+
+/// Export for other tools, e.g., pandas. The owners are sorted, and the values are printed in the
+/// shortest form that parses back to the same `f64`.
+impl<O: Eq + Hash + Ord + Serialize> ShapleyValues<O> {
+    fn sorted_by_owner(&self) -> BTreeMap<&O, f64> {
+        self.iter().map(|(o, u)| (o, *u)).collect()
+    }
+
+    fn write_csv_records<W: io::Write>(&self, wtr: &mut csv::Writer<W>) -> csv::Result<()> {
+        wtr.write_record(["owner", "value"])?;
+        for record in self.sorted_by_owner() {
+            wtr.serialize(record)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// CSV with an `owner,value` header and a row per owner, in ascending order of the owners. See
+    /// `to_csv` for a ranking.
+    pub fn to_csv_string(&self) -> csv::Result<String> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        self.write_csv_records(&mut wtr)?;
+        let bytes = wtr
+            .into_inner()
+            .map_err(|e| io::Error::new(e.error().kind(), e.error().to_string()))?;
+        Ok(String::from_utf8(bytes).expect("CSV of serialized owners and values is UTF-8"))
+    }
+
+    /// Write `to_csv_string` to `path`.
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> csv::Result<()> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        self.write_csv_records(&mut wtr)
+    }
+
+    /// Pretty-printed JSON object from owners to values.
+    pub fn to_json_string(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.sorted_by_owner())
+    }
+
+    /// Write `to_json_string` to `path`.
+    pub fn write_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json_string()? + "\n")
+    }
+
+    /// CSV with an `owner_id,shapley_value` header and a row per owner in the order of
+    /// `sorted_desc`, i.e., a ranking.
+    ///
+    /// Panic if an owner cannot be serialized as a CSV field, e.g., a struct.
+    pub fn to_csv(&self) -> String
+    where
+        O: Clone,
+    {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        let mut write = || -> csv::Result<()> {
+            wtr.write_record(["owner_id", "shapley_value"])?;
+            for record in self.sorted_desc() {
+                wtr.serialize(record)?;
+            }
+            wtr.flush()?;
+            Ok(())
+        };
+        write().unwrap_or_else(|e| panic!("failed to write CSV of Shapley values: {e}"));
+        let bytes = wtr.into_inner().expect("flushed into a vector");
+        String::from_utf8(bytes).expect("CSV of serialized owners and values is UTF-8")
+    }
+
+    /// Compact JSON object from owners to values, with the owners in ascending order, so that the
+    /// same values always give the same string.
+    ///
+    /// Panic if an owner cannot be a JSON key, e.g., a tuple.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.sorted_by_owner())
+            .unwrap_or_else(|e| panic!("failed to write JSON of Shapley values: {e}"))
+    }
+}
+
+impl<O: Eq + Hash> Default for ShapleyValues<O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A table of the owners and their values in the order of `sorted_desc`, with the values printed
+/// in the precision of the formatter (6 digits by default), e.g., `{:.3}`.
+impl<O: Eq + Hash + Ord + Clone + fmt::Display> fmt::Display for ShapleyValues<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(6);
+        let rows: Vec<_> = self
+            .sorted_desc()
+            .into_iter()
+            .map(|(o, u)| (o.to_string(), format!("{u:.precision$}")))
+            .collect();
+        let owner_width = rows
+            .iter()
+            .map(|(o, _)| o.len())
+            .fold("owner".len(), usize::max);
+        let value_width = rows
+            .iter()
+            .map(|(_, u)| u.len())
+            .fold("value".len(), usize::max);
+
+        write!(f, "{:<owner_width$}  {:>value_width$}", "owner", "value")?;
+        for (o, u) in rows {
+            write!(f, "\n{o:<owner_width$}  {u:>value_width$}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<O: Eq + Hash, const N: usize> From<[(O, f64); N]> for ShapleyValues<O> {
+    fn from(values: [(O, f64); N]) -> Self {
+        Self(HashMap::from(values))
+    }
+}
+
+impl<O: Eq + Hash> FromIterator<(O, f64)> for ShapleyValues<O> {
+    fn from_iter<T: IntoIterator<Item = (O, f64)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Without the `parallel` feature, `collect` goes through `FromIterator` instead.
+#[cfg(feature = "parallel")]
+impl<O: Eq + Hash + Send> FromParallelIterator<(O, f64)> for ShapleyValues<O> {
+    fn from_par_iter<T: IntoParallelIterator<Item = (O, f64)>>(par_iter: T) -> Self {
+        Self(par_iter.into_par_iter().collect())
+    }
+}
+
+impl<O: Eq + Hash, F> IntoIterator for ShapleyValues<O, F> {
+    type Item = (O, F);
+    type IntoIter = hash_map::IntoIter<O, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, O: Eq + Hash, F> IntoIterator for &'a ShapleyValues<O, F> {
+    type Item = (&'a O, &'a F);
+    type IntoIter = hash_map::Iter<'a, O, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<O: Eq + Hash + Send> IntoParallelIterator for ShapleyValues<O> {
+    type Item = (O, f64);
+    type Iter = rayon::collections::hash_map::IntoIter<O, f64>;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.0.into_par_iter()
+    }
+}
+
+impl<O: Eq + Hash> ShapleyValuesF32<O> {
+    /// Sum of the values, accumulated in `f64` by `stable_sum`.
+    pub fn sum(&self) -> f64 {
+        stable_sum(self.values().map(|u| f64::from(*u)))
+    }
+
+    /// Widen the values to `f64`, which keeps them exactly.
+    pub fn to_f64(&self) -> ShapleyValues<O>
+    where
+        O: Clone,
+    {
+        self.iter()
+            .map(|(o, u)| (o.clone(), f64::from(*u)))
+            .collect()
+    }
+}
+
+impl<O: Eq + Hash> FromIterator<(O, f32)> for ShapleyValuesF32<O> {
+    fn from_iter<T: IntoIterator<Item = (O, f32)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<O: Eq + Hash + Send> FromParallelIterator<(O, f32)> for ShapleyValuesF32<O> {
+    fn from_par_iter<T: IntoParallelIterator<Item = (O, f32)>>(par_iter: T) -> Self {
+        Self(par_iter.into_par_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::par::prelude::IntoParallelIterator;
+    use crate::tests::assert_f64_eq;
+
+    fn values() -> ShapleyValues {
+        ShapleyValues::from([
+            (OwnerId(1), 0.25),
+            (OwnerId(2), 0.125),
+            (OwnerId(3), 0.5),
+            (OwnerId(4), 0.125),
+        ])
+    }
+
+    #[test]
+    fn test_sum() {
+        assert_f64_eq(1., values().sum());
+        assert_f64_eq(0., ShapleyValues::<OwnerId>::new().sum());
+
+        // the same bits whatever the order of the maps, each with its own random hasher
+        let values = [0.1, 0.7, 1e16, 0.2, -1e16, 0.3];
+        let sv: ShapleyValues = (0..6).map(|i| (OwnerId(i), values[i as usize])).collect();
+        let members = BTreeSet::from_iter((0..6).map(OwnerId));
+        for _ in 0..10 {
+            let other: ShapleyValues = (0..6)
+                .rev()
+                .map(|i| (OwnerId(i), values[i as usize]))
+                .collect();
+            assert_eq!(sv.sum().to_bits(), other.sum().to_bits());
+            assert_eq!(sv.sum().to_bits(), other.coalition_sum(&members).to_bits());
+        }
+    }
+
+    #[test]
+    fn test_assert_efficient() {
+        values().assert_efficient(1., 1e-9);
+
+        let mut sv = values();
+        *sv.get_mut(&OwnerId(1)).unwrap() += 1e-6;
+        sv.assert_efficient(1., 1e-5);
+    }
+
+    #[test]
+    fn test_check_efficiency() {
+        assert_eq!(Ok(()), values().check_efficiency(1., 0.));
+
+        let mut sv = values();
+        sv.insert(OwnerId(3), 0.6);
+        assert_eq!(Ok(()), sv.check_efficiency(1., 0.2));
+        let sum = sv.check_efficiency(1., 1e-5).unwrap_err();
+        assert_f64_eq(1.1, sum);
+
+        assert_eq!(
+            Err(0.),
+            ShapleyValues::<OwnerId>::new().check_efficiency(1., 1e-5)
+        );
+        // e.g., a game that always wins
+        assert_eq!(
+            Ok(()),
+            ShapleyValues::<OwnerId>::new().check_efficiency(0., 1e-5)
+        );
+        assert_eq!(Err(1.), values().check_efficiency(0., 1e-5));
+    }
+
+    #[test]
+    #[should_panic(expected = "rather than 1")]
+    fn test_assert_efficient_corrupted() {
+        let mut sv = values();
+        sv.insert(OwnerId(3), 0.6);
+        sv.assert_efficient(1., 1e-5);
+    }
+
+    #[test]
+    fn test_bloc_power() {
+        let members = BTreeSet::from([OwnerId(1), OwnerId(3)]);
+        assert_f64_eq(0.75, values().coalition_sum(&members));
+
+        // absent owners count as 0
+        let members = BTreeSet::from([OwnerId(2), OwnerId(5)]);
+        assert_f64_eq(0.125, values().coalition_sum(&members));
+        assert_eq!(0., values().coalition_sum(&BTreeSet::new()));
+
+        let mut sv = values();
+        sv.values_mut().for_each(|u| *u *= 2.);
+        let bloc = sv.bloc_power(&BTreeSet::from([OwnerId(3)]));
+        assert_f64_eq(1., bloc.power);
+        assert_f64_eq(0.5, bloc.fraction);
+
+        let bloc = ShapleyValues::new().bloc_power(&BTreeSet::from([OwnerId(1)]));
+        assert_eq!(
+            BlocPower {
+                power: 0.,
+                fraction: 0.
+            },
+            bloc
+        );
+    }
+
+    #[test]
+    fn test_sorted_desc() {
+        assert_eq!(
+            vec![
+                (OwnerId(3), 0.5),
+                (OwnerId(1), 0.25),
+                (OwnerId(2), 0.125),
+                (OwnerId(4), 0.125),
+            ],
+            values().sorted_desc()
+        );
+    }
+
+    #[test]
+    fn test_top_k() {
+        assert!(values().top_k(0).is_empty());
+        assert_eq!(
+            vec![(OwnerId(3), 0.5), (OwnerId(1), 0.25), (OwnerId(2), 0.125)],
+            values().top_k(3)
+        );
+        assert_eq!(4, values().top_k(10).len());
+    }
+
+    #[test]
+    fn test_get_or_zero() {
+        let sv = values();
+        assert_eq!(0.5, sv.get_or_zero(&OwnerId(3)));
+        assert_eq!(0., sv.get_or_zero(&OwnerId(5)));
+        // indexing through `Deref`
+        assert_eq!(0.25, sv[&OwnerId(1)]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let sv = values().merge(ShapleyValues::from([(OwnerId(1), 0.5), (OwnerId(5), 1.)]));
+        assert_eq!(5, sv.len());
+        assert_f64_eq(0.75, sv[&OwnerId(1)]);
+        assert_f64_eq(1., sv[&OwnerId(5)]);
+
+        let sv: ShapleyValues = (0..4)
+            .into_par_iter()
+            .map(|_| values())
+            .reduce(ShapleyValues::default, ShapleyValues::merge);
+        assert_f64_eq(4., sv.sum());
+    }
+
+    #[test]
+    fn test_display() {
+        let expect = "\
+owner     value
+3      0.500000
+1      0.250000
+2      0.125000
+4      0.125000";
+        assert_eq!(expect, values().to_string());
+
+        let sv =
+            ShapleyValues::from([("alice".to_string(), 2. / 3.), ("bob".to_string(), 1. / 3.)]);
+        assert_eq!(
+            "owner  value\nalice  0.667\nbob    0.333",
+            format!("{sv:.3}")
+        );
+        assert_eq!("owner  value", ShapleyValues::<OwnerId>::new().to_string());
+    }
+
+    fn precise_values() -> ShapleyValues {
+        ShapleyValues::from([
+            (OwnerId(10), 0.1 + 0.2),
+            (OwnerId(2), 1. / 3.),
+            (OwnerId(1), 1e-20),
+            (OwnerId(3), 0.),
+        ])
+    }
+
+    #[test]
+    fn test_to_csv_string() {
+        let csv = precise_values().to_csv_string().unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(Some("owner,value"), lines.next());
+        let rows: Vec<(u32, f64)> = lines
+            .map(|line| {
+                let (o, u) = line.split_once(',').unwrap();
+                (o.parse().unwrap(), u.parse().unwrap())
+            })
+            .collect();
+        // sorted by owner ids rather than their strings
+        assert_eq!(
+            vec![(1, 1e-20), (2, 1. / 3.), (3, 0.), (10, 0.1 + 0.2)],
+            rows
+        );
+
+        let sv = ShapleyValues::from([("a,b".to_string(), 0.5), ("c".to_string(), 0.5)]);
+        assert_eq!(
+            "owner,value\n\"a,b\",0.5\nc,0.5\n",
+            sv.to_csv_string().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_to_json_string() {
+        let sv = precise_values();
+        let json = sv.to_json_string().unwrap();
+        assert_round_trip(&sv, &json);
+        let actual: ShapleyValues = serde_json::from_str(&json).unwrap();
+        assert_eq!(sv, actual);
+
+        // shortest round-trip digits
+        assert!(json.contains(r#""2": 0.3333333333333333"#), "{json}");
+        assert!(json.contains(r#""10": 0.30000000000000004"#), "{json}");
+        assert!(json.contains(r#""1": 1e-20"#), "{json}");
+
+        // sorted by owner ids rather than their strings
+        let pos = |key: &str| json.find(&format!("\"{key}\"")).unwrap();
+        assert!(pos("1") < pos("2") && pos("2") < pos("3") && pos("3") < pos("10"));
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let sv = precise_values();
+        let csv = sv.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(Some("owner_id,shapley_value"), lines.next());
+        let rows: Vec<(OwnerId, f64)> = lines
+            .map(|line| {
+                let (o, u) = line.split_once(',').unwrap();
+                (OwnerId(o.parse().unwrap()), u.parse().unwrap())
+            })
+            .collect();
+        // descending values, reproduced exactly
+        assert_eq!(sv.sorted_desc(), rows);
+        assert_eq!(OwnerId(2), rows[0].0);
+        for (o, u) in rows {
+            assert_eq!(sv[&o].to_bits(), u.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_to_json() {
+        let sv = precise_values();
+        assert_eq!(
+            r#"{"1":1e-20,"2":0.3333333333333333,"3":0.0,"10":0.30000000000000004}"#,
+            sv.to_json()
+        );
+        let actual: ShapleyValues = serde_json::from_str(&sv.to_json()).unwrap();
+        assert_eq!(sv, actual);
+    }
+
+    fn assert_round_trip(expect: &ShapleyValues, json: &str) {
+        let actual: ShapleyValues = serde_json::from_str(json).unwrap();
+        assert_eq!(expect.len(), actual.len());
+        for (o, u) in &actual {
+            assert!(
+                (expect[o] - u).abs() <= f64::EPSILON * expect[o],
+                "owner {o}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_write() {
+        let dir = std::env::temp_dir();
+        let sv = precise_values();
+
+        let csv_path = dir.join(format!("shapley-values-{}.csv", std::process::id()));
+        sv.write_csv(&csv_path).unwrap();
+        assert_eq!(
+            sv.to_csv_string().unwrap(),
+            fs::read_to_string(&csv_path).unwrap()
+        );
+        fs::remove_file(&csv_path).unwrap();
+
+        let json_path = dir.join(format!("shapley-values-{}.json", std::process::id()));
+        sv.write_json(&json_path).unwrap();
+        assert_round_trip(&sv, &fs::read_to_string(&json_path).unwrap());
+        fs::remove_file(&json_path).unwrap();
+    }
+
+    #[test]
+    fn test_serde() {
+        let sv = values();
+        let json = serde_json::to_string(&sv).unwrap();
+        assert_eq!(sv, serde_json::from_str(&json).unwrap());
+    }
+}
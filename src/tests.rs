@@ -1,10 +1,9 @@
 use super::*;
+use crate::dnf::Implicant;
 use once_cell::sync::Lazy;
+use proptest::prelude::*;
 
-static FIXTURE_GAME: Lazy<Game> = Lazy::new(|| {
-    let exp = dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5);
-    Game::new(exp.map_variable(|owner_id| OwnerId(*owner_id)))
-});
+static FIXTURE_GAME: Lazy<Game> = Lazy::new(|| game(dnf!(1 2 4 + 1 2 5 + 2 3 4 + 2 3 5 + 4 5)));
 
 static FIXTURE_RESULT: Lazy<ShapleyValues> = Lazy::new(|| {
     ShapleyValues::from([
@@ -32,8 +31,38 @@ pub(crate) fn test_method(f: impl Fn(&Game) -> ShapleyValues, is_accurate: bool)
     }
 }
 
+/// The game of `exp` over the owners in it, e.g., `game(dnf!(1 2 + 3))`.
+pub(crate) fn game(exp: Dnf<u32>) -> Game {
+    Game::from_dnf(exp.map_variable(|id| OwnerId(*id)))
+}
+
+/// The 16-implicant game of `test_performance`, which mixes every kind of decomposition.
+pub(crate) fn sixteen_implicants() -> Game {
+    game(
+        dnf!(0 4 12 17 + 0 7 12 17 + 0 4 5 9 17 + 0 4 5 10 17 + 0 4 9 15 17 + 0 4 10 15 17 + 4 5 10 13 17 + 4 10 12 13 17 + 4 10 13 15 17 + 7 10 12 13 17 + 0 5 6 7 9 17 + 0 5 6 7 10 17 + 0 6 7 9 15 17 + 0 6 7 10 15 17 + 5 6 7 10 13 17 + 6 7 10 13 15 17),
+    )
+}
+
 pub(crate) fn assert_f64_eq(expect: f64, actual: f64) {
     if (expect - actual).abs() > 1e-5 {
         panic!("assert failed. expect: {expect}, actual: {actual}.");
     }
 }
+
+/// Minimized monotone DNFs over owners `1..=max_owners` with 1 to `max_implicants` implicants of 1
+/// to 4 owners each. Failing cases shrink to fewer and smaller implicants, so print the DNF with
+/// `Display` to get a readable counterexample.
+pub(crate) fn monotone_dnf(
+    max_owners: u32,
+    max_implicants: usize,
+) -> impl Strategy<Value = Dnf<OwnerId>> {
+    let imp = prop::collection::btree_set(1..=max_owners, 1..=4.min(max_owners as usize));
+    prop::collection::vec(imp, 1..=max_implicants).prop_map(|imps| {
+        let mut exp: Dnf<OwnerId> = imps
+            .into_iter()
+            .map(|imp| Implicant(imp.into_iter().map(OwnerId).collect()))
+            .collect();
+        exp.minimize();
+        exp
+    })
+}
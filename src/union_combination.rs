@@ -1,4 +1,8 @@
-use rayon::prelude::*;
+use crate::par::prelude::*;
+use std::{
+    convert::Infallible,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 #[derive(Clone)]
 pub struct Union<T> {
@@ -27,6 +31,37 @@ where
         INIT: Fn(usize) -> T + Sync + Send,
         INC: Fn(&T, usize) -> Option<T> + Sync + Send,
     {
+        let unions = Self::try_new(
+            input_len,
+            usize::MAX,
+            || -> Infallible { unreachable!("unlimited unions") },
+            init_op,
+            |old, i| Ok(inc_op(old, i)),
+        );
+        match unions {
+            Ok(unions) => unions,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Same as `new`, but `inc_op` may fail, and give up with `too_many()` as soon as there are
+    /// more than `max_len` unions. Either way, the enumeration stops at the first error.
+    pub fn try_new<E, TOO, INIT, INC>(
+        input_len: usize,
+        max_len: usize,
+        too_many: TOO,
+        init_op: INIT,
+        inc_op: INC,
+    ) -> Result<Self, E>
+    where
+        E: Send,
+        TOO: Fn() -> E + Sync + Send,
+        INIT: Fn(usize) -> T + Sync + Send,
+        INC: Fn(&T, usize) -> Result<Option<T>, E> + Sync + Send,
+    {
+        if input_len > max_len {
+            return Err(too_many());
+        }
         let mut cur = 0;
         let mut unions: Vec<Union<T>> = (0..input_len)
             .into_par_iter()
@@ -35,28 +70,35 @@ where
                 data: init_op(id),
             })
             .collect();
+        let len = AtomicUsize::new(input_len);
 
         while cur < unions.len() {
-            let new_unions: Vec<Union<T>> = unions[cur..]
+            let new_unions: Result<Vec<Union<T>>, E> = unions[cur..]
                 .par_iter()
                 .flat_map(|old_u| {
                     (old_u.max_id + 1..input_len)
                         .into_par_iter()
                         .filter_map(|new_id| {
-                            let data = inc_op(&old_u.data, new_id)?;
-                            Some(Union {
+                            let data = match inc_op(&old_u.data, new_id) {
+                                Ok(data) => data?,
+                                Err(e) => return Some(Err(e)),
+                            };
+                            if len.fetch_add(1, Ordering::Relaxed) >= max_len {
+                                return Some(Err(too_many()));
+                            }
+                            Some(Ok(Union {
                                 max_id: new_id,
                                 data,
-                            })
+                            }))
                         })
                 })
                 .collect();
 
             cur = unions.len();
-            unions.extend(new_unions);
+            unions.extend(new_unions?);
         }
 
-        Self(unions)
+        Ok(Self(unions))
     }
 
     pub fn len(&self) -> usize {
@@ -67,3 +109,43 @@ where
         self.0.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All the non-empty subsets of `0..n` as bitmasks, or the number of bits of the first one
+    /// with more than `max_bits` of them.
+    fn subsets(n: usize, max_len: usize, max_bits: u32) -> Result<UnionCombination<u32>, u32> {
+        UnionCombination::try_new(
+            n,
+            max_len,
+            || 0,
+            |i| 1 << i,
+            |mask, i| {
+                let mask: u32 = mask | 1 << i;
+                if mask.count_ones() > max_bits {
+                    return Err(mask.count_ones());
+                }
+                Ok(Some(mask))
+            },
+        )
+    }
+
+    #[test]
+    fn test_try_new() {
+        let unions = subsets(5, 31, 5).unwrap();
+        assert_eq!(31, unions.len());
+        let mut masks: Vec<_> = unions.0.into_iter().map(Union::into_inner).collect();
+        masks.sort_unstable();
+        assert_eq!((1..32).collect::<Vec<_>>(), masks);
+
+        assert_eq!(Some(0), subsets(5, 30, 5).err());
+        assert_eq!(Some(0), subsets(5, 4, 5).err());
+        assert_eq!(Some(3), subsets(5, 31, 2).err());
+        assert_eq!(
+            1023,
+            UnionCombination::new(10, |i| 1 << i, |mask: &u32, i| Some(mask | 1 << i)).len()
+        );
+    }
+}
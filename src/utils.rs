@@ -1,4 +1,4 @@
-use crate::{Dnf, OwnerId, OwnerSet};
+use crate::{par::ThreadPoolBuilder, Dnf, OwnerId, OwnerSet};
 use anyhow::{Error, Result};
 use ref_cast::RefCast;
 #[cfg(test)]
@@ -17,7 +17,7 @@ pub fn init_tracing_subscriber(default_filter: &str) -> Result<()> {
 
 pub fn setup_rayon(num_threads: Option<usize>) -> Result<()> {
     if let Some(num_threads) = num_threads {
-        rayon::ThreadPoolBuilder::new()
+        ThreadPoolBuilder::new()
             .num_threads(num_threads)
             .build_global()?;
     }
@@ -69,6 +69,11 @@ where
     acc
 }
 
+/// Merge two maps by adding up the values of the same keys.
+///
+/// Used to reduce parallel results, it only gives the same result for any number of threads if
+/// the addition is exact, e.g., of IE coefficients. Floating-point values depend on the order of
+/// the reduction, so they should be collected in order and folded sequentially instead.
 #[inline]
 pub fn hashmap_reduce<K, V>(a: HashMap<K, V>, b: HashMap<K, V>) -> HashMap<K, V>
 where
@@ -82,6 +87,54 @@ where
     to_mutate
 }
 
+/// Same as `hashmap_reduce`, but add up the values of the same keys by `add`, which may fail,
+/// e.g., on an overflow.
+pub fn try_hashmap_reduce<K, V, E>(
+    a: HashMap<K, V>,
+    b: HashMap<K, V>,
+    add: impl Fn(V, V) -> Result<V, E>,
+) -> Result<HashMap<K, V>, E>
+where
+    K: Eq + Hash,
+{
+    let (to_consume, mut to_mutate) = if a.len() < b.len() { (a, b) } else { (b, a) };
+    for (k, v) in to_consume {
+        let v = match to_mutate.remove(&k) {
+            Some(old) => add(old, v)?,
+            None => v,
+        };
+        to_mutate.insert(k, v);
+    }
+    Ok(to_mutate)
+}
+
+/// Sum of `values` regardless of their order, e.g., the iteration order of a `HashMap`, which
+/// differs between runs. They are sorted and then added up with Neumaier's compensated summation,
+/// which also keeps the cancellation of large terms accurate.
+pub fn stable_sum(values: impl IntoIterator<Item = f64>) -> f64 {
+    let mut values: Vec<f64> = values.into_iter().collect();
+    values.sort_unstable_by(f64::total_cmp);
+    let (mut sum, mut compensation) = (0_f64, 0_f64);
+    for v in values {
+        let t = sum + v;
+        compensation += if sum.abs() >= v.abs() {
+            (sum - t) + v
+        } else {
+            (v - t) + sum
+        };
+        sum = t;
+    }
+    sum + compensation
+}
+
+/// Scale the values so that they sum up to 1. Values summing up to 0 are left untouched.
+pub fn normalize_values<K>(values: &mut HashMap<K, f64>) {
+    let total = stable_sum(values.values().copied());
+    if total != 0. {
+        values.values_mut().for_each(|v| *v /= total);
+    }
+}
+
 #[inline]
 pub fn dnf_to_syns(exp: &Dnf<OwnerId>) -> Vec<&'_ OwnerSet> {
     exp.iter().map(|imp| OwnerSet::ref_cast(&imp.0)).collect()
@@ -134,18 +187,44 @@ mod tests {
     fn test_binom() {
         let n = 10;
         let coeffs = binom_coeffs(n);
-        for k in 0..=n {
-            assert_eq!(binom(k, n), coeffs[k]);
+        for (k, &coeff) in coeffs.iter().enumerate() {
+            assert_eq!(binom(k, n), coeff);
         }
     }
 
+    #[test]
+    fn test_stable_sum() {
+        assert_eq!(0., stable_sum([]));
+        // naively 0 from left to right
+        assert_eq!(2., stable_sum([1., 1e100, 1., -1e100]));
+        let values = [0.1, 0.7, 1e-9, 0.2, 3.3e-5];
+        let expect = stable_sum(values);
+        let mut reversed = values;
+        reversed.reverse();
+        assert_eq!(expect.to_bits(), stable_sum(reversed).to_bits());
+    }
+
+    #[test]
+    fn test_try_hashmap_reduce() {
+        let a = HashMap::from([(1, 1_i8), (2, 100)]);
+        let b = HashMap::from([(2, 20), (3, 3)]);
+        assert_eq!(
+            Ok(HashMap::from([(1, 1), (2, 120), (3, 3)])),
+            try_hashmap_reduce(a.clone(), b, |x, y| x.checked_add(y).ok_or(()))
+        );
+        assert_eq!(
+            Err(()),
+            try_hashmap_reduce(a.clone(), a, |x, y| x.checked_add(y).ok_or(()))
+        );
+    }
+
     #[test]
     fn test_cartesian_product() {
         let a = OwnerSet::from_iter([1, 2, 3]);
         let b = OwnerSet::from_iter([4, 5]);
         let c = OwnerSet::from_iter([6, 7, 8]);
 
-        let products = cartesian_product(&vec![a, b, c]);
+        let products = cartesian_product(&[a, b, c]);
         dbg!(&products);
     }
 }
@@ -0,0 +1,129 @@
+use assert_cmd::Command;
+use std::collections::BTreeMap;
+
+fn compute(args: &[&str]) -> Command {
+    let mut cmd = Command::cargo_bin("shapley").unwrap();
+    cmd.arg("compute").args(args);
+    cmd
+}
+
+fn compute_json(args: &[&str]) -> BTreeMap<u32, f64> {
+    let output = compute(args).args(["--output", "json"]).assert().success();
+    serde_json::from_slice(&output.get_output().stdout).unwrap()
+}
+
+fn assert_values(expect: &[(u32, f64)], actual: &BTreeMap<u32, f64>) {
+    assert_eq!(expect.len(), actual.len());
+    for (owner, value) in expect {
+        assert!(
+            (actual[owner] - value).abs() < 1e-9,
+            "owner {owner}: expect {value}, actual {}",
+            actual[owner]
+        );
+    }
+}
+
+const HYBRID_SV: [(u32, f64); 5] = [
+    (1, 1. / 15.),
+    (2, 7. / 30.),
+    (3, 1. / 15.),
+    (4, 19. / 60.),
+    (5, 19. / 60.),
+];
+
+#[test]
+fn test_compute_json() {
+    let sv = compute_json(&["--input", "tests/data/hybrid.txt"]);
+    assert_values(&HYBRID_SV, &sv);
+
+    for algorithm in ["recursive-decompose", "exact"] {
+        let sv = compute_json(&["-i", "tests/data/hybrid.txt", "-a", algorithm]);
+        assert_values(&HYBRID_SV, &sv);
+    }
+
+    for ablation in ["no-vertical", "no-horizontal", "no-hybrid"] {
+        let sv = compute_json(&["-i", "tests/data/hybrid.txt", "--ablation", ablation]);
+        assert_values(&HYBRID_SV, &sv);
+    }
+}
+
+#[test]
+fn test_compute_owners_header() {
+    let sv = compute_json(&["-i", "tests/data/dummy.txt"]);
+    assert_values(&[(1, 0.5), (2, 0.5), (3, 0.)], &sv);
+}
+
+#[test]
+fn test_compute_table() {
+    compute(&["-i", "tests/data/dummy.txt"])
+        .assert()
+        .success()
+        .stdout("owner\tvalue\n1\t0.500000\n2\t0.500000\n3\t0.000000\n");
+}
+
+#[test]
+fn test_compute_dnf_string() {
+    for ablation in ["no-vertical", "no-horizontal", "no-hybrid"] {
+        let output = compute(&[
+            "-i",
+            "tests/data/hybrid_dnf.txt",
+            "--ablation",
+            ablation,
+            "--format",
+            "json",
+        ])
+        .assert()
+        .success()
+        .stderr("");
+        let sv: BTreeMap<u32, f64> = serde_json::from_slice(&output.get_output().stdout).unwrap();
+        assert_values(&HYBRID_SV, &sv);
+    }
+}
+
+#[test]
+fn test_compute_table_sorted() {
+    compute(&["-i", "tests/data/hybrid.txt"])
+        .assert()
+        .success()
+        .stdout("owner\tvalue\n4\t0.316667\n5\t0.316667\n2\t0.233333\n1\t0.066667\n3\t0.066667\n");
+}
+
+#[test]
+fn test_compute_csv() {
+    let output = compute(&["-i", "tests/data/hybrid.txt", "--output", "csv"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    let mut lines = stdout.lines();
+    assert_eq!(Some("owner,value"), lines.next());
+    let sv: BTreeMap<u32, f64> = lines
+        .map(|line| {
+            let (owner, value) = line.split_once(',').unwrap();
+            (owner.parse().unwrap(), value.parse().unwrap())
+        })
+        .collect();
+    assert_values(&HYBRID_SV, &sv);
+}
+
+#[test]
+fn test_compute_error() {
+    let output = compute(&["-i", "tests/data/redundant.txt"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(stderr.contains("absorbed"), "{stderr}");
+
+    compute(&[
+        "-i",
+        "tests/data/hybrid.txt",
+        "-a",
+        "exact",
+        "--ablation",
+        "no-hybrid",
+    ])
+    .assert()
+    .failure();
+    compute(&["-i", "tests/data/missing.txt"])
+        .assert()
+        .failure();
+}
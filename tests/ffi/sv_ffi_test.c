@@ -0,0 +1,77 @@
+/*
+ * Test of the C API of `src/ffi.rs`, compiled by `build.rs` with the `ffi` feature and run by
+ * `ffi::tests::test_c_program`. With `-DSV_FFI_STANDALONE`, it is a program of its own to link
+ * with the static library of the crate.
+ */
+
+#include <stdint.h>
+#include <stdio.h>
+
+#include "shapley_sv.h"
+
+#define CHECK(cond)                                                          \
+    do {                                                                     \
+        if (!(cond)) {                                                       \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #cond);                                                  \
+            return __LINE__;                                                 \
+        }                                                                    \
+    } while (0)
+
+/* 1 2 3 + 1 2 4 over the owners 1 to 4, as in `test_cal_sv_recursive_decompose` */
+static int test_fixture(int ablation) {
+    const uint64_t flat_implicants[] = {1, 2, 3, 1, 2, 4};
+    const uintptr_t offsets[] = {0, 3, 6};
+    const uint64_t owners[] = {4, 3, 2, 1};
+    const double expect[] = {5. / 12., 5. / 12., 1. / 12., 1. / 12.};
+
+    SvGame *game = sv_game_new(flat_implicants, offsets, 2, owners, 4);
+    CHECK(game != NULL);
+    SvResult *result = NULL;
+    CHECK(sv_compute(game, ablation, &result) == SV_STATUS_OK);
+    sv_free_game(game);
+
+    CHECK(sv_result_len(result) == 4);
+    const uint64_t *result_owners = sv_result_owners(result);
+    const double *values = sv_result_values(result);
+    for (uintptr_t i = 0; i < 4; i++) {
+        CHECK(result_owners[i] == i + 1);
+        CHECK(values[i] - expect[i] < 1e-9 && expect[i] - values[i] < 1e-9);
+    }
+    sv_free_result(result);
+    return 0;
+}
+
+static int test_errors(void) {
+    /* owner 2 is not in the owner set */
+    const uint64_t flat_implicants[] = {1, 2};
+    const uintptr_t offsets[] = {0, 2};
+    const uint64_t owners[] = {1, 2};
+    CHECK(sv_game_new(flat_implicants, offsets, 1, owners, 1) == NULL);
+
+    SvGame *game = sv_game_new(flat_implicants, offsets, 1, owners, 2);
+    CHECK(game != NULL);
+    SvResult *result = NULL;
+    CHECK(sv_compute(game, 42, &result) == SV_STATUS_INVALID_ABLATION);
+    CHECK(sv_compute(game, SV_ABLATION_NONE, NULL) == SV_STATUS_NULL_POINTER);
+    CHECK(result == NULL);
+    sv_free_game(game);
+    sv_free_result(NULL);
+    return 0;
+}
+
+int sv_ffi_test_main(void) {
+    const int ablations[] = {SV_ABLATION_NONE, SV_ABLATION_NO_HORIZONTAL,
+                             SV_ABLATION_NO_VERTICAL, SV_ABLATION_NO_HYBRID};
+    for (int i = 0; i < 4; i++) {
+        int line = test_fixture(ablations[i]);
+        if (line != 0) {
+            return line;
+        }
+    }
+    return test_errors();
+}
+
+#ifdef SV_FFI_STANDALONE
+int main(void) { return sv_ffi_test_main(); }
+#endif
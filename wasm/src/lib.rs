@@ -0,0 +1,51 @@
+//! WebAssembly bindings of the Shapley values of simple games, for `wasm32-unknown-unknown`, built
+//! with wasm-pack:
+//!
+//! ```bash
+//! cd wasm && wasm-pack build --release --target web && wasm-pack test --headless --firefox
+//! ```
+//!
+//! The crate is built without the `parallel` and `data` features, so everything runs on the
+//! calling thread and polars is left out. Without threads `SvOptions::timeout` is ignored, too.
+//! The `try_` functions return an `SvError`, e.g., for a leaf too large or, without `bigint`, an
+//! overflowing coefficient, which is thrown as an `Error`. Only a bug would panic, which traps
+//! instead as panics abort on wasm32.
+
+use shapley_value_decomposition::{
+    alg::synthesis_sv::recursive_decompose::try_cal_sv_recursive_decompose, Dnf, Game, OwnerId,
+    OwnerSet,
+};
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// Without `bigint`, coefficients beyond i64 overflow, and `compute_shapley` throws the
+// `SvError::Overflow`.
+#[cfg(not(feature = "bigint"))]
+const PRECISION: &str = "i64";
+#[cfg(feature = "bigint")]
+const PRECISION: &str = "bigint";
+
+/// Shapley value of each owner of the game whose DNF is `dnf_text`, e.g., `1 2 + 1 3`, over
+/// `owners`, which must include those of the DNF. Return a `Map` from owners to values.
+///
+/// Throw an `Error` if the DNF cannot be parsed, the game is invalid as in `Game::new`, or the
+/// computation fails with an `SvError`, e.g., `SvError::Overflow` without `bigint`.
+#[wasm_bindgen]
+pub fn compute_shapley(dnf_text: &str, owners: &[u32]) -> Result<JsValue, JsError> {
+    let dnf: Dnf<u32> = dnf_text.parse()?;
+    let game = Game::new(
+        dnf.minimized().map_variable(|id| OwnerId(*id)),
+        OwnerSet::from_iter(owners.iter().copied().map(OwnerId)),
+    )?;
+    let sv: BTreeMap<u32, f64> = try_cal_sv_recursive_decompose(&game)?
+        .iter()
+        .map(|(owner_id, u)| (owner_id.0, *u))
+        .collect();
+    Ok(serde_wasm_bindgen::to_value(&sv)?)
+}
+
+/// Precision of the IE coefficients of the build, `"bigint"` or `"i64"`.
+#[wasm_bindgen]
+pub fn precision() -> String {
+    PRECISION.to_string()
+}
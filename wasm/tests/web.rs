@@ -0,0 +1,70 @@
+//! Headless browser tests, run by `wasm-pack test --headless --firefox`.
+
+use shapley_value_decomposition::{
+    alg::synthesis_sv::recursive_decompose::try_cal_sv_recursive_decompose_with, Dnf, Game,
+    OwnerId, SvOptions,
+};
+use shapley_wasm::{compute_shapley, precision};
+use std::{collections::BTreeMap, time::Duration};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn compute(dnf_text: &str, owners: &[u32]) -> BTreeMap<u32, f64> {
+    let value = compute_shapley(dnf_text, owners)
+        .map_err(JsValue::from)
+        .unwrap();
+    serde_wasm_bindgen::from_value(value).unwrap()
+}
+
+fn assert_f64_eq(expect: f64, actual: f64) {
+    assert!((expect - actual).abs() < 1e-9, "{expect} != {actual}");
+}
+
+#[wasm_bindgen_test]
+fn test_compute_shapley() {
+    // as in `test_cal_sv_recursive_decompose`
+    let sv = compute("1 2 3 + 1 2 4", &[1, 2, 3, 4]);
+    assert_eq!(vec![1, 2, 3, 4], sv.keys().copied().collect::<Vec<_>>());
+    for (owner, expect) in [(1, 5. / 12.), (2, 5. / 12.), (3, 1. / 12.), (4, 1. / 12.)] {
+        assert_f64_eq(expect, sv[&owner]);
+    }
+
+    // null players
+    let sv = compute("1 2", &[1, 2, 3]);
+    assert_f64_eq(0.5, sv[&1]);
+    assert_f64_eq(0., sv[&3]);
+}
+
+#[wasm_bindgen_test]
+fn test_compute_shapley_error() {
+    assert!(compute_shapley("1 + +", &[1]).is_err());
+    // owner 2 is not in the owner set
+    assert!(compute_shapley("1 2", &[1]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_timeout_ignored() {
+    // no watchdog thread can be spawned on wasm32, so the timeout is ignored instead of panicking
+    let dnf: Dnf<u32> = "1 2 3 + 1 2 4".parse().unwrap();
+    let game = Game::from_dnf(dnf.map_variable(|id| OwnerId(*id)));
+    let options = SvOptions {
+        timeout: Some(Duration::ZERO),
+        ..Default::default()
+    };
+    let sv = try_cal_sv_recursive_decompose_with(&game, &options).unwrap();
+    assert_f64_eq(5. / 12., sv[&OwnerId(1)]);
+}
+
+#[wasm_bindgen_test]
+fn test_precision() {
+    assert_eq!(
+        if cfg!(feature = "bigint") {
+            "bigint"
+        } else {
+            "i64"
+        },
+        precision()
+    );
+}